@@ -5,4 +5,5 @@ export type APINodeFlags =
   | "IsProcessed"
   | "IsRequesting"
   | "IsBlocked"
-  | "HadError";
+  | "HadError"
+  | "GaveUp";
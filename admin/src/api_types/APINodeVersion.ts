@@ -0,0 +1,15 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { APINodeItem } from "./APINodeItem";
+
+/**
+ * A version of a node. Versions are numbered from 1, the last version is the current node.
+ */
+export type APINodeVersion = {
+  version: number;
+  node: APINodeItem;
+  /**
+   * Unix timestamp (in milliseconds) of when this version was replaced by the next one.
+   * Not set for the current version.
+   */
+  replaced_at: bigint | null;
+};
@@ -1,11 +1,16 @@
 // This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { Answer } from "./Answer";
 import type { Classification } from "./Classification";
 import type { ClassifierSettings } from "./ClassifierSettings";
+import type { ContentChange } from "./ContentChange";
+import type { CrawlBudget } from "./CrawlBudget";
 import type { CrawlerSettings } from "./CrawlerSettings";
 import type { EntityName } from "./EntityName";
 import type { ExtractedEntity } from "./ExtractedEntity";
+import type { FetchErrorDetails } from "./FetchErrorDetails";
 import type { Link } from "./Link";
 import type { ProjectSettings } from "./ProjectSettings";
+import type { RecrawlSettings } from "./RecrawlSettings";
 import type { TableRow } from "./TableRow";
 import type { WebMetadata } from "./WebMetadata";
 
@@ -24,4 +29,9 @@ export type APIPayload =
   | { type: "ClassifierSettings"; data: ClassifierSettings }
   | { type: "Classification"; data: Classification }
   | { type: "NamedEntitiesToExtract"; data: Array<EntityName> }
-  | { type: "ExtractedNamedEntities"; data: Array<ExtractedEntity> };
+  | { type: "ExtractedNamedEntities"; data: Array<ExtractedEntity> }
+  | { type: "FetchError"; data: FetchErrorDetails }
+  | { type: "Answer"; data: Answer }
+  | { type: "RecrawlSettings"; data: RecrawlSettings }
+  | { type: "ContentChange"; data: ContentChange }
+  | { type: "CrawlBudget"; data: CrawlBudget };
@@ -0,0 +1,18 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { Answer } from "./Answer";
+import type { Citation } from "./Citation";
+import type { QuestionStatus } from "./QuestionStatus";
+
+/**
+ * Schema for response to the ask and get answer API requests.
+ */
+export type APIQuestion = {
+  question_node_id: number;
+  status: QuestionStatus;
+  /**
+   * The content nodes which the question is answered from, most relevant first
+   */
+  sources: Array<Citation>;
+  answer_node_id: number | null;
+  answer: Answer | null;
+};
@@ -0,0 +1,17 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { APINodeItem } from "./APINodeItem";
+import type { SemanticSearchResult } from "./SemanticSearchResult";
+
+/**
+ * Schema for response to the semantic search API request.
+ */
+export type APISemanticSearchResults = {
+  /**
+   * Closest matches first
+   */
+  results: Array<SemanticSearchResult>;
+  /**
+   * The matching nodes and their web pages and links
+   */
+  nodes: Array<APINodeItem>;
+};
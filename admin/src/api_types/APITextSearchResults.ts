@@ -0,0 +1,17 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { APINodeItem } from "./APINodeItem";
+import type { TextSearchResult } from "./TextSearchResult";
+
+/**
+ * Schema for response to the full-text search API request.
+ */
+export type APITextSearchResults = {
+  /**
+   * Best matches first
+   */
+  results: Array<TextSearchResult>;
+  /**
+   * The matching nodes and their web pages and links
+   */
+  nodes: Array<APINodeItem>;
+};
@@ -0,0 +1,19 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { APIEdges } from "./APIEdges";
+import type { APINodeItem } from "./APINodeItem";
+import type { TraversedNode } from "./TraversedNode";
+
+/**
+ * Schema for response to the traversal and shortest path API requests.
+ */
+export type APITraversal = {
+  /**
+   * The visited nodes in the order of the traversal, or of the path
+   */
+  traversed_nodes: Array<TraversedNode>;
+  nodes: Array<APINodeItem>;
+  /**
+   * The edges between the visited nodes
+   */
+  edges: APIEdges;
+};
@@ -0,0 +1,13 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { Citation } from "./Citation";
+
+/**
+ * An answer to a question about a project, written by the LLM from the content in the graph.
+ */
+export type Answer = {
+  text: string;
+  /**
+   * The sources which the answer is based on
+   */
+  citations: Array<Citation>;
+};
@@ -0,0 +1,12 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+export type AskWrite = {
+  /**
+   * A question about the content of the project
+   */
+  question: string;
+  /**
+   * Number of content nodes to answer from
+   */
+  max_sources?: number;
+};
@@ -0,0 +1,6 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * A content node which an answer is based on, with the URL of the web page it is from.
+ */
+export type Citation = { node_id: number; url: string | null };
@@ -0,0 +1,15 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * A change in the content of a web page, found when its link was fetched again.
+ */
+export type ContentChange = {
+  url: string;
+  /**
+   * The version of the web page node before the change, see the versions of a node
+   */
+  previous_version: number;
+  lines_added: number;
+  lines_removed: number;
+  detected_at: string;
+};
@@ -0,0 +1,28 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Limits on how much a project crawls. Limits which are not set are not checked.
+ */
+export type CrawlBudget = {
+  /**
+   * Maximum number of web pages to fetch, including pages which are fetched again.
+   */
+  max_pages: number | null;
+  /**
+   * Maximum number of links followed from a seed link to a web page. Seed links are added
+   * by the user or found by web search and are at depth 0.
+   */
+  max_depth: number | null;
+  /**
+   * Maximum number of web pages to fetch from any one domain.
+   */
+  max_pages_per_domain: number | null;
+  /**
+   * Maximum minutes to crawl for, from when the first web page was fetched.
+   */
+  max_duration_in_minutes: number | null;
+  /**
+   * Maximum spend on LLM requests, in US cents.
+   */
+  max_llm_spend_in_cents: number | null;
+};
@@ -0,0 +1,14 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { CrawlStopReason } from "./CrawlStopReason";
+
+/**
+ * What a project has crawled so far.
+ */
+export type CrawlState = {
+  started_at: string | null;
+  count_pages_fetched: number;
+  count_pages_fetched_by_domain: { [key in string]?: number };
+  llm_spend_in_cents: number;
+  stop_reason: CrawlStopReason | null;
+  stopped_at: string | null;
+};
@@ -0,0 +1,8 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { CrawlBudget } from "./CrawlBudget";
+import type { CrawlState } from "./CrawlState";
+
+/**
+ * The crawl budget of a project with what it has crawled so far.
+ */
+export type CrawlStatus = { budget: CrawlBudget; state: CrawlState };
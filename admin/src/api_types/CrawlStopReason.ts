@@ -0,0 +1,6 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Why a project stopped crawling. No more web pages are fetched till the budget is raised.
+ */
+export type CrawlStopReason = "MaxPages" | "MaxDuration" | "MaxLLMSpend";
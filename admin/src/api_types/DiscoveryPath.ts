@@ -0,0 +1,17 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { DiscoveryStep } from "./DiscoveryStep";
+
+/**
+ * How a link or web page was reached from a seed link, added by the user or found by web search.
+ */
+export type DiscoveryPath = {
+  node_id: number;
+  /**
+   * None when the link was found before depths were recorded.
+   */
+  depth: number | null;
+  /**
+   * Links followed from the seed link, the last one is the link itself.
+   */
+  steps: Array<DiscoveryStep>;
+};
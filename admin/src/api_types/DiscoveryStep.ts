@@ -0,0 +1,13 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * A link on the path by which a link or web page was reached.
+ */
+export type DiscoveryStep = {
+  link_node_id: number;
+  url: string | null;
+  /**
+   * Number of links followed from the seed link, which is at depth 0.
+   */
+  depth: number;
+};
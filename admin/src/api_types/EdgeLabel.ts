@@ -11,4 +11,12 @@ export type EdgeLabel =
   | "Suggests"
   | "SuggestedFor"
   | "Classifies"
-  | "ClassifiedFor";
+  | "ClassifiedFor"
+  | "FailedWith"
+  | "FailureOf"
+  | "Answers"
+  | "AnsweredBy"
+  | "Cites"
+  | "CitedBy"
+  | "ChangedWith"
+  | "ChangeOf";
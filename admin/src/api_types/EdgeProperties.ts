@@ -0,0 +1,10 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { EdgeSource } from "./EdgeSource";
+
+export type EdgeProperties = {
+  source: EdgeSource;
+  weight: number | null;
+  confidence: number | null;
+  created_at: string;
+  attributes: { [key in string]?: string };
+};
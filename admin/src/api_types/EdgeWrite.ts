@@ -4,4 +4,16 @@ import type { EdgeLabel } from "./EdgeLabel";
 export type EdgeWrite = {
   node_ids: [number, number];
   edge_labels: [EdgeLabel, EdgeLabel];
+  /**
+   * Optional weight of the edge, used when creating an edge
+   */
+  weight?: number;
+  /**
+   * Optional confidence in the edge, from 0 to 1, used when creating an edge
+   */
+  confidence?: number;
+  /**
+   * Optional free-form attributes of the edge, used when creating an edge
+   */
+  attributes?: { [key in string]?: string };
 };
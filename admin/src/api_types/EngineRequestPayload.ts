@@ -1,6 +1,19 @@
 // This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { AskWrite } from "./AskWrite";
+import type { CrawlBudget } from "./CrawlBudget";
 import type { EdgeWrite } from "./EdgeWrite";
+import type { ExportFormat } from "./ExportFormat";
+import type { ImportWrite } from "./ImportWrite";
 import type { NodeWrite } from "./NodeWrite";
+import type { NodesAsOfRead } from "./NodesAsOfRead";
+import type { QueryWrite } from "./QueryWrite";
+import type { RecrawlSettings } from "./RecrawlSettings";
+import type { SemanticSearchWrite } from "./SemanticSearchWrite";
+import type { ShortestPathWrite } from "./ShortestPathWrite";
+import type { SnapshotCreate } from "./SnapshotCreate";
+import type { TextSearchWrite } from "./TextSearchWrite";
+import type { Traversal } from "./Traversal";
+import type { TraversalFilter } from "./TraversalFilter";
 
 export type EngineRequestPayload =
   | { Explore: number | null }
@@ -10,7 +23,28 @@ export type EngineRequestPayload =
   | { GetNodesWithLabel: string }
   | { GetNodesWithIds: Array<number> }
   | { GetAllNodes: bigint }
+  | { GetNodesAsOf: NodesAsOfRead }
+  | { GetNodeVersions: number }
+  | { DiffNodeVersions: [number, number, number] }
+  | { GetDiscoveryPath: number }
   | { GetAllEdges: bigint }
   | { CreateNode: NodeWrite }
   | { CreateEdge: EdgeWrite }
-  | { Query: number };
+  | { DeleteNode: [number, boolean] }
+  | { DeleteEdge: EdgeWrite }
+  | { Query: number }
+  | { GraphQuery: QueryWrite }
+  | { Traverse: Traversal }
+  | { FindShortestPath: ShortestPathWrite }
+  | { GetConnectedComponents: TraversalFilter }
+  | { SearchText: TextSearchWrite }
+  | { SemanticSearch: SemanticSearchWrite }
+  | { Ask: AskWrite }
+  | { GetAnswer: number }
+  | { CreateSnapshot: SnapshotCreate }
+  | { Export: ExportFormat }
+  | { Import: ImportWrite }
+  | "GetRecrawlSettings"
+  | { SetRecrawlSettings: RecrawlSettings }
+  | "GetCrawlStatus"
+  | { SetCrawlBudget: CrawlBudget };
@@ -1,9 +1,21 @@
 // This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
 import type { APIEdges } from "./APIEdges";
 import type { APINodeItem } from "./APINodeItem";
+import type { APINodeVersion } from "./APINodeVersion";
+import type { APIQuestion } from "./APIQuestion";
+import type { APISemanticSearchResults } from "./APISemanticSearchResults";
+import type { APITextSearchResults } from "./APITextSearchResults";
+import type { APITraversal } from "./APITraversal";
 import type { ClassifiedItem } from "./ClassifiedItem";
+import type { CrawlStatus } from "./CrawlStatus";
+import type { DiscoveryPath } from "./DiscoveryPath";
 import type { EntityGroup } from "./EntityGroup";
 import type { Explore } from "./Explore";
+import type { ImportResults } from "./ImportResults";
+import type { NodeVersionDiff } from "./NodeVersionDiff";
+import type { QueryResults } from "./QueryResults";
+import type { RecrawlSettings } from "./RecrawlSettings";
+import type { Snapshot } from "./Snapshot";
 
 /**
  * Engine's response for an API request.
@@ -15,10 +27,27 @@ import type { Explore } from "./Explore";
 export type EngineResponsePayload =
   | { type: "NodeCreatedSuccessfully"; data: number }
   | { type: "EdgeCreatedSuccessfully" }
+  | { type: "NodesDeletedSuccessfully"; data: Array<number> }
+  | { type: "EdgeDeletedSuccessfully" }
   | { type: "Nodes"; data: Array<APINodeItem> }
+  | { type: "NodeVersions"; data: Array<APINodeVersion> }
+  | { type: "NodeVersionDiff"; data: NodeVersionDiff }
+  | { type: "DiscoveryPath"; data: DiscoveryPath }
   | { type: "Edges"; data: APIEdges }
   | { type: "Labels"; data: Array<string> }
   | { type: "Entities"; data: Array<EntityGroup> }
   | { type: "Classifications"; data: Array<ClassifiedItem> }
   | { type: "Explore"; data: Explore }
+  | { type: "QueryResults"; data: QueryResults }
+  | { type: "Traversal"; data: APITraversal }
+  | { type: "ShortestPath"; data: APITraversal | null }
+  | { type: "ConnectedComponents"; data: Array<Array<number>> }
+  | { type: "TextSearchResults"; data: APITextSearchResults }
+  | { type: "SemanticSearchResults"; data: APISemanticSearchResults }
+  | { type: "Answer"; data: APIQuestion }
+  | { type: "Snapshot"; data: Snapshot }
+  | { type: "Export"; data: string }
+  | { type: "ImportResults"; data: ImportResults }
+  | { type: "RecrawlSettings"; data: RecrawlSettings }
+  | { type: "CrawlStatus"; data: CrawlStatus }
   | { type: "Error"; data: string };
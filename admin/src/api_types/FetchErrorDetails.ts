@@ -0,0 +1,10 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { FetchErrorKind } from "./FetchErrorKind";
+
+export type FetchErrorDetails = {
+  url: string;
+  status: number | null;
+  kind: FetchErrorKind;
+  message: string;
+  failed_at: string;
+};
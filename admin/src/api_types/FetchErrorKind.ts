@@ -0,0 +1,13 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+export type FetchErrorKind =
+  | "Timeout"
+  | "DNS"
+  | "TLS"
+  | "Connection"
+  | "ClientError"
+  | "ServerError"
+  | "RobotsDenied"
+  | "RateLimited"
+  | "Other"
+  | "DomainUnavailable";
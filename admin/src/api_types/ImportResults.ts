@@ -0,0 +1,12 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { ImportRowResult } from "./ImportRowResult";
+
+/**
+ * Results of a bulk import, one for each row in the order of the file.
+ */
+export type ImportResults = {
+  count_created: number;
+  count_existing: number;
+  count_failed: number;
+  rows: Array<ImportRowResult>;
+};
@@ -0,0 +1,42 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { EdgeLabel } from "./EdgeLabel";
+import type { ImportRowType } from "./ImportRowType";
+import type { NodeLabel } from "./NodeLabel";
+
+/**
+ * A row of a bulk import. The same fields are used in NDJSON and CSV,
+ * fields which are not needed for the `type` of the row are left out or empty.
+ */
+export type ImportRow = {
+  type: ImportRowType;
+  /**
+   * Name of the node within the import, so edges can refer to it
+   */
+  key: string | null;
+  /**
+   * URL of a `Link`
+   */
+  url: string | null;
+  /**
+   * Text of a `SearchTerm` or `Text`
+   */
+  text: string | null;
+  /**
+   * Optional label of a `Text`: `Title`, `Heading`, `Paragraph` or `ListItem`
+   */
+  label: NodeLabel | null;
+  /**
+   * Node of an `Edge` is from, the `key` of a node in the import or the ID of a node
+   */
+  from: string | null;
+  /**
+   * Node of an `Edge` is to, the `key` of a node in the import or the ID of a node
+   */
+  to: string | null;
+  edge_label: EdgeLabel | null;
+  /**
+   * Label of the edge in the other direction, the same as `edge_label` if left out
+   */
+  reverse_edge_label: EdgeLabel | null;
+  weight: number | null;
+};
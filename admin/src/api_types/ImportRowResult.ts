@@ -0,0 +1,21 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { ImportRowStatus } from "./ImportRowStatus";
+
+/**
+ * Result of importing a row.
+ */
+export type ImportRowResult = {
+  /**
+   * Line of the row in the imported file, starting at 1
+   */
+  line: number;
+  status: ImportRowStatus;
+  /**
+   * The node which was created or found, not set for edges
+   */
+  node_id: number | null;
+  /**
+   * Why the row could not be imported
+   */
+  error: string | null;
+};
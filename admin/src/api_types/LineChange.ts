@@ -0,0 +1,15 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { LineChangeType } from "./LineChangeType";
+
+/**
+ * A line which was added to or removed from a text field.
+ */
+export type LineChange = {
+  change: LineChangeType;
+  /**
+   * Line number (from 1) in the earlier version for removed lines,
+   * or in the later version for added lines
+   */
+  line_number: number;
+  text: string;
+};
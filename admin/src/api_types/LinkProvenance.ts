@@ -0,0 +1,6 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Where the link of a search result came from.
+ */
+export type LinkProvenance = "AddedByUser" | "AddedByWebSearch" | "Crawled";
@@ -27,4 +27,10 @@ export type NodeLabel =
   | "ClassifierSettings"
   | "Classification"
   | "NamedEntitiesToExtract"
-  | "ExtractedNamedEntities";
+  | "ExtractedNamedEntities"
+  | "FetchError"
+  | "Question"
+  | "Answer"
+  | "RecrawlSettings"
+  | "ContentChange"
+  | "CrawlBudget";
@@ -0,0 +1,15 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { NodeLabel } from "./NodeLabel";
+import type { PayloadFieldChange } from "./PayloadFieldChange";
+
+/**
+ * Differences between two versions of a node.
+ */
+export type NodeVersionDiff = {
+  node_id: number;
+  from_version: number;
+  to_version: number;
+  labels_added: Array<NodeLabel>;
+  labels_removed: Array<NodeLabel>;
+  changes: Array<PayloadFieldChange>;
+};
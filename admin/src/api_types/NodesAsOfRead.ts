@@ -0,0 +1,16 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+export type NodesAsOfRead = {
+  /**
+   * The node label to filter nodes by. If provided, ids will be ignored.
+   */
+  label: string | null;
+  /**
+   * The IDs of the nodes to read. All nodes are read if neither label nor ids are provided.
+   */
+  ids: Array<number> | null;
+  /**
+   * Unix timestamp (in milliseconds) to read the nodes as of
+   */
+  as_of: bigint;
+};
@@ -0,0 +1,23 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { FieldChangeType } from "./FieldChangeType";
+import type { LineChange } from "./LineChange";
+
+/**
+ * A field of the payload which changed between two versions of a node.
+ * Fields are named like in exports of the graph: `payload_type`, `payload` for text payloads
+ * or `payload.<field>` for the fields of other payloads.
+ */
+export type PayloadFieldChange = {
+  field: string;
+  change: FieldChangeType;
+  /**
+   * The value in the earlier version, as JSON. Not set for texts with more than one line,
+   * which have the changed lines instead.
+   */
+  from: string | null;
+  /**
+   * The value in the later version, as JSON. Not set for texts with more than one line.
+   */
+  to: string | null;
+  lines: Array<LineChange>;
+};
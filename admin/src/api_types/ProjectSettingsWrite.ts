@@ -1,7 +1,13 @@
 // This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { CrawlBudget } from "./CrawlBudget";
 
 export type ProjectSettingsWrite = {
   extract_data_only_from_specified_links: boolean;
   crawl_within_domains_of_specified_links: boolean;
   crawl_direct_links_from_specified_links: boolean;
+  /**
+   * Limits on how much the project crawls, saved in a CrawlBudget node.
+   * Set `max_depth` to crawl up to that many links from the specified links.
+   */
+  crawl_budget?: CrawlBudget;
 };
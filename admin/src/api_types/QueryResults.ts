@@ -0,0 +1,13 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { QueryValue } from "./QueryValue";
+
+/**
+ * Results of a query, with a row for each match of the pattern in the query.
+ */
+export type QueryResults = {
+  /**
+   * The items of `RETURN` in the query, like `d.text`
+   */
+  columns: Array<string>;
+  rows: Array<Array<QueryValue>>;
+};
@@ -0,0 +1,13 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { APINodeItem } from "./APINodeItem";
+
+/**
+ * A value in the results of a query.
+ */
+export type QueryValue =
+  | { type: "Null" }
+  | { type: "Bool"; data: boolean }
+  | { type: "Number"; data: number }
+  | { type: "Text"; data: string }
+  | { type: "List"; data: Array<string> }
+  | { type: "Node"; data: APINodeItem };
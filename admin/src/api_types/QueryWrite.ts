@@ -0,0 +1,8 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+export type QueryWrite = {
+  /**
+   * The query, like `MATCH (d:DomainName)-[:OwnerOf]->(l:Link) RETURN d.text, l.path LIMIT 10`
+   */
+  query: string;
+};
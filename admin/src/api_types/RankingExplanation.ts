@@ -0,0 +1,26 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { LinkProvenance } from "./LinkProvenance";
+import type { RankingFeature } from "./RankingFeature";
+
+/**
+ * Why a result of hybrid ranking has its score.
+ */
+export type RankingExplanation = {
+  /**
+   * BM25 score of the text
+   */
+  text_score: number;
+  /**
+   * Classification of the web page, if it has been classified
+   */
+  is_relevant: boolean | null;
+  /**
+   * Number of edges from the closest objective, if it is close to one
+   */
+  objective_distance: number | null;
+  provenance: LinkProvenance | null;
+  /**
+   * The features which add up to the score
+   */
+  features: Array<RankingFeature>;
+};
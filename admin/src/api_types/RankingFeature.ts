@@ -0,0 +1,18 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { RankingFeatureName } from "./RankingFeatureName";
+
+/**
+ * The value of one feature of a result and how much it added to the score.
+ */
+export type RankingFeature = {
+  name: RankingFeatureName;
+  /**
+   * From 0 to 1
+   */
+  value: number;
+  weight: number;
+  /**
+   * Value times weight
+   */
+  contribution: number;
+};
@@ -0,0 +1,7 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+export type RankingFeatureName =
+  | "Text"
+  | "Relevance"
+  | "ObjectiveProximity"
+  | "Provenance";
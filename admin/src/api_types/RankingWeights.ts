@@ -0,0 +1,24 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Weights of the features in hybrid ranking, the score of a result is the weighted sum
+ * of its features. Each feature is from 0 to 1.
+ */
+export type RankingWeights = {
+  /**
+   * BM25 score of the text, relative to the best match
+   */
+  text: number;
+  /**
+   * Whether the web page was classified as relevant to the objective
+   */
+  relevance: number;
+  /**
+   * How close the result is to the objective in the graph
+   */
+  objective_proximity: number;
+  /**
+   * Whether the link was added by the user, found by web search or found while crawling
+   */
+  provenance: number;
+};
@@ -0,0 +1,17 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * How often the web pages of a project are fetched again, to find out if they have changed.
+ */
+export type RecrawlSettings = {
+  /**
+   * Minutes after which a web page is fetched again.
+   * If not set, web pages are fetched only once.
+   */
+  interval_in_minutes: number | null;
+  /**
+   * Intervals for the web pages of specific domains, like `example.com`.
+   * These are used instead of the interval of the project.
+   */
+  interval_in_minutes_by_domain: { [key in string]?: number };
+};
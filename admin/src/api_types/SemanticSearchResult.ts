@@ -0,0 +1,15 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * A content node which is close in meaning to a semantic search, with the web page
+ * and link it is from.
+ */
+export type SemanticSearchResult = {
+  node_id: number;
+  /**
+   * Cosine similarity of the node to the query, higher is better
+   */
+  score: number;
+  web_page_node_id: number | null;
+  link_node_id: number | null;
+};
@@ -0,0 +1,9 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+export type SemanticSearchWrite = {
+  /**
+   * Text to find content close in meaning to, like a question or a sentence
+   */
+  query: string;
+  limit?: number;
+};
@@ -3,4 +3,5 @@
 export type Settings = {
   pathToStorageDir: string | null;
   hostname: string | null;
+  maxAttemptsPerNode: number | null;
 };
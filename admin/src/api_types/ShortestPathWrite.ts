@@ -0,0 +1,12 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { TraversalFilter } from "./TraversalFilter";
+
+export type ShortestPathWrite = {
+  from_node_id: number;
+  to_node_id: number;
+  filter: TraversalFilter;
+  /**
+   * Paths longer than this many edges are not searched
+   */
+  max_depth: number;
+};
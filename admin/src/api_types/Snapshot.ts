@@ -0,0 +1,21 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+export type Snapshot = {
+  /**
+   * Snapshot ID (UUID)
+   */
+  uuid: string;
+  /**
+   * The project this is a snapshot of
+   */
+  project_uuid: string;
+  /**
+   * Optional label, like the reason for taking the snapshot
+   */
+  label: string | null;
+  created_at: string;
+  /**
+   * Size of the files of the snapshot on disk
+   */
+  size_in_bytes: number;
+};
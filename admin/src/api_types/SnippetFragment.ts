@@ -0,0 +1,6 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * A part of the text of a search result, matching parts are highlighted.
+ */
+export type SnippetFragment = { text: string; is_match: boolean };
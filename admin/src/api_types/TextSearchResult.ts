@@ -0,0 +1,24 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { RankingExplanation } from "./RankingExplanation";
+import type { SnippetFragment } from "./SnippetFragment";
+
+/**
+ * A content node which matches a full-text search, with the web page and link it is from.
+ */
+export type TextSearchResult = {
+  node_id: number;
+  /**
+   * BM25 score of the match, or the combined score with hybrid ranking, higher is better
+   */
+  score: number;
+  web_page_node_id: number | null;
+  link_node_id: number | null;
+  /**
+   * The text around the first match
+   */
+  snippet: Array<SnippetFragment>;
+  /**
+   * How the score was calculated, only with hybrid ranking
+   */
+  explanation: RankingExplanation | null;
+};
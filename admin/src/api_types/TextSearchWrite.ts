@@ -0,0 +1,19 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { RankingWeights } from "./RankingWeights";
+import type { SearchRanking } from "./SearchRanking";
+
+export type TextSearchWrite = {
+  /**
+   * Words and "quoted phrases" to search for, like `crawler "web page" -robots`
+   */
+  query: string;
+  limit?: number;
+  /**
+   * Ranks by text score if not given
+   */
+  ranking?: SearchRanking;
+  /**
+   * Weights of the features for hybrid ranking, the default weights are used if not given
+   */
+  weights?: RankingWeights;
+};
@@ -0,0 +1,23 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { TraversalFilter } from "./TraversalFilter";
+import type { TraversalOrder } from "./TraversalOrder";
+
+/**
+ * A traversal of the graph, from the start nodes up to a depth.
+ */
+export type Traversal = {
+  /**
+   * The nodes to start from, these are visited even if they do not match the filter
+   */
+  start_node_ids: Array<number>;
+  filter: TraversalFilter;
+  /**
+   * Number of edges to follow from the start nodes
+   */
+  max_depth: number;
+  /**
+   * The traversal stops when this many nodes are visited
+   */
+  max_nodes: number | null;
+  order: TraversalOrder;
+};
@@ -0,0 +1,17 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { EdgeLabel } from "./EdgeLabel";
+import type { NodeLabel } from "./NodeLabel";
+
+/**
+ * Which edges and nodes a traversal of the graph may follow.
+ */
+export type TraversalFilter = {
+  /**
+   * Edges with any of these labels are followed. All edges are followed if this is empty.
+   */
+  edge_labels: Array<EdgeLabel>;
+  /**
+   * Nodes with any of these labels are visited. All nodes are visited if this is empty.
+   */
+  node_labels: Array<NodeLabel>;
+};
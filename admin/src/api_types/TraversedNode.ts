@@ -0,0 +1,18 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { EdgeLabel } from "./EdgeLabel";
+
+/**
+ * A node visited in a traversal, with the node and the edge it was reached from.
+ */
+export type TraversedNode = {
+  node_id: number;
+  /**
+   * Number of edges from the start node
+   */
+  depth: number;
+  /**
+   * The node this node was reached from, none for the start nodes
+   */
+  parent_node_id: number | null;
+  edge_label: EdgeLabel | null;
+};
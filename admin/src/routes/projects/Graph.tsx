@@ -50,7 +50,7 @@ const getNodeUrl = (
   if (node.payload.type === "Link") {
     const relatedNodes = [];
     for (const edge of edges[node.id].edges) {
-      let [nId]: [number, string] = edge;
+      let [nId] = edge;
       if (nId in nodes) {
         relatedNodes.push(nodes[nId]);
       }
@@ -120,14 +120,37 @@ fn load_rustls_config(with_hostname: &WithHostname) -> PiResult<ServerConfig> {
         hello,
         projects::api::read_projects,
         projects::api::create_project,
+        projects::api::read_snapshots,
+        projects::api::create_snapshot,
+        projects::api::restore_snapshot,
+        projects::api::delete_snapshot,
         engine::api::get_labels,
         engine::api::get_nodes,
+        engine::api::get_node_versions,
+        engine::api::diff_node_versions,
+        engine::api::get_discovery_path,
         engine::api::get_edges,
         engine::api::create_node,
         engine::api::create_edge,
+        engine::api::delete_node,
+        engine::api::delete_edge,
         engine::api::search_results,
+        engine::api::query_graph,
+        engine::api::export_graph,
+        engine::api::import_nodes,
+        engine::api::search_text,
+        engine::api::semantic_search,
+        engine::api::ask,
+        engine::api::get_answer,
+        engine::api::traverse_graph,
+        engine::api::find_shortest_path,
+        engine::api::get_connected_components,
         engine::api::get_classifications,
         engine::api::get_entities,
+        engine::api::get_recrawl_settings,
+        engine::api::update_recrawl_settings,
+        engine::api::get_crawl_status_of_project,
+        engine::api::update_crawl_budget,
     ),
     tags(
         (name = "pixlie_ai", description = "Pixlie AI"),
@@ -1,7 +1,11 @@
 use log::{debug, error, info};
 use pixlie_ai::api::{send_api_error, APIChannel};
-use pixlie_ai::engine::Engine;
+use pixlie_ai::config::Settings;
+use pixlie_ai::engine::export::ExportFormat;
+use pixlie_ai::engine::import::ImportFormat;
+use pixlie_ai::engine::{Engine, RetryPolicy};
 use pixlie_ai::error::{PiError, PiResult};
+use pixlie_ai::projects::snapshots::Snapshot;
 use pixlie_ai::projects::Project;
 use pixlie_ai::utils::fetcher::fetcher_runtime;
 use pixlie_ai::{api::api_manager, config::check_cli_settings, FetchResponse, PiChannel, PiEvent};
@@ -9,6 +13,7 @@ use std::collections::HashMap;
 use std::env::var;
 use std::io::Write;
 use std::path::PathBuf;
+use std::str::FromStr;
 use std::sync::atomic::AtomicBool;
 use std::sync::{Arc, Mutex};
 use threadpool::ThreadPool;
@@ -21,6 +26,9 @@ fn load_project_to_engine(
     fetcher_tx: tokio::sync::mpsc::Sender<PiEvent>,
     pool: Arc<ThreadPool>,
 ) -> PiResult<()> {
+    if Snapshot::is_restoring(project_uuid)? {
+        return Err(PiError::ProjectRestoringError(project_uuid.to_string()));
+    }
     let mut channels_per_project = match channels_per_project.lock() {
         Ok(channels_per_project) => channels_per_project,
         Err(err) => {
@@ -57,6 +65,16 @@ fn load_project_to_engine(
             return Err(err);
         }
     };
+    if let Ok(Settings {
+        max_attempts_per_node: Some(max_attempts),
+        ..
+    }) = Settings::get_cli_settings()
+    {
+        engine.set_retry_policy(RetryPolicy {
+            max_attempts,
+            ..Default::default()
+        });
+    }
     let arced_engine = Arc::new(engine);
     {
         let arced_engine = arced_engine.clone();
@@ -74,6 +92,106 @@ fn load_project_to_engine(
     Ok(())
 }
 
+const USAGE: &str = "Usage:
+  cli                                             Run the API server
+  cli snapshot list <project_id>                  List the snapshots of a project
+  cli snapshot create <project_id> [label]        Take a snapshot of a project
+  cli snapshot restore <project_id> <snapshot_id> Restore a project to a snapshot
+  cli snapshot delete <project_id> <snapshot_id>  Delete a snapshot
+  cli export <project_id> <format> [path]         Export the graph of a project to a file,
+                                                  or to the output if there is no path.
+                                                  The format is one of graphml, gexf,
+                                                  jsonld, nodes_csv or edges_csv
+  cli import <project_id> <format> <path>         Import links, search terms, text nodes and
+                                                  edges from a file. The format is ndjson or csv";
+
+// Snapshots, exports and imports work from the command line while the API server is not running,
+// the API server keeps the DB of a loaded project open
+// The engine is not ticking, so no nodes are processed or crawled
+fn open_project_without_ticker(project_id: &str) -> PiResult<Engine> {
+    let (path_to_storage_dir, _) = Project::check_project_db(project_id)?;
+    let (fetcher_tx, _) = tokio::sync::mpsc::channel::<PiEvent>(1);
+    Engine::open(
+        project_id,
+        &path_to_storage_dir,
+        PiChannel::new(),
+        PiChannel::new().tx,
+        fetcher_tx,
+    )
+}
+
+fn run_command(args: &[String]) -> PiResult<()> {
+    let args: Vec<&str> = args.iter().map(|arg| arg.as_str()).collect();
+    match args.as_slice() {
+        ["snapshot", "list", project_id] => {
+            for snapshot in Snapshot::read_list_of_project(project_id)? {
+                println!(
+                    "{}  {}  {} bytes  {}",
+                    snapshot.uuid,
+                    snapshot.created_at.to_rfc3339(),
+                    snapshot.size_in_bytes,
+                    snapshot.label.unwrap_or_default()
+                );
+            }
+        }
+        ["snapshot", "create", project_id, label @ ..] => {
+            let label = match label.is_empty() {
+                true => None,
+                false => Some(label.join(" ")),
+            };
+            let snapshot = Snapshot::create(project_id, label)?;
+            println!("Created snapshot {}", snapshot.uuid);
+        }
+        ["snapshot", "restore", project_id, snapshot_id] => {
+            let snapshot = Snapshot::restore(project_id, snapshot_id)?;
+            println!(
+                "Restored project {} to snapshot {}",
+                project_id, snapshot.uuid
+            );
+        }
+        ["snapshot", "delete", project_id, snapshot_id] => {
+            let snapshot = Snapshot::delete(project_id, snapshot_id)?;
+            println!("Deleted snapshot {}", snapshot.uuid);
+        }
+        ["export", project_id, format, path @ ..] if path.len() <= 1 => {
+            let format = ExportFormat::from_str(format).map_err(|_| {
+                PiError::InternalError(format!("Unknown export format {}\n{}", format, USAGE))
+            })?;
+            let contents = open_project_without_ticker(project_id)?.export(&format)?;
+            match path.first() {
+                Some(path) => {
+                    std::fs::write(path, contents)?;
+                    println!("Exported project {} to {}", project_id, path);
+                }
+                None => print!("{}", contents),
+            }
+        }
+        ["import", project_id, format, path] => {
+            let format = ImportFormat::from_str(format).map_err(|_| {
+                PiError::InternalError(format!("Unknown import format {}\n{}", format, USAGE))
+            })?;
+            let contents = std::fs::read_to_string(path)?;
+            let results = open_project_without_ticker(project_id)?.import(&format, &contents)?;
+            for row in results.rows.iter() {
+                if let Some(error) = &row.error {
+                    println!("Line {}: {}", row.line, error);
+                }
+            }
+            println!(
+                "Imported {}: {} created, {} already existed, {} failed",
+                path, results.count_created, results.count_existing, results.count_failed
+            );
+        }
+        _ => {
+            return Err(PiError::InternalError(format!(
+                "Unknown command\n{}",
+                USAGE
+            )));
+        }
+    }
+    Ok(())
+}
+
 fn main() {
     env_logger::builder()
         .format(|buf, record| {
@@ -119,6 +237,17 @@ fn main() {
         }
     }
 
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if !args.is_empty() {
+        match run_command(&args) {
+            Ok(_) => {}
+            Err(err) => {
+                error!("{}", err);
+            }
+        }
+        return;
+    }
+
     let pool: Arc<ThreadPool> = Arc::new(
         threadpool::Builder::new()
             .thread_name("pixlie_ai_thread".to_string())
@@ -321,6 +450,19 @@ fn main() {
                     }
                 }
             }
+            PiEvent::UnloadEngine(project_id) => match channels_per_project.lock() {
+                Ok(mut channels_per_project) => {
+                    // The engine is loaded again with the next API request for the project
+                    if let Some(channel) = channels_per_project.remove(&project_id) {
+                        if let Err(err) = channel.tx.send(PiEvent::UnloadEngine(project_id)) {
+                            error!("Error sending PiEvent in Engine: {}", err);
+                        }
+                    }
+                }
+                Err(err) => {
+                    error!("Error locking channels_per_project: {}", err);
+                }
+            },
             PiEvent::EngineExit(project_id) => match channels_per_project.lock() {
                 Ok(mut channels_per_project) => {
                     error!("Engine exited for project {}", project_id);
@@ -344,14 +486,16 @@ fn main() {
                             project_id: response.project_id.clone(),
                             node_id: response.node_id,
                             url: response.url.clone(),
+                            status: response.status,
+                            headers: response.headers.clone(),
                             contents: response.contents.clone(),
                         })) {
                             error!("Error sending PiEvent in Engine: {}", err);
                         }
                     }
                     None => {
+                        // The project may have been unloaded while the request was open
                         error!("Project {} is not loaded", &response.project_id);
-                        break;
                     }
                 }
             }
@@ -374,6 +518,27 @@ fn main() {
                     }
                 }
             }
+            PiEvent::FetchThrottled(throttled) => {
+                let channels_per_project = match channels_per_project.lock() {
+                    Ok(channels_per_project) => channels_per_project,
+                    Err(err) => {
+                        error!("Error locking channels_per_project: {}", err);
+                        return;
+                    }
+                };
+                match channels_per_project.get(&throttled.project_id) {
+                    Some(channel) => {
+                        if let Err(err) =
+                            channel.tx.send(PiEvent::FetchThrottled(throttled.clone()))
+                        {
+                            error!("Error sending PiEvent in Engine: {}", err);
+                        }
+                    }
+                    None => {
+                        error!("Project {} is not loaded", &throttled.project_id);
+                    }
+                }
+            }
             PiEvent::Shutdown => {
                 break;
             }
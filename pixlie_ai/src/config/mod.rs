@@ -33,6 +33,9 @@ pub struct Settings {
     pub path_to_storage_dir: Option<String>,
     // When the hostname is set, we look for the `Certs/<hostname>/` directory in the storage directory
     pub hostname: Option<String>,
+    // How many times a node which had an error, like a link which could not be fetched,
+    // is attempted before giving up on it
+    pub max_attempts_per_node: Option<u32>,
 }
 
 pub struct WithHostname {
@@ -260,6 +263,9 @@ impl Settings {
         if updates.hostname.is_some() {
             self.hostname = updates.hostname.clone();
         }
+        if updates.max_attempts_per_node.is_some() {
+            self.max_attempts_per_node = updates.max_attempts_per_node;
+        }
     }
 
     pub fn write_to_config_file(&self) -> PiResult<()> {
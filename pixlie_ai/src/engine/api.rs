@@ -5,29 +5,45 @@
 //
 // https://github.com/pixlie/PixlieAI/blob/main/LICENSE
 
+use super::embeddings::SemanticSearchResult;
 use super::node::{ArcedNodeItem, NodeLabel};
-use super::{EdgeLabel, Engine, NodeFlags};
+use super::query::{Query, QueryResults};
+use super::ranking::{RankingWeights, SearchRanking};
+use super::text_index::TextSearchResult;
+use super::traversal::{Traversal, TraversalFilter, TraversalOrder, TraversedNode};
+use super::{EdgeLabel, EdgeProperties, EdgeSource, Engine, NodeEdges, NodeFlags};
+use crate::engine::export::ExportFormat;
+use crate::engine::import::{ImportFormat, ImportResults, ImportWrite};
 use crate::engine::node::{NodeId, NodeItem, Payload};
+use crate::engine::node_history::{APINodeVersion, NodeVersionDiff, NodesAsOfRead};
 use crate::entity::classifier::{Classification, ClassifierSettings};
 use crate::entity::content::TableRow;
+use crate::entity::crawler::budget::{CrawlBudget, CrawlStatus};
 use crate::entity::crawler::CrawlerSettings;
+use crate::entity::fetch_error::FetchErrorDetails;
 use crate::entity::named_entity::{EntityName, ExtractedEntity};
 use crate::entity::project_settings::ProjectSettings;
+use crate::entity::question::{Answer, Citation, Question, QuestionStatus};
 use crate::entity::search::saved_search::SavedSearch;
+use crate::entity::web::discovery::{DiscoveryPath, LinkDiscovery};
 use crate::entity::web::domain::{Domain, FindDomainOf};
 use crate::entity::web::link::Link;
+use crate::entity::web::recrawl::{ContentChange, RecrawlSettings};
 use crate::entity::web::web_metadata::WebMetadata;
 use crate::error::PiError;
+use crate::projects::snapshots::{Snapshot, SnapshotCreate};
 use crate::PiEvent;
 use crate::{api::ApiState, error::PiResult};
 use actix_web::http::StatusCode;
-use actix_web::{get, post, web, HttpResponse, HttpResponseBuilder};
+use actix_web::{delete, get, post, put, web, HttpResponse, HttpResponseBuilder};
+use chrono::DateTime;
 use itertools::Itertools;
 use log::debug;
 use serde::{Deserialize, Serialize};
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::str::FromStr;
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 use strum::Display;
 use ts_rs::TS;
 use utoipa::{IntoParams, ToSchema};
@@ -46,6 +62,11 @@ pub struct ProjectSettingsWrite {
     pub extract_data_only_from_specified_links: bool,
     pub crawl_within_domains_of_specified_links: bool,
     pub crawl_direct_links_from_specified_links: bool,
+    /// Limits on how much the project crawls, saved in a CrawlBudget node.
+    /// Set `max_depth` to crawl up to that many links from the specified links.
+    #[serde(default)]
+    #[ts(optional)]
+    pub crawl_budget: Option<CrawlBudget>,
 }
 
 #[derive(Clone, Deserialize, Display, ToSchema, TS)]
@@ -62,6 +83,76 @@ pub enum NodeWrite {
 pub struct EdgeWrite {
     node_ids: (NodeId, NodeId),
     edge_labels: (EdgeLabel, EdgeLabel),
+    /// Optional weight of the edge, used when creating an edge
+    #[ts(optional)]
+    weight: Option<f32>,
+    /// Optional confidence in the edge, from 0 to 1, used when creating an edge
+    #[ts(optional)]
+    confidence: Option<f32>,
+    /// Optional free-form attributes of the edge, used when creating an edge
+    #[ts(optional)]
+    attributes: Option<BTreeMap<String, String>>,
+}
+
+#[derive(Clone, Deserialize, ToSchema, TS)]
+#[ts(export)]
+pub struct ShortestPathWrite {
+    pub from_node_id: NodeId,
+    pub to_node_id: NodeId,
+    #[serde(default)]
+    pub filter: TraversalFilter,
+    /// Paths longer than this many edges are not searched
+    pub max_depth: usize,
+}
+
+// Number of content nodes given to the LLM to answer a question from
+const DEFAULT_MAX_SOURCES_FOR_ANSWER: usize = 8;
+// How long the ask API waits for the answer, after which the answer can be read separately
+const ANSWER_TIMEOUT: Duration = Duration::from_secs(60);
+const ANSWER_POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+// Number of full-text or semantic search results when the request does not give a limit
+const DEFAULT_TEXT_SEARCH_LIMIT: usize = 50;
+
+#[derive(Clone, Deserialize, ToSchema, TS)]
+#[ts(export)]
+pub struct TextSearchWrite {
+    /// Words and "quoted phrases" to search for, like `crawler "web page" -robots`
+    pub query: String,
+    #[ts(optional)]
+    pub limit: Option<usize>,
+    /// Ranks by text score if not given
+    #[ts(optional)]
+    pub ranking: Option<SearchRanking>,
+    /// Weights of the features for hybrid ranking, the default weights are used if not given
+    #[ts(optional)]
+    pub weights: Option<RankingWeights>,
+}
+
+#[derive(Clone, Deserialize, ToSchema, TS)]
+#[ts(export)]
+pub struct SemanticSearchWrite {
+    /// Text to find content close in meaning to, like a question or a sentence
+    pub query: String,
+    #[ts(optional)]
+    pub limit: Option<usize>,
+}
+
+#[derive(Clone, Deserialize, ToSchema, TS)]
+#[ts(export)]
+pub struct AskWrite {
+    /// A question about the content of the project
+    pub question: String,
+    /// Number of content nodes to answer from
+    #[ts(optional)]
+    pub max_sources: Option<usize>,
+}
+
+#[derive(Clone, Deserialize, ToSchema, TS)]
+#[ts(export)]
+pub struct QueryWrite {
+    /// The query, like `MATCH (d:DomainName)-[:OwnerOf]->(l:Link) RETURN d.text, l.path LIMIT 10`
+    pub query: String,
 }
 
 #[derive(Clone, Deserialize, Display, TS)]
@@ -75,27 +166,69 @@ pub enum EngineRequestPayload {
     GetNodesWithLabel(String),
     GetNodesWithIds(Vec<u32>),
     GetAllNodes(i64),
+    // Nodes with the payload they had at a given time, see `node_history`
+    GetNodesAsOf(NodesAsOfRead),
+    GetNodeVersions(u32),
+    DiffNodeVersions(u32, u32, u32), // Node id and the two versions to compare
+    GetDiscoveryPath(u32),           // Node id of a link or web page
     GetAllEdges(i64),
 
     CreateNode(NodeWrite),
     CreateEdge(EdgeWrite),
 
+    DeleteNode(u32, bool), // Node id and whether to delete nodes left without edges
+    DeleteEdge(EdgeWrite),
+
     // Some nodes allow a "query", which can generate any number of nodes, like a search
     Query(u32),
+    // A query in the graph query language, see `engine::query`
+    GraphQuery(QueryWrite),
+
+    Traverse(Traversal),
+    FindShortestPath(ShortestPathWrite),
+    GetConnectedComponents(TraversalFilter),
+    SearchText(TextSearchWrite),
+    SemanticSearch(SemanticSearchWrite),
+    Ask(AskWrite),
+    GetAnswer(NodeId),
+    // Snapshots of a project are managed from `projects::api`, the engine takes them
+    // since it keeps the DB of the project open
+    CreateSnapshot(SnapshotCreate),
+    Export(ExportFormat),
+    Import(ImportWrite),
+    // How often web pages are fetched again to track their changes, see `RecrawlSettings`
+    GetRecrawlSettings,
+    SetRecrawlSettings(RecrawlSettings),
+    // The crawl budget with what has been crawled and why the crawl stopped, if it did
+    GetCrawlStatus,
+    SetCrawlBudget(CrawlBudget),
 }
 
-/// A list of all outgoing edges of a node, with the ID of the node and the label of the edge.
+/// A list of all outgoing edges of a node, with the ID of the node, the label of the edge
+/// and the properties of the edge.
 /// The UNIX timestamp represents when a node's edge list was last written to.
 #[derive(Clone, Serialize, ToSchema, TS)]
 #[ts(export)]
 pub struct APINodeEdges {
-    // TODO: The edges should be Vec<(NodeId, EdgeLabel)>
+    // TODO: The edges should be Vec<(NodeId, EdgeLabel, EdgeProperties)>
     // Change this and handle chain-effects, if any
-    #[ts(type = "Array<[number, string]>")]
-    pub edges: Vec<(NodeId, String)>,
+    pub edges: Vec<(NodeId, String, EdgeProperties)>,
     pub written_at: i64,
 }
 
+impl APINodeEdges {
+    pub fn from_node_edges(node_edges: &NodeEdges) -> APINodeEdges {
+        APINodeEdges {
+            edges: node_edges
+                .edges
+                .iter()
+                .map(|x| (x.0, x.1.to_string(), x.2.clone()))
+                .collect(),
+            written_at: node_edges.written_at.timestamp_millis(),
+        }
+    }
+}
+
 /// A map of node IDs to their outgoing edges.
 #[derive(Clone, Serialize, ToSchema, TS)]
 #[ts(export)]
@@ -110,6 +243,99 @@ pub struct Explore {
     pub sibling_nodes: Vec<Vec<NodeId>>, // Nodes that are grouped together because they are siblings
 }
 
+/// Schema for response to the full-text search API request.
+#[derive(Clone, Serialize, ToSchema, TS)]
+#[ts(export)]
+pub struct APITextSearchResults {
+    /// Best matches first
+    pub results: Vec<TextSearchResult>,
+    /// The matching nodes and their web pages and links
+    pub nodes: Vec<APINodeItem>,
+}
+
+/// Schema for response to the semantic search API request.
+#[derive(Clone, Serialize, ToSchema, TS)]
+#[ts(export)]
+pub struct APISemanticSearchResults {
+    /// Closest matches first
+    pub results: Vec<SemanticSearchResult>,
+    /// The matching nodes and their web pages and links
+    pub nodes: Vec<APINodeItem>,
+}
+
+/// Schema for response to the ask and get answer API requests.
+#[derive(Clone, Serialize, ToSchema, TS)]
+#[ts(export)]
+pub struct APIQuestion {
+    pub question_node_id: NodeId,
+    pub status: QuestionStatus,
+    /// The content nodes which the question is answered from, most relevant first
+    pub sources: Vec<Citation>,
+    pub answer_node_id: Option<NodeId>,
+    pub answer: Option<Answer>,
+}
+
+impl APIQuestion {
+    fn from_question_node_id(
+        engine: Arc<&Engine>,
+        question_node_id: NodeId,
+    ) -> PiResult<APIQuestion> {
+        let (answer_node_id, answer) =
+            match Question::get_answer(&question_node_id, engine.clone())? {
+                Some((answer_node_id, answer)) => (Some(answer_node_id), Some(answer)),
+                None => (None, None),
+            };
+        Ok(APIQuestion {
+            question_node_id,
+            status: Question::get_status(&question_node_id, engine.clone())?,
+            sources: Question::get_citations(&question_node_id, engine)?,
+            answer_node_id,
+            answer,
+        })
+    }
+}
+
+/// Schema for response to the traversal and shortest path API requests.
+#[derive(Clone, Serialize, ToSchema, TS)]
+#[ts(export)]
+pub struct APITraversal {
+    /// The visited nodes in the order of the traversal, or of the path
+    pub traversed_nodes: Vec<TraversedNode>,
+    pub nodes: Vec<APINodeItem>,
+    /// The edges between the visited nodes
+    pub edges: APIEdges,
+}
+
+impl APITraversal {
+    fn from_traversed_nodes(
+        engine: Arc<&Engine>,
+        traversed_nodes: Vec<TraversedNode>,
+    ) -> PiResult<APITraversal> {
+        let node_ids: HashSet<NodeId> = traversed_nodes.iter().map(|x| x.node_id).collect();
+        let mut nodes: Vec<APINodeItem> = vec![];
+        let mut edges: HashMap<NodeId, APINodeEdges> = HashMap::new();
+        for traversed_node in traversed_nodes.iter() {
+            if let Some(node) = engine.get_node_without_content(&traversed_node.node_id) {
+                nodes.push(APINodeItem::from_node(&node));
+            }
+            if let Some(mut node_edges) = engine.get_connected_nodes(&traversed_node.node_id)? {
+                node_edges
+                    .edges
+                    .retain(|(other_node_id, _, _)| node_ids.contains(other_node_id));
+                edges.insert(
+                    traversed_node.node_id,
+                    APINodeEdges::from_node_edges(&node_edges),
+                );
+            }
+        }
+        Ok(APITraversal {
+            traversed_nodes,
+            nodes,
+            edges: APIEdges(edges),
+        })
+    }
+}
+
 #[derive(Clone, Serialize, TS, ToSchema)]
 pub struct EntityGroup {
     pub entity_name: String,
@@ -138,8 +364,18 @@ pub enum EngineResponsePayload {
     NodeCreatedSuccessfully(NodeId),
     /// Response for edge creation.
     EdgeCreatedSuccessfully,
+    /// Response for node deletion. Returns the IDs of all deleted nodes.
+    NodesDeletedSuccessfully(Vec<NodeId>),
+    /// Response for edge deletion.
+    EdgeDeletedSuccessfully,
     /// Response for a node query. Returns a list of nodes.
     Nodes(Vec<APINodeItem>),
+    /// Response for the versions of a node. Returns all versions, oldest first.
+    NodeVersions(Vec<APINodeVersion>),
+    /// Response for comparing two versions of a node.
+    NodeVersionDiff(NodeVersionDiff),
+    /// Response for how a link or web page was reached from a seed link.
+    DiscoveryPath(DiscoveryPath),
     /// Response for edge retrieval. Returns a list of edges.
     Edges(APIEdges),
     // TODO: The below should be Labels(Vec<NodeLabel>)
@@ -151,6 +387,31 @@ pub enum EngineResponsePayload {
     /// Response for classifications retrieval. Returns a list of classifications.
     Classifications(Vec<ClassifiedItem>),
     Explore(Explore),
+    /// Response for a graph query. Returns the columns and rows of the results.
+    QueryResults(QueryResults),
+    /// Response for a traversal. Returns the visited nodes and the edges between them.
+    Traversal(APITraversal),
+    /// Response for a shortest path. Returns the nodes on the path, if there is a path.
+    ShortestPath(Option<APITraversal>),
+    /// Response for connected components. Returns the node IDs of each component.
+    ConnectedComponents(Vec<Vec<NodeId>>),
+    /// Response for a full-text search. Returns ranked results with their nodes.
+    TextSearchResults(APITextSearchResults),
+    /// Response for a semantic search. Returns ranked results with their nodes.
+    SemanticSearchResults(APISemanticSearchResults),
+    /// Response for asking a question. Returns the question with its sources and its answer,
+    /// once the LLM has answered.
+    Answer(APIQuestion),
+    /// Response for taking a snapshot of the project.
+    Snapshot(Snapshot),
+    /// Response for exporting the graph. Returns the graph in the requested format.
+    Export(String),
+    /// Response for a bulk import. Returns the result of each row.
+    ImportResults(ImportResults),
+    /// Response for reading or saving the recrawl settings of the project.
+    RecrawlSettings(RecrawlSettings),
+    /// Response for reading the crawl status or saving the crawl budget of the project.
+    CrawlStatus(CrawlStatus),
     /// Error response.
     Error(String),
 }
@@ -187,6 +448,16 @@ pub enum APIPayload {
     NamedEntitiesToExtract(Vec<EntityName>),
     /// These are the extracted named entities from the content if the content is classified as relevant.
     ExtractedNamedEntities(Vec<ExtractedEntity>),
+    /// This stores why an external data request (like fetching a URL) of a node failed.
+    FetchError(FetchErrorDetails),
+    /// This stores an answer to a question about the project, with the sources it cites.
+    Answer(Answer),
+    /// These are the settings of how often web pages are fetched again to track their changes.
+    RecrawlSettings(RecrawlSettings),
+    /// This stores how the content of a web page changed when its link was fetched again.
+    ContentChange(ContentChange),
+    /// This stores the limits on how much a project crawls.
+    CrawlBudget(CrawlBudget),
 }
 
 #[derive(Clone, Default, Serialize, ToSchema, TS)]
@@ -198,6 +469,7 @@ pub enum APINodeFlags {
     IsRequesting,
     IsBlocked,
     HadError,
+    GaveUp,
 }
 
 impl APINodeFlags {
@@ -215,6 +487,9 @@ impl APINodeFlags {
         if flags.contains(NodeFlags::HAD_ERROR) {
             api_flags.push(APINodeFlags::HadError);
         }
+        if flags.contains(NodeFlags::GAVE_UP) {
+            api_flags.push(APINodeFlags::GaveUp);
+        }
         api_flags
     }
 }
@@ -271,6 +546,15 @@ impl APINodeItem {
             Payload::ExtractedNamedEntities(extracted_named_entities) => {
                 APIPayload::ExtractedNamedEntities(extracted_named_entities.clone())
             }
+            Payload::FetchError(fetch_error) => APIPayload::FetchError(fetch_error.clone()),
+            Payload::Answer(answer) => APIPayload::Answer(answer.clone()),
+            Payload::RecrawlSettings(recrawl_settings) => {
+                APIPayload::RecrawlSettings(recrawl_settings.clone())
+            }
+            Payload::ContentChange(content_change) => {
+                APIPayload::ContentChange(content_change.clone())
+            }
+            Payload::CrawlBudget(crawl_budget) => APIPayload::CrawlBudget(crawl_budget.clone()),
         };
         APINodeItem {
             id: arced_node.id,
@@ -295,6 +579,17 @@ pub struct QueryNodes {
     /// The timestamp (in milliseconds) to filter nodes by.
     /// Nodes written after this timestamp will be returned.
     since: Option<i64>,
+    /// The timestamp (in milliseconds) to read nodes as of.
+    /// If provided, nodes are returned with the payload they had at this time and since will be ignored.
+    as_of: Option<i64>,
+}
+
+#[derive(Deserialize, IntoParams)]
+pub struct QueryNodeVersionDiff {
+    /// The earlier version to compare, versions are numbered from 1.
+    from: u32,
+    /// The later version to compare.
+    to: u32,
 }
 
 #[derive(Deserialize, IntoParams)]
@@ -304,6 +599,26 @@ pub struct QueryEdges {
     since: Option<i64>,
 }
 
+#[derive(Deserialize, IntoParams)]
+pub struct QueryDeleteNode {
+    /// If `true`, nodes which are left without any edges are deleted as well.
+    /// If `false` or not provided, the node is only deleted when no other node is left without edges.
+    cascade: Option<bool>,
+}
+
+#[derive(Deserialize, IntoParams)]
+pub struct QueryExport {
+    /// The format to export the graph in: `graphml`, `gexf`, `jsonld`,
+    /// `nodes_csv` or `edges_csv`.
+    format: ExportFormat,
+}
+
+#[derive(Deserialize, IntoParams)]
+pub struct QueryImport {
+    /// The format of the imported file: `ndjson` or `csv`.
+    format: ImportFormat,
+}
+
 #[derive(Deserialize, IntoParams)]
 pub struct QueryClassifications {
     /// The optional `is_relevant` flag to filter classifications.
@@ -321,11 +636,12 @@ pub struct QueryEntities {
     entity_name: Option<String>,
 }
 
-async fn api_helper(
+// Sends a request to the engine of a project and waits for its response
+pub async fn send_engine_request(
     project_id: String,
     payload: EngineRequestPayload,
-    api_state: web::Data<ApiState>,
-) -> HttpResponse {
+    api_state: &web::Data<ApiState>,
+) -> PiResult<EngineResponsePayload> {
     let this_request_id = api_state.req_id.fetch_add(1);
     let this_project_id = project_id;
 
@@ -345,8 +661,9 @@ async fn api_helper(
     }) {
         Ok(_) => {}
         Err(_) => {
-            return HttpResponseBuilder::new(StatusCode::INTERNAL_SERVER_ERROR)
-                .json("Could not send API request to engine");
+            return Err(PiError::InternalError(
+                "Could not send API request to engine".to_string(),
+            ));
         }
     };
 
@@ -370,15 +687,24 @@ async fn api_helper(
     }
 
     debug!("Got response for request {}", this_request_id);
-    match response_opt {
-        Some(response) => {
+    response_opt.ok_or_else(|| PiError::InternalError("Could not get a response".to_string()))
+}
+
+async fn api_helper(
+    project_id: String,
+    payload: EngineRequestPayload,
+    api_state: web::Data<ApiState>,
+) -> HttpResponse {
+    match send_engine_request(project_id, payload, &api_state).await {
+        Ok(response) => {
             if matches!(response, EngineResponsePayload::Error(_)) {
                 return HttpResponseBuilder::new(StatusCode::INTERNAL_SERVER_ERROR).json(response);
             }
-            return HttpResponseBuilder::new(StatusCode::OK).json(response);
+            HttpResponseBuilder::new(StatusCode::OK).json(response)
+        }
+        Err(err) => {
+            HttpResponseBuilder::new(StatusCode::INTERNAL_SERVER_ERROR).json(err.to_string())
         }
-        None => HttpResponseBuilder::new(StatusCode::INTERNAL_SERVER_ERROR)
-            .json("Could not get a response"),
     }
 }
 
@@ -474,7 +800,27 @@ pub async fn get_nodes(
     params: web::Query<QueryNodes>,
     api_state: web::Data<ApiState>,
 ) -> HttpResponse {
-    let payload = if let Some(label) = &params.label {
+    let payload = if let Some(as_of) = params.as_of {
+        let ids = match &params.ids {
+            Some(ids) => match ids
+                .split(",")
+                .map(|id| id.parse::<u32>())
+                .collect::<Result<Vec<u32>, _>>()
+            {
+                Ok(ids) => Some(ids),
+                Err(err) => {
+                    return HttpResponseBuilder::new(StatusCode::BAD_REQUEST)
+                        .json(format!("Invalid node IDs: {}", err));
+                }
+            },
+            None => None,
+        };
+        EngineRequestPayload::GetNodesAsOf(NodesAsOfRead {
+            label: params.label.clone(),
+            ids,
+            as_of,
+        })
+    } else if let Some(label) = &params.label {
         EngineRequestPayload::GetNodesWithLabel(label.clone())
     } else if let Some(ids) = &params.ids {
         let u32_ids: Vec<u32> = ids
@@ -500,6 +846,133 @@ pub async fn get_nodes(
     api_helper(project_id.into_inner(), payload, api_state).await
 }
 
+/// Get all versions of a node, oldest first
+///
+/// A version is kept each time the payload of a node is updated. The last version is the current node.
+#[utoipa::path(
+    path = "/engine/{project_id}/nodes/{node_id}/versions",
+    responses(
+        (
+            status = 200,
+            description = "Versions retrieved successfully. Returns `EngineResponsePayload` of `type` `NodeVersions` or `Error`.",
+            body = EngineResponsePayload
+        ),
+        (status = 500, description = "Internal server error"),
+    ),
+    params(
+        (
+            "project_id" = uuid::Uuid,
+            description = "The ID of the project",
+            example = "123e4567-e89b-12d3-a456-426614174000"
+        ),
+        (
+            "node_id" = NodeId,
+            description = "The ID of the node",
+            example = 123
+        ),
+    ),
+    tag = "engine",
+)]
+#[get("/nodes/{node_id}/versions")]
+pub async fn get_node_versions(
+    path: web::Path<(String, u32)>,
+    api_state: web::Data<ApiState>,
+) -> HttpResponse {
+    let (project_id, node_id) = path.into_inner();
+    api_helper(
+        project_id,
+        EngineRequestPayload::GetNodeVersions(node_id),
+        api_state,
+    )
+    .await
+}
+
+/// Compare two versions of a node
+///
+/// Returns the labels which were added or removed and the fields of the payload which changed.
+/// For texts with more than one line, like web pages, the lines which changed are returned.
+#[utoipa::path(
+    path = "/engine/{project_id}/nodes/{node_id}/versions/diff",
+    responses(
+        (
+            status = 200,
+            description = "Versions compared successfully. Returns `EngineResponsePayload` of `type` `NodeVersionDiff` or `Error`.",
+            body = EngineResponsePayload
+        ),
+        (status = 500, description = "Internal server error"),
+    ),
+    params(
+        (
+            "project_id" = uuid::Uuid,
+            description = "The ID of the project",
+            example = "123e4567-e89b-12d3-a456-426614174000"
+        ),
+        (
+            "node_id" = NodeId,
+            description = "The ID of the node",
+            example = 123
+        ),
+        QueryNodeVersionDiff,
+    ),
+    tag = "engine",
+)]
+#[get("/nodes/{node_id}/versions/diff")]
+pub async fn diff_node_versions(
+    path: web::Path<(String, u32)>,
+    params: web::Query<QueryNodeVersionDiff>,
+    api_state: web::Data<ApiState>,
+) -> HttpResponse {
+    let (project_id, node_id) = path.into_inner();
+    api_helper(
+        project_id,
+        EngineRequestPayload::DiffNodeVersions(node_id, params.from, params.to),
+        api_state,
+    )
+    .await
+}
+
+/// Get how a link or web page was reached
+///
+/// Returns the links followed from a link added by the user or found by web search,
+/// with the depth of each link.
+#[utoipa::path(
+    path = "/engine/{project_id}/nodes/{node_id}/discovery_path",
+    responses(
+        (
+            status = 200,
+            description = "Discovery path retrieved successfully. Returns `EngineResponsePayload` of `type` `DiscoveryPath` or `Error`.",
+            body = EngineResponsePayload
+        ),
+        (status = 500, description = "Internal server error"),
+    ),
+    params(
+        (
+            "project_id" = uuid::Uuid,
+            description = "The ID of the project",
+            example = "123e4567-e89b-12d3-a456-426614174000"
+        ),
+        (
+            "node_id" = NodeId,
+            description = "The ID of a link or web page node",
+            example = 123
+        ),
+    ),
+    tag = "engine",
+)]
+#[get("/nodes/{node_id}/discovery_path")]
+pub async fn get_discovery_path(
+    path: web::Path<(String, u32)>,
+    api_state: web::Data<ApiState>,
+) -> HttpResponse {
+    let (project_id, node_id) = path.into_inner();
+    api_helper(
+        project_id,
+        EngineRequestPayload::GetDiscoveryPath(node_id),
+        api_state,
+    )
+    .await
+}
+
 /// Get all edges for a project
 #[utoipa::path(
     path = "/engine/{project_id}/edges",
@@ -611,6 +1084,83 @@ pub async fn create_edge(
     .await
 }
 
+/// Delete a node, along with its edges, from a project
+#[utoipa::path(
+    path = "/engine/{project_id}/nodes/{node_id}",
+    responses(
+        (
+            status = 200,
+            description = "Node deleted successfully. Returns `EngineResponsePayload` of `type` `NodesDeletedSuccessfully` or `Error`.",
+            body = EngineResponsePayload
+        ),
+        (status = 500, description = "Internal server error"),
+    ),
+    params(
+        (
+            "project_id" = uuid::Uuid,
+            description = "The ID of the project",
+            example = "123e4567-e89b-12d3-a456-426614174000"
+        ),
+        (
+            "node_id" = NodeId,
+            description = "The ID of the node to delete",
+            example = 123
+        ),
+        QueryDeleteNode,
+    ),
+    tag = "engine",
+)]
+#[delete("/nodes/{node_id}")]
+pub async fn delete_node(
+    path: web::Path<(String, u32)>,
+    params: web::Query<QueryDeleteNode>,
+    api_state: web::Data<ApiState>,
+) -> HttpResponse {
+    let (project_id, node_id) = path.into_inner();
+
+    api_helper(
+        project_id,
+        EngineRequestPayload::DeleteNode(node_id, params.cascade.unwrap_or(false)),
+        api_state,
+    )
+    .await
+}
+
+/// Delete an edge from a project
+#[utoipa::path(
+    path = "/engine/{project_id}/edges",
+    request_body = EdgeWrite,
+    responses(
+        (
+            status = 200,
+            description = "Edge deleted successfully. Returns `EngineResponsePayload` of `type` `EdgeDeletedSuccessfully` or `Error`.",
+            body = EngineResponsePayload
+        ),
+        (status = 500, description = "Internal server error"),
+    ),
+    params(
+        (
+            "project_id" = uuid::Uuid,
+            description = "The ID of the project",
+            example = "123e4567-e89b-12d3-a456-426614174000"
+        ),
+    ),
+    tag = "engine",
+)]
+#[delete("/edges")]
+pub async fn delete_edge(
+    project_id: web::Path<String>,
+    edge: web::Json<EdgeWrite>,
+    api_state: web::Data<ApiState>,
+) -> HttpResponse {
+    api_helper(
+        project_id.into_inner(),
+        EngineRequestPayload::DeleteEdge(edge.into_inner()),
+        api_state,
+    )
+    .await
+}
+
 /// [Incomplete] Get the results of a search for a node in a project
 ///
 /// This endpoint contains outdated implementation and may not work as expected.
@@ -648,13 +1198,437 @@ pub async fn search_results(
     api_helper(project_id, EngineRequestPayload::Query(node_id), api_state).await
 }
 
-/// Get all entities for a project
+/// Search the text of the content nodes of a project
+///
+/// Words are matched in any form, like crawling for crawler. Results are ranked with BM25.
+/// Supports "quoted phrases", OR between alternatives and NOT or a leading - to exclude words.
+/// With `Hybrid` ranking, the text score is combined with the classification of the web page,
+/// the distance from the objective and where the link came from, and each result explains its score.
 #[utoipa::path(
-    path = "/engine/{project_id}/entities",
+    path = "/engine/{project_id}/search",
+    request_body = TextSearchWrite,
     responses(
         (
             status = 200,
-            description = "Entities retrieved successfully. Returns `EngineResponsePayload` of `type` `Entities` or `Error`.",
+            description = "Search ran successfully. Returns `EngineResponsePayload` of `type` `TextSearchResults` or `Error`.",
+            body = EngineResponsePayload
+        ),
+        (status = 500, description = "Internal server error"),
+    ),
+    params(
+        (
+            "project_id" = uuid::Uuid,
+            description = "The ID of the project",
+            example = "123e4567-e89b-12d3-a456-426614174000"
+        ),
+    ),
+    tag = "engine",
+)]
+#[post("/search")]
+pub async fn search_text(
+    project_id: web::Path<String>,
+    text_search: web::Json<TextSearchWrite>,
+    api_state: web::Data<ApiState>,
+) -> HttpResponse {
+    api_helper(
+        project_id.into_inner(),
+        EngineRequestPayload::SearchText(text_search.into_inner()),
+        api_state,
+    )
+    .await
+}
+
+/// Search the content nodes of a project by meaning
+///
+/// The query is embedded with the local embedding model and compared to the embeddings of
+/// the content nodes. Returns `Error` if the embedding model is not installed.
+#[utoipa::path(
+    path = "/engine/{project_id}/semantic_search",
+    request_body = SemanticSearchWrite,
+    responses(
+        (
+            status = 200,
+            description = "Search ran successfully. Returns `EngineResponsePayload` of `type` `SemanticSearchResults` or `Error`.",
+            body = EngineResponsePayload
+        ),
+        (status = 500, description = "Internal server error"),
+    ),
+    params(
+        (
+            "project_id" = uuid::Uuid,
+            description = "The ID of the project",
+            example = "123e4567-e89b-12d3-a456-426614174000"
+        ),
+    ),
+    tag = "engine",
+)]
+#[post("/semantic_search")]
+pub async fn semantic_search(
+    project_id: web::Path<String>,
+    semantic_search: web::Json<SemanticSearchWrite>,
+    api_state: web::Data<ApiState>,
+) -> HttpResponse {
+    api_helper(
+        project_id.into_inner(),
+        EngineRequestPayload::SemanticSearch(semantic_search.into_inner()),
+        api_state,
+    )
+    .await
+}
+
+/// Ask a question about the content of a project
+///
+/// The content nodes most relevant to the question are given to the LLM, which answers
+/// the question with citations of the content. The question and the answer are saved as nodes.
+/// Waits for the answer for up to a minute, after that the `status` is `Pending`
+/// and the answer can be read with the `answer` endpoint.
+#[utoipa::path(
+    path = "/engine/{project_id}/ask",
+    request_body = AskWrite,
+    responses(
+        (
+            status = 200,
+            description = "Question asked successfully. Returns `EngineResponsePayload` of `type` `Answer` or `Error`.",
+            body = EngineResponsePayload
+        ),
+        (status = 500, description = "Internal server error"),
+    ),
+    params(
+        (
+            "project_id" = uuid::Uuid,
+            description = "The ID of the project",
+            example = "123e4567-e89b-12d3-a456-426614174000"
+        ),
+    ),
+    tag = "engine",
+)]
+#[post("/ask")]
+pub async fn ask(
+    project_id: web::Path<String>,
+    ask: web::Json<AskWrite>,
+    api_state: web::Data<ApiState>,
+) -> HttpResponse {
+    let project_id = project_id.into_inner();
+    let mut response = send_engine_request(
+        project_id.clone(),
+        EngineRequestPayload::Ask(ask.into_inner()),
+        &api_state,
+    )
+    .await;
+    let started_at = Instant::now();
+    while let Ok(EngineResponsePayload::Answer(question)) = &response {
+        if !matches!(question.status, QuestionStatus::Pending)
+            || started_at.elapsed() >= ANSWER_TIMEOUT
+        {
+            break;
+        }
+        actix_web::rt::time::sleep(ANSWER_POLL_INTERVAL).await;
+        response = send_engine_request(
+            project_id.clone(),
+            EngineRequestPayload::GetAnswer(question.question_node_id),
+            &api_state,
+        )
+        .await;
+    }
+    match response {
+        Ok(response) => {
+            if matches!(response, EngineResponsePayload::Error(_)) {
+                return HttpResponseBuilder::new(StatusCode::INTERNAL_SERVER_ERROR).json(response);
+            }
+            HttpResponseBuilder::new(StatusCode::OK).json(response)
+        }
+        Err(err) => {
+            HttpResponseBuilder::new(StatusCode::INTERNAL_SERVER_ERROR).json(err.to_string())
+        }
+    }
+}
+
+/// Get the answer to a question which was asked about a project
+#[utoipa::path(
+    path = "/engine/{project_id}/answer/{question_node_id}",
+    responses(
+        (
+            status = 200,
+            description = "Answer retrieved successfully. Returns `EngineResponsePayload` of `type` `Answer` or `Error`.",
+            body = EngineResponsePayload
+        ),
+        (status = 500, description = "Internal server error"),
+    ),
+    params(
+        (
+            "project_id" = uuid::Uuid,
+            description = "The ID of the project",
+            example = "123e4567-e89b-12d3-a456-426614174000"
+        ),
+        (
+            "question_node_id" = NodeId,
+            description = "The ID of the question node",
+            example = 123
+        ),
+    ),
+    tag = "engine",
+)]
+#[get("/answer/{question_node_id}")]
+pub async fn get_answer(
+    path: web::Path<(String, u32)>,
+    api_state: web::Data<ApiState>,
+) -> HttpResponse {
+    let (project_id, question_node_id) = path.into_inner();
+    api_helper(
+        project_id,
+        EngineRequestPayload::GetAnswer(question_node_id),
+        api_state,
+    )
+    .await
+}
+
+/// Run a graph query on a project
+///
+/// The query language is similar to Cypher, for example:
+/// `MATCH (d:DomainName)-[:OwnerOf]->(l:Link) WHERE l.path STARTS WITH "/blog" RETURN d.text, l.path LIMIT 10`
+#[utoipa::path(
+    path = "/engine/{project_id}/query",
+    request_body = QueryWrite,
+    responses(
+        (
+            status = 200,
+            description = "Query ran successfully. Returns `EngineResponsePayload` of `type` `QueryResults` or `Error`.",
+            body = EngineResponsePayload
+        ),
+        (status = 500, description = "Internal server error"),
+    ),
+    params(
+        (
+            "project_id" = uuid::Uuid,
+            description = "The ID of the project",
+            example = "123e4567-e89b-12d3-a456-426614174000"
+        ),
+    ),
+    tag = "engine",
+)]
+#[post("/query")]
+pub async fn query_graph(
+    project_id: web::Path<String>,
+    query: web::Json<QueryWrite>,
+    api_state: web::Data<ApiState>,
+) -> HttpResponse {
+    api_helper(
+        project_id.into_inner(),
+        EngineRequestPayload::GraphQuery(query.into_inner()),
+        api_state,
+    )
+    .await
+}
+
+/// Export the graph of a project
+///
+/// Node labels, edge labels and the fields of node payloads are exported as attributes.
+/// Large content, like the HTML of web pages, is not exported.
+#[utoipa::path(
+    path = "/engine/{project_id}/export",
+    responses(
+        (status = 200, description = "Graph exported successfully, as a file in the requested format"),
+        (status = 500, description = "Internal server error"),
+    ),
+    params(
+        (
+            "project_id" = uuid::Uuid,
+            description = "The ID of the project",
+            example = "123e4567-e89b-12d3-a456-426614174000"
+        ),
+        QueryExport,
+    ),
+    tag = "engine",
+)]
+#[get("/export")]
+pub async fn export_graph(
+    project_id: web::Path<String>,
+    params: web::Query<QueryExport>,
+    api_state: web::Data<ApiState>,
+) -> HttpResponse {
+    let project_id = project_id.into_inner();
+    let format = params.into_inner().format;
+    match send_engine_request(
+        project_id.clone(),
+        EngineRequestPayload::Export(format.clone()),
+        &api_state,
+    )
+    .await
+    {
+        Ok(EngineResponsePayload::Export(contents)) => HttpResponseBuilder::new(StatusCode::OK)
+            .content_type(format.get_content_type())
+            .insert_header((
+                "Content-Disposition",
+                format!(
+                    "attachment; filename=\"{}\"",
+                    format.get_file_name(&project_id)
+                ),
+            ))
+            .body(contents),
+        Ok(response) => HttpResponseBuilder::new(StatusCode::INTERNAL_SERVER_ERROR).json(response),
+        Err(err) => {
+            HttpResponseBuilder::new(StatusCode::INTERNAL_SERVER_ERROR).json(err.to_string())
+        }
+    }
+}
+
+/// Import links, search terms, text nodes and edges in bulk
+///
+/// The body is an NDJSON or CSV file of `ImportRow`. Nodes which are already in the graph
+/// are not added again. Each row is imported on its own, the result of each row is returned.
+#[utoipa::path(
+    path = "/engine/{project_id}/import",
+    request_body(content = String, description = "NDJSON or CSV file of `ImportRow`", content_type = "text/plain"),
+    responses(
+        (
+            status = 200,
+            description = "Import ran successfully. Returns `EngineResponsePayload` of `type` `ImportResults` or `Error`.",
+            body = EngineResponsePayload
+        ),
+        (status = 500, description = "Internal server error"),
+    ),
+    params(
+        (
+            "project_id" = uuid::Uuid,
+            description = "The ID of the project",
+            example = "123e4567-e89b-12d3-a456-426614174000"
+        ),
+        QueryImport,
+    ),
+    tag = "engine",
+)]
+#[post("/import")]
+pub async fn import_nodes(
+    project_id: web::Path<String>,
+    params: web::Query<QueryImport>,
+    contents: String,
+    api_state: web::Data<ApiState>,
+) -> HttpResponse {
+    api_helper(
+        project_id.into_inner(),
+        EngineRequestPayload::Import(ImportWrite {
+            format: params.into_inner().format,
+            contents,
+        }),
+        api_state,
+    )
+    .await
+}
+
+/// Traverse the graph of a project from the given nodes
+///
+/// Edges and nodes can be limited with labels, the traversal is limited by depth and number of nodes.
+#[utoipa::path(
+    path = "/engine/{project_id}/traverse",
+    request_body = Traversal,
+    responses(
+        (
+            status = 200,
+            description = "Traversal ran successfully. Returns `EngineResponsePayload` of `type` `Traversal` or `Error`.",
+            body = EngineResponsePayload
+        ),
+        (status = 500, description = "Internal server error"),
+    ),
+    params(
+        (
+            "project_id" = uuid::Uuid,
+            description = "The ID of the project",
+            example = "123e4567-e89b-12d3-a456-426614174000"
+        ),
+    ),
+    tag = "engine",
+)]
+#[post("/traverse")]
+pub async fn traverse_graph(
+    project_id: web::Path<String>,
+    traversal: web::Json<Traversal>,
+    api_state: web::Data<ApiState>,
+) -> HttpResponse {
+    api_helper(
+        project_id.into_inner(),
+        EngineRequestPayload::Traverse(traversal.into_inner()),
+        api_state,
+    )
+    .await
+}
+
+/// Find a shortest path between two nodes of a project
+#[utoipa::path(
+    path = "/engine/{project_id}/shortest_path",
+    request_body = ShortestPathWrite,
+    responses(
+        (
+            status = 200,
+            description = "Search for a path ran successfully. Returns `EngineResponsePayload` of `type` `ShortestPath` or `Error`.",
+            body = EngineResponsePayload
+        ),
+        (status = 500, description = "Internal server error"),
+    ),
+    params(
+        (
+            "project_id" = uuid::Uuid,
+            description = "The ID of the project",
+            example = "123e4567-e89b-12d3-a456-426614174000"
+        ),
+    ),
+    tag = "engine",
+)]
+#[post("/shortest_path")]
+pub async fn find_shortest_path(
+    project_id: web::Path<String>,
+    shortest_path: web::Json<ShortestPathWrite>,
+    api_state: web::Data<ApiState>,
+) -> HttpResponse {
+    api_helper(
+        project_id.into_inner(),
+        EngineRequestPayload::FindShortestPath(shortest_path.into_inner()),
+        api_state,
+    )
+    .await
+}
+
+/// Get the connected components of the graph of a project
+#[utoipa::path(
+    path = "/engine/{project_id}/components",
+    request_body = TraversalFilter,
+    responses(
+        (
+            status = 200,
+            description = "Components retrieved successfully. Returns `EngineResponsePayload` of `type` `ConnectedComponents` or `Error`.",
+            body = EngineResponsePayload
+        ),
+        (status = 500, description = "Internal server error"),
+    ),
+    params(
+        (
+            "project_id" = uuid::Uuid,
+            description = "The ID of the project",
+            example = "123e4567-e89b-12d3-a456-426614174000"
+        ),
+    ),
+    tag = "engine",
+)]
+#[post("/components")]
+pub async fn get_connected_components(
+    project_id: web::Path<String>,
+    filter: web::Json<TraversalFilter>,
+    api_state: web::Data<ApiState>,
+) -> HttpResponse {
+    api_helper(
+        project_id.into_inner(),
+        EngineRequestPayload::GetConnectedComponents(filter.into_inner()),
+        api_state,
+    )
+    .await
+}
+
+/// Get all entities for a project
+#[utoipa::path(
+    path = "/engine/{project_id}/entities",
+    responses(
+        (
+            status = 200,
+            description = "Entities retrieved successfully. Returns `EngineResponsePayload` of `type` `Entities` or `Error`.",
             body = EngineResponsePayload
         ),
         (status = 500, description = "Internal server error"),
@@ -827,21 +1801,218 @@ pub async fn get_classifications(
     }
 }
 
+/// Get the recrawl settings of a project
+///
+/// Web pages are fetched again once their interval has passed. When the content of a page
+/// has changed, it is processed again and the change is saved as a `ContentChange` node.
+#[utoipa::path(
+    path = "/engine/{project_id}/recrawl_settings",
+    responses(
+        (
+            status = 200,
+            description = "Recrawl settings retrieved successfully. Returns `EngineResponsePayload` of `type` `RecrawlSettings` or `Error`.",
+            body = EngineResponsePayload
+        ),
+        (status = 500, description = "Internal server error"),
+    ),
+    params(
+        (
+            "project_id" = uuid::Uuid,
+            description = "The ID of the project",
+            example = "123e4567-e89b-12d3-a456-426614174000"
+        ),
+    ),
+    tag = "engine",
+)]
+#[get("/recrawl_settings")]
+pub async fn get_recrawl_settings(
+    project_id: web::Path<String>,
+    api_state: web::Data<ApiState>,
+) -> HttpResponse {
+    api_helper(
+        project_id.into_inner(),
+        EngineRequestPayload::GetRecrawlSettings,
+        api_state,
+    )
+    .await
+}
+
+/// Save the recrawl settings of a project
+#[utoipa::path(
+    path = "/engine/{project_id}/recrawl_settings",
+    request_body = RecrawlSettings,
+    responses(
+        (
+            status = 200,
+            description = "Recrawl settings saved successfully. Returns `EngineResponsePayload` of `type` `RecrawlSettings` or `Error`.",
+            body = EngineResponsePayload
+        ),
+        (status = 500, description = "Internal server error"),
+    ),
+    params(
+        (
+            "project_id" = uuid::Uuid,
+            description = "The ID of the project",
+            example = "123e4567-e89b-12d3-a456-426614174000"
+        ),
+    ),
+    tag = "engine",
+)]
+#[put("/recrawl_settings")]
+pub async fn update_recrawl_settings(
+    project_id: web::Path<String>,
+    settings: web::Json<RecrawlSettings>,
+    api_state: web::Data<ApiState>,
+) -> HttpResponse {
+    api_helper(
+        project_id.into_inner(),
+        EngineRequestPayload::SetRecrawlSettings(settings.into_inner()),
+        api_state,
+    )
+    .await
+}
+
+/// Get the crawl status of a project
+///
+/// Returns the crawl budget with how many pages have been fetched, the LLM spend,
+/// and the reason the crawl stopped if it did.
+#[utoipa::path(
+    path = "/engine/{project_id}/crawl_status",
+    responses(
+        (
+            status = 200,
+            description = "Crawl status retrieved successfully. Returns `EngineResponsePayload` of `type` `CrawlStatus` or `Error`.",
+            body = EngineResponsePayload
+        ),
+        (status = 500, description = "Internal server error"),
+    ),
+    params(
+        (
+            "project_id" = uuid::Uuid,
+            description = "The ID of the project",
+            example = "123e4567-e89b-12d3-a456-426614174000"
+        ),
+    ),
+    tag = "engine",
+)]
+#[get("/crawl_status")]
+pub async fn get_crawl_status_of_project(
+    project_id: web::Path<String>,
+    api_state: web::Data<ApiState>,
+) -> HttpResponse {
+    api_helper(
+        project_id.into_inner(),
+        EngineRequestPayload::GetCrawlStatus,
+        api_state,
+    )
+    .await
+}
+
+/// Save the crawl budget of a project
+///
+/// A crawl which stopped on reaching its budget resumes when the budget is raised.
+#[utoipa::path(
+    path = "/engine/{project_id}/crawl_budget",
+    request_body = CrawlBudget,
+    responses(
+        (
+            status = 200,
+            description = "Crawl budget saved successfully. Returns `EngineResponsePayload` of `type` `CrawlStatus` or `Error`.",
+            body = EngineResponsePayload
+        ),
+        (status = 500, description = "Internal server error"),
+    ),
+    params(
+        (
+            "project_id" = uuid::Uuid,
+            description = "The ID of the project",
+            example = "123e4567-e89b-12d3-a456-426614174000"
+        ),
+    ),
+    tag = "engine",
+)]
+#[put("/crawl_budget")]
+pub async fn update_crawl_budget(
+    project_id: web::Path<String>,
+    budget: web::Json<CrawlBudget>,
+    api_state: web::Data<ApiState>,
+) -> HttpResponse {
+    api_helper(
+        project_id.into_inner(),
+        EngineRequestPayload::SetCrawlBudget(budget.into_inner()),
+        api_state,
+    )
+    .await
+}
+
+fn get_crawl_status(engine: Arc<&Engine>) -> PiResult<CrawlStatus> {
+    // Checking the budget first records a change in the stop reason
+    CrawlBudget::check(engine.clone())?;
+    Ok(CrawlStatus {
+        budget: CrawlBudget::find(engine.clone())
+            .map(|(_, budget)| budget)
+            .unwrap_or_default(),
+        state: engine.get_crawl_state()?,
+    })
+}
+
 pub fn configure_api_engine(app_config: &mut utoipa_actix_web::service_config::ServiceConfig) {
     app_config.service(
         utoipa_actix_web::scope::scope("/engine/{project_id}")
             .service(get_labels)
             .service(get_nodes)
+            .service(get_node_versions)
+            .service(diff_node_versions)
+            .service(get_discovery_path)
             .service(get_edges)
             .service(create_node)
             .service(create_edge)
+            .service(delete_node)
+            .service(delete_edge)
             .service(search_results)
+            .service(query_graph)
+            .service(export_graph)
+            .service(import_nodes)
+            .service(search_text)
+            .service(semantic_search)
+            .service(ask)
+            .service(get_answer)
+            .service(traverse_graph)
+            .service(find_shortest_path)
+            .service(get_connected_components)
             .service(explore)
             .service(get_entities)
-            .service(get_classifications),
+            .service(get_classifications)
+            .service(get_recrawl_settings)
+            .service(update_recrawl_settings)
+            .service(get_crawl_status_of_project)
+            .service(update_crawl_budget),
     );
 }
 
+// The nodes of search results with their web pages and links, each node once
+fn get_search_result_nodes(
+    engine: Arc<&Engine>,
+    results: impl Iterator<Item = (NodeId, Option<NodeId>, Option<NodeId>)>,
+) -> Vec<APINodeItem> {
+    let mut node_ids: Vec<NodeId> = vec![];
+    for (node_id, web_page_node_id, link_node_id) in results {
+        for node_id in [Some(node_id), web_page_node_id, link_node_id]
+            .into_iter()
+            .flatten()
+        {
+            if !node_ids.contains(&node_id) {
+                node_ids.push(node_id);
+            }
+        }
+    }
+    node_ids
+        .iter()
+        .filter_map(|node_id| engine.get_node_without_content(node_id))
+        .map(|node| APINodeItem::from_node(&node))
+        .collect()
+}
+
 pub fn handle_engine_api_request(
     project_id: String,
     request_id: u32,
@@ -854,11 +2025,11 @@ pub fn handle_engine_api_request(
             // The `Explore` request helps the UI show the graph in a way that makes it easy to visualize.
             // We start with nodes in a manner similar to how we process, and the UI can ask for further nodes.
             let starting_node = match optional_current_node_id {
-                Some(current_node_id) => {
-                    engine.get_node_by_id(&current_node_id).ok_or_else(|| {
+                Some(current_node_id) => engine
+                    .get_node_without_content(&current_node_id)
+                    .ok_or_else(|| {
                         PiError::InternalError("Cannot find given starting node".to_string())
-                    })?
-                }
+                    })?,
                 None => {
                     // When no starting node is given, we find the first objective node
                     let mut node_ids_with_label =
@@ -867,7 +2038,7 @@ pub fn handle_engine_api_request(
 
                     node_ids_with_label
                         .iter()
-                        .find_map(|node_id| match engine.get_node_by_id(node_id) {
+                        .find_map(|node_id| match engine.get_node_without_content(node_id) {
                             Some(arced_node) => Some(arced_node),
                             None => None,
                         })
@@ -882,7 +2053,7 @@ pub fn handle_engine_api_request(
             // With the starting node, we fetch nodes and edges.
             // We fetch 5 levels of nodes, and 4 levels of edges between them.
             let max_depth = 6;
-            let node_labels_of_interest = [
+            let node_labels_of_interest = vec![
                 NodeLabel::Objective,
                 NodeLabel::ProjectSettings,
                 NodeLabel::CrawlerSettings,
@@ -891,91 +2062,42 @@ pub fn handle_engine_api_request(
                 NodeLabel::DomainName,
                 NodeLabel::WebSearch,
             ];
-
-            let mut node_ids_to_check: Vec<Vec<NodeId>> = vec![vec![starting_node.id]]; // By depth
-            let mut node_ids: Vec<NodeId> = vec![starting_node.id];
-            let mut nodes: Vec<ArcedNodeItem> = vec![starting_node];
+            let traversed_nodes = engine.traverse(&Traversal {
+                start_node_ids: vec![starting_node.id],
+                filter: TraversalFilter {
+                    edge_labels: vec![],
+                    node_labels: node_labels_of_interest,
+                },
+                max_depth,
+                max_nodes: None,
+                order: TraversalOrder::BreadthFirst,
+            })?;
+
+            let mut nodes: Vec<ArcedNodeItem> = vec![];
             let mut edges: HashMap<NodeId, APINodeEdges> = HashMap::new();
-            let mut sibling_nodes: Vec<Vec<NodeId>> = vec![];
-
-            // Loop over nodes connected to the current node and add them to nodes and edges to explore.
-            for depth in 0..max_depth {
-                node_ids_to_check.push(vec![]);
-                for node_id in node_ids_to_check[depth].clone().iter() {
-                    let mut visited_sibling_nodes: HashMap<String, Vec<NodeId>> = HashMap::new();
-                    match engine.get_connected_nodes(node_id) {
-                        Ok(optional_edges) => {
-                            match optional_edges {
-                                Some(node_edges) => {
-                                    // Read and populate the connected nodes
-                                    for edge in node_edges.edges.iter() {
-                                        if node_ids.contains(&edge.0) {
-                                            continue;
-                                        }
-
-                                        match engine.get_node_by_id(&edge.0) {
-                                            Some(node) => {
-                                                // Are we interested in this node?
-                                                match node_labels_of_interest
-                                                    .iter()
-                                                    .find(|label| node.labels.contains(label))
-                                                {
-                                                    Some(_) => {
-                                                        node_ids.push(node.id);
-
-                                                        node_ids_to_check[depth + 1].push(node.id);
-
-                                                        // Save the node
-                                                        nodes.push(node.clone());
-
-                                                        // If this node is connected to the starting node using the same edge label
-                                                        // as an earlier node, then it is a sibling of the starting node.
-                                                        visited_sibling_nodes
-                                                            .entry(
-                                                                node.labels
-                                                                    .iter()
-                                                                    .sorted()
-                                                                    .join(","),
-                                                            )
-                                                            .and_modify(|siblings| {
-                                                                siblings.push(node.id);
-                                                            })
-                                                            .or_insert(vec![node.id]);
-                                                    }
-                                                    None => {}
-                                                }
-                                            }
-                                            None => {}
-                                        }
-                                    }
-
-                                    // For every group of siblings with length > 1, we insert into node_siblings
-                                    for (_edge_label, siblings) in visited_sibling_nodes.iter() {
-                                        if siblings.len() > 1 {
-                                            sibling_nodes.push(siblings.clone());
-                                        }
-                                    }
-
-                                    // Save all the edges
-                                    edges.insert(
-                                        node_id.clone(),
-                                        APINodeEdges {
-                                            edges: node_edges
-                                                .edges
-                                                .iter()
-                                                .map(|x| (x.0, x.1.to_string()))
-                                                .collect(),
-                                            written_at: node_edges.written_at.timestamp_millis(),
-                                        },
-                                    );
-                                }
-                                None => {}
-                            }
-                        }
-                        Err(_) => {}
+            // Nodes reached from the same node which have the same labels are siblings
+            let mut sibling_groups: BTreeMap<(NodeId, String), Vec<NodeId>> = BTreeMap::new();
+            for traversed_node in traversed_nodes.iter() {
+                let Some(node) = engine.get_node_without_content(&traversed_node.node_id) else {
+                    continue;
+                };
+                if let Some(parent_node_id) = traversed_node.parent_node_id {
+                    sibling_groups
+                        .entry((parent_node_id, node.labels.iter().sorted().join(",")))
+                        .or_default()
+                        .push(node.id);
+                }
+                if traversed_node.depth < max_depth {
+                    if let Some(node_edges) = engine.get_connected_nodes(&node.id)? {
+                        edges.insert(node.id, APINodeEdges::from_node_edges(&node_edges));
                     }
                 }
+                nodes.push(node);
             }
+            let sibling_nodes: Vec<Vec<NodeId>> = sibling_groups
+                .into_values()
+                .filter(|siblings| siblings.len() > 1)
+                .collect();
 
             EngineResponsePayload::Explore(Explore {
                 nodes: nodes.iter().map(|x| APINodeItem::from_node(x)).collect(),
@@ -993,7 +2115,7 @@ pub fn handle_engine_api_request(
             node_ids_with_label.sort();
             let nodes: Vec<APINodeItem> = node_ids_with_label
                 .iter()
-                .filter_map(|node_id| match engine.get_node_by_id(node_id) {
+                .filter_map(|node_id| match engine.get_node_without_content(node_id) {
                     Some(arced_node) => Some(APINodeItem::from_node(&arced_node)),
                     None => None,
                 })
@@ -1004,7 +2126,7 @@ pub fn handle_engine_api_request(
             node_ids.sort();
             let mut nodes: Vec<APINodeItem> = vec![];
             for node_id in node_ids {
-                if let Some(arced_node) = engine.get_node_by_id(&node_id) {
+                if let Some(arced_node) = engine.get_node_without_content(&node_id) {
                     nodes.push(APINodeItem::from_node(&arced_node));
                 }
             }
@@ -1029,6 +2151,41 @@ pub fn handle_engine_api_request(
 
             EngineResponsePayload::Nodes(nodes)
         }
+        EngineRequestPayload::GetNodesAsOf(nodes_as_of) => {
+            let mut node_ids: Vec<NodeId> = match (nodes_as_of.label, nodes_as_of.ids) {
+                (Some(label), _) => engine
+                    .get_node_ids_with_label(&NodeLabel::from_str(&label)?)
+                    .iter()
+                    .map(|node_id| **node_id)
+                    .collect(),
+                (None, Some(node_ids)) => node_ids,
+                (None, None) => engine.get_all_nodes().iter().map(|node| node.id).collect(),
+            };
+            node_ids.sort();
+            let as_of = DateTime::from_timestamp_millis(nodes_as_of.as_of).ok_or_else(|| {
+                PiError::GraphError(format!("Invalid timestamp {}", nodes_as_of.as_of))
+            })?;
+            let mut nodes: Vec<APINodeItem> = vec![];
+            for node_id in node_ids {
+                if let Some(arced_node) = engine.get_node_as_of(&node_id, &as_of)? {
+                    nodes.push(APINodeItem::from_node(&arced_node));
+                }
+            }
+            EngineResponsePayload::Nodes(nodes)
+        }
+        EngineRequestPayload::GetNodeVersions(node_id) => {
+            EngineResponsePayload::NodeVersions(engine.get_api_node_versions(&node_id)?)
+        }
+        EngineRequestPayload::GetDiscoveryPath(node_id) => {
+            EngineResponsePayload::DiscoveryPath(LinkDiscovery::get_path(engine.clone(), &node_id)?)
+        }
+        EngineRequestPayload::DiffNodeVersions(node_id, from_version, to_version) => {
+            EngineResponsePayload::NodeVersionDiff(engine.diff_node_versions(
+                &node_id,
+                from_version,
+                to_version,
+            )?)
+        }
         EngineRequestPayload::GetAllEdges(since) => {
             let edges: HashMap<NodeId, APINodeEdges> = engine
                 .get_all_edges()
@@ -1038,17 +2195,7 @@ pub fn handle_engine_api_request(
                     // Compare at the millisecond level, since browser date objects
                     // do not support sub-millisecond precision
                     if node_edges.written_at.timestamp_millis() > since {
-                        Some((
-                            **node_id,
-                            APINodeEdges {
-                                edges: node_edges
-                                    .edges
-                                    .iter()
-                                    .map(|x| (x.0, x.1.to_string()))
-                                    .collect(),
-                                written_at: node_edges.written_at.timestamp_millis(),
-                            },
-                        ))
+                        Some((**node_id, APINodeEdges::from_node_edges(node_edges)))
                     } else {
                         None
                     }
@@ -1082,36 +2229,59 @@ pub fn handle_engine_api_request(
                         None,
                     )?
                     .get_node_id(),
-                NodeWrite::ProjectSettings(project_settings_write) => engine
-                    .get_or_add_node(
-                        Payload::ProjectSettings(ProjectSettings {
-                            only_extract_data_from_specified_links: project_settings_write
-                                .extract_data_only_from_specified_links,
-                            only_crawl_within_domains_of_specified_links: project_settings_write
-                                .crawl_within_domains_of_specified_links,
-                            only_crawl_direct_links_from_specified_links: project_settings_write
-                                .crawl_direct_links_from_specified_links,
-                        }),
-                        vec![NodeLabel::AddedByUser, NodeLabel::ProjectSettings],
-                        true,
-                        None,
-                    )?
-                    .get_node_id(),
+                NodeWrite::ProjectSettings(project_settings_write) => {
+                    let node_id = engine
+                        .get_or_add_node(
+                            Payload::ProjectSettings(ProjectSettings {
+                                only_extract_data_from_specified_links: project_settings_write
+                                    .extract_data_only_from_specified_links,
+                                only_crawl_within_domains_of_specified_links:
+                                    project_settings_write.crawl_within_domains_of_specified_links,
+                                only_crawl_direct_links_from_specified_links:
+                                    project_settings_write.crawl_direct_links_from_specified_links,
+                            }),
+                            vec![NodeLabel::AddedByUser, NodeLabel::ProjectSettings],
+                            true,
+                            None,
+                        )?
+                        .get_node_id();
+                    // The budget is connected to the settings node, so it is saved after it
+                    if let Some(crawl_budget) = project_settings_write.crawl_budget {
+                        crawl_budget.save(engine.clone())?;
+                    }
+                    node_id
+                }
             };
             EngineResponsePayload::NodeCreatedSuccessfully(node_id)
         }
         EngineRequestPayload::CreateEdge(edge_write) => {
-            engine.add_connection(edge_write.node_ids, edge_write.edge_labels)?;
+            engine.add_connection_with_properties(
+                edge_write.node_ids,
+                edge_write.edge_labels,
+                EdgeProperties {
+                    weight: edge_write.weight,
+                    confidence: edge_write.confidence,
+                    attributes: edge_write.attributes.unwrap_or_default(),
+                    ..EdgeProperties::new(EdgeSource::User)
+                },
+            )?;
             EngineResponsePayload::EdgeCreatedSuccessfully
         }
-        EngineRequestPayload::Query(node_id) => match engine.get_node_by_id(&node_id) {
+        EngineRequestPayload::DeleteNode(node_id, cascade) => {
+            EngineResponsePayload::NodesDeletedSuccessfully(engine.delete_node(&node_id, cascade)?)
+        }
+        EngineRequestPayload::DeleteEdge(edge_write) => {
+            engine.remove_connection(edge_write.node_ids, edge_write.edge_labels)?;
+            EngineResponsePayload::EdgeDeletedSuccessfully
+        }
+        EngineRequestPayload::Query(node_id) => match engine.get_node_without_content(&node_id) {
             Some(node) => {
                 if node.labels.contains(&NodeLabel::SearchTerm) {
                     match &node.payload {
                         Payload::Text(_) => {
-                            let mut results: Vec<NodeItem> =
+                            // Results are ranked, best matches first
+                            let results: Vec<NodeItem> =
                                 SavedSearch::query(&node, engine.clone(), &node_id.into())?;
-                            results.sort_by(|a, b| a.id.cmp(&b.id));
 
                             EngineResponsePayload::Nodes(
                                 results
@@ -1134,22 +2304,121 @@ pub fn handle_engine_api_request(
             }
             None => EngineResponsePayload::Error(format!("Node {} not found", node_id)),
         },
+        EngineRequestPayload::GraphQuery(query_write) => EngineResponsePayload::QueryResults(
+            Query::parse(&query_write.query)?.execute(engine.clone())?,
+        ),
+        EngineRequestPayload::SearchText(text_search_write) => {
+            let limit = text_search_write.limit.unwrap_or(DEFAULT_TEXT_SEARCH_LIMIT);
+            let results = match text_search_write.ranking.unwrap_or_default() {
+                SearchRanking::Text => engine.search_text(&text_search_write.query, limit)?,
+                SearchRanking::Hybrid => engine.search_text_hybrid(
+                    &text_search_write.query,
+                    limit,
+                    &text_search_write.weights.unwrap_or_default(),
+                )?,
+            };
+            let nodes = get_search_result_nodes(
+                engine.clone(),
+                results
+                    .iter()
+                    .map(|result| (result.node_id, result.web_page_node_id, result.link_node_id)),
+            );
+            EngineResponsePayload::TextSearchResults(APITextSearchResults { results, nodes })
+        }
+        EngineRequestPayload::SemanticSearch(semantic_search_write) => {
+            let results = engine.semantic_search(
+                &semantic_search_write.query,
+                semantic_search_write
+                    .limit
+                    .unwrap_or(DEFAULT_TEXT_SEARCH_LIMIT),
+            )?;
+            let nodes = get_search_result_nodes(
+                engine.clone(),
+                results
+                    .iter()
+                    .map(|result| (result.node_id, result.web_page_node_id, result.link_node_id)),
+            );
+            EngineResponsePayload::SemanticSearchResults(APISemanticSearchResults {
+                results,
+                nodes,
+            })
+        }
+        EngineRequestPayload::Ask(ask_write) => {
+            let question_node_id = Question::add(
+                engine.clone(),
+                &ask_write.question,
+                ask_write
+                    .max_sources
+                    .unwrap_or(DEFAULT_MAX_SOURCES_FOR_ANSWER),
+            )?;
+            EngineResponsePayload::Answer(APIQuestion::from_question_node_id(
+                engine.clone(),
+                question_node_id,
+            )?)
+        }
+        EngineRequestPayload::GetAnswer(question_node_id) => EngineResponsePayload::Answer(
+            APIQuestion::from_question_node_id(engine.clone(), question_node_id)?,
+        ),
+        EngineRequestPayload::Import(import_write) => EngineResponsePayload::ImportResults(
+            engine.import(&import_write.format, &import_write.contents)?,
+        ),
+        EngineRequestPayload::GetRecrawlSettings => EngineResponsePayload::RecrawlSettings(
+            RecrawlSettings::find(engine.clone())
+                .map(|(_, settings)| settings)
+                .unwrap_or_default(),
+        ),
+        EngineRequestPayload::SetRecrawlSettings(settings) => {
+            settings.save(engine.clone())?;
+            EngineResponsePayload::RecrawlSettings(settings)
+        }
+        EngineRequestPayload::GetCrawlStatus => {
+            EngineResponsePayload::CrawlStatus(get_crawl_status(engine.clone())?)
+        }
+        EngineRequestPayload::SetCrawlBudget(budget) => {
+            budget.save(engine.clone())?;
+            EngineResponsePayload::CrawlStatus(get_crawl_status(engine.clone())?)
+        }
+        EngineRequestPayload::Export(format) => {
+            EngineResponsePayload::Export(engine.export(&format)?)
+        }
+        EngineRequestPayload::CreateSnapshot(snapshot_create) => {
+            EngineResponsePayload::Snapshot(engine.create_snapshot(snapshot_create.label)?)
+        }
+        EngineRequestPayload::Traverse(traversal) => EngineResponsePayload::Traversal(
+            APITraversal::from_traversed_nodes(engine.clone(), engine.traverse(&traversal)?)?,
+        ),
+        EngineRequestPayload::FindShortestPath(shortest_path_write) => {
+            match engine.find_shortest_path(
+                &shortest_path_write.from_node_id,
+                &shortest_path_write.to_node_id,
+                &shortest_path_write.filter,
+                shortest_path_write.max_depth,
+            )? {
+                Some(path) => EngineResponsePayload::ShortestPath(Some(
+                    APITraversal::from_traversed_nodes(engine.clone(), path)?,
+                )),
+                None => EngineResponsePayload::ShortestPath(None),
+            }
+        }
+        EngineRequestPayload::GetConnectedComponents(filter) => {
+            EngineResponsePayload::ConnectedComponents(engine.get_connected_components(&filter)?)
+        }
         EngineRequestPayload::GetEntities => {
             let mut grouped_entities = vec![];
             let mut web_page_node_ids = engine.get_node_ids_with_label(&NodeLabel::WebPage);
             web_page_node_ids.sort();
             for web_page_node_id in web_page_node_ids {
-                let Some(web_page_node) = engine.get_node_by_id(&web_page_node_id) else {
+                let Some(web_page_node) = engine.get_node_without_content(&web_page_node_id) else {
                     continue;
                 };
                 let extracted_entities: Option<Vec<ExtractedEntity>> = engine
                     .get_connected_nodes(&web_page_node.id)?
                     .and_then(|edges| {
-                        edges.edges.iter().find_map(|(id, label)| {
+                        edges.edges.iter().find_map(|(id, label, _)| {
                             if *label != EdgeLabel::Suggests {
                                 return None;
                             }
-                            let node = engine.get_node_by_id(id)?;
+                            let node = engine.get_node_without_content(id)?;
                             if node.labels.contains(&NodeLabel::ExtractedNamedEntities) {
                                 if let Payload::ExtractedNamedEntities(entities) = &node.payload {
                                     return Some(entities.clone());
@@ -1185,18 +2454,18 @@ pub fn handle_engine_api_request(
             let mut web_page_node_ids = engine.get_node_ids_with_label(&NodeLabel::WebPage);
             web_page_node_ids.sort();
             for web_page_node_id in web_page_node_ids {
-                let Some(web_page_node) = engine.get_node_by_id(&web_page_node_id) else {
+                let Some(web_page_node) = engine.get_node_without_content(&web_page_node_id) else {
                     continue;
                 };
                 let Some(full_url) =
                     engine
                         .get_connected_nodes(&web_page_node.id)?
                         .and_then(|edges| {
-                            edges.edges.iter().find_map(|(id, label)| {
+                            edges.edges.iter().find_map(|(id, label, _)| {
                                 if *label != EdgeLabel::ParentOf {
                                     return None;
                                 }
-                                let link_node = engine.get_node_by_id(id)?;
+                                let link_node = engine.get_node_without_content(id)?;
                                 if !link_node.labels.contains(&NodeLabel::Link) {
                                     return None;
                                 }
@@ -1227,11 +2496,11 @@ pub fn handle_engine_api_request(
                     engine
                         .get_connected_nodes(&web_page_node.id)?
                         .and_then(|edges| {
-                            edges.edges.iter().find_map(|(id, label)| {
+                            edges.edges.iter().find_map(|(id, label, _)| {
                                 if *label != EdgeLabel::Classifies {
                                     return None;
                                 }
-                                let node = engine.get_node_by_id(id)?;
+                                let node = engine.get_node_without_content(id)?;
                                 if node.labels.contains(&NodeLabel::Classification) {
                                     if let Payload::Classification(classification) = &node.payload {
                                         return Some(classification.clone());
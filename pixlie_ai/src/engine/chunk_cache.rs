@@ -0,0 +1,125 @@
+// Copyright 2025 Pixlie Web Solutions Pvt. Ltd.
+// Licensed under the GNU General Public License version 3.0;
+// You may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// https://github.com/pixlie/PixlieAI/blob/main/LICENSE
+
+use crate::engine::node::NodeId;
+use std::collections::HashMap;
+
+struct CachedChunk<T> {
+    items: HashMap<NodeId, T>,
+    // Changed chunks are not evicted till they are saved to disk
+    is_dirty: bool,
+    last_used_at: u64,
+}
+
+// Least recently used cache of chunks (of nodes or edges) which have been read from the DB.
+// Only chunks which are needed are read, so projects larger than memory can be opened
+pub(super) struct ChunkCache<T> {
+    chunks: HashMap<u32, CachedChunk<T>>,
+    capacity: usize,
+    use_counter: u64,
+}
+
+impl<T> ChunkCache<T> {
+    pub(super) fn new(capacity: usize) -> Self {
+        ChunkCache {
+            chunks: HashMap::new(),
+            capacity,
+            use_counter: 0,
+        }
+    }
+
+    pub(super) fn contains_chunk(&self, chunk_id: u32) -> bool {
+        self.chunks.contains_key(&chunk_id)
+    }
+
+    pub(super) fn get_chunk(&mut self, chunk_id: u32) -> Option<&HashMap<NodeId, T>> {
+        self.use_counter += 1;
+        let use_counter = self.use_counter;
+        self.chunks.get_mut(&chunk_id).map(|chunk| {
+            chunk.last_used_at = use_counter;
+            &chunk.items
+        })
+    }
+
+    // The chunk is marked as changed, it stays in the cache till `mark_saved` is called
+    pub(super) fn get_chunk_mut(&mut self, chunk_id: u32) -> Option<&mut HashMap<NodeId, T>> {
+        self.use_counter += 1;
+        let use_counter = self.use_counter;
+        self.chunks.get_mut(&chunk_id).map(|chunk| {
+            chunk.last_used_at = use_counter;
+            chunk.is_dirty = true;
+            &mut chunk.items
+        })
+    }
+
+    pub(super) fn insert_chunk(&mut self, chunk_id: u32, items: HashMap<NodeId, T>) {
+        self.use_counter += 1;
+        self.chunks.insert(
+            chunk_id,
+            CachedChunk {
+                items,
+                is_dirty: false,
+                last_used_at: self.use_counter,
+            },
+        );
+        self.evict();
+    }
+
+    pub(super) fn mark_saved(&mut self, chunk_id: u32) {
+        if let Some(chunk) = self.chunks.get_mut(&chunk_id) {
+            chunk.is_dirty = false;
+        }
+        self.evict();
+    }
+
+    fn evict(&mut self) {
+        while self.chunks.len() > self.capacity {
+            let least_recently_used = self
+                .chunks
+                .iter()
+                .filter(|(_, chunk)| !chunk.is_dirty)
+                .min_by_key(|(_, chunk)| chunk.last_used_at)
+                .map(|(chunk_id, _)| *chunk_id);
+            match least_recently_used {
+                Some(chunk_id) => {
+                    self.chunks.remove(&chunk_id);
+                }
+                None => break,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_least_recently_used_clean_chunk_is_evicted() {
+        let mut cache: ChunkCache<String> = ChunkCache::new(2);
+        cache.insert_chunk(0, HashMap::from([(1, "first".to_string())]));
+        cache.insert_chunk(1, HashMap::from([(101, "second".to_string())]));
+        // Chunk 0 is changed, so chunk 1 is evicted even though it was used later
+        cache
+            .get_chunk_mut(0)
+            .unwrap()
+            .insert(2, "changed".to_string());
+        cache.get_chunk(1);
+        cache.insert_chunk(2, HashMap::from([(201, "third".to_string())]));
+        assert!(cache.contains_chunk(0));
+        assert!(!cache.contains_chunk(1));
+        assert!(cache.contains_chunk(2));
+
+        // Once saved, chunk 0 can be evicted as well
+        cache.get_chunk(2);
+        cache.mark_saved(0);
+        cache.insert_chunk(3, HashMap::new());
+        assert!(!cache.contains_chunk(0));
+        assert!(cache.contains_chunk(2));
+        assert!(cache.contains_chunk(3));
+    }
+}
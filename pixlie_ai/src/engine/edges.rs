@@ -1,122 +1,281 @@
-use crate::engine::node::{ArcedNodeId, NodeId};
-use crate::engine::{get_chunk_id_and_node_ids, NodeEdges};
+use crate::engine::chunk_cache::ChunkCache;
+use crate::engine::migrations::{from_versioned_bytes, to_versioned_bytes};
+use crate::engine::node::NodeId;
+use crate::engine::{get_chunk_id_and_node_ids, EdgeLabel, EdgeProperties, NodeEdges};
 use crate::error::{PiError, PiResult};
+use chrono::Utc;
 use log::error;
-use postcard::{from_bytes, to_allocvec};
-use rocksdb::{Options, SliceTransform, DB};
+use rocksdb::{WriteBatch, DB};
 use std::collections::HashMap;
-use std::path::PathBuf;
-use std::sync::Arc;
+use std::sync::{Arc, Mutex, MutexGuard};
 
-const EDGES_CHUNK_PREFIX: &str = "edges/chunk/";
+pub(super) const EDGES_CHUNK_PREFIX: &str = "edges/chunk/";
 
+// Number of edge chunks (of the edges of 100 nodes each) which are kept in memory
+const EDGE_CHUNKS_IN_CACHE: usize = 1000;
+
+// Edges are read from the DB in chunks when they are needed, see `ChunkCache`
 pub(super) struct Edges {
-    pub(super) data: HashMap<ArcedNodeId, NodeEdges>,
+    chunks: Mutex<ChunkCache<NodeEdges>>,
 }
 
 impl Edges {
     pub(super) fn new() -> Edges {
         Edges {
-            data: HashMap::new(),
+            chunks: Mutex::new(ChunkCache::new(EDGE_CHUNKS_IN_CACHE)),
+        }
+    }
+
+    fn lock_chunks(&self) -> PiResult<MutexGuard<'_, ChunkCache<NodeEdges>>> {
+        self.chunks.lock().map_err(|err| {
+            error!("Error locking edge chunks: {}", err);
+            PiError::InternalError(format!("Error locking edge chunks: {}", err))
+        })
+    }
+
+    // Edges of all nodes in a chunk. Chunks which are not in the cache are read
+    // but not added to the cache, since this is used to go over all edges
+    pub(super) fn read_chunk(&self, db: &DB, chunk_id: u32) -> PiResult<Vec<(NodeId, NodeEdges)>> {
+        {
+            let mut chunks = self.lock_chunks()?;
+            if let Some(items) = chunks.get_chunk(chunk_id) {
+                return Ok(items
+                    .iter()
+                    .map(|(node_id, node_edges)| (*node_id, node_edges.clone()))
+                    .collect());
+            }
+        }
+        match db.get(format!("{}{}", EDGES_CHUNK_PREFIX, chunk_id))? {
+            Some(bytes) => Ok(from_versioned_bytes(&bytes)?),
+            None => Ok(vec![]),
+        }
+    }
+
+    fn read_chunk_into_cache(
+        db: &DB,
+        chunks: &mut ChunkCache<NodeEdges>,
+        chunk_id: u32,
+    ) -> PiResult<()> {
+        if !chunks.contains_chunk(chunk_id) {
+            let items: HashMap<NodeId, NodeEdges> =
+                match db.get(format!("{}{}", EDGES_CHUNK_PREFIX, chunk_id))? {
+                    Some(bytes) => {
+                        let data: Vec<(NodeId, NodeEdges)> = from_versioned_bytes(&bytes)?;
+                        data.into_iter().collect()
+                    }
+                    None => HashMap::new(),
+                };
+            chunks.insert_chunk(chunk_id, items);
+        }
+        Ok(())
+    }
+
+    fn get_chunk_mut(
+        &mut self,
+        db: &DB,
+        node_id: &NodeId,
+    ) -> PiResult<&mut HashMap<NodeId, NodeEdges>> {
+        let chunk_id = get_chunk_id_and_node_ids(node_id).0;
+        let chunks = self
+            .chunks
+            .get_mut()
+            .map_err(|err| PiError::InternalError(format!("Error locking edge chunks: {}", err)))?;
+        Self::read_chunk_into_cache(db, chunks, chunk_id)?;
+        chunks.get_chunk_mut(chunk_id).ok_or_else(|| {
+            PiError::InternalError(format!("Cannot find chunk {} of edges", chunk_id))
+        })
+    }
+
+    pub(super) fn get_node_edges(&self, db: &DB, node_id: &NodeId) -> PiResult<Option<NodeEdges>> {
+        self.get_node_edges_ref(db, node_id, |node_edges| node_edges.clone())
+    }
+
+    // Like `get_node_edges`, but reads the edges of the node in place instead of cloning them
+    fn get_node_edges_ref<T>(
+        &self,
+        db: &DB,
+        node_id: &NodeId,
+        read: impl FnOnce(&NodeEdges) -> T,
+    ) -> PiResult<Option<T>> {
+        let chunk_id = get_chunk_id_and_node_ids(node_id).0;
+        let mut chunks = self.lock_chunks()?;
+        Self::read_chunk_into_cache(db, &mut chunks, chunk_id)?;
+        Ok(chunks
+            .get_chunk(chunk_id)
+            .and_then(|items| items.get(node_id).map(read)))
+    }
+
+    // Adds an edge unless the node already has an edge with this label to the other node.
+    // Returns true if the edge was added
+    pub(super) fn add_edge(
+        &mut self,
+        db: &DB,
+        node_id: &NodeId,
+        other_node_id: &NodeId,
+        edge_label: EdgeLabel,
+        edge_properties: EdgeProperties,
+    ) -> PiResult<bool> {
+        // Check before getting the chunk to change, so an existing edge does not mark it as changed
+        if self
+            .get_node_edges_ref(db, node_id, |node_edges| {
+                node_edges.has_edge(other_node_id, &edge_label)
+            })?
+            .unwrap_or(false)
+        {
+            return Ok(false);
+        }
+        let node_edges = self
+            .get_chunk_mut(db, node_id)?
+            .entry(*node_id)
+            .or_insert(NodeEdges {
+                edges: vec![],
+                written_at: Utc::now(),
+            });
+        node_edges
+            .edges
+            .push((*other_node_id, edge_label, edge_properties));
+        node_edges.written_at = Utc::now();
+        Ok(true)
+    }
+
+    // Removes the edges from a node to another node, only those with the given label if one is given.
+    // Returns true if any edge was removed
+    pub(super) fn remove_edges(
+        &mut self,
+        db: &DB,
+        node_id: &NodeId,
+        other_node_id: &NodeId,
+        edge_label: Option<&EdgeLabel>,
+    ) -> PiResult<bool> {
+        match self.get_chunk_mut(db, node_id)?.get_mut(node_id) {
+            Some(node_edges) => {
+                let count_edges = node_edges.edges.len();
+                node_edges.edges.retain(|(x_node_id, x_edge_label, _)| {
+                    x_node_id != other_node_id
+                        || edge_label.is_some_and(|edge_label| edge_label != x_edge_label)
+                });
+                if node_edges.edges.len() == count_edges {
+                    return Ok(false);
+                }
+                node_edges.written_at = Utc::now();
+                Ok(true)
+            }
+            None => Ok(false),
         }
     }
 
+    // Removes all edges of a node, the edges of connected nodes to this node are not changed
+    pub(super) fn remove_node_edges(
+        &mut self,
+        db: &DB,
+        node_id: &NodeId,
+    ) -> PiResult<Option<NodeEdges>> {
+        Ok(self.get_chunk_mut(db, node_id)?.remove(node_id))
+    }
+
     pub(super) fn save_item_chunk_to_disk(&self, db: Arc<DB>, node_id: &NodeId) -> PiResult<()> {
+        let mut batch = WriteBatch::default();
+        self.write_chunk_to_batch(&mut batch, node_id)?;
+        db.write(batch)?;
+        self.mark_chunk_saved(node_id)
+    }
+
+    pub(super) fn write_chunk_to_batch(
+        &self,
+        batch: &mut WriteBatch,
+        node_id: &NodeId,
+    ) -> PiResult<()> {
         // We store this (and all other edges in its chunk) edge to DB
         // in the chunk corresponding to the node ID divided by 100
         // Create a chunk of data from the start node ID to the end node ID of this chunk
         let (chunk_id, node_ids) = get_chunk_id_and_node_ids(node_id);
+        let mut chunks = self.lock_chunks()?;
+        // A chunk which is not in the cache has not changed since it was read
+        let Some(items) = chunks.get_chunk(chunk_id) else {
+            return Ok(());
+        };
         let chunk: Vec<(NodeId, NodeEdges)> = node_ids
             .iter()
-            .filter_map(|x_node_id| match self.data.get(x_node_id) {
-                Some(node_edges) => Some((*x_node_id, node_edges.clone())),
-                None => None,
+            .filter_map(|x_node_id| {
+                items
+                    .get(x_node_id)
+                    .map(|node_edges| (*x_node_id, node_edges.clone()))
             })
             .collect();
-        db.put(
-            format!("{}{}", EDGES_CHUNK_PREFIX, chunk_id),
-            to_allocvec(&chunk)?,
-        )?;
+        if chunk.is_empty() {
+            batch.delete(format!("{}{}", EDGES_CHUNK_PREFIX, chunk_id));
+        } else {
+            batch.put(
+                format!("{}{}", EDGES_CHUNK_PREFIX, chunk_id),
+                to_versioned_bytes(&chunk)?,
+            );
+        }
         Ok(())
     }
 
-    pub(super) fn open(db_path: &PathBuf) -> PiResult<Self> {
-        let prefix_extractor = SliceTransform::create_fixed_prefix(EDGES_CHUNK_PREFIX.len());
-        let mut edges = Edges::new();
-        let mut opts = Options::default();
-        opts.create_if_missing(false);
-        opts.set_prefix_extractor(prefix_extractor);
-        let db = match DB::open(&opts, db_path) {
-            Ok(db) => db,
-            Err(err) => {
-                return Err(PiError::RocksdbError(err));
-            }
-        };
-        for chunk in db.prefix_iterator(EDGES_CHUNK_PREFIX) {
-            match chunk {
-                Ok(chunk) => {
-                    let data: Vec<(NodeId, NodeEdges)> = from_bytes(&chunk.1)?;
-                    for (node_id, edge) in data {
-                        edges.data.insert(Arc::new(node_id), edge);
-                    }
-                }
-                Err(err) => {
-                    error!("Error reading chunk from DB: {}", err);
-                    return Err(PiError::RocksdbError(err));
-                }
-            }
-        }
-        Ok(edges)
+    // Called once the batch with the chunk has been written, so the chunk can be evicted
+    pub(super) fn mark_chunk_saved(&self, node_id: &NodeId) -> PiResult<()> {
+        self.lock_chunks()?
+            .mark_saved(get_chunk_id_and_node_ids(node_id).0);
+        Ok(())
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::engine::EdgeLabel;
-    use chrono::Utc;
+    use crate::engine::EdgeSource;
     use rocksdb::DB;
+    use std::collections::BTreeMap;
+    use std::path::PathBuf;
 
     #[test]
     fn test_save_to_disk_and_load_from_disk() {
         let mut node_id: NodeId = 0;
-        let mut edges: Vec<(NodeId, NodeEdges)> = vec![];
+        let mut edges: Vec<(NodeId, NodeId, EdgeLabel, EdgeProperties)> = vec![];
         edges.push((
             node_id,
-            NodeEdges {
-                edges: vec![(node_id + 1, EdgeLabel::ParentOf)],
-                written_at: Utc::now(),
-            },
+            node_id + 1,
+            EdgeLabel::ParentOf,
+            EdgeProperties::default(),
         ));
         node_id += 1;
         edges.push((
             node_id,
-            NodeEdges {
-                edges: vec![(node_id + 1, EdgeLabel::ParentOf)],
-                written_at: Utc::now(),
-            },
+            node_id + 1,
+            EdgeLabel::ParentOf,
+            EdgeProperties::default(),
         ));
         node_id += 1;
         edges.push((
             node_id,
-            NodeEdges {
-                edges: vec![
-                    (node_id + 1, EdgeLabel::ParentOf),
-                    (node_id + 2, EdgeLabel::ChildOf),
-                ],
-                written_at: Utc::now(),
+            node_id + 1,
+            EdgeLabel::ParentOf,
+            EdgeProperties::default(),
+        ));
+        edges.push((
+            node_id,
+            node_id + 2,
+            EdgeLabel::ChildOf,
+            EdgeProperties {
+                weight: Some(0.5),
+                confidence: Some(0.9),
+                attributes: BTreeMap::from([("model".to_string(), "gliner".to_string())]),
+                ..EdgeProperties::new(EdgeSource::Gliner)
             },
         ));
         node_id += 1;
         edges.push((
             node_id,
-            NodeEdges {
-                edges: vec![
-                    (node_id + 1, EdgeLabel::ParentOf),
-                    (node_id + 2, EdgeLabel::ChildOf),
-                ],
-                written_at: Utc::now(),
-            },
+            node_id + 1,
+            EdgeLabel::ParentOf,
+            EdgeProperties::new(EdgeSource::Scraper),
+        ));
+        edges.push((
+            node_id,
+            node_id + 2,
+            EdgeLabel::ChildOf,
+            EdgeProperties::new(EdgeSource::User),
         ));
 
         let temp_dir = tempfile::Builder::new()
@@ -125,36 +284,51 @@ mod tests {
             .expect("Failed to create temporary path for the _path_for_rocksdb_storage2.");
         let db_path = PathBuf::from(temp_dir.path());
 
+        let mut saved_edges: Vec<(NodeId, NodeEdges)> = vec![];
         {
             let db = DB::open_default(db_path.clone()).unwrap();
             let arced_db = Arc::new(db);
             let mut db_edges: Edges = Edges::new();
             // Insert all edges into the DB
-            for (node_id, node_edges) in edges.iter() {
+            for (node_id, other_node_id, edge_label, edge_properties) in edges.iter() {
                 db_edges
-                    .data
-                    .insert(Arc::new(node_id.clone()), node_edges.clone());
+                    .add_edge(
+                        &arced_db,
+                        node_id,
+                        other_node_id,
+                        edge_label.clone(),
+                        edge_properties.clone(),
+                    )
+                    .unwrap();
             }
 
             // Save the edges to disk
             db_edges
                 .save_item_chunk_to_disk(arced_db.clone(), &node_id)
                 .unwrap();
+            for x_node_id in 0..=node_id {
+                saved_edges.push((
+                    x_node_id,
+                    db_edges
+                        .get_node_edges(&arced_db, &x_node_id)
+                        .unwrap()
+                        .unwrap(),
+                ));
+            }
         }
 
         {
             // Load data from disk and check that it is the same as the original
-            let db_edges = Edges::open(&db_path).unwrap();
+            let db = DB::open_default(db_path.clone()).unwrap();
+            let db_edges = Edges::new();
 
-            for (node_id, node_edges) in edges.iter() {
+            for (node_id, node_edges) in saved_edges.iter() {
                 // Check the ID and payload of each node against the one in the DB
-                let db_edges = db_edges.data.get(node_id).unwrap();
+                let db_edges = db_edges.get_node_edges(&db, node_id).unwrap().unwrap();
                 assert_eq!(node_edges.edges.len(), db_edges.edges.len());
                 assert_eq!(node_edges.written_at, db_edges.written_at);
-                for (db_node_id, db_edge_label) in db_edges.edges.iter() {
-                    assert!(node_edges
-                        .edges
-                        .contains(&(db_node_id.clone(), db_edge_label.clone())));
+                for db_edge in db_edges.edges.iter() {
+                    assert!(node_edges.edges.contains(db_edge));
                 }
             }
         }
@@ -0,0 +1,348 @@
+// Copyright 2025 Pixlie Web Solutions Pvt. Ltd.
+// Licensed under the GNU General Public License version 3.0;
+// You may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// https://github.com/pixlie/PixlieAI/blob/main/LICENSE
+
+use crate::engine::get_chunk_id_and_node_ids;
+use crate::engine::migrations::{from_versioned_bytes, to_versioned_bytes};
+use crate::engine::node::NodeId;
+use crate::error::{PiError, PiResult};
+use log::error;
+use rocksdb::{WriteBatch, DB};
+use serde::Serialize;
+use std::collections::{BTreeSet, HashMap, HashSet};
+use ts_rs::TS;
+use utoipa::ToSchema;
+
+// Embeddings of the content nodes, in chunks like the nodes.
+// The prefix is at least as long as the fixed prefix of the prefix extractor, see `Nodes::open`
+pub(super) const EMBEDDINGS_PREFIX: &str = "embeddings/chunk/";
+
+// The approximate nearest neighbor index hashes each vector with random hyperplanes,
+// vectors which are close fall in the same bucket in at least one of the tables
+const HASH_TABLE_COUNT: usize = 8;
+const HASH_BIT_COUNT: usize = 10;
+// Hyperplanes are generated from a fixed seed, so the index is the same every time it is built
+const HYPERPLANE_SEED: u64 = 0x9e37_79b9_7f4a_7c15;
+
+/// A content node which is close in meaning to a semantic search, with the web page
+/// and link it is from.
+#[derive(Clone, Serialize, ToSchema, TS)]
+#[ts(export)]
+pub struct SemanticSearchResult {
+    pub node_id: NodeId,
+    /// Cosine similarity of the node to the query, higher is better
+    pub score: f32,
+    pub web_page_node_id: Option<NodeId>,
+    pub link_node_id: Option<NodeId>,
+}
+
+fn cosine_similarity(a: &[f32], b: &[f32]) -> f32 {
+    let dot: f32 = a.iter().zip(b.iter()).map(|(x, y)| x * y).sum();
+    let norm_a: f32 = a.iter().map(|x| x * x).sum::<f32>().sqrt();
+    let norm_b: f32 = b.iter().map(|x| x * x).sum::<f32>().sqrt();
+    if norm_a == 0.0 || norm_b == 0.0 {
+        0.0
+    } else {
+        dot / (norm_a * norm_b)
+    }
+}
+
+// Random-hyperplane locality sensitive hashing
+struct VectorIndex {
+    hyperplanes: Vec<Vec<Vec<f32>>>, // Hyperplanes of each table, one for each bit of the hash
+    buckets: Vec<HashMap<u32, Vec<NodeId>>>, // Node IDs by hash, for each table
+}
+
+impl VectorIndex {
+    fn new(dimension: usize) -> Self {
+        // xorshift, we only need the hyperplanes to be spread out, not to be unpredictable
+        let mut state = HYPERPLANE_SEED;
+        let mut next_random = || {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            (state >> 40) as f32 / (1u64 << 23) as f32 - 1.0
+        };
+        let hyperplanes = (0..HASH_TABLE_COUNT)
+            .map(|_| {
+                (0..HASH_BIT_COUNT)
+                    .map(|_| (0..dimension).map(|_| next_random()).collect())
+                    .collect()
+            })
+            .collect();
+        VectorIndex {
+            hyperplanes,
+            buckets: vec![HashMap::new(); HASH_TABLE_COUNT],
+        }
+    }
+
+    // One hash for each table, each bit is the side of a hyperplane which the vector is on
+    fn get_hashes(&self, vector: &[f32]) -> Vec<u32> {
+        self.hyperplanes
+            .iter()
+            .map(|table_hyperplanes| {
+                table_hyperplanes
+                    .iter()
+                    .enumerate()
+                    .fold(0, |hash, (bit, hyperplane)| {
+                        let dot: f32 = hyperplane.iter().zip(vector).map(|(x, y)| x * y).sum();
+                        if dot >= 0.0 {
+                            hash | (1 << bit)
+                        } else {
+                            hash
+                        }
+                    })
+            })
+            .collect()
+    }
+
+    fn insert(&mut self, node_id: NodeId, vector: &[f32]) {
+        for (table, hash) in self.get_hashes(vector).into_iter().enumerate() {
+            self.buckets[table].entry(hash).or_default().push(node_id);
+        }
+    }
+
+    fn remove(&mut self, node_id: &NodeId, vector: &[f32]) {
+        for (table, hash) in self.get_hashes(vector).into_iter().enumerate() {
+            if let Some(bucket) = self.buckets[table].get_mut(&hash) {
+                bucket.retain(|x_node_id| x_node_id != node_id);
+                if bucket.is_empty() {
+                    self.buckets[table].remove(&hash);
+                }
+            }
+        }
+    }
+
+    // Nodes in the same bucket as the vector, or in a bucket which differs by one bit
+    fn get_candidates(&self, vector: &[f32]) -> HashSet<NodeId> {
+        let mut candidates: HashSet<NodeId> = HashSet::new();
+        for (table, hash) in self.get_hashes(vector).into_iter().enumerate() {
+            let probes =
+                std::iter::once(hash).chain((0..HASH_BIT_COUNT).map(|bit| hash ^ (1 << bit)));
+            for probe in probes {
+                if let Some(bucket) = self.buckets[table].get(&probe) {
+                    candidates.extend(bucket.iter().copied());
+                }
+            }
+        }
+        candidates
+    }
+}
+
+// Embeddings of content nodes, with an approximate nearest neighbor index over them.
+// All embeddings are kept in memory, they are small compared to the text they are from
+pub(super) struct Embeddings {
+    vectors: HashMap<NodeId, Vec<f32>>,
+    index: Option<VectorIndex>, // Created with the dimension of the first embedding
+}
+
+impl Embeddings {
+    pub(super) fn new() -> Self {
+        Embeddings {
+            vectors: HashMap::new(),
+            index: None,
+        }
+    }
+
+    pub(super) fn open(db: &DB) -> PiResult<Self> {
+        let mut embeddings = Embeddings::new();
+        for item in db.prefix_iterator(EMBEDDINGS_PREFIX) {
+            match item {
+                Ok((key, value)) => {
+                    if !key.starts_with(EMBEDDINGS_PREFIX.as_bytes()) {
+                        break;
+                    }
+                    let data: Vec<(NodeId, Vec<f32>)> = from_versioned_bytes(&value)?;
+                    for (node_id, vector) in data {
+                        embeddings.set_vector(node_id, Some(vector));
+                    }
+                }
+                Err(err) => {
+                    error!("RocksDB error: {}", err);
+                    return Err(PiError::RocksdbError(err));
+                }
+            }
+        }
+        Ok(embeddings)
+    }
+
+    pub(super) fn contains(&self, node_id: &NodeId) -> bool {
+        self.vectors.contains_key(node_id)
+    }
+
+    fn set_vector(&mut self, node_id: NodeId, vector: Option<Vec<f32>>) {
+        if let Some(previous) = self.vectors.remove(&node_id) {
+            if let Some(index) = self.index.as_mut() {
+                index.remove(&node_id, &previous);
+            }
+        }
+        if let Some(vector) = vector {
+            self.index
+                .get_or_insert_with(|| VectorIndex::new(vector.len()))
+                .insert(node_id, &vector);
+            self.vectors.insert(node_id, vector);
+        }
+    }
+
+    // Writes the chunks of these nodes to the batch, chunks without embeddings are deleted
+    pub(super) fn write_chunks_to_batch(
+        &self,
+        batch: &mut WriteBatch,
+        node_ids: impl Iterator<Item = NodeId>,
+    ) -> PiResult<()> {
+        let chunk_ids: BTreeSet<u32> = node_ids
+            .map(|node_id| get_chunk_id_and_node_ids(&node_id).0)
+            .collect();
+        for chunk_id in chunk_ids {
+            let (_, chunk_node_ids) = get_chunk_id_and_node_ids(&(chunk_id * 100));
+            let chunk: Vec<(NodeId, &Vec<f32>)> = chunk_node_ids
+                .iter()
+                .filter_map(|node_id| self.vectors.get(node_id).map(|vector| (*node_id, vector)))
+                .collect();
+            let key = format!("{}{}", EMBEDDINGS_PREFIX, chunk_id);
+            if chunk.is_empty() {
+                batch.delete(key);
+            } else {
+                batch.put(key, to_versioned_bytes(&chunk)?);
+            }
+        }
+        Ok(())
+    }
+
+    // Replaces any previous embeddings of these nodes, the engine saves their chunks
+    pub(super) fn insert(&mut self, items: Vec<(NodeId, Vec<f32>)>) {
+        for (node_id, vector) in items {
+            self.set_vector(node_id, Some(vector));
+        }
+    }
+
+    // Returns true if the node had an embedding, then the engine saves its chunk
+    pub(super) fn remove(&mut self, node_id: &NodeId) -> bool {
+        if !self.contains(node_id) {
+            return false;
+        }
+        self.set_vector(*node_id, None);
+        true
+    }
+
+    // Nodes closest to the query with their cosine similarity, closest first. The index gives
+    // the candidates, if there are fewer candidates than the limit then all nodes are compared
+    pub(super) fn search(&self, query: &[f32], limit: usize) -> Vec<(NodeId, f32)> {
+        let Some(index) = &self.index else {
+            return vec![];
+        };
+        let mut candidates = index.get_candidates(query);
+        if candidates.len() < limit {
+            candidates = self.vectors.keys().copied().collect();
+        }
+        let mut matches: Vec<(NodeId, f32)> = candidates
+            .into_iter()
+            .filter_map(|node_id| {
+                self.vectors
+                    .get(&node_id)
+                    .map(|vector| (node_id, cosine_similarity(query, vector)))
+            })
+            .collect();
+        matches.sort_by(|a, b| b.1.total_cmp(&a.1).then(a.0.cmp(&b.0)));
+        matches.truncate(limit);
+        matches
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::engine::{add_content_node, get_test_engine};
+    use crate::engine::node::{NodeLabel, Payload};
+    use crate::services::embedding::Embedder;
+    use std::sync::Arc;
+
+    // Bag of words embeddings, texts which share words are close
+    struct WordsEmbedder;
+
+    impl Embedder for WordsEmbedder {
+        fn embed(&self, texts: Vec<String>) -> PiResult<Vec<Vec<f32>>> {
+            Ok(texts
+                .iter()
+                .map(|text| {
+                    let mut vector = vec![0.0; 32];
+                    for word in text.to_lowercase().split_whitespace() {
+                        let hash = word.bytes().fold(7usize, |hash, x| hash * 31 + x as usize);
+                        vector[hash % 32] += 1.0;
+                    }
+                    vector
+                })
+                .collect())
+        }
+    }
+
+    #[test]
+    fn test_index_finds_nearest_vectors() {
+        let mut embeddings = Embeddings::new();
+        let mut state: u64 = 42;
+        let mut next_random = || {
+            state = state.wrapping_mul(6364136223846793005).wrapping_add(1);
+            (state >> 33) as f32 / (1u64 << 31) as f32 - 0.5
+        };
+        for node_id in 0..500 {
+            let vector: Vec<f32> = (0..16).map(|_| next_random()).collect();
+            embeddings.set_vector(node_id, Some(vector));
+        }
+        let query: Vec<f32> = embeddings.vectors[&123]
+            .iter()
+            .map(|x| x + next_random() * 0.05)
+            .collect();
+
+        // The candidates are a small part of all nodes, and include the nearest one
+        let candidates = embeddings.index.as_ref().unwrap().get_candidates(&query);
+        assert!(candidates.contains(&123));
+        assert!(candidates.len() < 500);
+        assert_eq!(embeddings.search(&query, 1)[0].0, 123);
+
+        embeddings.set_vector(123, None);
+        assert_ne!(embeddings.search(&query, 1)[0].0, 123);
+        assert_eq!(embeddings.search(&query, 1000).len(), 499);
+    }
+
+    #[test]
+    fn test_semantic_search_embeds_pending_nodes() {
+        let engine = get_test_engine();
+        let web_page_node_id = engine
+            .get_or_add_node(
+                Payload::Text("<html></html>".to_string()),
+                vec![NodeLabel::WebPage],
+                true,
+                None,
+            )
+            .unwrap()
+            .get_node_id();
+        let crawler = add_content_node(&engine, web_page_node_id, "the web crawler reads pages");
+        let graph = add_content_node(&engine, web_page_node_id, "nodes and edges of a graph");
+        engine.set_embedder(Arc::new(WordsEmbedder));
+
+        // Nodes are searchable once they are embedded
+        assert!(engine
+            .semantic_search("graph edges", 10)
+            .unwrap()
+            .is_empty());
+        engine.embed_pending_nodes().unwrap();
+        let results = engine.semantic_search("graph edges", 10).unwrap();
+        assert_eq!(results[0].node_id, graph);
+        assert_eq!(results[0].web_page_node_id, Some(web_page_node_id));
+        assert_eq!(results.len(), 2);
+
+        // Updated nodes are embedded again, deleted nodes are not returned
+        engine
+            .update_node(&crawler, Payload::Text("a graph of web pages".to_string()))
+            .unwrap();
+        engine.embed_pending_nodes().unwrap();
+        engine.delete_node(&graph, false).unwrap();
+        let results = engine.semantic_search("graph edges", 10).unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].node_id, crawler);
+        assert!(results[0].score > 0.0);
+    }
+}
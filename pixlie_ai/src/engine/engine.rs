@@ -5,33 +5,80 @@
 //
 // https://github.com/pixlie/PixlieAI/blob/main/LICENSE
 
-use super::{EdgeLabel, NodeEdges, NodeFlags};
+use super::{
+    get_chunk_id_and_node_ids, EdgeLabel, EdgeProperties, NodeEdges, NodeFlags, RetryPolicy,
+};
 use crate::engine::api::{handle_engine_api_request, EngineResponsePayload};
 use crate::engine::edges::Edges;
+use crate::engine::embeddings::{Embeddings, SemanticSearchResult};
+use crate::engine::migrations::{from_versioned_bytes, migrate, to_versioned_bytes};
 use crate::engine::node::{
-    ArcedNodeId, ArcedNodeItem, ExistingOrNewNodeId, NodeId, NodeItem, NodeLabel, Payload,
+    ArcedNodeId, ArcedNodeItem, ExistingOrNewNodeId, NodeId, NodeItem, NodeLabel, NodeVersion,
+    Payload,
 };
 use crate::engine::nodes::Nodes;
+use crate::engine::processor::{ArcedNodeProcessor, ProcessingDependency, ProcessorRegistry};
+use crate::engine::text_index::{
+    get_indexed_text, get_snippet, TextIndex, TextQuery, TextSearchResult,
+};
+use crate::engine::work_queue::WorkQueue;
+use crate::entity::crawler::budget::{CrawlBudget, CrawlState, CrawlStopReason};
+use crate::entity::fetch_error::{FetchErrorDetails, FetchErrorKind};
 use crate::entity::search::saved_search::SavedSearch;
+use crate::entity::web::discovery::LinkDiscovery;
 use crate::entity::web::domain::{Domain, FindDomainOf};
 use crate::entity::web::link::Link;
+use crate::entity::web::recrawl::{LinkFetch, RecrawlQueue, RecrawlSettings};
 use crate::error::{PiError, PiResult};
+use crate::projects::snapshots::Snapshot;
 use crate::projects::{Project, ProjectOwner};
-use crate::{FetchRequest, InternalFetchRequest, PiChannel, PiEvent};
-use chrono::{TimeDelta, Utc};
+use crate::services::anthropic::Anthropic;
+use crate::services::embedding::{ArcedEmbedder, TextEmbedder};
+use crate::{
+    FetchError, FetchRequest, FetchResponse, FetchThrottled, InternalFetchRequest, PiChannel,
+    PiEvent,
+};
+use chrono::{DateTime, Utc};
 use log::{debug, error, info};
-use rocksdb::DB;
+use rocksdb::{WriteBatch, DB};
 use std::backtrace::Backtrace;
-use std::collections::{HashMap, HashSet};
-use std::ops::Deref;
-use std::sync::atomic::AtomicU32;
-use std::sync::RwLock;
-use std::time::Duration;
-use std::{path::PathBuf, sync::Arc, thread};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::sync::atomic::{AtomicBool, AtomicU32};
+use std::sync::{Mutex, MutexGuard, OnceLock, RwLock};
+use std::thread::{self, ThreadId};
+use std::time::{Duration, Instant};
+use std::{path::PathBuf, sync::Arc};
 use texting_robots::Robot;
 
+// The ticker wakes up at least this often, to check that the project still exists
+const TICKER_TIMEOUT: Duration = Duration::from_millis(2000);
+// Nodes are processed as they change, but we also scan all nodes once in a while,
+// for example to retry nodes which had an error
+const FULL_SCAN_INTERVAL: Duration = Duration::from_secs(30);
+// Content nodes are embedded in batches, a few batches in each tick so the ticker is not held up
+const EMBEDDING_BATCH_SIZE: usize = 32;
+const EMBEDDING_BATCHES_PER_TICK: usize = 4;
+// How each link was last fetched, used to fetch it again once its recrawl interval has passed
+pub(super) const LINK_FETCH_PREFIX: &str = "links/fetch/";
+pub(super) const LINK_DISCOVERY_PREFIX: &str = "links/discovery/";
+// What the project has crawled so far, checked against its crawl budget
+pub(super) const CRAWL_STATE_KEY: &str = "crawl/state";
+
+// Changes made inside `batch_writes` on one thread, which are written to the DB together when it
+// ends. Batches on other threads are written on their own
+#[derive(Default)]
+struct PendingWrites {
+    depth: u32, // Batches can be nested, the chunks are written when the outermost batch ends
+    node_chunks: BTreeMap<u32, NodeId>, // Chunk ID to any node ID in that chunk
+    edge_chunks: BTreeMap<u32, NodeId>,
+    embedding_chunks: BTreeSet<NodeId>, // Nodes whose embedding changed
+    keys: BTreeMap<String, Option<Vec<u8>>>, // Other keys, like the fetch state of links, None to delete
+    dirty_node_ids: BTreeSet<NodeId>,        // Processed once the batch is written
+}
+
 // The engine keeps track of all the data nodes and their relationships
 pub struct Engine {
+    // When both are locked, nodes are always locked before edges
     nodes: RwLock<Nodes>, // All nodes that are in the engine
     edges: RwLock<Edges>,
 
@@ -45,6 +92,18 @@ pub struct Engine {
     fetcher_tx: tokio::sync::mpsc::Sender<PiEvent>,
 
     count_open_fetch_requests: AtomicU32,
+    is_unloading: AtomicBool, // Set when the engine is asked to stop, like before a restore
+
+    processors: RwLock<ProcessorRegistry>, // Processors to be called for nodes, by label
+    work_queue: WorkQueue,                 // Nodes which have changed since they were processed
+    retry_policy: RwLock<RetryPolicy>,     // How nodes which had an error are retried
+    pending_writes: Mutex<HashMap<ThreadId, PendingWrites>>, // Changes of the current batches
+    text_index: RwLock<TextIndex>,         // Full-text index of the content nodes
+    embeddings: RwLock<Embeddings>,        // Embeddings of the content nodes, for semantic search
+    embedding_queue: Mutex<BTreeSet<NodeId>>, // Content nodes which are yet to be embedded
+    embedder: OnceLock<Option<ArcedEmbedder>>, // Loaded when first needed, None if not installed
+    crawl_state: Mutex<CrawlState>, // Written to the DB on each change, see `update_crawl_state`
+    recrawl_queue: Mutex<RecrawlQueue>, // Links by when they were fetched, see `set_link_fetch`
 }
 
 impl Engine {
@@ -57,14 +116,15 @@ impl Engine {
     ) -> PiResult<Self> {
         let path_to_db = path_to_storage_dir.join(format!("{}.rocksdb", project_uuid));
 
-        // Load all nodes and edges from the database
+        // Bring the database to the current format version, then load the node indexes from it,
+        // nodes and edges are read when they are needed
         // We need to run this before loading the project database
-        // since nodes.load_all_from_disk() & edges.load_all_from_disk() need
-        // to open the database with their prefix extractors and once the database
-        // is opened(and locked), we cannot open it again with a different prefix
-        // extractor or set a prefix extractor
-        let (nodes, last_node_id) = Nodes::open(&path_to_db)?;
-        let edges = Edges::open(&path_to_db)?;
+        // since Nodes::open() needs to open the database with its prefix extractor
+        // and once the database is opened(and locked), we cannot open it again
+        // with a different prefix extractor or set a prefix extractor
+        migrate(&path_to_db)?;
+        let (nodes, next_node_id) = Nodes::open(&path_to_db)?;
+        let edges = Edges::new();
         let mut opts = rocksdb::Options::default();
         opts.create_if_missing(false);
         let db = match DB::open(&opts, path_to_db.as_os_str()) {
@@ -74,12 +134,25 @@ impl Engine {
                 return Err(err.into());
             }
         };
+        let text_index = TextIndex::open(&db)?;
+        let embeddings = Embeddings::open(&db)?;
+        let crawl_state: CrawlState = match db.get(CRAWL_STATE_KEY)? {
+            Some(bytes) => from_versioned_bytes(&bytes)?,
+            None => CrawlState::default(),
+        };
+        // Content nodes which were indexed but not embedded, like those from before embeddings
+        let embedding_queue: BTreeSet<NodeId> = text_index
+            .get_node_ids()
+            .filter(|node_id| !embeddings.contains(node_id))
+            .copied()
+            .collect();
+        let recrawl_queue = open_recrawl_queue(&db, &nodes)?;
 
         let engine = Engine {
             nodes: RwLock::new(nodes),
             edges: RwLock::new(edges),
 
-            last_node_id: AtomicU32::new(0),
+            last_node_id: AtomicU32::new(next_node_id),
 
             project_uuid: project_uuid.to_string(),
             arced_db: Arc::new(db),
@@ -89,13 +162,20 @@ impl Engine {
             fetcher_tx,
 
             count_open_fetch_requests: AtomicU32::new(0),
+            is_unloading: AtomicBool::new(false),
+
+            processors: RwLock::new(ProcessorRegistry::with_builtin_processors()),
+            work_queue: WorkQueue::default(),
+            retry_policy: RwLock::new(RetryPolicy::default()),
+            pending_writes: Mutex::new(HashMap::new()),
+            text_index: RwLock::new(text_index),
+            embeddings: RwLock::new(embeddings),
+            embedding_queue: Mutex::new(embedding_queue),
+            embedder: OnceLock::new(),
+            crawl_state: Mutex::new(crawl_state),
+            recrawl_queue: Mutex::new(recrawl_queue),
         };
 
-        if last_node_id != 0 {
-            engine
-                .last_node_id
-                .store(last_node_id + 1, std::sync::atomic::Ordering::Relaxed);
-        }
         Ok(engine)
     }
 
@@ -103,9 +183,52 @@ impl Engine {
         &self.project_uuid
     }
 
+    // The DB stays open while the engine runs, so the snapshot is taken from it here
+    pub fn create_snapshot(&self, label: Option<String>) -> PiResult<Snapshot> {
+        Snapshot::create_from_db(&self.arced_db, &self.project_uuid, label)
+    }
+
+    pub fn register_processor(
+        &self,
+        label: NodeLabel,
+        order: u16,
+        dependencies: Vec<ProcessingDependency>,
+        processor: ArcedNodeProcessor,
+    ) {
+        match self.processors.write() {
+            Ok(mut processors) => processors.register(label, order, dependencies, processor),
+            Err(err) => {
+                error!("Error locking processors: {}", err);
+            }
+        }
+    }
+
+    pub(super) fn get_processors(&self, labels: &[NodeLabel]) -> Vec<ArcedNodeProcessor> {
+        match self.processors.read() {
+            Ok(processors) => processors.get_processors(labels),
+            Err(err) => {
+                error!("Error locking processors: {}", err);
+                vec![]
+            }
+        }
+    }
+
+    pub fn set_retry_policy(&self, retry_policy: RetryPolicy) {
+        match self.retry_policy.write() {
+            Ok(mut existing) => *existing = retry_policy,
+            Err(err) => {
+                error!("Error locking retry policy: {}", err);
+            }
+        }
+    }
+
     pub fn ticker(&self) {
+        let mut last_full_scan_at: Option<Instant> = None;
         loop {
-            thread::sleep(Duration::from_millis(2000));
+            let dirty_node_ids = self.work_queue.wait_for_dirty(TICKER_TIMEOUT);
+            if self.is_unloading.load(std::sync::atomic::Ordering::Relaxed) {
+                break;
+            }
             match Project::check_project_db(&self.project_uuid) {
                 Ok(_) => {}
                 Err(_) => {
@@ -113,7 +236,24 @@ impl Engine {
                     break;
                 }
             }
-            self.process_nodes();
+            match last_full_scan_at {
+                Some(last_full_scan_at) if last_full_scan_at.elapsed() < FULL_SCAN_INTERVAL => {
+                    if !dirty_node_ids.is_empty() {
+                        self.process_node_ids(Some(dirty_node_ids));
+                    }
+                }
+                _ => {
+                    if let Err(err) = RecrawlSettings::schedule_recrawls(Arc::new(self)) {
+                        error!("Error scheduling links to be fetched again: {}", err);
+                    }
+                    // The full scan includes the dirty nodes
+                    self.process_nodes();
+                    last_full_scan_at = Some(Instant::now());
+                }
+            }
+            if let Err(err) = self.embed_pending_nodes() {
+                error!("Error embedding content nodes: {}", err);
+            }
         }
     }
 
@@ -168,61 +308,15 @@ impl Engine {
                         }
                     }
                 }
-                PiEvent::FetchResponse(response) => {
-                    // Call the node that had needs this data
-                    match self.get_node_by_id(&response.node_id) {
-                        Some(node) => {
-                            match self.toggle_flag(&node.id, NodeFlags::IS_REQUESTING) {
-                                Ok(_) => {}
-                                Err(err) => {
-                                    error!(
-                                        "Error toggling IS_REQUESTING flag for node with ID {}: {}",
-                                        &node.id, err
-                                    );
-                                    continue;
-                                }
-                            }
-                            // Reduce the number of open fetch requests by 1
-                            self.count_open_fetch_requests
-                                .fetch_sub(1, std::sync::atomic::Ordering::Relaxed);
-                            let arced_engine = Arc::new(self);
-                            match node.handle_fetch_response(arced_engine.clone(), response) {
-                                Ok(_) => {}
-                                Err(err) => {
-                                    error!("Error processing node: {}", err);
-                                }
-                            }
-                        }
-                        None => {}
-                    }
-                }
-                PiEvent::FetchError(error) => {
-                    // We have received the error from the previous request
-                    match self.get_node_by_id(&error.node_id) {
-                        Some(node) => {
-                            match self.toggle_flag(&node.id, NodeFlags::IS_REQUESTING) {
-                                Ok(_) => {}
-                                Err(err) => {
-                                    error!(
-                                        "Error toggling IS_REQUESTING flag for node with ID {}: {}",
-                                        &node.id, err
-                                    );
-                                    continue;
-                                }
-                            }
-                            // Reduce the number of open fetch requests by 1
-                            self.count_open_fetch_requests
-                                .fetch_sub(1, std::sync::atomic::Ordering::Relaxed);
-                            let engine = Arc::new(self);
-                            match node.handle_fetch_error(engine, error) {
-                                Ok(_) => {}
-                                Err(err) => {
-                                    error!("Error processing node: {}", err);
-                                }
-                            }
-                        }
-                        None => {}
-                    };
+                PiEvent::FetchResponse(response) => self.receive_fetch_response(response),
+                PiEvent::FetchError(error) => self.receive_fetch_error(error),
+                PiEvent::FetchThrottled(throttled) => self.receive_fetch_throttled(throttled),
+                PiEvent::UnloadEngine(_) => {
+                    // The main thread has already removed this engine. The ticker stops
+                    // at its next tick, the DB is closed when both have stopped
+                    self.is_unloading
+                        .store(true, std::sync::atomic::Ordering::Relaxed);
+                    return;
                 }
                 event => {
                     info!("Unhandled event: {}", event.to_string());
@@ -232,9 +326,123 @@ impl Engine {
         self.exit();
     }
 
-    pub fn get_node_by_id(&self, node_id: &NodeId) -> Option<ArcedNodeItem> {
+    fn receive_fetch_response(&self, response: FetchResponse) {
+        // Call the node that had needs this data
+        match self.get_node_with_content(&response.node_id) {
+            Some(node) => {
+                match self.toggle_flag(&node.id, NodeFlags::IS_REQUESTING) {
+                    Ok(_) => {}
+                    Err(err) => {
+                        error!(
+                            "Error toggling IS_REQUESTING flag for node with ID {}: {}",
+                            &node.id, err
+                        );
+                        return;
+                    }
+                }
+                // Reduce the number of open fetch requests by 1
+                self.count_open_fetch_requests
+                    .fetch_sub(1, std::sync::atomic::Ordering::Relaxed);
+                // Nodes waiting for a fetch request slot can try again
+                self.work_queue.requeue_waiting();
+                if let Some(cost_in_cents) = Anthropic::get_cost_in_cents(&response) {
+                    if let Err(err) =
+                        self.update_crawl_state(|state| state.llm_spend_in_cents += cost_in_cents)
+                    {
+                        error!("Error recording LLM spend: {}", err);
+                    }
+                }
+                let arced_engine = Arc::new(self);
+                match node.handle_fetch_response(arced_engine.clone(), response) {
+                    Ok(_) => {}
+                    Err(err) => {
+                        error!("Error processing node: {}", err);
+                    }
+                }
+            }
+            None => {}
+        }
+    }
+
+    fn receive_fetch_throttled(&self, throttled: FetchThrottled) {
+        // The request was not sent, the node fetches again once the domain or URL can be
+        // fetched from. This is not counted as an error of the node
+        debug!(
+            "Fetch for node with ID {} was throttled: {}",
+            throttled.node_id, throttled.reason
+        );
+        self.work_queue
+            .mark_waiting_until(throttled.node_id, Instant::now() + throttled.wait_time);
+        match self.toggle_flag(&throttled.node_id, NodeFlags::IS_REQUESTING) {
+            Ok(_) => {}
+            Err(err) => {
+                error!(
+                    "Error toggling IS_REQUESTING flag for node with ID {}: {}",
+                    &throttled.node_id, err
+                );
+                return;
+            }
+        }
+        self.count_open_fetch_requests
+            .fetch_sub(1, std::sync::atomic::Ordering::Relaxed);
+    }
+
+    fn receive_fetch_error(&self, error: FetchError) {
+        // We have received the error from the previous request
+        match self.get_node_with_content(&error.node_id) {
+            Some(node) => {
+                match self.toggle_flag(&node.id, NodeFlags::IS_REQUESTING) {
+                    Ok(_) => {}
+                    Err(err) => {
+                        error!(
+                            "Error toggling IS_REQUESTING flag for node with ID {}: {}",
+                            &node.id, err
+                        );
+                        return;
+                    }
+                }
+                // Reduce the number of open fetch requests by 1
+                self.count_open_fetch_requests
+                    .fetch_sub(1, std::sync::atomic::Ordering::Relaxed);
+                // Nodes waiting for a fetch request slot can try again
+                self.work_queue.requeue_waiting();
+                let engine = Arc::new(self);
+                // Keep the details of the error in the graph
+                match FetchErrorDetails::add(
+                    engine.clone(),
+                    &node.id,
+                    FetchErrorDetails::from_fetch_error(&error),
+                ) {
+                    Ok(_) => {}
+                    Err(err) => {
+                        error!(
+                            "Error saving fetch error for node with ID {}: {}",
+                            &node.id, err
+                        );
+                    }
+                }
+                match node.handle_fetch_error(engine, error) {
+                    Ok(_) => {}
+                    Err(err) => {
+                        error!("Error processing node: {}", err);
+                    }
+                }
+            }
+            None => {}
+        };
+    }
+
+    // Large content, like the HTML of a web page, is not part of the node we get here, the
+    // payload of a web page is empty. Use `get_node_with_content` when the content is needed
+    pub fn get_node_without_content(&self, node_id: &NodeId) -> Option<ArcedNodeItem> {
         match self.nodes.read() {
-            Ok(nodes) => nodes.data.get(node_id).map(|x| x.clone()),
+            Ok(nodes) => match nodes.get_node(&self.arced_db, node_id) {
+                Ok(node) => node,
+                Err(err) => {
+                    error!("Error reading node with ID {}: {}", node_id, err);
+                    None
+                }
+            },
             Err(err) => {
                 error!("Error locking nodes: {}", err);
                 None
@@ -242,51 +450,225 @@ impl Engine {
         }
     }
 
+    pub fn get_node_with_content(&self, node_id: &NodeId) -> Option<ArcedNodeItem> {
+        match self.nodes.read() {
+            Ok(nodes) => match nodes.get_node_with_content(&self.arced_db, node_id) {
+                Ok(node) => node,
+                Err(err) => {
+                    error!("Error reading node with ID {}: {}", node_id, err);
+                    None
+                }
+            },
+            Err(err) => {
+                error!("Error locking nodes: {}", err);
+                None
+            }
+        }
+    }
+
+    // Earlier versions of a node, oldest first, see `get_node_as_of` for the node at a given time
+    pub fn get_node_versions(&self, node_id: &NodeId) -> PiResult<Vec<NodeVersion>> {
+        match self.nodes.read() {
+            Ok(nodes) => nodes.get_node_versions(&self.arced_db, node_id),
+            Err(err) => {
+                error!("Error locking nodes: {}", err);
+                Err(PiError::InternalError(format!(
+                    "Error locking nodes: {}",
+                    err
+                )))
+            }
+        }
+    }
+
+    pub fn get_link_fetch(&self, node_id: &NodeId) -> PiResult<Option<LinkFetch>> {
+        match self.get_key(&format!("{}{}", LINK_FETCH_PREFIX, node_id))? {
+            Some(bytes) => Ok(Some(from_versioned_bytes(&bytes)?)),
+            None => Ok(None),
+        }
+    }
+
+    pub fn set_link_fetch(&self, node_id: &NodeId, link_fetch: &LinkFetch) -> PiResult<()> {
+        self.put_key(
+            format!("{}{}", LINK_FETCH_PREFIX, node_id),
+            Some(to_versioned_bytes(link_fetch)?),
+        )?;
+        self.set_link_fetched_at(node_id, Some(link_fetch.fetched_at))
+    }
+
+    fn set_link_fetched_at(
+        &self,
+        node_id: &NodeId,
+        fetched_at: Option<DateTime<Utc>>,
+    ) -> PiResult<()> {
+        match self.recrawl_queue.lock() {
+            Ok(mut recrawl_queue) => {
+                recrawl_queue.set_fetched_at(*node_id, fetched_at);
+                Ok(())
+            }
+            Err(err) => {
+                error!("Error locking recrawl queue: {}", err);
+                Err(PiError::InternalError(format!(
+                    "Error locking recrawl queue: {}",
+                    err
+                )))
+            }
+        }
+    }
+
+    // Links fetched at or before the given time, earliest first, without reading any node
+    pub fn get_links_fetched_before(
+        &self,
+        time: DateTime<Utc>,
+    ) -> PiResult<Vec<(NodeId, DateTime<Utc>)>> {
+        match self.recrawl_queue.lock() {
+            Ok(recrawl_queue) => Ok(recrawl_queue.get_fetched_before(time)),
+            Err(err) => {
+                error!("Error locking recrawl queue: {}", err);
+                Err(PiError::InternalError(format!(
+                    "Error locking recrawl queue: {}",
+                    err
+                )))
+            }
+        }
+    }
+
+    pub fn get_link_discovery(&self, node_id: &NodeId) -> PiResult<Option<LinkDiscovery>> {
+        match self.get_key(&format!("{}{}", LINK_DISCOVERY_PREFIX, node_id))? {
+            Some(bytes) => Ok(Some(from_versioned_bytes(&bytes)?)),
+            None => Ok(None),
+        }
+    }
+
+    pub fn set_link_discovery(
+        &self,
+        node_id: &NodeId,
+        link_discovery: &LinkDiscovery,
+    ) -> PiResult<()> {
+        self.put_key(
+            format!("{}{}", LINK_DISCOVERY_PREFIX, node_id),
+            Some(to_versioned_bytes(link_discovery)?),
+        )?;
+        // The link may be closer to a seed link than before, and within the crawl budget now
+        self.work_queue
+            .requeue_waiting_for_budget(Some(&[*node_id]));
+        Ok(())
+    }
+
+    // Called when the crawl budget changes, so links which it did not allow are checked again
+    pub fn requeue_links_waiting_for_budget(&self) {
+        self.work_queue.requeue_waiting_for_budget(None);
+    }
+
+    pub fn get_crawl_state(&self) -> PiResult<CrawlState> {
+        match self.crawl_state.lock() {
+            Ok(crawl_state) => Ok(crawl_state.clone()),
+            Err(err) => Err(PiError::InternalError(format!(
+                "Error locking crawl state: {}",
+                err
+            ))),
+        }
+    }
+
+    // Changes the crawl state and saves it, the lock is held while it changes
+    // so updates from the ticker and the channel listener are not lost
+    pub fn update_crawl_state<T>(&self, update: impl FnOnce(&mut CrawlState) -> T) -> PiResult<T> {
+        let mut crawl_state = match self.crawl_state.lock() {
+            Ok(crawl_state) => crawl_state,
+            Err(err) => {
+                return Err(PiError::InternalError(format!(
+                    "Error locking crawl state: {}",
+                    err
+                )));
+            }
+        };
+        let value = update(&mut crawl_state);
+        self.put_key(
+            CRAWL_STATE_KEY.to_string(),
+            Some(to_versioned_bytes(&*crawl_state)?),
+        )?;
+        Ok(value)
+    }
+
+    // Process all nodes which are ready to be processed
     pub fn process_nodes(&self) {
+        self.process_node_ids(None);
+    }
+
+    // Process the nodes which have changed since the last call, see `WorkQueue`
+    pub fn process_dirty_nodes(&self) {
+        let dirty_node_ids = self.work_queue.take_dirty();
+        if !dirty_node_ids.is_empty() {
+            self.process_node_ids(Some(dirty_node_ids));
+        }
+    }
+
+    fn process_node_ids(&self, node_ids: Option<Vec<NodeId>>) {
         let arced_self = Arc::new(self);
 
         let flags_to_be_skipped = vec![
             NodeFlags::IS_PROCESSED,
             NodeFlags::IS_REQUESTING,
             NodeFlags::IS_BLOCKED,
+            NodeFlags::GAVE_UP,
         ];
+        // Once the crawl budget is reached, links are not fetched
+        let is_crawl_stopped = match CrawlBudget::check(arced_self.clone()) {
+            Ok(stop_reason) => stop_reason.is_some(),
+            Err(err) => {
+                error!("Error checking crawl budget: {}", err);
+                false
+            }
+        };
 
-        // TODO: Make dependency processing more generic,
-        // so edge based dependencies can be added
-        // For now, we cautiously check only nodes that are known to currently have solo nodes.
-        // For e.g. Objective
-        let mut processing_dependencies: HashMap<NodeLabel, Vec<NodeLabel>> = HashMap::new();
-        processing_dependencies.insert(NodeLabel::WebPage, vec![NodeLabel::Objective]);
-        processing_dependencies.insert(NodeLabel::WebSearch, vec![NodeLabel::Objective]);
-        let mut dependency_labels_to_check = vec![];
-        processing_dependencies
-            .iter()
-            .for_each(|(_, dependencies)| {
-                dependencies.iter().for_each(|dependency| {
-                    if !dependency_labels_to_check.contains(dependency) {
-                        dependency_labels_to_check.push(dependency.clone());
-                    }
-                });
-            });
+        // Labels are sorted so that labels come after the labels they depend on,
+        // the position of a label is used to order the ready queue
+        let (label_order, dependencies_by_label): (
+            HashMap<NodeLabel, usize>,
+            HashMap<NodeLabel, Vec<ProcessingDependency>>,
+        ) = match self.processors.read() {
+            Ok(processors) => {
+                let labels = processors.get_labels_in_dependency_order();
+                (
+                    labels
+                        .iter()
+                        .enumerate()
+                        .map(|(position, label)| (label.clone(), position))
+                        .collect(),
+                    labels
+                        .iter()
+                        .map(|label| {
+                            (
+                                label.clone(),
+                                processors.get_dependencies(std::slice::from_ref(label)),
+                            )
+                        })
+                        .collect(),
+                )
+            }
+            Err(err) => {
+                error!("Error locking processors: {}", err);
+                return;
+            }
+        };
+
+        let node_ids_by_dependency_label: HashMap<NodeLabel, Vec<ArcedNodeId>> =
+            dependencies_by_label
+                .values()
+                .flatten()
+                .filter_map(|dependency| match dependency {
+                    ProcessingDependency::Label(label) => Some(label.clone()),
+                    _ => None,
+                })
+                .collect::<HashSet<NodeLabel>>()
+                .into_iter()
+                .map(|label| {
+                    let node_ids = self.get_node_ids_with_label(&label);
+                    (label, node_ids)
+                })
+                .collect();
 
-        // When the number of the nodes to be processed is large,
-        // we do not process nodes that generate more nodes. WebPage nodes are like that.
-        let limited_labels_to_be_processed = vec![
-            NodeLabel::DomainName,
-            NodeLabel::Objective,
-            NodeLabel::WebPage,
-            NodeLabel::WebSearch,
-        ];
-        let all_labels_to_be_processed = vec![
-            NodeLabel::DomainName,
-            NodeLabel::Link,
-            NodeLabel::Objective,
-            NodeLabel::WebPage,
-            NodeLabel::WebSearch,
-        ];
-        let mut node_count: usize = 0;
         let current_time = Utc::now();
-        let mut node_ids: Vec<NodeId> = {
+        let mut ready_queue: Vec<(usize, NodeId)> = {
             let nodes = match self.nodes.read() {
                 Ok(nodes) => nodes,
                 Err(err) => {
@@ -294,68 +676,112 @@ impl Engine {
                     return;
                 }
             };
-            let mut processing_status: HashMap<NodeLabel, bool> = HashMap::new();
-            nodes.data.iter().for_each(|item| {
-                if dependency_labels_to_check
-                    .iter()
-                    .any(|label| item.1.labels.contains(label))
-                    && item.1.flags.contains(NodeFlags::IS_PROCESSED)
-                {
-                    processing_status.insert(item.1.labels[0].clone(), true);
-                }
-            });
-            nodes
-                .data
+            // A dependency which gave up or is blocked will not be processed, so nodes do not
+            // wait for it. Nodes which depend on it find what it would have created missing
+            let is_resolved = |flags: &NodeFlags| {
+                flags.intersects(
+                    NodeFlags::IS_PROCESSED | NodeFlags::GAVE_UP | NodeFlags::IS_BLOCKED,
+                )
+            };
+            // Labels which have at least one node that is not processed yet
+            let labels_with_pending_nodes: HashSet<NodeLabel> = node_ids_by_dependency_label
                 .iter()
-                .filter_map(|item| {
+                .filter_map(|(label, node_ids)| {
+                    if node_ids
+                        .iter()
+                        .any(|node_id| match nodes.get_index_entry(node_id) {
+                            Some(index_entry) => !is_resolved(&index_entry.flags),
+                            None => false,
+                        })
+                    {
+                        Some(label.clone())
+                    } else {
+                        None
+                    }
+                })
+                .collect();
+            let is_dependency_met =
+                |node_id: &NodeId, dependency: &ProcessingDependency| match dependency {
+                    ProcessingDependency::Label(label) => {
+                        !labels_with_pending_nodes.contains(label)
+                    }
+                    ProcessingDependency::ConnectedWith(edge_label) => {
+                        match self.get_node_ids_connected_with_label(node_id, edge_label) {
+                            Ok(connected_node_ids) => {
+                                connected_node_ids.iter().all(|connected_node_id| {
+                                    match nodes.get_index_entry(connected_node_id) {
+                                        Some(index_entry) => is_resolved(&index_entry.flags),
+                                        None => false,
+                                    }
+                                })
+                            }
+                            Err(_) => false,
+                        }
+                    }
+                };
+
+            // Labels and flags are in the node index, so nodes are only read from the DB
+            // when we need to check when they can be attempted again
+            let candidates: Vec<NodeId> = match node_ids {
+                Some(node_ids) => node_ids
+                    .into_iter()
+                    .filter(|node_id| nodes.contains_node(node_id))
+                    .collect(),
+                None => nodes.get_node_ids(),
+            };
+            // Links which the crawl budget does not allow, or which are throttled, are not
+            // checked again on every scan
+            let parked = self.work_queue.get_parked();
+            candidates
+                .into_iter()
+                .filter(|node_id| !parked.contains(node_id))
+                .filter_map(|node_id| {
+                    let index_entry = nodes.get_index_entry(&node_id)?;
+                    // Only nodes with a processor, and whose labels are not in a dependency cycle
+                    let position = index_entry
+                        .labels
+                        .iter()
+                        .filter_map(|label| label_order.get(label))
+                        .max()?;
                     // Skip nodes that are not ready to be processed:
                     // - If the node has one of the flags to be skipped
-                    // - If the node depends on other nodes having been processed
+                    // - If the node had an error and it is not yet time for the next attempt
+                    // - If the node depends on other nodes which have not been processed,
+                    //   these nodes wait in the work queue till some node is processed
                     if flags_to_be_skipped
                         .iter()
-                        .any(|flag| item.1.flags.contains(flag.clone()))
-                        || (processing_dependencies.iter().any(|(label, dependencies)| {
-                            item.1.labels.contains(label)
-                                && dependencies.iter().all(|dependency| {
-                                    *processing_status.get(dependency).unwrap_or_else(|| &false)
-                                })
-                        }))
+                        .any(|flag| index_entry.flags.contains(flag.clone()))
+                        || (is_crawl_stopped && index_entry.labels.contains(&NodeLabel::Link))
+                        || (index_entry.flags.contains(NodeFlags::HAD_ERROR)
+                            && nodes
+                                .get_node(&self.arced_db, &node_id)
+                                .ok()
+                                .flatten()
+                                .and_then(|node| node.next_attempt_at)
+                                .is_some_and(|next_attempt_at| current_time < next_attempt_at))
                     {
                         None
-                    } else if item.1.flags.contains(NodeFlags::HAD_ERROR) {
-                        if current_time - item.1.written_at > TimeDelta::seconds(60) {
-                            Some(*item.0.deref())
-                        } else {
-                            None
-                        }
-                    } else {
-                        if node_count < 100
-                            && all_labels_to_be_processed
+                    } else if !index_entry.labels.iter().all(|label| {
+                        match dependencies_by_label.get(label) {
+                            Some(dependencies) => dependencies
                                 .iter()
-                                .any(|label| item.1.labels.contains(label))
-                        {
-                            node_count += 1;
-                            Some(*item.0.deref())
-                        } else if limited_labels_to_be_processed
-                            .iter()
-                            .any(|label| item.1.labels.contains(label))
-                        {
-                            Some(*item.0.deref())
-                        } else {
-                            None
+                                .all(|dependency| is_dependency_met(&node_id, dependency)),
+                            None => true,
                         }
+                    }) {
+                        self.work_queue.mark_waiting(node_id);
+                        None
+                    } else {
+                        Some((*position, node_id))
                     }
                 })
                 .collect()
         };
-        node_ids.sort();
+        // Nodes are processed after the nodes they depend on, oldest first
+        ready_queue.sort();
 
-        // info!(
-        //     "Processing nodes {}",
-        //     node_ids.iter().map(|x| x.to_string()).join(", ")
-        // );
-        for node_id in node_ids {
-            if let Some(node) = self.get_node_by_id(&node_id) {
+        for (_, node_id) in ready_queue {
+            if let Some(node) = self.get_node_with_content(&node_id) {
                 match node.process(arced_self.clone()) {
                     Ok(_) => {}
                     Err(error) => {
@@ -366,24 +792,398 @@ impl Engine {
         }
     }
 
-    fn create_node(&self, id: NodeId, payload: Payload, labels: Vec<NodeLabel>) -> PiResult<()> {
-        let arced_id = Arc::new(id);
+    fn lock_pending_writes(&self) -> PiResult<MutexGuard<'_, HashMap<ThreadId, PendingWrites>>> {
+        self.pending_writes.lock().map_err(|err| {
+            error!("Error locking pending writes: {}", err);
+            PiError::InternalError(format!("Error locking pending writes: {}", err))
+        })
+    }
 
+    // Groups all changes made in `write` on the calling thread into one atomic write to the DB,
+    // so a crash cannot leave edges to nodes which were never saved. Each changed chunk
+    // is serialized once. The changes are saved even if `write` fails, since they are
+    // already in memory and other nodes may have seen them
+    pub fn batch_writes<T>(&self, write: impl FnOnce() -> PiResult<T>) -> PiResult<T> {
+        let thread_id = thread::current().id();
+        self.lock_pending_writes()?
+            .entry(thread_id)
+            .or_default()
+            .depth += 1;
+        let result = write();
+        let mut pending_writes = {
+            let mut all_pending_writes = self.lock_pending_writes()?;
+            let Some(pending_writes) = all_pending_writes.get_mut(&thread_id) else {
+                return Err(PiError::InternalError(
+                    "Pending writes of the batch are missing".to_string(),
+                ));
+            };
+            pending_writes.depth -= 1;
+            if pending_writes.depth > 0 {
+                return result;
+            }
+            all_pending_writes.remove(&thread_id).unwrap_or_default()
+        };
+        let dirty_node_ids = std::mem::take(&mut pending_writes.dirty_node_ids);
+        let saved = self.write_pending_chunks(pending_writes);
+        // Nodes changed in the batch are processed only after all of its changes,
+        // like the edges of a new node, are made
+        for node_id in dirty_node_ids {
+            self.work_queue.mark_dirty(node_id);
+        }
+        let value = result?;
+        saved?;
+        Ok(value)
+    }
+
+    // Calls `defer` with the changes of the batch on the calling thread, if there is one.
+    // Returns false when the write is not part of a batch and has to be saved now
+    fn defer_write(&self, defer: impl FnOnce(&mut PendingWrites)) -> PiResult<bool> {
+        match self.lock_pending_writes()?.get_mut(&thread::current().id()) {
+            Some(pending_writes) => {
+                defer(pending_writes);
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+
+    // Queues a changed node to be processed, at the end of the current batch if there is one
+    fn mark_dirty(&self, node_id: NodeId) -> PiResult<()> {
+        if !self.defer_write(|pending_writes| {
+            pending_writes.dirty_node_ids.insert(node_id);
+        })? {
+            self.work_queue.mark_dirty(node_id);
+        }
+        Ok(())
+    }
+
+    // Writes a key which is not part of a node or edge chunk, a value of None deletes it
+    fn put_key(&self, key: String, value: Option<Vec<u8>>) -> PiResult<()> {
+        self.put_keys(vec![(key, value)])
+    }
+
+    // Writes keys with the current batch, or together when there is no batch
+    fn put_keys(&self, writes: Vec<(String, Option<Vec<u8>>)>) -> PiResult<()> {
+        let mut writes = Some(writes);
+        if self.defer_write(|pending_writes| {
+            if let Some(writes) = writes.take() {
+                pending_writes.keys.extend(writes);
+            }
+        })? {
+            return Ok(());
+        }
+        let mut batch = WriteBatch::default();
+        for (key, value) in writes.unwrap_or_default() {
+            match value {
+                Some(value) => batch.put(key, value),
+                None => batch.delete(key),
+            }
+        }
+        self.arced_db.write(batch)?;
+        Ok(())
+    }
+
+    // Reads a key written by `put_key`, including writes of the current batch
+    fn get_key(&self, key: &str) -> PiResult<Option<Vec<u8>>> {
+        if let Some(value) = self
+            .lock_pending_writes()?
+            .get(&thread::current().id())
+            .and_then(|pending_writes| pending_writes.keys.get(key))
+        {
+            return Ok(value.clone());
+        }
+        Ok(self.arced_db.get(key)?)
+    }
+
+    fn write_pending_chunks(&self, pending_writes: PendingWrites) -> PiResult<()> {
+        if pending_writes.node_chunks.is_empty()
+            && pending_writes.edge_chunks.is_empty()
+            && pending_writes.embedding_chunks.is_empty()
+            && pending_writes.keys.is_empty()
+        {
+            return Ok(());
+        }
+        let nodes = match self.nodes.read() {
+            Ok(nodes) => nodes,
+            Err(err) => {
+                return Err(PiError::InternalError(format!(
+                    "Error locking nodes: {}",
+                    err
+                )));
+            }
+        };
+        let edges = match self.edges.read() {
+            Ok(edges) => edges,
+            Err(err) => {
+                return Err(PiError::InternalError(format!(
+                    "Error locking edges: {}",
+                    err
+                )));
+            }
+        };
+        let mut batch = WriteBatch::default();
+        for node_id in pending_writes.node_chunks.values() {
+            nodes.write_chunk_to_batch(&mut batch, node_id)?;
+        }
+        for node_id in pending_writes.edge_chunks.values() {
+            edges.write_chunk_to_batch(&mut batch, node_id)?;
+        }
+        if !pending_writes.embedding_chunks.is_empty() {
+            match self.embeddings.read() {
+                Ok(embeddings) => embeddings.write_chunks_to_batch(
+                    &mut batch,
+                    pending_writes.embedding_chunks.into_iter(),
+                )?,
+                Err(err) => {
+                    return Err(PiError::InternalError(format!(
+                        "Error locking embeddings: {}",
+                        err
+                    )));
+                }
+            }
+        }
+        for (key, value) in pending_writes.keys {
+            match value {
+                Some(value) => batch.put(key, value),
+                None => batch.delete(key),
+            }
+        }
+        self.arced_db.write(batch)?;
+        for node_id in pending_writes.node_chunks.values() {
+            nodes.mark_chunk_saved(node_id)?;
+        }
+        for node_id in pending_writes.edge_chunks.values() {
+            edges.mark_chunk_saved(node_id)?;
+        }
+        Ok(())
+    }
+
+    // Saves the chunk of this node now, or at the end of the current batch of writes
+    fn save_node_chunk(&self, nodes: &Nodes, node_id: &NodeId) -> PiResult<()> {
+        if self.defer_write(|pending_writes| {
+            pending_writes
+                .node_chunks
+                .entry(get_chunk_id_and_node_ids(node_id).0)
+                .or_insert(*node_id);
+        })? {
+            return Ok(());
+        }
+        nodes.save_item_chunk_to_disk(self.arced_db.clone(), node_id)
+    }
+
+    fn save_edge_chunk(&self, edges: &Edges, node_id: &NodeId) -> PiResult<()> {
+        if self.defer_write(|pending_writes| {
+            pending_writes
+                .edge_chunks
+                .entry(get_chunk_id_and_node_ids(node_id).0)
+                .or_insert(*node_id);
+        })? {
+            return Ok(());
+        }
+        edges.save_item_chunk_to_disk(self.arced_db.clone(), node_id)
+    }
+
+    // Saves the embedding chunks of these nodes now, or at the end of the current batch of writes
+    fn save_embedding_chunks(&self, embeddings: &Embeddings, node_ids: &[NodeId]) -> PiResult<()> {
+        if self.defer_write(|pending_writes| {
+            pending_writes
+                .embedding_chunks
+                .extend(node_ids.iter().copied());
+        })? {
+            return Ok(());
+        }
+        let mut batch = WriteBatch::default();
+        embeddings.write_chunks_to_batch(&mut batch, node_ids.iter().copied())?;
+        self.arced_db.write(batch)?;
+        Ok(())
+    }
+
+    // Writes the full-text index for a node whose indexed text changed, see `get_indexed_text`
+    fn update_text_index(
+        &self,
+        node_id: &NodeId,
+        previous_text: Option<&str>,
+        text: Option<&str>,
+    ) -> PiResult<()> {
+        if previous_text.is_none() && text.is_none() {
+            return Ok(());
+        }
+        match self.text_index.write() {
+            Ok(mut text_index) => {
+                let writes = text_index.update_node(node_id, previous_text, text)?;
+                self.put_keys(writes)?;
+            }
+            Err(err) => {
+                error!("Error locking text index: {}", err);
+                return Err(PiError::InternalError(format!(
+                    "Error locking text index: {}",
+                    err
+                )));
+            }
+        }
+        if previous_text != text {
+            self.update_embedding(node_id, text.is_some())?;
+        }
+        Ok(())
+    }
+
+    // Removes the embedding of a node whose text changed, the node is embedded again in the
+    // ticker if it still has text
+    fn update_embedding(&self, node_id: &NodeId, has_text: bool) -> PiResult<()> {
+        match self.embeddings.write() {
+            Ok(mut embeddings) => {
+                if embeddings.remove(node_id) {
+                    self.save_embedding_chunks(&embeddings, &[*node_id])?;
+                }
+            }
+            Err(err) => {
+                error!("Error locking embeddings: {}", err);
+                return Err(PiError::InternalError(format!(
+                    "Error locking embeddings: {}",
+                    err
+                )));
+            }
+        }
+        let mut embedding_queue = self.lock_embedding_queue()?;
+        if has_text {
+            embedding_queue.insert(*node_id);
+        } else {
+            embedding_queue.remove(node_id);
+        }
+        Ok(())
+    }
+
+    fn lock_embedding_queue(&self) -> PiResult<MutexGuard<'_, BTreeSet<NodeId>>> {
+        self.embedding_queue.lock().map_err(|err| {
+            error!("Error locking embedding queue: {}", err);
+            PiError::InternalError(format!("Error locking embedding queue: {}", err))
+        })
+    }
+
+    fn get_embedder(&self) -> Option<ArcedEmbedder> {
+        self.embedder
+            .get_or_init(|| match TextEmbedder::load() {
+                Ok(Some(text_embedder)) => Some(Arc::new(text_embedder)),
+                Ok(None) => {
+                    info!("Embedding model is not installed, semantic search is not available");
+                    None
+                }
+                Err(err) => {
+                    error!("Could not load embedding model: {}", err);
+                    None
+                }
+            })
+            .clone()
+    }
+
+    // Used instead of the local model, for example in tests. Only works before the model is loaded
+    pub fn set_embedder(&self, embedder: ArcedEmbedder) {
+        if self.embedder.set(Some(embedder)).is_err() {
+            error!("Embedder is already set for project {}", self.project_uuid);
+        }
+    }
+
+    // Embeds the content nodes which are waiting in the queue, a few batches at a time
+    pub fn embed_pending_nodes(&self) -> PiResult<()> {
+        if self.lock_embedding_queue()?.is_empty() {
+            return Ok(());
+        }
+        let Some(embedder) = self.get_embedder() else {
+            return Ok(());
+        };
+        for _ in 0..EMBEDDING_BATCHES_PER_TICK {
+            // Nodes leave the queue before they are embedded, nodes which change meanwhile
+            // are queued again
+            let node_ids: Vec<NodeId> = {
+                let mut embedding_queue = self.lock_embedding_queue()?;
+                let node_ids: Vec<NodeId> = embedding_queue
+                    .iter()
+                    .take(EMBEDDING_BATCH_SIZE)
+                    .copied()
+                    .collect();
+                for node_id in node_ids.iter() {
+                    embedding_queue.remove(node_id);
+                }
+                node_ids
+            };
+            if node_ids.is_empty() {
+                break;
+            }
+            let mut embedding_node_ids: Vec<NodeId> = vec![];
+            let mut texts: Vec<String> = vec![];
+            for node_id in node_ids {
+                if let Some(node) = self.get_node_without_content(&node_id) {
+                    if let Some(text) = get_indexed_text(&node.labels, &node.payload) {
+                        embedding_node_ids.push(node_id);
+                        texts.push(text.to_string());
+                    }
+                }
+            }
+            let vectors = embedder.embed(texts.clone())?;
+            // Nodes may have changed or been removed while they were embedded, then they
+            // are queued again or have no text. Nodes are locked so they cannot change
+            // till their embeddings are stored
+            let nodes = match self.nodes.read() {
+                Ok(nodes) => nodes,
+                Err(err) => {
+                    error!("Error locking nodes: {}", err);
+                    return Err(PiError::InternalError(format!(
+                        "Error locking nodes: {}",
+                        err
+                    )));
+                }
+            };
+            let mut items: Vec<(NodeId, Vec<f32>)> = vec![];
+            for ((node_id, text), vector) in embedding_node_ids.into_iter().zip(texts).zip(vectors)
+            {
+                let is_unchanged = nodes
+                    .get_node(&self.arced_db, &node_id)?
+                    .is_some_and(|node| {
+                        get_indexed_text(&node.labels, &node.payload) == Some(text.as_str())
+                    });
+                if is_unchanged {
+                    items.push((node_id, vector));
+                }
+            }
+            match self.embeddings.write() {
+                Ok(mut embeddings) => {
+                    let node_ids: Vec<NodeId> = items.iter().map(|(node_id, _)| *node_id).collect();
+                    embeddings.insert(items);
+                    self.save_embedding_chunks(&embeddings, &node_ids)?;
+                }
+                Err(err) => {
+                    error!("Error locking embeddings: {}", err);
+                    return Err(PiError::InternalError(format!(
+                        "Error locking embeddings: {}",
+                        err
+                    )));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn create_node(&self, id: NodeId, payload: Payload, labels: Vec<NodeLabel>) -> PiResult<()> {
+        let text = get_indexed_text(&labels, &payload).map(|text| text.to_string());
         // Store the node in the engine
         match self.nodes.write() {
             Ok(mut nodes) => {
-                nodes.data.insert(
-                    arced_id.clone(),
-                    Arc::new(NodeItem {
+                nodes.insert_node(
+                    &self.arced_db,
+                    NodeItem {
                         id,
                         payload,
 
                         labels,
                         flags: NodeFlags::default(),
                         written_at: Utc::now(),
-                    }),
-                );
-                nodes.save_item_chunk_to_disk(self.arced_db.clone(), &id)?;
+                        retry_count: 0,
+                        next_attempt_at: None,
+                    },
+                )?;
+                self.save_node_chunk(&nodes, &id)?;
+                self.update_text_index(&id, None, text.as_deref())?;
+                self.mark_dirty(id)?;
             }
             Err(error) => {
                 return Err(PiError::InternalError(format!(
@@ -413,81 +1213,236 @@ impl Engine {
             ));
         }
 
+        Ok(ExistingOrNewNodeId::New(self.add_node(payload, labels)?))
+    }
+
+    // Adds a node even if a node with the same payload exists, like a question asked again
+    pub fn add_node(&self, payload: Payload, labels: Vec<NodeLabel>) -> PiResult<NodeId> {
         let id = {
             self.last_node_id
                 .fetch_add(1, std::sync::atomic::Ordering::Relaxed)
         };
+        self.create_node(id, payload, labels)?;
+        Ok(id)
+    }
 
-        match self.create_node(id, payload, labels) {
-            Ok(_) => {}
-            Err(err) => {
-                return Err(err);
+    pub fn add_connection(
+        &self,
+        node_ids: (NodeId, NodeId),
+        edge_labels: (EdgeLabel, EdgeLabel),
+    ) -> PiResult<bool> {
+        self.add_connection_with_properties(node_ids, edge_labels, EdgeProperties::default())
+    }
+
+    pub fn add_connection_with_properties(
+        &self,
+        node_ids: (NodeId, NodeId),
+        edge_labels: (EdgeLabel, EdgeLabel),
+        edge_properties: EdgeProperties,
+    ) -> PiResult<bool> {
+        // Add a connection edge from the parent node to the new node and vice versa,
+        // both edges have the same properties. Edges which exist already are not added again,
+        // returns true if any edge was added
+        self.batch_writes(|| match self.edges.write() {
+            Ok(mut edges) => {
+                let added = edges.add_edge(
+                    &self.arced_db,
+                    &node_ids.0,
+                    &node_ids.1,
+                    edge_labels.0,
+                    edge_properties.clone(),
+                )?;
+                let added_reverse = edges.add_edge(
+                    &self.arced_db,
+                    &node_ids.1,
+                    &node_ids.0,
+                    edge_labels.1,
+                    edge_properties,
+                )?;
+                if added {
+                    self.save_edge_chunk(&edges, &node_ids.0)?;
+                }
+                if added_reverse {
+                    self.save_edge_chunk(&edges, &node_ids.1)?;
+                }
+                Ok(added || added_reverse)
             }
-        };
-        Ok(ExistingOrNewNodeId::New(id))
+            Err(err) => Err(PiError::InternalError(format!(
+                "Error locking edges: {}",
+                err
+            ))),
+        })
     }
 
-    pub fn add_connection(
+    pub fn remove_connection(
         &self,
         node_ids: (NodeId, NodeId),
         edge_labels: (EdgeLabel, EdgeLabel),
     ) -> PiResult<()> {
-        let arced_node_ids = (Arc::new(node_ids.0), Arc::new(node_ids.1));
-        // let arced_edge_labels = (edge_labels.0, edge_labels.1);
-        // Add a connection edge from the parent node to the new node and vice versa
-        match self.edges.write() {
+        // Remove the connection edge from the parent node to the child node and vice versa
+        self.batch_writes(|| match self.edges.write() {
             Ok(mut edges) => {
-                edges
-                    .data
-                    .entry(arced_node_ids.0.clone())
-                    .or_insert(NodeEdges {
-                        edges: vec![],
-                        written_at: Utc::now(),
-                    });
-                edges
-                    .data
-                    .entry(arced_node_ids.1.clone())
-                    .or_insert(NodeEdges {
-                        edges: vec![],
-                        written_at: Utc::now(),
-                    });
-                // Update connections data for the parent node
-                edges
-                    .data
-                    .get_mut(&arced_node_ids.0)
-                    .unwrap()
-                    .edges
-                    .push((*arced_node_ids.1, edge_labels.0));
-                // Update the last written time for the parent node
-                edges.data.get_mut(&arced_node_ids.0).unwrap().written_at = Utc::now();
-
-                // Update connections data for the child node
-                edges
-                    .data
-                    .get_mut(&arced_node_ids.1)
-                    .unwrap()
-                    .edges
-                    .push((*arced_node_ids.0, edge_labels.1));
-                // Update the last written time for the child node
-                edges.data.get_mut(&arced_node_ids.1).unwrap().written_at = Utc::now();
-                edges.save_item_chunk_to_disk(self.arced_db.clone(), &node_ids.0)?;
-                edges.save_item_chunk_to_disk(self.arced_db.clone(), &node_ids.1)?;
+                let removed_from_parent = edges.remove_edges(
+                    &self.arced_db,
+                    &node_ids.0,
+                    &node_ids.1,
+                    Some(&edge_labels.0),
+                )?;
+                let removed_from_child = edges.remove_edges(
+                    &self.arced_db,
+                    &node_ids.1,
+                    &node_ids.0,
+                    Some(&edge_labels.1),
+                )?;
+                if !removed_from_parent && !removed_from_child {
+                    return Err(PiError::GraphError(format!(
+                        "Cannot find edge {} from node {} to node {}",
+                        edge_labels.0, node_ids.0, node_ids.1
+                    )));
+                }
+                self.save_edge_chunk(&edges, &node_ids.0)?;
+                self.save_edge_chunk(&edges, &node_ids.1)
+            }
+            Err(err) => Err(PiError::InternalError(format!(
+                "Error locking edges: {}",
+                err
+            ))),
+        })
+    }
+
+    // Deletes a node along with all its edges. If this leaves any connected node without edges,
+    // that node is deleted too when `cascade` is set, otherwise nothing is deleted and we return
+    // an error. Returns the IDs of all deleted nodes
+    pub fn delete_node(&self, node_id: &NodeId, cascade: bool) -> PiResult<Vec<NodeId>> {
+        // All changed chunks are written together, once the locks below are released
+        self.batch_writes(|| self.delete_node_and_orphans(node_id, cascade))
+    }
+
+    fn delete_node_and_orphans(&self, node_id: &NodeId, cascade: bool) -> PiResult<Vec<NodeId>> {
+        // Both are locked so the graph does not change while we delete. Like everywhere else,
+        // nodes are locked before edges
+        let mut nodes = match self.nodes.write() {
+            Ok(nodes) => nodes,
+            Err(err) => {
+                return Err(PiError::InternalError(format!(
+                    "Error locking nodes: {}",
+                    err
+                )));
+            }
+        };
+        let mut edges = match self.edges.write() {
+            Ok(edges) => edges,
+            Err(err) => {
+                return Err(PiError::InternalError(format!(
+                    "Error locking edges: {}",
+                    err
+                )));
+            }
+        };
+        if !nodes.contains_node(node_id) {
+            return Err(PiError::GraphError(format!(
+                "Cannot find node with ID {}",
+                node_id
+            )));
+        }
+
+        // Find all nodes to be deleted before we change anything
+        let mut node_ids_to_delete: Vec<NodeId> = vec![*node_id];
+        let mut position = 0;
+        while position < node_ids_to_delete.len() {
+            let deleting_node_id = node_ids_to_delete[position];
+            position += 1;
+            // The response of an open fetch request needs the node
+            if nodes
+                .get_index_entry(&deleting_node_id)
+                .is_some_and(|index_entry| index_entry.flags.contains(NodeFlags::IS_REQUESTING))
+            {
+                return Err(PiError::GraphError(format!(
+                    "Node {} has an open fetch request and cannot be deleted now",
+                    deleting_node_id
+                )));
+            }
+            let Some(node_edges) = edges.get_node_edges(&self.arced_db, &deleting_node_id)? else {
+                continue;
+            };
+            for (connected_node_id, _, _) in node_edges.edges.iter() {
+                if node_ids_to_delete.contains(connected_node_id) {
+                    continue;
+                }
+                let is_orphaned = match edges.get_node_edges(&self.arced_db, connected_node_id)? {
+                    Some(connected_node_edges) => connected_node_edges
+                        .edges
+                        .iter()
+                        .all(|(x_node_id, _, _)| node_ids_to_delete.contains(x_node_id)),
+                    None => true,
+                };
+                if is_orphaned {
+                    if !cascade {
+                        return Err(PiError::GraphError(format!(
+                            "Deleting node {} would leave node {} without any edges",
+                            node_id, connected_node_id
+                        )));
+                    }
+                    node_ids_to_delete.push(*connected_node_id);
+                }
+            }
+        }
+
+        let mut node_ids_with_changed_edges: Vec<NodeId> = vec![];
+        for deleting_node_id in node_ids_to_delete.iter() {
+            if let Some(node_edges) = edges.remove_node_edges(&self.arced_db, deleting_node_id)? {
+                for (connected_node_id, _, _) in node_edges.edges {
+                    if !node_ids_to_delete.contains(&connected_node_id) {
+                        edges.remove_edges(
+                            &self.arced_db,
+                            &connected_node_id,
+                            deleting_node_id,
+                            None,
+                        )?;
+                        node_ids_with_changed_edges.push(connected_node_id);
+                    }
+                }
             }
-            Err(err) => {
-                return Err(PiError::InternalError(format!(
-                    "Error locking edges: {}",
-                    err
-                )));
+            if let Some(node) = nodes.remove_node(&self.arced_db, deleting_node_id)? {
+                let text = get_indexed_text(&node.labels, &node.payload);
+                self.update_text_index(deleting_node_id, text, None)?;
+                if node.labels.contains(&NodeLabel::Link) {
+                    self.put_key(format!("{}{}", LINK_FETCH_PREFIX, deleting_node_id), None)?;
+                    self.set_link_fetched_at(deleting_node_id, None)?;
+                    self.put_key(
+                        format!("{}{}", LINK_DISCOVERY_PREFIX, deleting_node_id),
+                        None,
+                    )?;
+                }
             }
-        };
-        Ok(())
+        }
+
+        for deleting_node_id in node_ids_to_delete.iter() {
+            self.save_node_chunk(&nodes, deleting_node_id)?;
+        }
+        for changed_node_id in node_ids_to_delete
+            .iter()
+            .chain(node_ids_with_changed_edges.iter())
+        {
+            self.save_edge_chunk(&edges, changed_node_id)?;
+        }
+        Ok(node_ids_to_delete)
     }
 
     pub fn update_node(&self, node_id: &NodeId, payload: Payload) -> PiResult<()> {
         match self.nodes.write() {
             Ok(mut nodes) => {
-                nodes.update_node(node_id, payload)?;
-                nodes.save_item_chunk_to_disk(self.arced_db.clone(), node_id)?;
+                let (previous_text, text) = match nodes.get_node(&self.arced_db, node_id)? {
+                    Some(node) => (
+                        get_indexed_text(&node.labels, &node.payload).map(|x| x.to_string()),
+                        get_indexed_text(&node.labels, &payload).map(|x| x.to_string()),
+                    ),
+                    None => (None, None),
+                };
+                nodes.update_node(&self.arced_db, node_id, payload)?;
+                self.save_node_chunk(&nodes, node_id)?;
+                self.update_text_index(node_id, previous_text.as_deref(), text.as_deref())?;
+                self.mark_dirty(*node_id)?;
                 Ok(())
             }
             Err(err) => {
@@ -544,7 +1499,7 @@ impl Engine {
                 )));
             }
         };
-        Ok(edges.data.get(my_node_id).cloned())
+        edges.get_node_edges(&self.arced_db, my_node_id)
     }
 
     pub fn get_node_ids_connected_with_label(
@@ -566,8 +1521,8 @@ impl Engine {
             }
         };
         let mut connected_node_ids: Vec<NodeId> = vec![];
-        if let Some(edges_from_node) = edges.data.get(my_node_id) {
-            for (node_id, node_label) in edges_from_node.edges.iter() {
+        if let Some(edges_from_node) = edges.get_node_edges(&self.arced_db, my_node_id)? {
+            for (node_id, node_label, _) in edges_from_node.edges.iter() {
                 if node_label == my_edge_to_other && !connected_node_ids.contains(node_id) {
                     connected_node_ids.push(node_id.clone());
                 }
@@ -585,11 +1540,14 @@ impl Engine {
             .load(std::sync::atomic::Ordering::Relaxed)
             >= 5
         {
+            self.work_queue
+                .mark_waiting(fetch_request.requesting_node_id);
             return Ok(());
         }
 
         let engine = Arc::new(self);
-        let calling_node = match engine.get_node_by_id(&fetch_request.requesting_node_id) {
+        let calling_node = match engine.get_node_without_content(&fetch_request.requesting_node_id)
+        {
             Some(node) => node,
             None => {
                 error!("Cannot find link node for URL {}", &fetch_request.url);
@@ -651,6 +1609,31 @@ impl Engine {
             ));
         };
 
+        // Fetching robots.txt of a domain is not limited by the crawl budget
+        let is_page_fetch = calling_node.id != domain.id;
+        if is_page_fetch
+            && (domain.flags.contains(NodeFlags::IS_BLOCKED)
+                || domain.flags.contains(NodeFlags::GAVE_UP))
+        {
+            // The link cannot be crawled without the robots.txt of its domain, it is blocked
+            // so the link and nodes which depend on it do not wait for the domain forever
+            FetchErrorDetails::add(
+                engine.clone(),
+                &calling_node.id,
+                FetchErrorDetails {
+                    url: fetch_request.url.clone(),
+                    status: None,
+                    kind: FetchErrorKind::DomainUnavailable,
+                    message: format!(
+                        "URL {} cannot be crawled since its domain is blocked or its robots.txt could not be fetched",
+                        &fetch_request.url,
+                    ),
+                    failed_at: Utc::now(),
+                },
+            )?;
+            self.toggle_flag(&calling_node.id, NodeFlags::IS_BLOCKED)?;
+            return Ok(());
+        }
         if domain.flags.contains(NodeFlags::IS_BLOCKED) {
             // debug!("Domain is blocked, cannot fetch");
             return Ok(());
@@ -658,6 +1641,24 @@ impl Engine {
 
         let domain_name = Domain::get_domain_name(&domain)?;
 
+        if is_page_fetch {
+            // The link is not checked again till the budget changes, see `CrawlBudget::save`
+            if CrawlBudget::check(engine.clone())?.is_some() {
+                self.work_queue
+                    .mark_waiting_for_budget(fetch_request.requesting_node_id);
+                return Ok(());
+            }
+            if !CrawlBudget::can_fetch_link(engine.clone(), &calling_node.id, &domain_name)? {
+                debug!(
+                    "Crawl budget does not allow fetching URL {}",
+                    &fetch_request.url
+                );
+                self.work_queue
+                    .mark_waiting_for_budget(fetch_request.requesting_node_id);
+                return Ok(());
+            }
+        }
+
         // Find the RobotsTxt node connected to the domain node
         match &domain.payload {
             Payload::Text(text) => {
@@ -665,12 +1666,14 @@ impl Engine {
                     let connected_node_ids =
                         self.get_node_ids_connected_with_label(&domain.id, &EdgeLabel::OwnerOf)?;
                     if connected_node_ids.len() == 0 {
-                        // We will try to fetch the robots.txt file in the next tick
+                        // We will try again once the domain has fetched its robots.txt file
                         debug!("robots.txt node not found for domain {}", text);
+                        self.work_queue
+                            .mark_waiting(fetch_request.requesting_node_id);
                         return Ok(());
                     }
                     for connected_node_id in connected_node_ids {
-                        match self.get_node_by_id(&connected_node_id) {
+                        match self.get_node_without_content(&connected_node_id) {
                             Some(node) => match node.payload {
                                 Payload::Text(ref robots_txt) => {
                                     if robots_txt.is_empty() {
@@ -689,10 +1692,26 @@ impl Engine {
                                             };
                                         // Check if we can crawl
                                         if !robot.allowed(&fetch_request.url) {
-                                            return Err(PiError::FetchError(format!(
-                                                "URL {} is not allowed to crawl by robots.txt",
-                                                &fetch_request.url,
-                                            )));
+                                            // The node stays blocked, we keep the reason in the graph
+                                            FetchErrorDetails::add(
+                                                engine.clone(),
+                                                &calling_node.id,
+                                                FetchErrorDetails {
+                                                    url: fetch_request.url.clone(),
+                                                    status: None,
+                                                    kind: FetchErrorKind::RobotsDenied,
+                                                    message: format!(
+                                                        "URL {} is not allowed to crawl by robots.txt",
+                                                        &fetch_request.url,
+                                                    ),
+                                                    failed_at: Utc::now(),
+                                                },
+                                            )?;
+                                            self.toggle_flag(
+                                                &calling_node.id,
+                                                NodeFlags::IS_BLOCKED,
+                                            )?;
+                                            return Ok(());
                                         }
                                         break;
                                     }
@@ -741,10 +1760,13 @@ impl Engine {
             .load(std::sync::atomic::Ordering::Relaxed)
             >= 5
         {
+            self.work_queue
+                .mark_waiting(fetch_request.requesting_node_id);
             return Ok(());
         }
         let engine = Arc::new(self);
-        let calling_node = match engine.get_node_by_id(&fetch_request.requesting_node_id) {
+        let calling_node = match engine.get_node_without_content(&fetch_request.requesting_node_id)
+        {
             Some(node) => node,
             None => {
                 error!("Cannot find link node for URL {}", &fetch_request.url);
@@ -768,6 +1790,14 @@ impl Engine {
                 &fetch_request.url
             )));
         }
+        if Anthropic::is_llm_request(&fetch_request.url)
+            && CrawlBudget::check(engine.clone())? == Some(CrawlStopReason::MaxLLMSpend)
+        {
+            return Err(PiError::FetchError(
+                "Cannot send LLM request since the LLM spend of the crawl budget is reached"
+                    .to_string(),
+            ));
+        }
 
         self.toggle_flag(&fetch_request.requesting_node_id, NodeFlags::IS_REQUESTING)?;
         self.count_open_fetch_requests
@@ -795,15 +1825,21 @@ impl Engine {
                 return vec![];
             }
         };
-        let mut labels: HashSet<NodeLabel> = HashSet::new();
-        for node in nodes.data.values() {
-            labels.extend(node.labels.iter().cloned());
-        }
-        labels.iter().map(|x| x.clone()).collect()
+        nodes.get_all_labels()
+    }
+
+    pub fn get_all_node_ids(&self) -> Vec<NodeId> {
+        let nodes = match self.nodes.read() {
+            Ok(nodes) => nodes,
+            Err(err) => {
+                error!("Could not lock nodes in get_all_node_ids: {}", err);
+                return vec![];
+            }
+        };
+        nodes.get_node_ids()
     }
 
     pub fn get_node_ids_with_label(&self, label: &NodeLabel) -> Vec<ArcedNodeId> {
-        // TODO: Use a cached HashMap of node_ids_by_label
         let nodes = match self.nodes.read() {
             Ok(nodes) => nodes,
             Err(err) => {
@@ -812,18 +1848,37 @@ impl Engine {
             }
         };
         nodes
-            .data
-            .iter()
-            .filter_map(|(id, node)| {
-                if node.labels.contains(label) {
-                    Some(id.clone())
-                } else {
-                    None
-                }
-            })
+            .get_node_ids_with_label(label)
+            .into_iter()
+            .map(Arc::new)
             .collect()
     }
 
+    // Finds a node with the given label and exactly the same payload, using the payload hash index
+    pub fn find_node_with_payload(
+        &self,
+        label: &NodeLabel,
+        payload: &Payload,
+    ) -> PiResult<Option<ArcedNodeItem>> {
+        let nodes = match self.nodes.read() {
+            Ok(nodes) => nodes,
+            Err(err) => {
+                error!("Could not lock nodes in find_node_with_payload: {}", err);
+                return Err(PiError::InternalError(format!(
+                    "Could not lock nodes in find_node_with_payload: {}",
+                    err
+                )));
+            }
+        };
+        match nodes
+            .get_node_ids_with_payload(&self.arced_db, label, payload)?
+            .first()
+        {
+            Some(node_id) => nodes.get_node(&self.arced_db, node_id),
+            None => Ok(None),
+        }
+    }
+
     pub fn map_nodes(
         &self,
         f: impl Fn(&ArcedNodeId, &ArcedNodeItem) -> Option<NodeItem>,
@@ -841,11 +1896,11 @@ impl Engine {
                     )));
                 }
             };
-            nodes.data.keys().cloned().collect()
+            nodes.get_node_ids().into_iter().map(Arc::new).collect()
         };
         let mut results: Vec<Option<NodeItem>> = vec![];
         for node_id in node_ids {
-            let node = self.get_node_by_id(&node_id);
+            let node = self.get_node_without_content(&node_id);
             if let Some(node) = node {
                 results.push(f(&node_id, &node));
             }
@@ -861,10 +1916,31 @@ impl Engine {
                 return vec![];
             }
         };
-        nodes.data.values().map(|x| x.clone()).collect()
+        nodes
+            .get_node_ids()
+            .iter()
+            .filter_map(|node_id| match nodes.get_node(&self.arced_db, node_id) {
+                Ok(node) => node,
+                Err(err) => {
+                    error!("Error reading node with ID {}: {}", node_id, err);
+                    None
+                }
+            })
+            .collect()
     }
 
     pub fn get_all_edges(&self) -> HashMap<ArcedNodeId, NodeEdges> {
+        let chunk_ids: BTreeSet<u32> = match self.nodes.read() {
+            Ok(nodes) => nodes
+                .get_node_ids()
+                .iter()
+                .map(|node_id| get_chunk_id_and_node_ids(node_id).0)
+                .collect(),
+            Err(err) => {
+                error!("Error locking nodes in get_all_edges: {}", err);
+                return HashMap::new();
+            }
+        };
         let edges = match self.edges.read() {
             Ok(edges) => edges,
             Err(err) => {
@@ -872,18 +1948,221 @@ impl Engine {
                 return HashMap::new();
             }
         };
-        edges
-            .data
-            .iter()
-            .map(|(k, v)| (k.clone(), v.clone()))
-            .collect()
+        let mut all_edges: HashMap<ArcedNodeId, NodeEdges> = HashMap::new();
+        for chunk_id in chunk_ids {
+            match edges.read_chunk(&self.arced_db, chunk_id) {
+                Ok(chunk) => {
+                    all_edges.extend(
+                        chunk
+                            .into_iter()
+                            .map(|(node_id, node_edges)| (Arc::new(node_id), node_edges)),
+                    );
+                }
+                Err(err) => {
+                    error!("Error reading chunk {} of edges: {}", chunk_id, err);
+                }
+            }
+        }
+        all_edges
+    }
+
+    // Content nodes matching a full-text query, best matches first, see `TextQuery`
+    pub fn search_text(&self, query: &str, limit: usize) -> PiResult<Vec<TextSearchResult>> {
+        let text_query = TextQuery::parse(query);
+        if text_query.is_empty() {
+            return Ok(vec![]);
+        }
+        let matching_node_ids = match self.text_index.read() {
+            Ok(text_index) => text_index.search(&self.arced_db, &text_query, limit)?,
+            Err(err) => {
+                error!("Error locking text index: {}", err);
+                return Err(PiError::InternalError(format!(
+                    "Error locking text index: {}",
+                    err
+                )));
+            }
+        };
+        let highlighted_terms = text_query.get_highlighted_terms();
+        let mut results: Vec<TextSearchResult> = vec![];
+        for (node_id, score) in matching_node_ids {
+            let Some(node) = self.get_node_without_content(&node_id) else {
+                continue;
+            };
+            let snippet = match &node.payload {
+                Payload::Text(text) => get_snippet(text, &highlighted_terms),
+                _ => vec![],
+            };
+            let (web_page_node_id, link_node_id) = self.find_web_page_and_link_of(&node_id)?;
+            results.push(TextSearchResult {
+                node_id,
+                score,
+                web_page_node_id,
+                link_node_id,
+                snippet,
+                explanation: None,
+            });
+        }
+        Ok(results)
+    }
+
+    // Content nodes closest in meaning to the query, closest first. Needs the embedding model
+    pub fn semantic_search(
+        &self,
+        query: &str,
+        limit: usize,
+    ) -> PiResult<Vec<SemanticSearchResult>> {
+        let Some(embedder) = self.get_embedder() else {
+            return Err(PiError::FeatureNotAvailable(
+                "Semantic search needs the embedding model, which is not installed".to_string(),
+            ));
+        };
+        if query.trim().is_empty() {
+            return Ok(vec![]);
+        }
+        let Some(query_vector) = embedder.embed(vec![query.to_string()])?.pop() else {
+            return Ok(vec![]);
+        };
+        let matching_node_ids = match self.embeddings.read() {
+            Ok(embeddings) => embeddings.search(&query_vector, limit),
+            Err(err) => {
+                error!("Error locking embeddings: {}", err);
+                return Err(PiError::InternalError(format!(
+                    "Error locking embeddings: {}",
+                    err
+                )));
+            }
+        };
+        let mut results: Vec<SemanticSearchResult> = vec![];
+        for (node_id, score) in matching_node_ids {
+            let (web_page_node_id, link_node_id) = self.find_web_page_and_link_of(&node_id)?;
+            results.push(SemanticSearchResult {
+                node_id,
+                score,
+                web_page_node_id,
+                link_node_id,
+            });
+        }
+        Ok(results)
+    }
+
+    // The web page of a content node, and the link which the web page was fetched from
+    pub fn find_web_page_and_link_of(
+        &self,
+        node_id: &NodeId,
+    ) -> PiResult<(Option<NodeId>, Option<NodeId>)> {
+        let web_page_node_id = self.find_web_page_of(node_id)?;
+        let link_node_id = match web_page_node_id {
+            Some(web_page_node_id) => self
+                .get_node_ids_connected_with_label(&web_page_node_id, &EdgeLabel::ContentOf)?
+                .first()
+                .copied(),
+            None => None,
+        };
+        Ok((web_page_node_id, link_node_id))
+    }
+
+    // The web page which a content node is part of. Content nodes are children of the web page,
+    // or of other content nodes like list items are of their list
+    fn find_web_page_of(&self, node_id: &NodeId) -> PiResult<Option<NodeId>> {
+        let mut node_ids_to_check: Vec<NodeId> = vec![*node_id];
+        let mut checked_node_ids: HashSet<NodeId> = HashSet::new();
+        while let Some(checking_node_id) = node_ids_to_check.pop() {
+            if !checked_node_ids.insert(checking_node_id) {
+                continue;
+            }
+            for parent_node_id in
+                self.get_node_ids_connected_with_label(&checking_node_id, &EdgeLabel::ChildOf)?
+            {
+                match self.get_node_without_content(&parent_node_id) {
+                    Some(parent_node) if parent_node.labels.contains(&NodeLabel::WebPage) => {
+                        return Ok(Some(parent_node_id));
+                    }
+                    Some(parent_node) if parent_node.labels.contains(&NodeLabel::Partial) => {
+                        node_ids_to_check.push(parent_node_id);
+                    }
+                    _ => {}
+                }
+            }
+        }
+        Ok(None)
+    }
+
+    // Records a failed attempt at processing the node. The node is attempted again after a delay,
+    // unless it has run out of attempts, in which case it is flagged with GAVE_UP
+    pub fn record_node_error(&self, node_id: &NodeId) -> PiResult<()> {
+        let retry_policy = match self.retry_policy.read() {
+            Ok(retry_policy) => retry_policy.clone(),
+            Err(err) => {
+                error!("Error locking retry policy: {}", err);
+                return Err(PiError::InternalError(format!(
+                    "Error locking retry policy: {}",
+                    err
+                )));
+            }
+        };
+        match self.nodes.write() {
+            Ok(mut nodes) => {
+                nodes.record_error(&self.arced_db, node_id, &retry_policy)?;
+                self.save_node_chunk(&nodes, node_id)?;
+                if nodes
+                    .get_index_entry(node_id)
+                    .is_some_and(|index_entry| index_entry.flags.contains(NodeFlags::GAVE_UP))
+                {
+                    // Nodes waiting for this one do not wait for it any more
+                    self.work_queue.requeue_waiting();
+                }
+                Ok(())
+            }
+            Err(err) => {
+                error!("Error locking nodes: {}", err);
+                Err(PiError::InternalError(format!(
+                    "Error locking nodes: {}",
+                    err
+                )))
+            }
+        }
+    }
+
+    // Called when an attempt at processing the node succeeded, so earlier errors are not
+    // counted against its next attempts
+    pub fn clear_node_error(&self, node_id: &NodeId) -> PiResult<()> {
+        match self.nodes.write() {
+            Ok(mut nodes) => {
+                nodes.clear_error(&self.arced_db, node_id)?;
+                self.save_node_chunk(&nodes, node_id)?;
+                Ok(())
+            }
+            Err(err) => {
+                error!("Error locking nodes: {}", err);
+                Err(PiError::InternalError(format!(
+                    "Error locking nodes: {}",
+                    err
+                )))
+            }
+        }
     }
 
     pub fn toggle_flag(&self, node_id: &NodeId, flag: NodeFlags) -> PiResult<()> {
         match self.nodes.write() {
             Ok(mut nodes) => {
-                nodes.toggle_flag(node_id, flag);
-                nodes.save_item_chunk_to_disk(self.arced_db.clone(), node_id)?;
+                nodes.toggle_flag(&self.arced_db, node_id, flag.clone())?;
+                self.save_node_chunk(&nodes, node_id)?;
+                // A request starting or finishing is not a change to be processed, the response
+                // is given to the node by the engine. Otherwise the node could be processed again
+                // between the end of its request and the handling of the response
+                if !flag.clone().difference(NodeFlags::IS_REQUESTING).is_empty() {
+                    self.mark_dirty(*node_id)?;
+                }
+                let resolving_flags =
+                    flag.intersection(NodeFlags::IS_PROCESSED | NodeFlags::IS_BLOCKED);
+                if !resolving_flags.is_empty()
+                    && nodes.get_index_entry(node_id).is_some_and(|index_entry| {
+                        index_entry.flags.intersects(resolving_flags.clone())
+                    })
+                {
+                    // Nodes waiting for their dependencies to be processed can try again
+                    self.work_queue.requeue_waiting();
+                }
                 Ok(())
             }
             Err(err) => {
@@ -897,29 +2176,560 @@ impl Engine {
     }
 }
 
+// When each link was last fetched, read from the fetch state of links. Links which were
+// processed before the fetch state was stored use the time their node was written
+fn open_recrawl_queue(db: &DB, nodes: &Nodes) -> PiResult<RecrawlQueue> {
+    let mut recrawl_queue = RecrawlQueue::default();
+    for item in db.prefix_iterator(LINK_FETCH_PREFIX) {
+        match item {
+            Ok((key, value)) => {
+                if !key.starts_with(LINK_FETCH_PREFIX.as_bytes()) {
+                    break;
+                }
+                let node_id: NodeId = String::from_utf8_lossy(&key[LINK_FETCH_PREFIX.len()..])
+                    .parse()
+                    .map_err(|_| {
+                        PiError::InternalError(format!(
+                            "Cannot read node ID from key {}",
+                            String::from_utf8_lossy(&key)
+                        ))
+                    })?;
+                let link_fetch: LinkFetch = from_versioned_bytes(&value)?;
+                recrawl_queue.set_fetched_at(node_id, Some(link_fetch.fetched_at));
+            }
+            Err(err) => {
+                error!("RocksDB error: {}", err);
+                return Err(PiError::RocksdbError(err));
+            }
+        }
+    }
+    for node_id in nodes.get_node_ids_with_label(&NodeLabel::Link) {
+        if recrawl_queue.contains(&node_id)
+            || !nodes
+                .get_index_entry(&node_id)
+                .is_some_and(|entry| entry.flags.contains(NodeFlags::IS_PROCESSED))
+        {
+            continue;
+        }
+        if let Some(node) = nodes.get_node(db, &node_id)? {
+            recrawl_queue.set_fetched_at(node_id, Some(node.written_at));
+        }
+    }
+    Ok(recrawl_queue)
+}
+
 pub fn get_test_engine() -> Engine {
+    let (temp_dir, project_uuid, _) = create_test_project_db();
+    open_test_engine(&temp_dir, &project_uuid)
+}
+
+// An empty project DB in a temporary directory, returns the directory, the project UUID and
+// the path to the DB. Tests can write data to the DB before the engine opens it
+pub fn create_test_project_db() -> (tempfile::TempDir, String, PathBuf) {
     let temp_dir = tempfile::Builder::new()
         .prefix("_path_for_test_engine")
         .tempdir()
         .expect("Failed to create temporary path for the _path_for_test_engine.");
-    let path_to_storage_dir = PathBuf::from(temp_dir.path());
     let project = Project::new(
         Some("Test project".to_string()),
         Some("Test project description".to_string()),
         ProjectOwner::Myself,
     );
-    let path_to_db = path_to_storage_dir.join(format!("{}.rocksdb", &project.uuid));
+    let path_to_db = temp_dir.path().join(format!("{}.rocksdb", &project.uuid));
     Project::create_project_db(&path_to_db).unwrap();
+    (temp_dir, project.uuid, path_to_db)
+}
+
+pub fn open_test_engine(temp_dir: &tempfile::TempDir, project_uuid: &str) -> Engine {
     let channel_for_engine = PiChannel::new();
     let main_channel = PiChannel::new();
     let pi_channel_tx = main_channel.tx.clone();
     let (fetcher_tx, _fetcher_rx) = tokio::sync::mpsc::channel::<PiEvent>(100);
     Engine::open(
-        &project.uuid,
-        &path_to_storage_dir,
+        project_uuid,
+        &PathBuf::from(temp_dir.path()),
         channel_for_engine,
         pi_channel_tx,
         fetcher_tx,
     )
     .unwrap()
 }
+
+// A paragraph which is a child of another node, like the content the scraper finds on a web page
+#[cfg(test)]
+pub fn add_content_node(engine: &Engine, parent_node_id: NodeId, text: &str) -> NodeId {
+    let node_id = add_node(
+        engine,
+        Payload::Text(text.to_string()),
+        vec![NodeLabel::Paragraph, NodeLabel::Partial],
+    );
+    connect(engine, parent_node_id, node_id);
+    node_id
+}
+
+// A node with this payload, the existing node if there is one with the same payload
+#[cfg(test)]
+pub fn add_node(engine: &Engine, payload: Payload, labels: Vec<NodeLabel>) -> NodeId {
+    engine
+        .get_or_add_node(payload, labels, true, None)
+        .unwrap()
+        .get_node_id()
+}
+
+// Connects a parent node to a child node, like the parts of a web page
+#[cfg(test)]
+pub fn connect(engine: &Engine, parent_node_id: NodeId, child_node_id: NodeId) {
+    engine
+        .add_connection(
+            (parent_node_id, child_node_id),
+            (EdgeLabel::ParentOf, EdgeLabel::ChildOf),
+        )
+        .unwrap();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ExternalData;
+
+    #[test]
+    fn test_delete_node_checks_for_orphaned_nodes() {
+        let test_engine = get_test_engine();
+        // A heading with two paragraphs, one of which is also connected to a title
+        let heading_id = add_node(
+            &test_engine,
+            Payload::Text("Heading".to_string()),
+            vec![NodeLabel::Paragraph],
+        );
+        let first_id = add_node(
+            &test_engine,
+            Payload::Text("First paragraph".to_string()),
+            vec![NodeLabel::Paragraph],
+        );
+        let second_id = add_node(
+            &test_engine,
+            Payload::Text("Second paragraph".to_string()),
+            vec![NodeLabel::Paragraph],
+        );
+        let title_id = add_node(
+            &test_engine,
+            Payload::Text("Title".to_string()),
+            vec![NodeLabel::Paragraph],
+        );
+        for paragraph_id in [first_id, second_id] {
+            test_engine
+                .add_connection(
+                    (heading_id, paragraph_id),
+                    (EdgeLabel::ParentOf, EdgeLabel::ChildOf),
+                )
+                .unwrap();
+        }
+        test_engine
+            .add_connection(
+                (title_id, second_id),
+                (EdgeLabel::ParentOf, EdgeLabel::ChildOf),
+            )
+            .unwrap();
+
+        // The first paragraph would be left without edges
+        assert!(test_engine.delete_node(&heading_id, false).is_err());
+        assert!(test_engine.get_node_without_content(&heading_id).is_some());
+        assert_eq!(
+            test_engine
+                .get_node_ids_connected_with_label(&heading_id, &EdgeLabel::ParentOf)
+                .unwrap(),
+            vec![first_id, second_id]
+        );
+
+        assert_eq!(
+            test_engine.delete_node(&heading_id, true).unwrap(),
+            vec![heading_id, first_id]
+        );
+        assert!(test_engine.get_node_without_content(&heading_id).is_none());
+        assert!(test_engine.get_node_without_content(&first_id).is_none());
+        assert!(test_engine
+            .get_connected_nodes(&first_id)
+            .unwrap()
+            .is_none());
+        assert_eq!(
+            test_engine
+                .get_node_ids_connected_with_label(&second_id, &EdgeLabel::ChildOf)
+                .unwrap(),
+            vec![title_id]
+        );
+    }
+
+    #[test]
+    fn test_remove_connection() {
+        let test_engine = get_test_engine();
+        let heading_id = add_node(
+            &test_engine,
+            Payload::Text("Heading".to_string()),
+            vec![NodeLabel::Paragraph],
+        );
+        let paragraph_id = add_node(
+            &test_engine,
+            Payload::Text("Paragraph".to_string()),
+            vec![NodeLabel::Paragraph],
+        );
+        test_engine
+            .add_connection(
+                (heading_id, paragraph_id),
+                (EdgeLabel::ParentOf, EdgeLabel::ChildOf),
+            )
+            .unwrap();
+        test_engine
+            .add_connection(
+                (heading_id, paragraph_id),
+                (EdgeLabel::Suggests, EdgeLabel::SuggestedFor),
+            )
+            .unwrap();
+
+        test_engine
+            .remove_connection(
+                (heading_id, paragraph_id),
+                (EdgeLabel::ParentOf, EdgeLabel::ChildOf),
+            )
+            .unwrap();
+        assert!(test_engine
+            .get_node_ids_connected_with_label(&heading_id, &EdgeLabel::ParentOf)
+            .unwrap()
+            .is_empty());
+        assert_eq!(
+            test_engine
+                .get_node_ids_connected_with_label(&paragraph_id, &EdgeLabel::SuggestedFor)
+                .unwrap(),
+            vec![heading_id]
+        );
+        // The edge is already removed
+        assert!(test_engine
+            .remove_connection(
+                (heading_id, paragraph_id),
+                (EdgeLabel::ParentOf, EdgeLabel::ChildOf),
+            )
+            .is_err());
+    }
+
+    #[test]
+    fn test_add_connection_again_does_not_repeat_edges() {
+        let test_engine = get_test_engine();
+        let heading_id = add_node(
+            &test_engine,
+            Payload::Text("Heading".to_string()),
+            vec![NodeLabel::Paragraph],
+        );
+        let paragraph_id = add_node(
+            &test_engine,
+            Payload::Text("Paragraph".to_string()),
+            vec![NodeLabel::Paragraph],
+        );
+        assert!(test_engine
+            .add_connection(
+                (heading_id, paragraph_id),
+                (EdgeLabel::ParentOf, EdgeLabel::ChildOf),
+            )
+            .unwrap());
+        assert!(!test_engine
+            .add_connection(
+                (heading_id, paragraph_id),
+                (EdgeLabel::ParentOf, EdgeLabel::ChildOf),
+            )
+            .unwrap());
+        // Another label between the same nodes is a different edge
+        assert!(test_engine
+            .add_connection(
+                (heading_id, paragraph_id),
+                (EdgeLabel::Suggests, EdgeLabel::SuggestedFor),
+            )
+            .unwrap());
+
+        assert_eq!(
+            test_engine
+                .get_node_ids_connected_with_label(&heading_id, &EdgeLabel::ParentOf)
+                .unwrap(),
+            vec![paragraph_id]
+        );
+        assert_eq!(
+            test_engine
+                .get_connected_nodes(&paragraph_id)
+                .unwrap()
+                .unwrap()
+                .edges
+                .len(),
+            2
+        );
+    }
+
+    #[test]
+    fn test_batch_writes_are_saved_together() {
+        let test_engine = get_test_engine();
+        let (heading_id, paragraph_id) = test_engine
+            .batch_writes(|| {
+                let heading_id = add_node(
+                    &test_engine,
+                    Payload::Text("Heading".to_string()),
+                    vec![NodeLabel::Paragraph],
+                );
+                let paragraph_id = add_node(
+                    &test_engine,
+                    Payload::Text("Paragraph".to_string()),
+                    vec![NodeLabel::Paragraph],
+                );
+                test_engine.add_connection(
+                    (heading_id, paragraph_id),
+                    (EdgeLabel::ParentOf, EdgeLabel::ChildOf),
+                )?;
+                // Changes can be read before they are saved
+                assert_eq!(
+                    test_engine
+                        .get_node_ids_connected_with_label(&heading_id, &EdgeLabel::ParentOf)?,
+                    vec![paragraph_id]
+                );
+                // Nothing is written to the DB till the batch ends
+                assert!(test_engine.arced_db.get("nodes/chunk/0")?.is_none());
+                assert!(test_engine.arced_db.get("edges/chunk/0")?.is_none());
+                Ok((heading_id, paragraph_id))
+            })
+            .unwrap();
+
+        assert!(test_engine.arced_db.get("nodes/chunk/0").unwrap().is_some());
+        assert!(test_engine.arced_db.get("edges/chunk/0").unwrap().is_some());
+        assert!(test_engine.get_node_without_content(&heading_id).is_some());
+        assert!(test_engine
+            .get_node_without_content(&paragraph_id)
+            .is_some());
+    }
+
+    #[test]
+    fn test_batch_writes_on_other_threads_are_saved_separately() {
+        let test_engine = get_test_engine();
+        let discovery = LinkDiscovery {
+            depth: 1,
+            discovered_from: None,
+        };
+        test_engine
+            .batch_writes(|| {
+                test_engine.set_link_discovery(&1, &discovery)?;
+                // A batch on another thread ends without saving the changes of this one
+                std::thread::scope(|scope| {
+                    scope
+                        .spawn(|| {
+                            test_engine
+                                .batch_writes(|| test_engine.set_link_discovery(&2, &discovery))
+                        })
+                        .join()
+                        .unwrap()
+                })?;
+                assert!(test_engine.arced_db.get("links/discovery/2")?.is_some());
+                assert!(test_engine.arced_db.get("links/discovery/1")?.is_none());
+                assert_eq!(test_engine.get_link_discovery(&1)?, Some(discovery.clone()));
+                Ok(())
+            })
+            .unwrap();
+        assert!(test_engine
+            .arced_db
+            .get("links/discovery/1")
+            .unwrap()
+            .is_some());
+    }
+
+    #[test]
+    fn test_links_not_allowed_by_crawl_budget_wait_till_it_changes() {
+        let test_engine = get_test_engine();
+        let arced_test_engine = Arc::new(&test_engine);
+        let link_node_id = Link::add(
+            arced_test_engine.clone(),
+            &"https://pixlie.com/".to_string(),
+            vec![NodeLabel::AddedByUser, NodeLabel::Link],
+            vec![],
+            true,
+        )
+        .unwrap();
+        CrawlBudget {
+            max_pages: Some(0),
+            ..Default::default()
+        }
+        .save(arced_test_engine.clone())
+        .unwrap();
+        test_engine
+            .fetch(FetchRequest::new(link_node_id, "https://pixlie.com/"))
+            .unwrap();
+        assert!(test_engine.work_queue.get_parked().contains(&link_node_id));
+        test_engine.work_queue.requeue_waiting();
+        assert!(!test_engine.work_queue.take_dirty().contains(&link_node_id));
+
+        CrawlBudget {
+            max_pages: Some(10),
+            ..Default::default()
+        }
+        .save(arced_test_engine.clone())
+        .unwrap();
+        assert!(test_engine.work_queue.get_parked().is_empty());
+        assert!(test_engine.work_queue.take_dirty().contains(&link_node_id));
+    }
+
+    #[test]
+    fn test_links_are_blocked_when_their_domain_gave_up() {
+        let test_engine = get_test_engine();
+        let arced_test_engine = Arc::new(&test_engine);
+        let link_node_id = Link::add(
+            arced_test_engine.clone(),
+            &"https://pixlie.com/".to_string(),
+            vec![NodeLabel::AddedByUser, NodeLabel::Link],
+            vec![],
+            true,
+        )
+        .unwrap();
+        let domain_node_id =
+            Domain::find_existing(arced_test_engine.clone(), FindDomainOf::Node(link_node_id))
+                .unwrap()
+                .unwrap()
+                .id;
+        test_engine
+            .toggle_flag(&domain_node_id, NodeFlags::GAVE_UP)
+            .unwrap();
+
+        test_engine
+            .fetch(FetchRequest::new(link_node_id, "https://pixlie.com/"))
+            .unwrap();
+        let link_node = test_engine.get_node_without_content(&link_node_id).unwrap();
+        assert!(link_node.flags.contains(NodeFlags::IS_BLOCKED));
+        assert!(!test_engine.work_queue.get_parked().contains(&link_node_id));
+    }
+
+    #[test]
+    fn test_node_is_not_queued_again_when_its_response_arrives() {
+        let test_engine = get_test_engine();
+        let calls: Arc<Mutex<Vec<u16>>> = Arc::new(Mutex::new(vec![]));
+        {
+            let calls = calls.clone();
+            test_engine.register_processor(
+                NodeLabel::Paragraph,
+                10,
+                vec![],
+                Arc::new(
+                    move |_: &NodeItem, _: Arc<&Engine>, data: Option<ExternalData>| {
+                        if let Some(ExternalData::Response(response)) = data {
+                            calls.lock().unwrap().push(response.status);
+                        }
+                        Ok(())
+                    },
+                ),
+            );
+        }
+        let node_id = add_node(
+            &test_engine,
+            Payload::Text("A paragraph".to_string()),
+            vec![NodeLabel::Paragraph],
+        );
+        test_engine
+            .toggle_flag(&node_id, NodeFlags::IS_REQUESTING)
+            .unwrap();
+        test_engine
+            .count_open_fetch_requests
+            .fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+        test_engine.work_queue.take_dirty();
+
+        test_engine.receive_fetch_response(FetchResponse {
+            project_id: test_engine.project_uuid.clone(),
+            node_id,
+            url: "https://pixlie.com/".to_string(),
+            status: 200,
+            headers: reqwest::header::HeaderMap::new(),
+            contents: "".to_string(),
+        });
+        assert_eq!(*calls.lock().unwrap(), vec![200]);
+        assert!(!test_engine
+            .get_node_without_content(&node_id)
+            .unwrap()
+            .flags
+            .contains(NodeFlags::IS_REQUESTING));
+        assert!(!test_engine.work_queue.take_dirty().contains(&node_id));
+    }
+
+    #[test]
+    fn test_throttled_node_waits_till_it_can_be_fetched() {
+        let test_engine = get_test_engine();
+        let node_id = add_node(
+            &test_engine,
+            Payload::Text("A paragraph".to_string()),
+            vec![NodeLabel::Paragraph],
+        );
+        let waiting_node_id = add_node(
+            &test_engine,
+            Payload::Text("Another paragraph".to_string()),
+            vec![NodeLabel::Paragraph],
+        );
+        test_engine
+            .toggle_flag(&node_id, NodeFlags::IS_REQUESTING)
+            .unwrap();
+        test_engine
+            .count_open_fetch_requests
+            .fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+        test_engine.work_queue.take_dirty();
+        test_engine.work_queue.mark_waiting(waiting_node_id);
+
+        test_engine.receive_fetch_throttled(FetchThrottled {
+            project_id: test_engine.project_uuid.clone(),
+            node_id,
+            url: "https://pixlie.com/".to_string(),
+            reason: "Domain pixlie.com was recently fetched from".to_string(),
+            wait_time: Duration::from_millis(50),
+        });
+        // Other waiting nodes are not queued again, and the node waits for the domain
+        assert!(test_engine.work_queue.take_dirty().is_empty());
+        assert!(test_engine.work_queue.get_parked().contains(&node_id));
+        std::thread::sleep(Duration::from_millis(60));
+        assert_eq!(test_engine.work_queue.take_dirty(), vec![node_id]);
+    }
+
+    #[test]
+    fn test_node_added_in_batch_is_queued_when_batch_ends() {
+        let test_engine = get_test_engine();
+        let node_id = test_engine
+            .batch_writes(|| {
+                let node_id = add_node(
+                    &test_engine,
+                    Payload::Text("A paragraph".to_string()),
+                    vec![NodeLabel::Paragraph],
+                );
+                assert!(test_engine.work_queue.take_dirty().is_empty());
+                Ok(node_id)
+            })
+            .unwrap();
+        assert_eq!(test_engine.work_queue.take_dirty(), vec![node_id]);
+    }
+
+    #[test]
+    fn test_web_page_content_is_stored_separately() {
+        let test_engine = get_test_engine();
+        let html = "<html><body><p>A paragraph</p></body></html>".to_string();
+        let web_page_id = test_engine
+            .get_or_add_node(
+                Payload::Text(html.clone()),
+                vec![NodeLabel::Content, NodeLabel::WebPage],
+                true,
+                None,
+            )
+            .unwrap()
+            .get_node_id();
+
+        // The HTML is not kept with the node, it is read only when asked for
+        match &test_engine
+            .get_node_without_content(&web_page_id)
+            .unwrap()
+            .payload
+        {
+            Payload::Text(text) => assert!(text.is_empty()),
+            _ => panic!("Expected Payload::Text"),
+        }
+        match &test_engine
+            .get_node_with_content(&web_page_id)
+            .unwrap()
+            .payload
+        {
+            Payload::Text(text) => assert_eq!(text, &html),
+            _ => panic!("Expected Payload::Text"),
+        }
+    }
+}
@@ -0,0 +1,579 @@
+// Copyright 2025 Pixlie Web Solutions Pvt. Ltd.
+// Licensed under the GNU General Public License version 3.0;
+// You may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// https://github.com/pixlie/PixlieAI/blob/main/LICENSE
+
+// Exports the graph of a project to formats which other tools can read, like Gephi for GEXF
+// or spreadsheets for CSV. Large content, like the HTML of web pages, is not exported
+
+use super::node::{NodeId, Payload};
+use super::{EdgeProperties, Engine};
+use crate::error::PiResult;
+use serde::Deserialize;
+use serde_json::{json, Map, Value};
+use std::collections::{BTreeMap, HashSet};
+use strum::{Display, EnumString};
+use ts_rs::TS;
+use utoipa::ToSchema;
+
+// Node and edge labels and attributes are terms in this vocabulary in JSON-LD
+const JSON_LD_VOCABULARY: &str = "https://pixlie.com/schema/";
+
+#[derive(Clone, Deserialize, Display, EnumString, ToSchema, TS)]
+#[ts(export)]
+pub enum ExportFormat {
+    #[serde(rename = "graphml")]
+    #[strum(serialize = "graphml")]
+    GraphML,
+    #[serde(rename = "gexf")]
+    #[strum(serialize = "gexf")]
+    Gexf,
+    #[serde(rename = "jsonld")]
+    #[strum(serialize = "jsonld")]
+    JsonLd,
+    // One row per node, with a column per attribute
+    #[serde(rename = "nodes_csv")]
+    #[strum(serialize = "nodes_csv")]
+    NodesCsv,
+    // One row per edge, with the IDs of the nodes it connects
+    #[serde(rename = "edges_csv")]
+    #[strum(serialize = "edges_csv")]
+    EdgesCsv,
+}
+
+impl ExportFormat {
+    pub fn get_content_type(&self) -> &'static str {
+        match self {
+            ExportFormat::GraphML => "application/graphml+xml",
+            ExportFormat::Gexf => "application/gexf+xml",
+            ExportFormat::JsonLd => "application/ld+json",
+            ExportFormat::NodesCsv | ExportFormat::EdgesCsv => "text/csv",
+        }
+    }
+
+    pub fn get_file_name(&self, project_id: &str) -> String {
+        match self {
+            ExportFormat::GraphML => format!("{}.graphml", project_id),
+            ExportFormat::Gexf => format!("{}.gexf", project_id),
+            ExportFormat::JsonLd => format!("{}.jsonld", project_id),
+            ExportFormat::NodesCsv => format!("{}_nodes.csv", project_id),
+            ExportFormat::EdgesCsv => format!("{}_edges.csv", project_id),
+        }
+    }
+}
+
+// Attributes of nodes and edges are flat, nested values are kept as JSON
+type Attributes = BTreeMap<String, Value>;
+
+struct ExportedNode {
+    id: NodeId,
+    labels: Vec<String>,
+    attributes: Attributes,
+}
+
+struct ExportedEdge {
+    source: NodeId,
+    target: NodeId,
+    label: String,
+    attributes: Attributes,
+}
+
+struct ExportedGraph {
+    project_id: String,
+    nodes: Vec<ExportedNode>,
+    edges: Vec<ExportedEdge>,
+}
+
+// Attribute names in the order they are written, with the type of their values
+struct AttributeSchema {
+    names_and_types: Vec<(String, &'static str)>,
+}
+
+impl AttributeSchema {
+    fn new<'a>(all_attributes: impl Iterator<Item = &'a Attributes>) -> AttributeSchema {
+        let mut types: BTreeMap<String, &'static str> = BTreeMap::new();
+        for attributes in all_attributes {
+            for (name, value) in attributes {
+                let value_type = match value {
+                    Value::Number(_) => "double",
+                    Value::Bool(_) => "boolean",
+                    _ => "string",
+                };
+                types
+                    .entry(name.clone())
+                    .and_modify(|existing| {
+                        if *existing != value_type {
+                            *existing = "string";
+                        }
+                    })
+                    .or_insert(value_type);
+            }
+        }
+        AttributeSchema {
+            names_and_types: types.into_iter().collect(),
+        }
+    }
+}
+
+// Payloads are serialized as `{"Variant": data}`, the fields of the data become attributes
+pub(super) fn get_payload_attributes(payload: &Payload) -> PiResult<Attributes> {
+    let mut attributes = Attributes::new();
+    attributes.insert(
+        "payload_type".to_string(),
+        Value::String(payload.to_string()),
+    );
+    if let Value::Object(variant) = serde_json::to_value(payload)? {
+        for (_, data) in variant {
+            match data {
+                Value::Object(fields) => {
+                    for (name, value) in fields {
+                        if !value.is_null() {
+                            attributes.insert(format!("payload.{}", name), value);
+                        }
+                    }
+                }
+                Value::Null => {}
+                value => {
+                    attributes.insert("payload".to_string(), value);
+                }
+            }
+        }
+    }
+    Ok(attributes)
+}
+
+fn get_edge_attributes(properties: &EdgeProperties) -> Attributes {
+    let mut attributes = Attributes::new();
+    attributes.insert(
+        "created_by".to_string(),
+        Value::String(properties.source.to_string()),
+    );
+    attributes.insert(
+        "created_at".to_string(),
+        Value::String(properties.created_at.to_rfc3339()),
+    );
+    if let Some(weight) = properties.weight {
+        attributes.insert("weight".to_string(), json!(weight));
+    }
+    if let Some(confidence) = properties.confidence {
+        attributes.insert("confidence".to_string(), json!(confidence));
+    }
+    for (name, value) in properties.attributes.iter() {
+        attributes.insert(format!("attributes.{}", name), Value::String(value.clone()));
+    }
+    attributes
+}
+
+fn value_to_string(value: &Value) -> String {
+    match value {
+        Value::String(text) => text.clone(),
+        Value::Null => "".to_string(),
+        value => value.to_string(),
+    }
+}
+
+// Control characters other than whitespace are not allowed in XML 1.0
+fn escape_xml(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for character in text.chars() {
+        match character {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            '\t' | '\n' | '\r' => escaped.push(character),
+            character if character.is_control() => {}
+            character => escaped.push(character),
+        }
+    }
+    escaped
+}
+
+impl ExportedGraph {
+    fn to_graphml(&self) -> String {
+        let node_schema = AttributeSchema::new(self.nodes.iter().map(|node| &node.attributes));
+        let edge_schema = AttributeSchema::new(self.edges.iter().map(|edge| &edge.attributes));
+        // Keys of node attributes come first, then keys of edge attributes
+        let count_node_keys = node_schema.names_and_types.len();
+        let mut output = String::from(
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+            <graphml xmlns=\"http://graphml.graphdrawing.org/xmlns\" \
+            xmlns:xsi=\"http://www.w3.org/2001/XMLSchema-instance\" \
+            xsi:schemaLocation=\"http://graphml.graphdrawing.org/xmlns \
+            http://graphml.graphdrawing.org/xmlns/1.0/graphml.xsd\">\n",
+        );
+        output.push_str(
+            "  <key id=\"labels\" for=\"node\" attr.name=\"labels\" attr.type=\"string\"/>\n",
+        );
+        for (index, (name, value_type)) in node_schema.names_and_types.iter().enumerate() {
+            output.push_str(&format!(
+                "  <key id=\"d{}\" for=\"node\" attr.name=\"{}\" attr.type=\"{}\"/>\n",
+                index,
+                escape_xml(name),
+                value_type
+            ));
+        }
+        output.push_str(
+            "  <key id=\"label\" for=\"edge\" attr.name=\"label\" attr.type=\"string\"/>\n",
+        );
+        for (index, (name, value_type)) in edge_schema.names_and_types.iter().enumerate() {
+            output.push_str(&format!(
+                "  <key id=\"d{}\" for=\"edge\" attr.name=\"{}\" attr.type=\"{}\"/>\n",
+                count_node_keys + index,
+                escape_xml(name),
+                value_type
+            ));
+        }
+        output.push_str(&format!(
+            "  <graph id=\"{}\" edgedefault=\"directed\">\n",
+            escape_xml(&self.project_id)
+        ));
+        for node in self.nodes.iter() {
+            output.push_str(&format!("    <node id=\"n{}\">\n", node.id));
+            output.push_str(&format!(
+                "      <data key=\"labels\">{}</data>\n",
+                escape_xml(&node.labels.join(","))
+            ));
+            for (index, (name, _)) in node_schema.names_and_types.iter().enumerate() {
+                if let Some(value) = node.attributes.get(name) {
+                    output.push_str(&format!(
+                        "      <data key=\"d{}\">{}</data>\n",
+                        index,
+                        escape_xml(&value_to_string(value))
+                    ));
+                }
+            }
+            output.push_str("    </node>\n");
+        }
+        for (edge_index, edge) in self.edges.iter().enumerate() {
+            output.push_str(&format!(
+                "    <edge id=\"e{}\" source=\"n{}\" target=\"n{}\">\n",
+                edge_index, edge.source, edge.target
+            ));
+            output.push_str(&format!(
+                "      <data key=\"label\">{}</data>\n",
+                escape_xml(&edge.label)
+            ));
+            for (index, (name, _)) in edge_schema.names_and_types.iter().enumerate() {
+                if let Some(value) = edge.attributes.get(name) {
+                    output.push_str(&format!(
+                        "      <data key=\"d{}\">{}</data>\n",
+                        count_node_keys + index,
+                        escape_xml(&value_to_string(value))
+                    ));
+                }
+            }
+            output.push_str("    </edge>\n");
+        }
+        output.push_str("  </graph>\n</graphml>\n");
+        output
+    }
+
+    fn to_gexf(&self) -> String {
+        let node_schema = AttributeSchema::new(self.nodes.iter().map(|node| &node.attributes));
+        let edge_schema = AttributeSchema::new(self.edges.iter().map(|edge| &edge.attributes));
+        let mut output = String::from(
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+            <gexf xmlns=\"http://gexf.net/1.3\" version=\"1.3\">\n  \
+            <meta>\n    <creator>Pixlie AI</creator>\n  </meta>\n  \
+            <graph defaultedgetype=\"directed\" mode=\"static\">\n",
+        );
+        for (class, schema) in [("node", &node_schema), ("edge", &edge_schema)] {
+            output.push_str(&format!("    <attributes class=\"{}\">\n", class));
+            for (index, (name, value_type)) in schema.names_and_types.iter().enumerate() {
+                output.push_str(&format!(
+                    "      <attribute id=\"{}\" title=\"{}\" type=\"{}\"/>\n",
+                    index,
+                    escape_xml(name),
+                    value_type
+                ));
+            }
+            output.push_str("    </attributes>\n");
+        }
+        output.push_str("    <nodes>\n");
+        for node in self.nodes.iter() {
+            output.push_str(&format!(
+                "      <node id=\"{}\" label=\"{}\">\n        <attvalues>\n",
+                node.id,
+                escape_xml(&node.labels.join(","))
+            ));
+            for (index, (name, _)) in node_schema.names_and_types.iter().enumerate() {
+                if let Some(value) = node.attributes.get(name) {
+                    output.push_str(&format!(
+                        "          <attvalue for=\"{}\" value=\"{}\"/>\n",
+                        index,
+                        escape_xml(&value_to_string(value))
+                    ));
+                }
+            }
+            output.push_str("        </attvalues>\n      </node>\n");
+        }
+        output.push_str("    </nodes>\n    <edges>\n");
+        for (edge_index, edge) in self.edges.iter().enumerate() {
+            let weight = match edge.attributes.get("weight") {
+                Some(weight) => format!(" weight=\"{}\"", weight),
+                None => "".to_string(),
+            };
+            output.push_str(&format!(
+                "      <edge id=\"{}\" source=\"{}\" target=\"{}\" label=\"{}\"{}>\n        <attvalues>\n",
+                edge_index,
+                edge.source,
+                edge.target,
+                escape_xml(&edge.label),
+                weight
+            ));
+            for (index, (name, _)) in edge_schema.names_and_types.iter().enumerate() {
+                if let Some(value) = edge.attributes.get(name) {
+                    output.push_str(&format!(
+                        "          <attvalue for=\"{}\" value=\"{}\"/>\n",
+                        index,
+                        escape_xml(&value_to_string(value))
+                    ));
+                }
+            }
+            output.push_str("        </attvalues>\n      </edge>\n");
+        }
+        output.push_str("    </edges>\n  </graph>\n</gexf>\n");
+        output
+    }
+
+    // Node labels are types and edge labels are properties linking to other nodes.
+    // The properties of edges, like their weight, are not part of JSON-LD
+    fn to_json_ld(&self) -> PiResult<String> {
+        let mut graph: Vec<Value> = vec![];
+        for node in self.nodes.iter() {
+            let mut object = Map::new();
+            object.insert("@id".to_string(), json!(format!("_:n{}", node.id)));
+            object.insert("@type".to_string(), json!(node.labels));
+            for (name, value) in node.attributes.iter() {
+                object.insert(name.clone(), value.clone());
+            }
+            for edge in self.edges.iter().filter(|edge| edge.source == node.id) {
+                let targets = object
+                    .entry(edge.label.clone())
+                    .or_insert_with(|| Value::Array(vec![]));
+                if let Value::Array(targets) = targets {
+                    targets.push(json!({ "@id": format!("_:n{}", edge.target) }));
+                }
+            }
+            graph.push(Value::Object(object));
+        }
+        Ok(serde_json::to_string_pretty(&json!({
+            "@context": { "@vocab": JSON_LD_VOCABULARY },
+            "@graph": graph,
+        }))?)
+    }
+
+    fn to_nodes_csv(&self) -> PiResult<String> {
+        let schema = AttributeSchema::new(self.nodes.iter().map(|node| &node.attributes));
+        let mut writer = csv::Writer::from_writer(vec![]);
+        let mut header = vec!["id".to_string(), "labels".to_string()];
+        header.extend(schema.names_and_types.iter().map(|(name, _)| name.clone()));
+        writer.write_record(&header)?;
+        for node in self.nodes.iter() {
+            let mut record = vec![node.id.to_string(), node.labels.join(",")];
+            record.extend(schema.names_and_types.iter().map(|(name, _)| {
+                node.attributes
+                    .get(name)
+                    .map(value_to_string)
+                    .unwrap_or_default()
+            }));
+            writer.write_record(&record)?;
+        }
+        Self::csv_to_string(writer)
+    }
+
+    fn to_edges_csv(&self) -> PiResult<String> {
+        let schema = AttributeSchema::new(self.edges.iter().map(|edge| &edge.attributes));
+        let mut writer = csv::Writer::from_writer(vec![]);
+        let mut header = vec![
+            "source".to_string(),
+            "target".to_string(),
+            "label".to_string(),
+        ];
+        header.extend(schema.names_and_types.iter().map(|(name, _)| name.clone()));
+        writer.write_record(&header)?;
+        for edge in self.edges.iter() {
+            let mut record = vec![
+                edge.source.to_string(),
+                edge.target.to_string(),
+                edge.label.clone(),
+            ];
+            record.extend(schema.names_and_types.iter().map(|(name, _)| {
+                edge.attributes
+                    .get(name)
+                    .map(value_to_string)
+                    .unwrap_or_default()
+            }));
+            writer.write_record(&record)?;
+        }
+        Self::csv_to_string(writer)
+    }
+
+    fn csv_to_string(writer: csv::Writer<Vec<u8>>) -> PiResult<String> {
+        let bytes = writer
+            .into_inner()
+            .map_err(|err| csv::Error::from(err.into_error()))?;
+        // The CSV writer only writes the UTF-8 strings it is given
+        Ok(String::from_utf8_lossy(&bytes).to_string())
+    }
+}
+
+impl Engine {
+    fn get_exported_graph(&self) -> PiResult<ExportedGraph> {
+        let mut nodes: Vec<ExportedNode> = vec![];
+        for node in self.get_all_nodes() {
+            let mut attributes = get_payload_attributes(&node.payload)?;
+            attributes.insert(
+                "written_at".to_string(),
+                Value::String(node.written_at.to_rfc3339()),
+            );
+            nodes.push(ExportedNode {
+                id: node.id,
+                labels: node.labels.iter().map(|label| label.to_string()).collect(),
+                attributes,
+            });
+        }
+        nodes.sort_by_key(|node| node.id);
+
+        // Edges to nodes which cannot be read are left out, so every edge has both its nodes
+        let node_ids: HashSet<NodeId> = nodes.iter().map(|node| node.id).collect();
+        let mut all_edges: Vec<(NodeId, Vec<ExportedEdge>)> = self
+            .get_all_edges()
+            .into_iter()
+            .filter(|(node_id, _)| node_ids.contains(node_id))
+            .map(|(node_id, node_edges)| {
+                let edges = node_edges
+                    .edges
+                    .iter()
+                    .filter(|(target, _, _)| node_ids.contains(target))
+                    .map(|(target, edge_label, properties)| ExportedEdge {
+                        source: *node_id,
+                        target: *target,
+                        label: edge_label.to_string(),
+                        attributes: get_edge_attributes(properties),
+                    })
+                    .collect();
+                (*node_id, edges)
+            })
+            .collect();
+        all_edges.sort_by_key(|(node_id, _)| *node_id);
+
+        Ok(ExportedGraph {
+            project_id: self.get_project_id().to_string(),
+            nodes,
+            edges: all_edges.into_iter().flat_map(|(_, edges)| edges).collect(),
+        })
+    }
+
+    pub fn export(&self, format: &ExportFormat) -> PiResult<String> {
+        let graph = self.get_exported_graph()?;
+        match format {
+            ExportFormat::GraphML => Ok(graph.to_graphml()),
+            ExportFormat::Gexf => Ok(graph.to_gexf()),
+            ExportFormat::JsonLd => graph.to_json_ld(),
+            ExportFormat::NodesCsv => graph.to_nodes_csv(),
+            ExportFormat::EdgesCsv => graph.to_edges_csv(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::engine::get_test_engine;
+    use crate::engine::node::NodeLabel;
+    use crate::engine::{EdgeLabel, EdgeSource};
+    use std::str::FromStr;
+    use std::sync::Arc;
+
+    #[test]
+    fn test_export_maps_labels_and_payload_to_attributes() {
+        let test_engine = get_test_engine();
+        let arced_test_engine = Arc::new(&test_engine);
+        let question_node_id = arced_test_engine
+            .get_or_add_node(
+                Payload::Text("Who makes <fast> & safe databases?".to_string()),
+                vec![NodeLabel::AddedByUser, NodeLabel::Question],
+                true,
+                None,
+            )
+            .unwrap()
+            .get_node_id();
+        let paragraph_node_id = arced_test_engine
+            .get_or_add_node(
+                Payload::Text("RocksDB is an embedded, fast key-value store".to_string()),
+                vec![NodeLabel::Paragraph],
+                true,
+                None,
+            )
+            .unwrap()
+            .get_node_id();
+        arced_test_engine
+            .add_connection_with_properties(
+                (question_node_id, paragraph_node_id),
+                (EdgeLabel::Cites, EdgeLabel::CitedBy),
+                EdgeProperties {
+                    weight: Some(0.5),
+                    ..EdgeProperties::new(EdgeSource::Engine)
+                },
+            )
+            .unwrap();
+
+        let graphml = test_engine.export(&ExportFormat::GraphML).unwrap();
+        assert!(graphml.contains(&format!(
+            "<node id=\"n{}\">\n      <data key=\"labels\">AddedByUser,Question</data>",
+            question_node_id
+        )));
+        assert!(graphml.contains("Who makes &lt;fast&gt; &amp; safe databases?"));
+        assert!(graphml.contains(&format!(
+            "<edge id=\"e0\" source=\"n{}\" target=\"n{}\">\n      <data key=\"label\">Cites</data>",
+            question_node_id, paragraph_node_id
+        )));
+        assert!(graphml.contains("attr.name=\"weight\" attr.type=\"double\""));
+
+        let gexf = test_engine.export(&ExportFormat::Gexf).unwrap();
+        assert!(gexf.contains(&format!(
+            "source=\"{}\" target=\"{}\" label=\"Cites\" weight=\"0.5\"",
+            question_node_id, paragraph_node_id
+        )));
+
+        let json_ld: Value =
+            serde_json::from_str(&test_engine.export(&ExportFormat::JsonLd).unwrap()).unwrap();
+        let question = json_ld["@graph"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|node| node["@id"] == format!("_:n{}", question_node_id))
+            .unwrap();
+        assert_eq!(question["@type"], json!(["AddedByUser", "Question"]));
+        assert_eq!(question["payload_type"], "Text");
+        assert_eq!(
+            question["Cites"],
+            json!([{ "@id": format!("_:n{}", paragraph_node_id) }])
+        );
+
+        let nodes_csv = test_engine.export(&ExportFormat::NodesCsv).unwrap();
+        let mut lines = nodes_csv.lines();
+        assert_eq!(
+            lines.next(),
+            Some("id,labels,payload,payload_type,written_at")
+        );
+        assert!(nodes_csv.contains(&format!(
+            "{},Paragraph,\"RocksDB is an embedded, fast key-value store\",Text,",
+            paragraph_node_id
+        )));
+
+        let edges_csv = test_engine
+            .export(&ExportFormat::from_str("edges_csv").unwrap())
+            .unwrap();
+        assert_eq!(
+            edges_csv.lines().next(),
+            Some("source,target,label,created_at,created_by,weight")
+        );
+        assert_eq!(edges_csv.lines().count(), 3);
+    }
+}
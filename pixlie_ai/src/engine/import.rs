@@ -0,0 +1,388 @@
+// Copyright 2025 Pixlie Web Solutions Pvt. Ltd.
+// Licensed under the GNU General Public License version 3.0;
+// You may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// https://github.com/pixlie/PixlieAI/blob/main/LICENSE
+
+// Bulk import of links, search terms and text nodes, with edges between them.
+// Each row is imported on its own, a row which fails does not stop the others
+
+use super::node::{ExistingOrNewNodeId, NodeId, NodeLabel, Payload};
+use super::{EdgeLabel, EdgeProperties, EdgeSource, Engine};
+use crate::entity::web::link::Link;
+use crate::error::{PiError, PiResult};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Arc;
+use strum::{Display, EnumString};
+use ts_rs::TS;
+use utoipa::ToSchema;
+
+// Text nodes are content which is searched, they can have one of these labels as well
+const TEXT_LABELS: [NodeLabel; 4] = [
+    NodeLabel::Title,
+    NodeLabel::Heading,
+    NodeLabel::Paragraph,
+    NodeLabel::ListItem,
+];
+
+#[derive(Clone, Deserialize, Display, EnumString, ToSchema, TS)]
+#[ts(export)]
+pub enum ImportFormat {
+    // One JSON object per line
+    #[serde(rename = "ndjson")]
+    #[strum(serialize = "ndjson")]
+    NDJson,
+    // A header row with the names of the fields, then one row per line
+    #[serde(rename = "csv")]
+    #[strum(serialize = "csv")]
+    Csv,
+}
+
+#[derive(Clone, Deserialize, ToSchema, TS)]
+#[ts(export)]
+pub enum ImportRowType {
+    Link,
+    SearchTerm,
+    Text,
+    Edge,
+}
+
+/// A row of a bulk import. The same fields are used in NDJSON and CSV,
+/// fields which are not needed for the `type` of the row are left out or empty.
+#[derive(Clone, Deserialize, ToSchema, TS)]
+#[ts(export)]
+pub struct ImportRow {
+    #[serde(rename = "type")]
+    pub row_type: ImportRowType,
+    /// Name of the node within the import, so edges can refer to it
+    pub key: Option<String>,
+    /// URL of a `Link`
+    pub url: Option<String>,
+    /// Text of a `SearchTerm` or `Text`
+    pub text: Option<String>,
+    /// Optional label of a `Text`: `Title`, `Heading`, `Paragraph` or `ListItem`
+    pub label: Option<NodeLabel>,
+    /// Node of an `Edge` is from, the `key` of a node in the import or the ID of a node
+    pub from: Option<String>,
+    /// Node of an `Edge` is to, the `key` of a node in the import or the ID of a node
+    pub to: Option<String>,
+    pub edge_label: Option<EdgeLabel>,
+    /// Label of the edge in the other direction, the same as `edge_label` if left out
+    pub reverse_edge_label: Option<EdgeLabel>,
+    pub weight: Option<f32>,
+}
+
+#[derive(Clone, Deserialize, ToSchema, TS)]
+#[ts(export)]
+pub struct ImportWrite {
+    pub format: ImportFormat,
+    pub contents: String,
+}
+
+#[derive(Clone, Serialize, ToSchema, TS)]
+#[ts(export)]
+pub enum ImportRowStatus {
+    Created,
+    /// The node or edge was already in the graph
+    Existing,
+    Failed,
+}
+
+/// Result of importing a row.
+#[derive(Clone, Serialize, ToSchema, TS)]
+#[ts(export)]
+pub struct ImportRowResult {
+    /// Line of the row in the imported file, starting at 1
+    pub line: usize,
+    pub status: ImportRowStatus,
+    /// The node which was created or found, not set for edges
+    pub node_id: Option<NodeId>,
+    /// Why the row could not be imported
+    pub error: Option<String>,
+}
+
+/// Results of a bulk import, one for each row in the order of the file.
+#[derive(Clone, Serialize, ToSchema, TS)]
+#[ts(export)]
+pub struct ImportResults {
+    pub count_created: usize,
+    pub count_existing: usize,
+    pub count_failed: usize,
+    pub rows: Vec<ImportRowResult>,
+}
+
+// Each row with its line, or the reason it could not be read
+fn parse_rows(format: &ImportFormat, contents: &str) -> Vec<(usize, Result<ImportRow, String>)> {
+    match format {
+        ImportFormat::NDJson => contents
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| !line.trim().is_empty())
+            .map(|(index, line)| {
+                (
+                    index + 1,
+                    serde_json::from_str::<ImportRow>(line).map_err(|err| err.to_string()),
+                )
+            })
+            .collect(),
+        ImportFormat::Csv => {
+            let mut reader = csv::ReaderBuilder::new()
+                .trim(csv::Trim::All)
+                .from_reader(contents.as_bytes());
+            let headers = match reader.headers() {
+                Ok(headers) => headers.clone(),
+                Err(err) => return vec![(1, Err(err.to_string()))],
+            };
+            let mut rows: Vec<(usize, Result<ImportRow, String>)> = vec![];
+            let mut record = csv::StringRecord::new();
+            loop {
+                match reader.read_record(&mut record) {
+                    Ok(true) => {
+                        let line = record.position().map_or(0, |position| position.line());
+                        rows.push((
+                            line as usize,
+                            record
+                                .deserialize::<ImportRow>(Some(&headers))
+                                .map_err(|err| err.to_string()),
+                        ));
+                    }
+                    Ok(false) => break,
+                    Err(err) => {
+                        // The reader cannot continue after an error, like invalid UTF-8
+                        let line = err.position().map_or(0, |position| position.line());
+                        rows.push((line as usize, Err(err.to_string())));
+                        break;
+                    }
+                }
+            }
+            rows
+        }
+    }
+}
+
+fn get_required_field<'a>(field: &'a Option<String>, name: &str) -> PiResult<&'a str> {
+    match field.as_deref().map(|value| value.trim()) {
+        Some(value) if !value.is_empty() => Ok(value),
+        _ => Err(PiError::InternalError(format!(
+            "Field {} is required",
+            name
+        ))),
+    }
+}
+
+impl Engine {
+    fn import_node(&self, row: &ImportRow) -> PiResult<ExistingOrNewNodeId> {
+        match row.row_type {
+            ImportRowType::Link => Link::get_or_add(
+                Arc::new(self),
+                &get_required_field(&row.url, "url")?.to_string(),
+                vec![NodeLabel::AddedByUser, NodeLabel::Link],
+                vec![],
+                true,
+            ),
+            ImportRowType::SearchTerm => self.get_or_add_node(
+                Payload::Text(get_required_field(&row.text, "text")?.to_string()),
+                vec![NodeLabel::AddedByUser, NodeLabel::SearchTerm],
+                true,
+                None,
+            ),
+            ImportRowType::Text => {
+                let mut labels = vec![NodeLabel::AddedByUser, NodeLabel::Partial];
+                if let Some(label) = &row.label {
+                    if !TEXT_LABELS.contains(label) {
+                        return Err(PiError::InternalError(format!(
+                            "Text cannot have the label {}",
+                            label
+                        )));
+                    }
+                    labels.push(label.clone());
+                }
+                self.get_or_add_node(
+                    Payload::Text(get_required_field(&row.text, "text")?.to_string()),
+                    labels,
+                    true,
+                    None,
+                )
+            }
+            ImportRowType::Edge => Err(PiError::InternalError("An edge is not a node".to_string())),
+        }
+    }
+
+    // Returns true if the edge is new
+    fn import_edge(
+        &self,
+        row: &ImportRow,
+        node_ids_by_key: &HashMap<String, NodeId>,
+    ) -> PiResult<bool> {
+        let find_node_id = |field: &Option<String>, name: &str| -> PiResult<NodeId> {
+            let reference = get_required_field(field, name)?;
+            if let Some(node_id) = node_ids_by_key.get(reference) {
+                return Ok(*node_id);
+            }
+            match reference.parse::<NodeId>() {
+                Ok(node_id) if self.get_node_without_content(&node_id).is_some() => Ok(node_id),
+                _ => Err(PiError::InternalError(format!(
+                    "Field {} is not the key of an imported node or the ID of a node: {}",
+                    name, reference
+                ))),
+            }
+        };
+        let from_node_id = find_node_id(&row.from, "from")?;
+        let to_node_id = find_node_id(&row.to, "to")?;
+        let edge_label = row
+            .edge_label
+            .clone()
+            .ok_or_else(|| PiError::InternalError("Field edge_label is required".to_string()))?;
+        let reverse_edge_label = row
+            .reverse_edge_label
+            .clone()
+            .unwrap_or_else(|| edge_label.clone());
+
+        self.add_connection_with_properties(
+            (from_node_id, to_node_id),
+            (edge_label, reverse_edge_label),
+            EdgeProperties {
+                weight: row.weight,
+                ..EdgeProperties::new(EdgeSource::User)
+            },
+        )
+    }
+
+    // Nodes are imported first, so edges can refer to nodes on any row of the import
+    pub fn import(&self, format: &ImportFormat, contents: &str) -> PiResult<ImportResults> {
+        let rows = parse_rows(format, contents);
+        let mut results: Vec<ImportRowResult> = rows
+            .iter()
+            .map(|(line, row)| ImportRowResult {
+                line: *line,
+                status: ImportRowStatus::Failed,
+                node_id: None,
+                error: row.as_ref().err().cloned(),
+            })
+            .collect();
+
+        self.batch_writes(|| {
+            let mut node_ids_by_key: HashMap<String, NodeId> = HashMap::new();
+            for ((_, row), result) in rows.iter().zip(results.iter_mut()) {
+                let Ok(row) = row else {
+                    continue;
+                };
+                if matches!(row.row_type, ImportRowType::Edge) {
+                    continue;
+                }
+                match self.import_node(row) {
+                    Ok(node_id) => {
+                        if let Some(key) = &row.key {
+                            node_ids_by_key.insert(key.clone(), node_id.get_node_id());
+                        }
+                        result.node_id = Some(node_id.get_node_id());
+                        result.status = match node_id {
+                            ExistingOrNewNodeId::New(_) => ImportRowStatus::Created,
+                            ExistingOrNewNodeId::Existing(_) => ImportRowStatus::Existing,
+                        };
+                    }
+                    Err(err) => result.error = Some(err.to_string()),
+                }
+            }
+            for ((_, row), result) in rows.iter().zip(results.iter_mut()) {
+                let Ok(row) = row else {
+                    continue;
+                };
+                if matches!(row.row_type, ImportRowType::Edge) {
+                    match self.import_edge(row, &node_ids_by_key) {
+                        Ok(true) => result.status = ImportRowStatus::Created,
+                        Ok(false) => result.status = ImportRowStatus::Existing,
+                        Err(err) => result.error = Some(err.to_string()),
+                    }
+                }
+            }
+            Ok(())
+        })?;
+
+        let count_with_status = |status: fn(&ImportRowStatus) -> bool| {
+            results
+                .iter()
+                .filter(|result| status(&result.status))
+                .count()
+        };
+        Ok(ImportResults {
+            count_created: count_with_status(|status| matches!(status, ImportRowStatus::Created)),
+            count_existing: count_with_status(|status| matches!(status, ImportRowStatus::Existing)),
+            count_failed: count_with_status(|status| matches!(status, ImportRowStatus::Failed)),
+            rows: results,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::engine::get_test_engine;
+
+    #[test]
+    fn test_import_ndjson_and_csv_with_per_row_results() {
+        let test_engine = get_test_engine();
+        let ndjson = r#"{"type": "Link", "key": "home", "url": "https://pixlie.com/"}
+{"type": "SearchTerm", "key": "term", "text": "knowledge graph"}
+
+{"type": "Text", "key": "intro", "text": "Pixlie builds knowledge graphs", "label": "Paragraph"}
+{"type": "Edge", "from": "intro", "to": "term", "edge_label": "RelatedTo"}
+{"type": "Link", "url": "not a URL"}
+{"type": "Edge", "from": "intro", "to": "missing", "edge_label": "RelatedTo"}
+{"type": "Text"
+"#;
+        let results = test_engine.import(&ImportFormat::NDJson, ndjson).unwrap();
+        assert_eq!(results.count_created, 4);
+        assert_eq!(results.count_existing, 0);
+        assert_eq!(results.count_failed, 3);
+        assert_eq!(
+            results
+                .rows
+                .iter()
+                .map(|row| row.line)
+                .collect::<Vec<usize>>(),
+            vec![1, 2, 4, 5, 6, 7, 8]
+        );
+        assert!(results.rows[5].error.as_ref().unwrap().contains("missing"));
+        let intro_node_id = results.rows[2].node_id.unwrap();
+        let term_node_id = results.rows[1].node_id.unwrap();
+        assert_eq!(
+            test_engine
+                .get_node_ids_connected_with_label(&intro_node_id, &EdgeLabel::RelatedTo)
+                .unwrap(),
+            vec![term_node_id]
+        );
+        let intro_node = test_engine
+            .get_node_without_content(&intro_node_id)
+            .unwrap();
+        assert!(intro_node.labels.contains(&NodeLabel::Partial));
+        assert!(intro_node.labels.contains(&NodeLabel::Paragraph));
+
+        // Nodes and edges which are already in the graph are not added again
+        let csv = format!(
+            "type,key,url,text,label,from,to,edge_label,reverse_edge_label,weight\n\
+            SearchTerm,term,,knowledge graph,,,,,,\n\
+            Link,,https://pixlie.com/blog,,,,,,,\n\
+            Edge,,,,,{},term,RelatedTo,,\n\
+            Edge,,,,,{},term,ParentOf,ChildOf,0.5\n\
+            Text,,,A title,Link,,,,,\n",
+            intro_node_id, intro_node_id
+        );
+        let results = test_engine.import(&ImportFormat::Csv, &csv).unwrap();
+        assert!(matches!(results.rows[0].status, ImportRowStatus::Existing));
+        assert_eq!(results.rows[0].node_id, Some(term_node_id));
+        assert!(matches!(results.rows[1].status, ImportRowStatus::Created));
+        assert!(matches!(results.rows[2].status, ImportRowStatus::Existing));
+        assert!(matches!(results.rows[3].status, ImportRowStatus::Created));
+        assert!(matches!(results.rows[4].status, ImportRowStatus::Failed));
+        assert_eq!(results.rows[4].line, 6);
+        assert_eq!(
+            test_engine
+                .get_node_ids_connected_with_label(&term_node_id, &EdgeLabel::ChildOf)
+                .unwrap(),
+            vec![intro_node_id]
+        );
+    }
+}
@@ -0,0 +1,563 @@
+// Copyright 2025 Pixlie Web Solutions Pvt. Ltd.
+// Licensed under the GNU General Public License version 3.0;
+// You may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// https://github.com/pixlie/PixlieAI/blob/main/LICENSE
+
+use crate::engine::edges::EDGES_CHUNK_PREFIX;
+use crate::engine::embeddings::EMBEDDINGS_PREFIX;
+use crate::engine::engine::{CRAWL_STATE_KEY, LINK_DISCOVERY_PREFIX, LINK_FETCH_PREFIX};
+use crate::engine::node::{NodeId, NodeItem, NodeLabel, Payload};
+use crate::engine::nodes::{Nodes, NODES_CHUNK_PREFIX, NODES_INDEX_PREFIX, NODES_VERSIONS_PREFIX};
+use crate::engine::text_index::{
+    get_indexed_text, TextIndex, TEXT_COUNTS_PREFIX, TEXT_POSTINGS_PREFIX,
+};
+use crate::engine::{
+    get_chunk_id_and_node_ids, EdgeLabel, EdgeProperties, EdgeSource, NodeEdges, NodeFlags,
+};
+use crate::error::{PiError, PiResult};
+use chrono::{DateTime, Utc};
+use log::{error, info};
+use postcard::{from_bytes, take_from_bytes, to_allocvec};
+use rocksdb::{Options, SliceTransform, WriteBatch, DB};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+// Version of the format in which nodes and edges are stored in a project DB.
+// Postcard is not self-describing, so data written with older types cannot be read
+// with newer ones. When a stored type changes, increase this and add a migration below
+pub(super) const FORMAT_VERSION: u32 = 5;
+// Databases without this key were written before we had versions, they are version 0
+const FORMAT_VERSION_KEY: &str = "format/version";
+// All prefixes and keys of data written with `to_versioned_bytes`. Each migration changes the
+// version of all of these first, so data whose layout did not change stays readable, then
+// rewrites the data which did change. Data stored with a new prefix or key has to be added here
+const VERSIONED_PREFIXES: [&str; 9] = [
+    NODES_CHUNK_PREFIX,
+    NODES_INDEX_PREFIX,
+    NODES_VERSIONS_PREFIX,
+    EDGES_CHUNK_PREFIX,
+    TEXT_COUNTS_PREFIX,
+    TEXT_POSTINGS_PREFIX,
+    EMBEDDINGS_PREFIX,
+    LINK_FETCH_PREFIX,
+    LINK_DISCOVERY_PREFIX,
+];
+// Keys which are shorter than the fixed prefix of the prefix extractor are read on their own
+const VERSIONED_KEYS: [&str; 1] = [CRAWL_STATE_KEY];
+
+// Each chunk is stored with the format version it was written with, followed by its data
+pub(super) fn to_versioned_bytes<T: Serialize>(data: &T) -> PiResult<Vec<u8>> {
+    to_bytes_of_version(data, FORMAT_VERSION)
+}
+
+pub(super) fn from_versioned_bytes<T: DeserializeOwned>(bytes: &[u8]) -> PiResult<T> {
+    from_bytes_of_version(bytes, FORMAT_VERSION)
+}
+
+// Migrations read and write chunks of older versions with their own (frozen) types
+fn to_bytes_of_version<T: Serialize>(data: &T, version: u32) -> PiResult<Vec<u8>> {
+    Ok(to_allocvec(&(version, data))?)
+}
+
+fn from_bytes_of_version<T: DeserializeOwned>(bytes: &[u8], version: u32) -> PiResult<T> {
+    let (stored_version, data) = take_from_bytes::<u32>(bytes)?;
+    if stored_version != version {
+        return Err(PiError::InternalError(format!(
+            "Cannot read data in format version {}, expected version {}",
+            stored_version, version
+        )));
+    }
+    Ok(from_bytes(data)?)
+}
+
+fn get_format_version(db: &DB) -> PiResult<u32> {
+    match db.get(FORMAT_VERSION_KEY)? {
+        Some(bytes) => Ok(from_bytes(&bytes)?),
+        None => Ok(0),
+    }
+}
+
+// Key and value of all items with the given prefix
+fn read_prefix(db: &DB, prefix: &str) -> PiResult<Vec<(Vec<u8>, Vec<u8>)>> {
+    let mut items: Vec<(Vec<u8>, Vec<u8>)> = vec![];
+    for item in db.prefix_iterator(prefix) {
+        match item {
+            Ok((key, value)) => {
+                if !key.starts_with(prefix.as_bytes()) {
+                    break;
+                }
+                items.push((key.to_vec(), value.to_vec()));
+            }
+            Err(err) => {
+                error!("RocksDB error: {}", err);
+                return Err(PiError::RocksdbError(err));
+            }
+        }
+    }
+    Ok(items)
+}
+
+// NodeItem before retries were tracked in the node
+#[derive(Deserialize)]
+struct NodeItemV0 {
+    id: NodeId,
+    labels: Vec<NodeLabel>,
+    payload: Payload,
+    flags: NodeFlags,
+    written_at: DateTime<Utc>,
+}
+
+// NodeEdges before edges had properties
+#[derive(Deserialize, Serialize)]
+struct NodeEdgesV1 {
+    edges: Vec<(NodeId, EdgeLabel)>,
+    written_at: DateTime<Utc>,
+}
+
+// Version 0 to 1:
+// - chunks are stored with their format version
+// - NodeItem has retry_count and next_attempt_at
+// - nodes have index chunks and the HTML of web pages is stored outside the node chunks
+fn migrate_from_v0(db: &DB, batch: &mut WriteBatch) -> PiResult<()> {
+    // Node chunks are written by `Nodes` in the current format version, which is fine while
+    // NodeItem is unchanged since version 1. If it changes, this needs its own node types
+    let mut nodes = Nodes::new();
+    for (_, value) in read_prefix(db, NODES_CHUNK_PREFIX)? {
+        let data: Vec<(NodeId, NodeItemV0)> = from_bytes(&value)?;
+        let Some((first_node_id, _)) = data.first() else {
+            continue;
+        };
+        let first_node_id = *first_node_id;
+        nodes.insert_migrated_chunk(
+            db,
+            get_chunk_id_and_node_ids(&first_node_id).0,
+            data.into_iter()
+                .map(|(_, node)| NodeItem {
+                    id: node.id,
+                    labels: node.labels,
+                    payload: node.payload,
+                    flags: node.flags,
+                    written_at: node.written_at,
+                    retry_count: 0,
+                    next_attempt_at: None,
+                })
+                .collect(),
+        )?;
+        nodes.write_chunk_to_batch(batch, &first_node_id)?;
+        nodes.mark_chunk_saved(&first_node_id)?;
+    }
+    for (key, value) in read_prefix(db, EDGES_CHUNK_PREFIX)? {
+        let data: Vec<(NodeId, NodeEdgesV1)> = from_bytes(&value)?;
+        batch.put(key, to_bytes_of_version(&data, 1)?);
+    }
+    Ok(())
+}
+
+// Changes only the format version of a chunk, its data is kept as is
+fn set_version(value: &[u8], version: u32) -> PiResult<Vec<u8>> {
+    let (_, data) = take_from_bytes::<u32>(value)?;
+    let mut bytes = to_allocvec(&version)?;
+    bytes.extend_from_slice(data);
+    Ok(bytes)
+}
+
+// Changes the format version of all versioned data, see `VERSIONED_PREFIXES`
+fn set_version_of_all_data(db: &DB, batch: &mut WriteBatch, version: u32) -> PiResult<()> {
+    for prefix in VERSIONED_PREFIXES {
+        for (key, value) in read_prefix(db, prefix)? {
+            batch.put(key, set_version(&value, version)?);
+        }
+    }
+    for key in VERSIONED_KEYS {
+        if let Some(value) = db.get(key)? {
+            batch.put(key, set_version(&value, version)?);
+        }
+    }
+    Ok(())
+}
+
+// Version 1 to 2:
+// - edges have properties, existing edges are marked as created by the engine when the
+//   edges of their node were last written
+fn migrate_from_v1(db: &DB, batch: &mut WriteBatch) -> PiResult<()> {
+    for (key, value) in read_prefix(db, EDGES_CHUNK_PREFIX)? {
+        let data: Vec<(NodeId, NodeEdgesV1)> = from_bytes_of_version(&value, 1)?;
+        let data: Vec<(NodeId, NodeEdges)> = data
+            .into_iter()
+            .map(|(node_id, node_edges)| {
+                (
+                    node_id,
+                    NodeEdges {
+                        edges: node_edges
+                            .edges
+                            .into_iter()
+                            .map(|(other_node_id, edge_label)| {
+                                (
+                                    other_node_id,
+                                    edge_label,
+                                    EdgeProperties {
+                                        source: EdgeSource::Engine,
+                                        weight: None,
+                                        confidence: None,
+                                        created_at: node_edges.written_at,
+                                        attributes: BTreeMap::new(),
+                                    },
+                                )
+                            })
+                            .collect(),
+                        written_at: node_edges.written_at,
+                    },
+                )
+            })
+            .collect();
+        batch.put(key, to_bytes_of_version(&data, 2)?);
+    }
+    Ok(())
+}
+
+// Version 2 to 3:
+// - a node has at most one edge with a label to another node, repeated edges are removed
+fn migrate_from_v2(db: &DB, batch: &mut WriteBatch) -> PiResult<()> {
+    let mut count_removed_edges: usize = 0;
+    for (key, value) in read_prefix(db, EDGES_CHUNK_PREFIX)? {
+        let mut data: Vec<(NodeId, NodeEdges)> = from_bytes_of_version(&value, 2)?;
+        for (_, node_edges) in data.iter_mut() {
+            count_removed_edges += node_edges.remove_duplicate_edges();
+        }
+        batch.put(key, to_bytes_of_version(&data, 3)?);
+    }
+    info!("Removed {} repeated edges", count_removed_edges);
+    Ok(())
+}
+
+// Writes a new text index of the content nodes, which are stored in the given version
+fn index_texts_of_nodes(db: &DB, batch: &mut WriteBatch, version: u32) -> PiResult<()> {
+    let mut texts: Vec<(NodeId, String)> = vec![];
+    for (_, value) in read_prefix(db, NODES_CHUNK_PREFIX)? {
+        let data: Vec<(NodeId, NodeItem)> = from_bytes_of_version(&value, version)?;
+        texts.extend(data.into_iter().filter_map(|(node_id, node)| {
+            get_indexed_text(&node.labels, &node.payload).map(|text| (node_id, text.to_string()))
+        }));
+    }
+    info!("Indexing the text of {} nodes", texts.len());
+    TextIndex::write_new_index_to_batch(batch, texts)?;
+    Ok(())
+}
+
+// Version 3 to 4:
+// - the text of content nodes is in a full-text index, existing content nodes are indexed
+fn migrate_from_v3(db: &DB, batch: &mut WriteBatch) -> PiResult<()> {
+    index_texts_of_nodes(db, batch, 3)
+}
+
+// Version 4 to 5:
+// - postings and token counts of the text index are stored per node, the index is written again
+fn migrate_from_v4(db: &DB, batch: &mut WriteBatch) -> PiResult<()> {
+    for prefix in [TEXT_COUNTS_PREFIX, TEXT_POSTINGS_PREFIX] {
+        for (key, _) in read_prefix(db, prefix)? {
+            batch.delete(key);
+        }
+    }
+    index_texts_of_nodes(db, batch, 4)
+}
+
+// Migrations in order, the migration at position N migrates version N to N + 1.
+// Data which a migration does not rewrite has its version changed, see `VERSIONED_PREFIXES`
+type Migration = fn(&DB, &mut WriteBatch) -> PiResult<()>;
+const MIGRATIONS: [Migration; FORMAT_VERSION as usize] = [
+    migrate_from_v0,
+    migrate_from_v1,
+    migrate_from_v2,
+    migrate_from_v3,
+    migrate_from_v4,
+];
+
+// Brings a project DB to the current format version. Each migration is written in a single batch,
+// so a migration which fails leaves the DB in the previous version and is run again on next open
+pub(super) fn migrate(path_to_db: &PathBuf) -> PiResult<()> {
+    // Same prefix extractor as `Nodes::open`, so we can iterate over chunks
+    let mut opts = Options::default();
+    opts.create_if_missing(false);
+    opts.set_prefix_extractor(SliceTransform::create_fixed_prefix(
+        NODES_CHUNK_PREFIX.len(),
+    ));
+    let db = match DB::open(&opts, path_to_db) {
+        Ok(db) => db,
+        Err(err) => {
+            error!("RocksDB error: {}", err);
+            return Err(PiError::RocksdbError(err));
+        }
+    };
+    let mut version = get_format_version(&db)?;
+    if version > FORMAT_VERSION {
+        return Err(PiError::InternalError(format!(
+            "Project DB has format version {}, this version of Pixlie can read up to version {}",
+            version, FORMAT_VERSION
+        )));
+    }
+    while version < FORMAT_VERSION {
+        let mut batch = WriteBatch::default();
+        // Data of version 0 has no version, all of it is rewritten by its migration
+        if version > 0 {
+            set_version_of_all_data(&db, &mut batch, version + 1)?;
+        }
+        // Later writes to a key in the batch replace earlier ones
+        MIGRATIONS[version as usize](&db, &mut batch)?;
+        batch.put(FORMAT_VERSION_KEY, to_allocvec(&(version + 1))?);
+        db.write(batch)?;
+        info!(
+            "Migrated project DB from format version {} to {}",
+            version,
+            version + 1
+        );
+        version += 1;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::engine::{create_test_project_db, open_test_engine};
+    use crate::engine::nodes::NodeIndexEntry;
+    use crate::entity::crawler::budget::CrawlState;
+    use crate::entity::web::discovery::LinkDiscovery;
+    use chrono::Utc;
+    use std::fs::read;
+
+    #[test]
+    fn test_migrate_project_db_from_format_v0() {
+        let (temp_dir, project_uuid, path_to_db) = create_test_project_db();
+        {
+            // Chunks written by Pixlie before format versions: a domain, a link in the domain
+            // and the web page of the link
+            let db = DB::open_default(&path_to_db).unwrap();
+            db.put(
+                format!("{}0", NODES_CHUNK_PREFIX),
+                read("fixtures/format_v0/nodes_chunk_0.bin").unwrap(),
+            )
+            .unwrap();
+            db.put(
+                format!("{}0", EDGES_CHUNK_PREFIX),
+                read("fixtures/format_v0/edges_chunk_0.bin").unwrap(),
+            )
+            .unwrap();
+        }
+
+        for _ in 0..2 {
+            // Opening again does not run the migration again
+            let engine = open_test_engine(&temp_dir, &project_uuid);
+            let domain_node_id = *engine.get_node_ids_with_label(&NodeLabel::DomainName)[0];
+            match &engine
+                .get_node_without_content(&domain_node_id)
+                .unwrap()
+                .payload
+            {
+                Payload::Text(domain) => assert_eq!(domain, "pixlie.com"),
+                _ => panic!("Expected Payload::Text"),
+            }
+
+            let link_node_ids = engine
+                .get_node_ids_connected_with_label(&domain_node_id, &EdgeLabel::OwnerOf)
+                .unwrap();
+            assert_eq!(link_node_ids.len(), 1);
+            let domain_edges = engine
+                .get_connected_nodes(&domain_node_id)
+                .unwrap()
+                .unwrap();
+            assert!(domain_edges
+                .edges
+                .iter()
+                .all(|(_, _, properties)| properties.source == EdgeSource::Engine
+                    && properties.created_at == domain_edges.written_at));
+            let link_node = engine.get_node_without_content(&link_node_ids[0]).unwrap();
+            match &link_node.payload {
+                Payload::Link(link) => assert_eq!(link.get_full_link(), "/about?ref=home"),
+                _ => panic!("Expected Payload::Link"),
+            }
+            assert!(link_node.flags.contains(NodeFlags::IS_PROCESSED));
+            assert_eq!(link_node.retry_count, 0);
+
+            let web_page_node_ids = engine
+                .get_node_ids_connected_with_label(&link_node.id, &EdgeLabel::PathOf)
+                .unwrap();
+            assert_eq!(web_page_node_ids.len(), 1);
+            match &engine
+                .get_node_with_content(&web_page_node_ids[0])
+                .unwrap()
+                .payload
+            {
+                Payload::Text(html) => {
+                    assert_eq!(html, "<html><body><p>About Pixlie</p></body></html>")
+                }
+                _ => panic!("Expected Payload::Text"),
+            }
+        }
+    }
+
+    #[test]
+    fn test_migrate_removes_repeated_edges() {
+        let (temp_dir, project_uuid, path_to_db) = create_test_project_db();
+        {
+            // Edges in format version 2, where adding a connection again repeated its edges
+            let written_at = Utc::now();
+            let data: Vec<(NodeId, NodeEdges)> = vec![
+                (
+                    1,
+                    NodeEdges {
+                        edges: vec![
+                            (2, EdgeLabel::ParentOf, EdgeProperties::default()),
+                            (2, EdgeLabel::ParentOf, EdgeProperties::default()),
+                            (2, EdgeLabel::RelatedTo, EdgeProperties::default()),
+                            (3, EdgeLabel::ParentOf, EdgeProperties::default()),
+                        ],
+                        written_at,
+                    },
+                ),
+                (
+                    2,
+                    NodeEdges {
+                        edges: vec![
+                            (1, EdgeLabel::ChildOf, EdgeProperties::default()),
+                            (1, EdgeLabel::ChildOf, EdgeProperties::default()),
+                        ],
+                        written_at,
+                    },
+                ),
+            ];
+            let db = DB::open_default(&path_to_db).unwrap();
+            db.put(
+                format!("{}0", EDGES_CHUNK_PREFIX),
+                to_bytes_of_version(&data, 2).unwrap(),
+            )
+            .unwrap();
+            db.put(FORMAT_VERSION_KEY, to_allocvec(&2u32).unwrap())
+                .unwrap();
+        }
+
+        let engine = open_test_engine(&temp_dir, &project_uuid);
+        let node_edges = engine.get_connected_nodes(&1).unwrap().unwrap();
+        assert_eq!(node_edges.edges.len(), 3);
+        assert!(node_edges.has_edge(&2, &EdgeLabel::ParentOf));
+        assert!(node_edges.has_edge(&2, &EdgeLabel::RelatedTo));
+        assert!(node_edges.has_edge(&3, &EdgeLabel::ParentOf));
+        let node_edges = engine.get_connected_nodes(&2).unwrap().unwrap();
+        assert_eq!(node_edges.edges.len(), 1);
+    }
+
+    #[test]
+    fn test_migrate_indexes_text_of_content_nodes() {
+        let (temp_dir, project_uuid, path_to_db) = create_test_project_db();
+        {
+            // Nodes in format version 3, before content nodes were indexed
+            let nodes: Vec<(NodeId, NodeItem)> = vec![
+                (0, NodeLabel::Title, "Crawling the web"),
+                (1, NodeLabel::Paragraph, "Pixlie crawls websites"),
+                (2, NodeLabel::Paragraph, "Nothing to see here"),
+            ]
+            .into_iter()
+            .map(|(node_id, label, text)| {
+                (
+                    node_id,
+                    NodeItem {
+                        id: node_id,
+                        labels: vec![label, NodeLabel::Partial],
+                        payload: Payload::Text(text.to_string()),
+                        flags: NodeFlags::default(),
+                        written_at: Utc::now(),
+                        retry_count: 0,
+                        next_attempt_at: None,
+                    },
+                )
+            })
+            .collect();
+            let index: Vec<(NodeId, NodeIndexEntry)> = nodes
+                .iter()
+                .map(|(node_id, node)| {
+                    (
+                        *node_id,
+                        NodeIndexEntry {
+                            labels: node.labels.clone(),
+                            payload_hash: 0,
+                            flags: NodeFlags::default(),
+                        },
+                    )
+                })
+                .collect();
+            let db = DB::open_default(&path_to_db).unwrap();
+            db.put(
+                format!("{}0", NODES_CHUNK_PREFIX),
+                to_bytes_of_version(&nodes, 3).unwrap(),
+            )
+            .unwrap();
+            db.put(
+                format!("{}0", NODES_INDEX_PREFIX),
+                to_bytes_of_version(&index, 3).unwrap(),
+            )
+            .unwrap();
+            db.put(FORMAT_VERSION_KEY, to_allocvec(&3u32).unwrap())
+                .unwrap();
+        }
+
+        let engine = open_test_engine(&temp_dir, &project_uuid);
+        let results = engine.search_text("crawl", 10).unwrap();
+        let mut node_ids: Vec<NodeId> = results.iter().map(|result| result.node_id).collect();
+        node_ids.sort();
+        assert_eq!(node_ids, vec![0, 1]);
+        assert_eq!(engine.search_text("nothing", 10).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_migrate_keeps_data_whose_layout_did_not_change() {
+        let (temp_dir, project_uuid, path_to_db) = create_test_project_db();
+        {
+            // Data outside node and edge chunks in format version 3
+            let discovery = LinkDiscovery {
+                depth: 2,
+                discovered_from: Some(1),
+            };
+            let crawl_state = CrawlState {
+                count_pages_fetched: 7,
+                ..CrawlState::default()
+            };
+            let db = DB::open_default(&path_to_db).unwrap();
+            db.put(
+                format!("{}2", LINK_DISCOVERY_PREFIX),
+                to_bytes_of_version(&discovery, 3).unwrap(),
+            )
+            .unwrap();
+            db.put(
+                CRAWL_STATE_KEY,
+                to_bytes_of_version(&crawl_state, 3).unwrap(),
+            )
+            .unwrap();
+            db.put(FORMAT_VERSION_KEY, to_allocvec(&3u32).unwrap())
+                .unwrap();
+        }
+
+        let engine = open_test_engine(&temp_dir, &project_uuid);
+        assert_eq!(
+            engine.get_link_discovery(&2).unwrap(),
+            Some(LinkDiscovery {
+                depth: 2,
+                discovered_from: Some(1),
+            })
+        );
+        assert_eq!(engine.get_crawl_state().unwrap().count_pages_fetched, 7);
+    }
+
+    #[test]
+    fn test_versioned_bytes_of_other_version_are_not_read() {
+        let bytes = to_allocvec(&(FORMAT_VERSION + 1, vec![1u32, 2, 3])).unwrap();
+        assert!(from_versioned_bytes::<Vec<u32>>(&bytes).is_err());
+        let bytes = to_versioned_bytes(&vec![1u32, 2, 3]).unwrap();
+        assert_eq!(
+            from_versioned_bytes::<Vec<u32>>(&bytes).unwrap(),
+            vec![1, 2, 3]
+        );
+    }
+}
@@ -7,21 +7,34 @@
 
 use crate::engine::node::NodeId;
 use bitflags::bitflags;
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, TimeDelta, Utc};
 use serde::{Deserialize, Serialize};
-use strum::Display;
+use std::collections::{BTreeMap, HashSet};
+use strum::{Display, EnumString};
 use ts_rs::TS;
 use utoipa::ToSchema;
 
 pub mod api;
+mod chunk_cache;
 mod edges;
+mod embeddings;
 pub mod engine;
+pub mod export;
+pub mod import;
+mod migrations;
 pub mod node;
+pub mod node_history;
 mod nodes;
+pub mod processor;
+pub mod query;
+pub mod ranking;
+pub mod text_index;
+pub mod traversal;
+mod work_queue;
 
 pub use engine::Engine;
 
-#[derive(Clone, Deserialize, Display, Eq, Hash, PartialEq, Serialize, ToSchema, TS)]
+#[derive(Clone, Deserialize, Display, EnumString, Eq, Hash, PartialEq, Serialize, ToSchema, TS)]
 #[ts(export)]
 pub enum EdgeLabel {
     RelatedTo,
@@ -40,12 +53,83 @@ pub enum EdgeLabel {
 
     Classifies,
     ClassifiedFor,
+
+    FailedWith, // When a node's external data request failed, the other node has the details
+    FailureOf,
+
+    Answers, // When one node is the answer to a question
+    AnsweredBy,
+
+    Cites, // When one node is based on the content of another, like an answer on its sources
+    CitedBy,
+
+    ChangedWith, // When a node's content changed, the other node has the details of the change
+    ChangeOf,
+}
+
+// Who or what created an edge
+#[derive(Clone, Default, Deserialize, Display, PartialEq, Serialize, ToSchema, TS)]
+#[ts(export)]
+pub enum EdgeSource {
+    #[default]
+    Engine, // While processing nodes, like a link which is connected to its domain
+    User,
+    AI,
+    Gliner,
+    Scraper,
+}
+
+#[derive(Clone, Deserialize, PartialEq, Serialize, ToSchema, TS)]
+#[ts(export)]
+pub struct EdgeProperties {
+    pub source: EdgeSource,
+    pub weight: Option<f32>,
+    pub confidence: Option<f32>, // From 0 to 1, usually for edges suggested by AI/ML
+    pub created_at: DateTime<Utc>,
+    pub attributes: BTreeMap<String, String>,
+}
+
+impl EdgeProperties {
+    pub fn new(source: EdgeSource) -> Self {
+        EdgeProperties {
+            source,
+            weight: None,
+            confidence: None,
+            created_at: Utc::now(),
+            attributes: BTreeMap::new(),
+        }
+    }
+}
+
+impl Default for EdgeProperties {
+    fn default() -> Self {
+        EdgeProperties::new(EdgeSource::default())
+    }
 }
 
 #[derive(Clone, Deserialize, Serialize)]
 pub struct NodeEdges {
-    pub edges: Vec<(NodeId, EdgeLabel)>,
-    pub written_at: DateTime<Utc>,
+    pub edges: Vec<(NodeId, EdgeLabel, EdgeProperties)>,
+    pub written_at: DateTime<Utc>, // When any edge of the node was last written
+}
+
+impl NodeEdges {
+    // A node has at most one edge with a label to another node
+    pub fn has_edge(&self, other_node_id: &NodeId, edge_label: &EdgeLabel) -> bool {
+        self.edges.iter().any(|(x_node_id, x_edge_label, _)| {
+            x_node_id == other_node_id && x_edge_label == edge_label
+        })
+    }
+
+    // Removes repeated edges with the same label to the same node, keeping the first one.
+    // Returns the number of edges removed
+    pub fn remove_duplicate_edges(&mut self) -> usize {
+        let count_edges = self.edges.len();
+        let mut seen: HashSet<(NodeId, EdgeLabel)> = HashSet::new();
+        self.edges
+            .retain(|(x_node_id, x_edge_label, _)| seen.insert((*x_node_id, x_edge_label.clone())));
+        count_edges - self.edges.len()
+    }
 }
 
 bitflags! {
@@ -65,6 +149,44 @@ bitflags! {
         // This flag says that a node had an error and cannot be processed unless some code
         // or condition changes
         const HAD_ERROR = 1 << 3;
+
+        // This is set when a node had an error on each of the attempts allowed by the
+        // retry policy, the node will not be processed again
+        const GAVE_UP = 1 << 4;
+    }
+}
+
+// How nodes which had an error are retried. The delay before the next attempt doubles
+// with every failed attempt, starting at `initial_delay` and capped at `max_delay`
+#[derive(Clone)]
+pub struct RetryPolicy {
+    pub max_attempts: u32,
+    pub initial_delay: TimeDelta,
+    pub max_delay: TimeDelta,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        RetryPolicy {
+            max_attempts: 5,
+            initial_delay: TimeDelta::seconds(60),
+            max_delay: TimeDelta::hours(6),
+        }
+    }
+}
+
+impl RetryPolicy {
+    pub fn get_delay(&self, retry_count: u32) -> TimeDelta {
+        // retry_count is the number of failed attempts so far, at least 1 when called
+        let exponent = retry_count.saturating_sub(1).min(30);
+        match self.initial_delay.checked_mul(1 << exponent) {
+            Some(delay) => delay.min(self.max_delay),
+            None => self.max_delay,
+        }
+    }
+
+    pub fn has_attempts_left(&self, retry_count: u32) -> bool {
+        retry_count < self.max_attempts
     }
 }
 
@@ -100,4 +222,19 @@ mod tests {
         assert_eq!(node_ids[0], 10000);
         assert_eq!(node_ids[99], 10099);
     }
+
+    #[test]
+    fn test_retry_policy_backs_off_exponentially() {
+        let retry_policy = RetryPolicy {
+            max_attempts: 3,
+            initial_delay: TimeDelta::seconds(10),
+            max_delay: TimeDelta::seconds(30),
+        };
+        assert_eq!(retry_policy.get_delay(1), TimeDelta::seconds(10));
+        assert_eq!(retry_policy.get_delay(2), TimeDelta::seconds(20));
+        assert_eq!(retry_policy.get_delay(3), TimeDelta::seconds(30));
+        assert_eq!(retry_policy.get_delay(100), TimeDelta::seconds(30));
+        assert!(retry_policy.has_attempts_left(2));
+        assert!(!retry_policy.has_attempts_left(3));
+    }
 }
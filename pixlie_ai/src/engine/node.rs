@@ -6,17 +6,17 @@
 // https://github.com/pixlie/PixlieAI/blob/main/LICENSE
 
 use crate::engine::{Engine, NodeFlags};
-use crate::entity::classifier::{Classification, Classifier, ClassifierSettings};
+use crate::entity::classifier::{Classification, ClassifierSettings};
 use crate::entity::content::TableRow;
+use crate::entity::crawler::budget::CrawlBudget;
 use crate::entity::crawler::CrawlerSettings;
-use crate::entity::named_entity::{EntityExtraction, EntityName, ExtractedEntity};
-use crate::entity::objective::Objective;
+use crate::entity::fetch_error::FetchErrorDetails;
+use crate::entity::named_entity::{EntityName, ExtractedEntity};
 use crate::entity::project_settings::ProjectSettings;
-use crate::entity::search::web_search::WebSearch;
-use crate::entity::web::domain::Domain;
+use crate::entity::question::Answer;
 use crate::entity::web::link::Link;
+use crate::entity::web::recrawl::{ContentChange, RecrawlSettings};
 use crate::entity::web::web_metadata::WebMetadata;
-use crate::entity::web::web_page::WebPage;
 use crate::error::PiResult;
 use crate::{ExternalData, FetchError, FetchResponse};
 use chrono::{DateTime, Utc};
@@ -40,6 +40,11 @@ pub enum Payload {
     Classification(Classification),
     NamedEntitiesToExtract(Vec<EntityName>),
     ExtractedNamedEntities(Vec<ExtractedEntity>),
+    FetchError(FetchErrorDetails),
+    Answer(Answer),
+    RecrawlSettings(RecrawlSettings),
+    ContentChange(ContentChange),
+    CrawlBudget(CrawlBudget),
 }
 
 pub(crate) type NodeId = u32;
@@ -94,6 +99,12 @@ pub enum NodeLabel {
     Classification,
     NamedEntitiesToExtract,
     ExtractedNamedEntities,
+    FetchError,
+    Question,
+    Answer,
+    RecrawlSettings,
+    ContentChange,
+    CrawlBudget,
 }
 
 impl Default for NodeFlags {
@@ -110,24 +121,15 @@ pub struct NodeItem {
 
     pub flags: NodeFlags,
     pub written_at: DateTime<Utc>,
+
+    // Number of attempts at processing this node which had an error, and when to try again
+    pub retry_count: u32,
+    pub next_attempt_at: Option<DateTime<Utc>>,
 }
 
 impl NodeItem {
     pub(super) fn process(&self, arced_engine: Arc<&Engine>) -> PiResult<()> {
-        if self.labels.contains(&NodeLabel::DomainName) {
-            Domain::process(self, arced_engine.clone(), None)?;
-        } else if self.labels.contains(&NodeLabel::Link) {
-            Link::process(self, arced_engine.clone(), None)?;
-        } else if self.labels.contains(&NodeLabel::Objective) {
-            Objective::process(self, arced_engine.clone(), None)?;
-        } else if self.labels.contains(&NodeLabel::WebSearch) {
-            WebSearch::process(self, arced_engine.clone(), None)?;
-        } else if self.labels.contains(&NodeLabel::WebPage) {
-            WebPage::process(self, arced_engine.clone(), None)?;
-            Classifier::process(self, arced_engine.clone(), None)?;
-            EntityExtraction::process(self, arced_engine.clone(), None)?;
-        }
-        Ok(())
+        self.call_processors(arced_engine, None)
     }
 
     pub(super) fn handle_fetch_response(
@@ -135,48 +137,7 @@ impl NodeItem {
         arced_engine: Arc<&Engine>,
         response: FetchResponse,
     ) -> PiResult<()> {
-        if self.labels.contains(&NodeLabel::DomainName) {
-            Domain::process(
-                self,
-                arced_engine.clone(),
-                Some(ExternalData::Response(response)),
-            )?;
-        } else if self.labels.contains(&NodeLabel::Link) {
-            Link::process(
-                self,
-                arced_engine.clone(),
-                Some(ExternalData::Response(response)),
-            )?;
-        } else if self.labels.contains(&NodeLabel::Objective) {
-            Objective::process(
-                self,
-                arced_engine.clone(),
-                Some(ExternalData::Response(response)),
-            )?;
-        } else if self.labels.contains(&NodeLabel::WebSearch) {
-            WebSearch::process(
-                self,
-                arced_engine.clone(),
-                Some(ExternalData::Response(response)),
-            )?;
-        } else if self.labels.contains(&NodeLabel::WebPage) {
-            WebPage::process(
-                self,
-                arced_engine.clone(),
-                Some(ExternalData::Response(response.clone())),
-            )?;
-            Classifier::process(
-                self,
-                arced_engine.clone(),
-                Some(ExternalData::Response(response.clone())),
-            )?;
-            EntityExtraction::process(
-                self,
-                arced_engine.clone(),
-                Some(ExternalData::Response(response.clone())),
-            )?;
-        }
-        Ok(())
+        self.call_processors(arced_engine, Some(ExternalData::Response(response)))
     }
 
     pub(super) fn handle_fetch_error(
@@ -184,29 +145,19 @@ impl NodeItem {
         arced_engine: Arc<&Engine>,
         error: FetchError,
     ) -> PiResult<()> {
-        if self.labels.contains(&NodeLabel::DomainName) {
-            Domain::process(self, arced_engine.clone(), Some(ExternalData::Error(error)))?;
-        } else if self.labels.contains(&NodeLabel::Link) {
-            Link::process(self, arced_engine.clone(), Some(ExternalData::Error(error)))?;
-        } else if self.labels.contains(&NodeLabel::Objective) {
-            Objective::process(self, arced_engine.clone(), Some(ExternalData::Error(error)))?;
-        } else if self.labels.contains(&NodeLabel::WebSearch) {
-            WebSearch::process(self, arced_engine.clone(), Some(ExternalData::Error(error)))?;
-        } else if self.labels.contains(&NodeLabel::WebPage) {
-            WebPage::process(
-                self,
-                arced_engine.clone(),
-                Some(ExternalData::Error(error.clone())),
-            )?;
-            Classifier::process(
-                self,
-                arced_engine.clone(),
-                Some(ExternalData::Error(error.clone())),
-            )?;
-            EntityExtraction::process(
+        self.call_processors(arced_engine, Some(ExternalData::Error(error)))
+    }
+
+    fn call_processors(
+        &self,
+        arced_engine: Arc<&Engine>,
+        data_from_previous_request: Option<ExternalData>,
+    ) -> PiResult<()> {
+        for processor in arced_engine.get_processors(&self.labels) {
+            processor.process(
                 self,
                 arced_engine.clone(),
-                Some(ExternalData::Error(error.clone())),
+                data_from_previous_request.clone(),
             )?;
         }
         Ok(())
@@ -241,6 +192,13 @@ impl Eq for NodeItem {}
 
 pub type ArcedNodeItem = Arc<NodeItem>;
 
+// A node as it was before its payload was updated, with its full content
+#[derive(Clone, Deserialize, Serialize)]
+pub struct NodeVersion {
+    pub node: NodeItem,
+    pub replaced_at: DateTime<Utc>,
+}
+
 pub enum ExistingOrNewNodeId {
     Existing(NodeId),
     New(NodeId),
@@ -0,0 +1,393 @@
+// Copyright 2025 Pixlie Web Solutions Pvt. Ltd.
+// Licensed under the GNU General Public License version 3.0;
+// You may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// https://github.com/pixlie/PixlieAI/blob/main/LICENSE
+
+// Earlier versions of nodes are kept when their payload is updated, see `Nodes::update_node`.
+// This is how we see how a web page or a classification changed over time
+
+use super::api::APINodeItem;
+use super::export::get_payload_attributes;
+use super::node::{ArcedNodeItem, NodeId, NodeLabel};
+use super::Engine;
+use crate::error::{PiError, PiResult};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeSet;
+use std::sync::Arc;
+use ts_rs::TS;
+use utoipa::ToSchema;
+
+// Lines of text are compared with a table of this many cells at most, larger texts are
+// shown as all lines removed and added
+const MAX_CELLS_FOR_LINE_DIFF: usize = 4_000_000;
+
+/// A version of a node. Versions are numbered from 1, the last version is the current node.
+#[derive(Clone, Serialize, ToSchema, TS)]
+#[ts(export)]
+pub struct APINodeVersion {
+    pub version: u32,
+    pub node: APINodeItem,
+    /// Unix timestamp (in milliseconds) of when this version was replaced by the next one.
+    /// Not set for the current version.
+    pub replaced_at: Option<i64>,
+}
+
+#[derive(Clone, Deserialize, ToSchema, TS)]
+#[ts(export)]
+pub struct NodesAsOfRead {
+    /// The node label to filter nodes by. If provided, ids will be ignored.
+    pub label: Option<String>,
+    /// The IDs of the nodes to read. All nodes are read if neither label nor ids are provided.
+    pub ids: Option<Vec<NodeId>>,
+    /// Unix timestamp (in milliseconds) to read the nodes as of
+    pub as_of: i64,
+}
+
+#[derive(Clone, Serialize, ToSchema, TS)]
+#[ts(export)]
+pub enum FieldChangeType {
+    Added,
+    Removed,
+    Changed,
+}
+
+#[derive(Clone, Serialize, ToSchema, TS)]
+#[ts(export)]
+pub enum LineChangeType {
+    Added,
+    Removed,
+}
+
+/// A line which was added to or removed from a text field.
+#[derive(Clone, Serialize, ToSchema, TS)]
+#[ts(export)]
+pub struct LineChange {
+    pub change: LineChangeType,
+    /// Line number (from 1) in the earlier version for removed lines,
+    /// or in the later version for added lines
+    pub line_number: usize,
+    pub text: String,
+}
+
+/// A field of the payload which changed between two versions of a node.
+/// Fields are named like in exports of the graph: `payload_type`, `payload` for text payloads
+/// or `payload.<field>` for the fields of other payloads.
+#[derive(Clone, Serialize, ToSchema, TS)]
+#[ts(export)]
+pub struct PayloadFieldChange {
+    pub field: String,
+    pub change: FieldChangeType,
+    /// The value in the earlier version, as JSON. Not set for texts with more than one line,
+    /// which have the changed lines instead.
+    pub from: Option<String>,
+    /// The value in the later version, as JSON. Not set for texts with more than one line.
+    pub to: Option<String>,
+    pub lines: Vec<LineChange>,
+}
+
+/// Differences between two versions of a node.
+#[derive(Clone, Serialize, ToSchema, TS)]
+#[ts(export)]
+pub struct NodeVersionDiff {
+    pub node_id: NodeId,
+    pub from_version: u32,
+    pub to_version: u32,
+    pub labels_added: Vec<NodeLabel>,
+    pub labels_removed: Vec<NodeLabel>,
+    pub changes: Vec<PayloadFieldChange>,
+}
+
+fn is_multiline_text(value: Option<&Value>) -> bool {
+    match value {
+        Some(Value::String(text)) => text.contains('\n'),
+        Some(_) => false,
+        None => true,
+    }
+}
+
+// Changed lines from the longest common subsequence of lines. Lines which are the same
+// at the start and the end are skipped first, since pages mostly change in a few places
+fn get_line_changes(from: &str, to: &str) -> Vec<LineChange> {
+    let from_lines: Vec<&str> = from.lines().collect();
+    let to_lines: Vec<&str> = to.lines().collect();
+    let prefix = from_lines
+        .iter()
+        .zip(to_lines.iter())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let suffix = from_lines[prefix..]
+        .iter()
+        .rev()
+        .zip(to_lines[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let from_changed = &from_lines[prefix..from_lines.len() - suffix];
+    let to_changed = &to_lines[prefix..to_lines.len() - suffix];
+
+    let mut changes: Vec<LineChange> = vec![];
+    let removed = |index: usize| LineChange {
+        change: LineChangeType::Removed,
+        line_number: prefix + index + 1,
+        text: from_changed[index].to_string(),
+    };
+    let added = |index: usize| LineChange {
+        change: LineChangeType::Added,
+        line_number: prefix + index + 1,
+        text: to_changed[index].to_string(),
+    };
+    let (n, m) = (from_changed.len(), to_changed.len());
+    if (n + 1) * (m + 1) > MAX_CELLS_FOR_LINE_DIFF {
+        changes.extend((0..n).map(removed));
+        changes.extend((0..m).map(added));
+        return changes;
+    }
+
+    // Length of the longest common subsequence of the lines from i and j onwards
+    let mut lengths = vec![0u32; (n + 1) * (m + 1)];
+    for i in (0..n).rev() {
+        for j in (0..m).rev() {
+            lengths[i * (m + 1) + j] = if from_changed[i] == to_changed[j] {
+                lengths[(i + 1) * (m + 1) + j + 1] + 1
+            } else {
+                lengths[(i + 1) * (m + 1) + j].max(lengths[i * (m + 1) + j + 1])
+            };
+        }
+    }
+    let (mut i, mut j) = (0, 0);
+    while i < n && j < m {
+        if from_changed[i] == to_changed[j] {
+            i += 1;
+            j += 1;
+        } else if lengths[(i + 1) * (m + 1) + j] >= lengths[i * (m + 1) + j + 1] {
+            changes.push(removed(i));
+            i += 1;
+        } else {
+            changes.push(added(j));
+            j += 1;
+        }
+    }
+    changes.extend((i..n).map(removed));
+    changes.extend((j..m).map(added));
+    changes
+}
+
+fn get_field_change(
+    field: &str,
+    from: Option<&Value>,
+    to: Option<&Value>,
+) -> Option<PayloadFieldChange> {
+    let change = match (from, to) {
+        (Some(from), Some(to)) if from == to => return None,
+        (Some(_), Some(_)) => FieldChangeType::Changed,
+        (Some(_), None) => FieldChangeType::Removed,
+        (None, Some(_)) => FieldChangeType::Added,
+        (None, None) => return None,
+    };
+    if is_multiline_text(from) && is_multiline_text(to) {
+        let as_text = |value: Option<&Value>| match value {
+            Some(Value::String(text)) => text.clone(),
+            _ => "".to_string(),
+        };
+        return Some(PayloadFieldChange {
+            field: field.to_string(),
+            change,
+            from: None,
+            to: None,
+            lines: get_line_changes(&as_text(from), &as_text(to)),
+        });
+    }
+    Some(PayloadFieldChange {
+        field: field.to_string(),
+        change,
+        from: from.map(|value| value.to_string()),
+        to: to.map(|value| value.to_string()),
+        lines: vec![],
+    })
+}
+
+impl Engine {
+    // All versions of a node with their content, oldest first, and when each was replaced.
+    // The last one is the current node
+    fn get_all_versions_of_node(
+        &self,
+        node_id: &NodeId,
+    ) -> PiResult<Vec<(ArcedNodeItem, Option<DateTime<Utc>>)>> {
+        let current_node = self
+            .get_node_with_content(node_id)
+            .ok_or_else(|| PiError::GraphError(format!("Cannot find node with ID {}", node_id)))?;
+        let mut versions: Vec<(ArcedNodeItem, Option<DateTime<Utc>>)> = self
+            .get_node_versions(node_id)?
+            .into_iter()
+            .map(|version| (Arc::new(version.node), Some(version.replaced_at)))
+            .collect();
+        versions.push((current_node, None));
+        Ok(versions)
+    }
+
+    pub fn get_api_node_versions(&self, node_id: &NodeId) -> PiResult<Vec<APINodeVersion>> {
+        Ok(self
+            .get_all_versions_of_node(node_id)?
+            .iter()
+            .enumerate()
+            .map(|(index, (node, replaced_at))| APINodeVersion {
+                version: index as u32 + 1,
+                node: APINodeItem::from_node(node),
+                replaced_at: replaced_at.map(|replaced_at| replaced_at.timestamp_millis()),
+            })
+            .collect())
+    }
+
+    // The node as it was at the given time. Versions are only kept from when a payload is
+    // updated, a node which did not exist yet at that time is read with its first version
+    pub fn get_node_as_of(
+        &self,
+        node_id: &NodeId,
+        as_of: &DateTime<Utc>,
+    ) -> PiResult<Option<ArcedNodeItem>> {
+        let Some(current_node) = self.get_node_without_content(node_id) else {
+            return Ok(None);
+        };
+        match self
+            .get_node_versions(node_id)?
+            .into_iter()
+            .find(|version| version.replaced_at > *as_of)
+        {
+            Some(version) => Ok(Some(Arc::new(version.node))),
+            None => Ok(Some(current_node)),
+        }
+    }
+
+    pub fn diff_node_versions(
+        &self,
+        node_id: &NodeId,
+        from_version: u32,
+        to_version: u32,
+    ) -> PiResult<NodeVersionDiff> {
+        let versions = self.get_all_versions_of_node(node_id)?;
+        let get_version = |version: u32| {
+            versions
+                .get((version as usize).wrapping_sub(1))
+                .map(|(node, _)| node)
+                .ok_or_else(|| {
+                    PiError::GraphError(format!(
+                        "Node {} has no version {}, it has versions 1 to {}",
+                        node_id,
+                        version,
+                        versions.len()
+                    ))
+                })
+        };
+        let from_node = get_version(from_version)?;
+        let to_node = get_version(to_version)?;
+
+        let from_attributes = get_payload_attributes(&from_node.payload)?;
+        let to_attributes = get_payload_attributes(&to_node.payload)?;
+        let fields: BTreeSet<&String> =
+            from_attributes.keys().chain(to_attributes.keys()).collect();
+        let changes = fields
+            .into_iter()
+            .filter_map(|field| {
+                get_field_change(field, from_attributes.get(field), to_attributes.get(field))
+            })
+            .collect();
+
+        Ok(NodeVersionDiff {
+            node_id: *node_id,
+            from_version,
+            to_version,
+            labels_added: to_node
+                .labels
+                .iter()
+                .filter(|label| !from_node.labels.contains(label))
+                .cloned()
+                .collect(),
+            labels_removed: from_node
+                .labels
+                .iter()
+                .filter(|label| !to_node.labels.contains(label))
+                .cloned()
+                .collect(),
+            changes,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::engine::get_test_engine;
+    use crate::engine::node::Payload;
+
+    #[test]
+    fn test_updated_payloads_are_kept_as_versions() {
+        let test_engine = get_test_engine();
+        let arced_test_engine = Arc::new(&test_engine);
+        let node_id = arced_test_engine
+            .get_or_add_node(
+                Payload::Text("Pixlie AI\nCrawls the web\nFor your projects".to_string()),
+                vec![NodeLabel::Paragraph],
+                true,
+                None,
+            )
+            .unwrap()
+            .get_node_id();
+        let before_update = Utc::now();
+        test_engine
+            .update_node(
+                &node_id,
+                Payload::Text(
+                    "Pixlie AI\nCrawls and classifies the web\nFor your projects".to_string(),
+                ),
+            )
+            .unwrap();
+        // Updates with the same payload do not add a version
+        test_engine
+            .update_node(
+                &node_id,
+                Payload::Text(
+                    "Pixlie AI\nCrawls and classifies the web\nFor your projects".to_string(),
+                ),
+            )
+            .unwrap();
+
+        let versions = test_engine.get_api_node_versions(&node_id).unwrap();
+        assert_eq!(versions.len(), 2);
+        assert!(versions[0].replaced_at.is_some());
+        assert!(versions[1].replaced_at.is_none());
+
+        let node_before = test_engine
+            .get_node_as_of(&node_id, &before_update)
+            .unwrap()
+            .unwrap();
+        assert!(
+            matches!(&node_before.payload, Payload::Text(text) if text.contains("Crawls the web"))
+        );
+        let node_now = test_engine
+            .get_node_as_of(&node_id, &Utc::now())
+            .unwrap()
+            .unwrap();
+        assert!(matches!(&node_now.payload, Payload::Text(text) if text.contains("classifies")));
+
+        let diff = test_engine.diff_node_versions(&node_id, 1, 2).unwrap();
+        assert_eq!(diff.changes.len(), 1);
+        assert_eq!(diff.changes[0].field, "payload");
+        let lines: Vec<(usize, &str)> = diff.changes[0]
+            .lines
+            .iter()
+            .map(|line| (line.line_number, line.text.as_str()))
+            .collect();
+        assert_eq!(
+            lines,
+            vec![(2, "Crawls the web"), (2, "Crawls and classifies the web")]
+        );
+        assert!(matches!(
+            diff.changes[0].lines[0].change,
+            LineChangeType::Removed
+        ));
+        assert!(test_engine.diff_node_versions(&node_id, 0, 3).is_err());
+    }
+}
@@ -1,28 +1,345 @@
-use crate::engine::node::{ArcedNodeId, ArcedNodeItem, NodeId, NodeItem, Payload};
-use crate::engine::{get_chunk_id_and_node_ids, NodeFlags};
+use crate::engine::chunk_cache::ChunkCache;
+use crate::engine::migrations::{from_versioned_bytes, to_versioned_bytes};
+use crate::engine::node::{ArcedNodeItem, NodeId, NodeItem, NodeLabel, NodeVersion, Payload};
+use crate::engine::{get_chunk_id_and_node_ids, NodeFlags, RetryPolicy};
 use crate::error::{PiError, PiResult};
 use chrono::Utc;
 use log::error;
-use postcard::{from_bytes, to_allocvec};
-use rocksdb::{Options, SliceTransform, DB};
-use std::collections::HashMap;
+use postcard::to_allocvec;
+use rocksdb::{Options, SliceTransform, WriteBatch, DB};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
 use std::path::PathBuf;
-use std::sync::Arc;
+use std::sync::{Arc, Mutex, MutexGuard};
 
-const NODES_CHUNK_PREFIX: &str = "nodes/chunk/";
+// Both prefixes have the same length, since the prefix extractor uses a fixed length
+pub(super) const NODES_CHUNK_PREFIX: &str = "nodes/chunk/";
+pub(super) const NODES_INDEX_PREFIX: &str = "nodes/index/";
+// Content is only read by node ID, never iterated over
+const NODES_CONTENT_PREFIX: &str = "nodes/content/";
+// Earlier versions of a node, keyed by node ID and the time they were replaced at.
+// A migration which changes `NodeItem` has to migrate these too, like the node chunks
+pub(super) const NODES_VERSIONS_PREFIX: &str = "nodes/versions/";
+// The ID the next node gets, so IDs of deleted nodes are never used again. This is only written
+// when a node is deleted, otherwise the next ID follows the largest ID in the index.
+// The value is a big-endian u32 which does not change with the format version of the DB
+const NEXT_NODE_ID_KEY: &str = "nodes/next_id";
+
+// Number of node chunks (of 100 nodes each) which are kept in memory
+const NODE_CHUNKS_IN_CACHE: usize = 1000;
+
+// Labels, payload hash and flags of a node. These are kept in memory for all nodes and stored
+// in index chunks next to the node chunks, so they can be loaded without reading the payloads
+#[derive(Clone, Deserialize, Serialize)]
+pub(super) struct NodeIndexEntry {
+    pub(super) labels: Vec<NodeLabel>,
+    pub(super) payload_hash: u64,
+    pub(super) flags: NodeFlags,
+}
+
+// FNV-1a hash of the serialized payload. The hash is stored on disk,
+// so it must not change between Rust versions like `DefaultHasher` may
+fn get_payload_hash(payload: &Payload) -> PiResult<u64> {
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for byte in to_allocvec(payload)? {
+        hash ^= byte as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    Ok(hash)
+}
+
+// The raw HTML of a web page is large and only needed when the page is processed,
+// so it is stored separately and not in the node chunks
+fn has_separate_content(node: &NodeItem) -> bool {
+    node.labels.contains(&NodeLabel::WebPage) && matches!(node.payload, Payload::Text(_))
+}
+
+fn get_versions_prefix(node_id: &NodeId) -> String {
+    format!("{}{}/", NODES_VERSIONS_PREFIX, node_id)
+}
+
+// Keys of versions sort in the order the versions were replaced in
+fn get_version_key(version: &NodeVersion) -> String {
+    format!(
+        "{}{:020}",
+        get_versions_prefix(&version.node.id),
+        version
+            .replaced_at
+            .timestamp_nanos_opt()
+            .unwrap_or(i64::MAX)
+    )
+}
+
+fn read_chunk(
+    db: &DB,
+    chunk_id: u32,
+    index: &HashMap<NodeId, NodeIndexEntry>,
+) -> PiResult<HashMap<NodeId, ArcedNodeItem>> {
+    let mut items: HashMap<NodeId, ArcedNodeItem> = HashMap::new();
+    if let Some(bytes) = db.get(format!("{}{}", NODES_CHUNK_PREFIX, chunk_id))? {
+        let data: Vec<(NodeId, NodeItem)> = from_versioned_bytes(&bytes)?;
+        for (node_id, mut node) in data {
+            // Flags in the index are the current ones, see `Nodes::open`
+            if let Some(index_entry) = index.get(&node_id) {
+                node.flags = index_entry.flags.clone();
+                items.insert(node_id, Arc::new(node));
+            }
+        }
+    }
+    Ok(items)
+}
+
+fn get_next_node_id(db: &DB) -> PiResult<NodeId> {
+    match db.get(NEXT_NODE_ID_KEY)? {
+        Some(bytes) => match <[u8; 4]>::try_from(bytes.as_slice()) {
+            Ok(bytes) => Ok(NodeId::from_be_bytes(bytes)),
+            Err(_) => Err(PiError::InternalError(
+                "Cannot read the ID for the next node".to_string(),
+            )),
+        },
+        None => Ok(0),
+    }
+}
+
+fn read_versions(db: &DB, node_id: &NodeId) -> PiResult<Vec<(Vec<u8>, NodeVersion)>> {
+    let prefix = get_versions_prefix(node_id);
+    let mut versions: Vec<(Vec<u8>, NodeVersion)> = vec![];
+    for item in db.prefix_iterator(&prefix) {
+        match item {
+            Ok((key, value)) => {
+                if !key.starts_with(prefix.as_bytes()) {
+                    break;
+                }
+                versions.push((key.to_vec(), from_versioned_bytes(&value)?));
+            }
+            Err(err) => {
+                error!("RocksDB error: {}", err);
+                return Err(PiError::RocksdbError(err));
+            }
+        }
+    }
+    Ok(versions)
+}
 
 pub(super) struct Nodes {
-    pub(super) data: HashMap<ArcedNodeId, ArcedNodeItem>,
+    index: HashMap<NodeId, NodeIndexEntry>,
+    node_ids_by_label: HashMap<NodeLabel, BTreeSet<NodeId>>,
+    node_ids_by_payload_hash: HashMap<u64, BTreeSet<NodeId>>,
+    chunks: Mutex<ChunkCache<ArcedNodeItem>>,
+    // Writes of nodes outside their chunk, like the content of web pages or earlier versions,
+    // by chunk ID. These are written in the same batch as the chunk, see `write_chunk_to_batch`
+    unsaved_writes: Mutex<HashMap<u32, Vec<UnsavedWrite>>>,
 }
 
+// A put, or a delete when there is no value
+type UnsavedWrite = (String, Option<Vec<u8>>);
+
 impl Nodes {
     pub(super) fn new() -> Self {
         Nodes {
-            data: HashMap::new(),
+            index: HashMap::new(),
+            node_ids_by_label: HashMap::new(),
+            node_ids_by_payload_hash: HashMap::new(),
+            chunks: Mutex::new(ChunkCache::new(NODE_CHUNKS_IN_CACHE)),
+            unsaved_writes: Mutex::new(HashMap::new()),
+        }
+    }
+
+    fn add_to_indexes(&mut self, node_id: NodeId, index_entry: NodeIndexEntry) {
+        for label in index_entry.labels.iter() {
+            self.node_ids_by_label
+                .entry(label.clone())
+                .or_default()
+                .insert(node_id);
+        }
+        self.node_ids_by_payload_hash
+            .entry(index_entry.payload_hash)
+            .or_default()
+            .insert(node_id);
+        self.index.insert(node_id, index_entry);
+    }
+
+    fn remove_from_indexes(&mut self, node_id: &NodeId) {
+        let Some(index_entry) = self.index.remove(node_id) else {
+            return;
+        };
+        for label in index_entry.labels.iter() {
+            if let Some(node_ids) = self.node_ids_by_label.get_mut(label) {
+                node_ids.remove(node_id);
+                if node_ids.is_empty() {
+                    self.node_ids_by_label.remove(label);
+                }
+            }
+        }
+        if let Some(node_ids) = self
+            .node_ids_by_payload_hash
+            .get_mut(&index_entry.payload_hash)
+        {
+            node_ids.remove(node_id);
+            if node_ids.is_empty() {
+                self.node_ids_by_payload_hash
+                    .remove(&index_entry.payload_hash);
+            }
+        }
+    }
+
+    fn lock_chunks(&self) -> PiResult<MutexGuard<'_, ChunkCache<ArcedNodeItem>>> {
+        self.chunks.lock().map_err(|err| {
+            error!("Error locking node chunks: {}", err);
+            PiError::InternalError(format!("Error locking node chunks: {}", err))
+        })
+    }
+
+    fn add_unsaved_write(
+        &mut self,
+        node_id: &NodeId,
+        key: String,
+        value: Option<Vec<u8>>,
+    ) -> PiResult<()> {
+        self.unsaved_writes
+            .get_mut()
+            .map_err(|err| {
+                PiError::InternalError(format!("Error locking unsaved writes of nodes: {}", err))
+            })?
+            .entry(get_chunk_id_and_node_ids(node_id).0)
+            .or_default()
+            .push((key, value));
+        Ok(())
+    }
+
+    // The latest unsaved write of this key, which is read instead of the DB
+    fn get_unsaved_write(&self, node_id: &NodeId, key: &str) -> PiResult<Option<UnsavedWrite>> {
+        let unsaved_writes = self.unsaved_writes.lock().map_err(|err| {
+            PiError::InternalError(format!("Error locking unsaved writes of nodes: {}", err))
+        })?;
+        Ok(unsaved_writes
+            .get(&get_chunk_id_and_node_ids(node_id).0)
+            .and_then(|writes| writes.iter().rev().find(|(x_key, _)| x_key == key))
+            .cloned())
+    }
+
+    // Reads the chunk of this node from the DB if it is not in the cache yet
+    fn get_chunk_mut(
+        &mut self,
+        db: &DB,
+        node_id: &NodeId,
+    ) -> PiResult<&mut HashMap<NodeId, ArcedNodeItem>> {
+        let chunk_id = get_chunk_id_and_node_ids(node_id).0;
+        let chunks = self
+            .chunks
+            .get_mut()
+            .map_err(|err| PiError::InternalError(format!("Error locking node chunks: {}", err)))?;
+        if !chunks.contains_chunk(chunk_id) {
+            chunks.insert_chunk(chunk_id, read_chunk(db, chunk_id, &self.index)?);
         }
+        chunks.get_chunk_mut(chunk_id).ok_or_else(|| {
+            PiError::InternalError(format!("Cannot find chunk {} of nodes", chunk_id))
+        })
     }
 
-    pub(super) fn open(path_to_db: &PathBuf) -> PiResult<(Self, u32)> {
+    pub(super) fn contains_node(&self, node_id: &NodeId) -> bool {
+        self.index.contains_key(node_id)
+    }
+
+    pub(super) fn get_index_entry(&self, node_id: &NodeId) -> Option<&NodeIndexEntry> {
+        self.index.get(node_id)
+    }
+
+    // IDs of all nodes, sorted so that nodes in the same chunk come one after another
+    pub(super) fn get_node_ids(&self) -> Vec<NodeId> {
+        let mut node_ids: Vec<NodeId> = self.index.keys().cloned().collect();
+        node_ids.sort();
+        node_ids
+    }
+
+    // The node without separately stored content, see `get_node_with_content`
+    pub(super) fn get_node(&self, db: &DB, node_id: &NodeId) -> PiResult<Option<ArcedNodeItem>> {
+        if !self.contains_node(node_id) {
+            return Ok(None);
+        }
+        let chunk_id = get_chunk_id_and_node_ids(node_id).0;
+        let mut chunks = self.lock_chunks()?;
+        if !chunks.contains_chunk(chunk_id) {
+            chunks.insert_chunk(chunk_id, read_chunk(db, chunk_id, &self.index)?);
+        }
+        Ok(chunks
+            .get_chunk(chunk_id)
+            .and_then(|items| items.get(node_id).cloned()))
+    }
+
+    pub(super) fn get_node_with_content(
+        &self,
+        db: &DB,
+        node_id: &NodeId,
+    ) -> PiResult<Option<ArcedNodeItem>> {
+        let Some(node) = self.get_node(db, node_id)? else {
+            return Ok(None);
+        };
+        if !has_separate_content(&node) {
+            return Ok(Some(node));
+        }
+        let key = format!("{}{}", NODES_CONTENT_PREFIX, node_id);
+        let content = match self.get_unsaved_write(node_id, &key)? {
+            Some((_, value)) => value,
+            None => db.get(key)?,
+        };
+        match content {
+            Some(bytes) => Ok(Some(Arc::new(NodeItem {
+                payload: Payload::Text(String::from_utf8_lossy(&bytes).to_string()),
+                ..(*node).clone()
+            }))),
+            // Nodes written before content was stored separately have it in the chunk
+            None => Ok(Some(node)),
+        }
+    }
+
+    pub(super) fn get_all_labels(&self) -> Vec<NodeLabel> {
+        self.node_ids_by_label.keys().cloned().collect()
+    }
+
+    pub(super) fn get_node_ids_with_label(&self, label: &NodeLabel) -> Vec<NodeId> {
+        match self.node_ids_by_label.get(label) {
+            Some(node_ids) => node_ids.iter().cloned().collect(),
+            None => vec![],
+        }
+    }
+
+    // Nodes with the given label and exactly the same payload, found with the payload hash
+    pub(super) fn get_node_ids_with_payload(
+        &self,
+        db: &DB,
+        label: &NodeLabel,
+        payload: &Payload,
+    ) -> PiResult<Vec<NodeId>> {
+        let Some(node_ids) = self
+            .node_ids_by_payload_hash
+            .get(&get_payload_hash(payload)?)
+        else {
+            return Ok(vec![]);
+        };
+        let serialized_payload = to_allocvec(payload)?;
+        let mut matching_node_ids: Vec<NodeId> = vec![];
+        for node_id in node_ids {
+            if !self
+                .index
+                .get(node_id)
+                .is_some_and(|index_entry| index_entry.labels.contains(label))
+            {
+                continue;
+            }
+            // Different payloads can have the same hash
+            if let Some(node) = self.get_node_with_content(db, node_id)? {
+                if to_allocvec(&node.payload)? == serialized_payload {
+                    matching_node_ids.push(*node_id);
+                }
+            }
+        }
+        Ok(matching_node_ids)
+    }
+
+    // Only the indexes are read here, nodes are read from the DB in chunks when they are needed.
+    // Returns the nodes and the ID for the next node
+    pub(super) fn open(path_to_db: &PathBuf) -> PiResult<(Self, NodeId)> {
         let prefix_extractor = SliceTransform::create_fixed_prefix(NODES_CHUNK_PREFIX.len());
         let mut nodes = Nodes::new();
         let mut opts = Options::default();
@@ -35,17 +352,16 @@ impl Nodes {
                 return Err(PiError::RocksdbError(err));
             }
         };
-        let mut last_node_id: NodeId = 0;
-        for chunk in db.prefix_iterator(NODES_CHUNK_PREFIX) {
+        let mut next_node_id: NodeId = get_next_node_id(&db)?;
+        for chunk in db.prefix_iterator(NODES_INDEX_PREFIX) {
             match chunk {
                 Ok(chunk) => {
-                    let data: Vec<(NodeId, NodeItem)> = from_bytes(&chunk.1)?;
-                    last_node_id = data.last().unwrap().0;
-                    for (node_id, mut node) in data {
-                        if node.flags.contains(NodeFlags::IS_REQUESTING) {
-                            node.flags.toggle(NodeFlags::IS_REQUESTING);
-                        }
-                        nodes.data.insert(Arc::new(node_id), Arc::new(node));
+                    let data: Vec<(NodeId, NodeIndexEntry)> = from_versioned_bytes(&chunk.1)?;
+                    for (node_id, mut index_entry) in data {
+                        // Requests which were open when the project was closed will never finish
+                        index_entry.flags.remove(NodeFlags::IS_REQUESTING);
+                        next_node_id = next_node_id.max(node_id + 1);
+                        nodes.add_to_indexes(node_id, index_entry);
                     }
                 }
                 Err(err) => {
@@ -54,62 +370,370 @@ impl Nodes {
                 }
             }
         }
-        Ok((nodes, last_node_id))
+        Ok((nodes, next_node_id))
     }
 
     pub(super) fn save_item_chunk_to_disk(&self, db: Arc<DB>, node_id: &NodeId) -> PiResult<()> {
+        let mut batch = WriteBatch::default();
+        self.write_chunk_to_batch(&mut batch, node_id)?;
+        db.write(batch)?;
+        self.mark_chunk_saved(node_id)
+    }
+
+    pub(super) fn write_chunk_to_batch(
+        &self,
+        batch: &mut WriteBatch,
+        node_id: &NodeId,
+    ) -> PiResult<()> {
         // We store this (and all other nodes in its chunk) node to DB
         // in the chunk corresponding to the node ID divided by 100
         // Create a chunk of data from the start node ID to the end node ID of this chunk
         let (chunk_id, node_ids) = get_chunk_id_and_node_ids(node_id);
-        let chunk: Vec<(NodeId, NodeItem)> = node_ids
+        let unsaved_writes = self
+            .unsaved_writes
+            .lock()
+            .map_err(|err| {
+                PiError::InternalError(format!("Error locking unsaved writes of nodes: {}", err))
+            })?
+            .remove(&chunk_id)
+            .unwrap_or_default();
+        for (key, value) in unsaved_writes {
+            match value {
+                Some(value) => batch.put(key, value),
+                None => batch.delete(key),
+            }
+        }
+        let index_chunk: Vec<(NodeId, NodeIndexEntry)> = node_ids
             .iter()
-            .filter_map(|x_node_id| match self.data.get(x_node_id) {
-                Some(node) => Some((
-                    *x_node_id,
-                    NodeItem {
-                        id: *x_node_id,
-                        payload: node.payload.clone(),
-                        labels: node.labels.clone(),
-                        flags: node.flags.clone(),
-                        written_at: node.written_at.clone(),
-                    },
-                )),
-                None => None,
+            .filter_map(|x_node_id| {
+                self.index
+                    .get(x_node_id)
+                    .map(|index_entry| (*x_node_id, index_entry.clone()))
             })
             .collect();
-        db.put(
-            format!("{}{}", NODES_CHUNK_PREFIX, chunk_id),
-            to_allocvec(&chunk)?,
-        )?;
+        if index_chunk.is_empty() {
+            // All nodes of this chunk have been deleted
+            batch.delete(format!("{}{}", NODES_CHUNK_PREFIX, chunk_id));
+            batch.delete(format!("{}{}", NODES_INDEX_PREFIX, chunk_id));
+        } else {
+            // A chunk which is not in the cache has not changed since it was read
+            let mut chunks = self.lock_chunks()?;
+            if let Some(items) = chunks.get_chunk(chunk_id) {
+                let chunk: Vec<(NodeId, NodeItem)> = node_ids
+                    .iter()
+                    .filter_map(|x_node_id| {
+                        items
+                            .get(x_node_id)
+                            .map(|node| (*x_node_id, node.as_ref().clone()))
+                    })
+                    .collect();
+                batch.put(
+                    format!("{}{}", NODES_CHUNK_PREFIX, chunk_id),
+                    to_versioned_bytes(&chunk)?,
+                );
+            }
+            batch.put(
+                format!("{}{}", NODES_INDEX_PREFIX, chunk_id),
+                to_versioned_bytes(&index_chunk)?,
+            );
+        }
         Ok(())
     }
 
-    pub(super) fn update_node(&mut self, node_id: &NodeId, payload: Payload) -> PiResult<()> {
-        self.data.get_mut(node_id).map(|node| {
-            *node = Arc::new(NodeItem {
-                id: node.id.clone(),
-                payload,
-                labels: node.labels.clone(),
-                flags: node.flags.clone(),
-                written_at: Utc::now(),
-            });
-        });
+    // Called once the batch with the chunk has been written, so the chunk can be evicted
+    pub(super) fn mark_chunk_saved(&self, node_id: &NodeId) -> PiResult<()> {
+        self.lock_chunks()?
+            .mark_saved(get_chunk_id_and_node_ids(node_id).0);
+        Ok(())
+    }
+
+    // Used by migrations, the chunk on disk is in an older format so it is not read
+    pub(super) fn insert_migrated_chunk(
+        &mut self,
+        db: &DB,
+        chunk_id: u32,
+        chunk: Vec<NodeItem>,
+    ) -> PiResult<()> {
+        self.chunks
+            .get_mut()
+            .map_err(|err| PiError::InternalError(format!("Error locking node chunks: {}", err)))?
+            .insert_chunk(chunk_id, HashMap::new());
+        for node in chunk {
+            self.insert_node(db, node)?;
+        }
+        Ok(())
+    }
+
+    pub(super) fn insert_node(&mut self, db: &DB, node: NodeItem) -> PiResult<()> {
+        let index_entry = NodeIndexEntry {
+            labels: node.labels.clone(),
+            payload_hash: get_payload_hash(&node.payload)?,
+            flags: node.flags.clone(),
+        };
+        let node = match &node.payload {
+            Payload::Text(content) if has_separate_content(&node) => {
+                self.add_unsaved_write(
+                    &node.id,
+                    format!("{}{}", NODES_CONTENT_PREFIX, node.id),
+                    Some(content.as_bytes().to_vec()),
+                )?;
+                NodeItem {
+                    payload: Payload::Text(String::new()),
+                    ..node
+                }
+            }
+            _ => node,
+        };
+        self.remove_from_indexes(&node.id);
+        self.add_to_indexes(node.id, index_entry);
+        self.get_chunk_mut(db, &node.id)?
+            .insert(node.id, Arc::new(node));
+        Ok(())
+    }
+
+    pub(super) fn remove_node(
+        &mut self,
+        db: &DB,
+        node_id: &NodeId,
+    ) -> PiResult<Option<ArcedNodeItem>> {
+        if !self.contains_node(node_id) {
+            return Ok(None);
+        }
+        self.remove_from_indexes(node_id);
+        let removed = self.get_chunk_mut(db, node_id)?.remove(node_id);
+        if removed
+            .as_ref()
+            .is_some_and(|node| has_separate_content(node))
+        {
+            self.add_unsaved_write(
+                node_id,
+                format!("{}{}", NODES_CONTENT_PREFIX, node_id),
+                None,
+            )?;
+        }
+        // The versions of a deleted node are not kept
+        for key in self.get_version_keys(db, node_id)? {
+            self.add_unsaved_write(node_id, key, None)?;
+        }
+        self.keep_node_id_used(db, node_id)?;
+        Ok(removed)
+    }
+
+    // The ID of a removed node is not given to a new node after the DB is opened again.
+    // The counter is written with the chunk of the node, only its largest unsaved value is kept
+    fn keep_node_id_used(&mut self, db: &DB, node_id: &NodeId) -> PiResult<()> {
+        let unsaved_writes = self.unsaved_writes.get_mut().map_err(|err| {
+            PiError::InternalError(format!("Error locking unsaved writes of nodes: {}", err))
+        })?;
+        let mut next_node_id = get_next_node_id(db)?;
+        for writes in unsaved_writes.values_mut() {
+            for (_, value) in writes.iter().filter(|(key, _)| key == NEXT_NODE_ID_KEY) {
+                if let Some(Ok(bytes)) = value.as_deref().map(<[u8; 4]>::try_from) {
+                    next_node_id = next_node_id.max(NodeId::from_be_bytes(bytes));
+                }
+            }
+            writes.retain(|(key, _)| key != NEXT_NODE_ID_KEY);
+        }
+        next_node_id = next_node_id.max(node_id + 1);
+        unsaved_writes
+            .entry(get_chunk_id_and_node_ids(node_id).0)
+            .or_default()
+            .push((
+                NEXT_NODE_ID_KEY.to_string(),
+                Some(next_node_id.to_be_bytes().to_vec()),
+            ));
+        Ok(())
+    }
+
+    // Earlier versions of a node, oldest first. The current version is not included
+    pub(super) fn get_node_versions(
+        &self,
+        db: &DB,
+        node_id: &NodeId,
+    ) -> PiResult<Vec<NodeVersion>> {
+        let mut versions: BTreeMap<String, NodeVersion> = read_versions(db, node_id)?
+            .into_iter()
+            .map(|(key, version)| (String::from_utf8_lossy(&key).to_string(), version))
+            .collect();
+        let prefix = get_versions_prefix(node_id);
+        if let Some(writes) = self
+            .unsaved_writes
+            .lock()
+            .map_err(|err| {
+                PiError::InternalError(format!("Error locking unsaved writes of nodes: {}", err))
+            })?
+            .get(&get_chunk_id_and_node_ids(node_id).0)
+        {
+            for (key, value) in writes.iter().filter(|(key, _)| key.starts_with(&prefix)) {
+                match value {
+                    Some(value) => versions.insert(key.clone(), from_versioned_bytes(value)?),
+                    None => versions.remove(key),
+                };
+            }
+        }
+        Ok(versions.into_values().collect())
+    }
+
+    fn get_version_keys(&self, db: &DB, node_id: &NodeId) -> PiResult<Vec<String>> {
+        let mut keys: Vec<String> = read_versions(db, node_id)?
+            .into_iter()
+            .map(|(key, _)| String::from_utf8_lossy(&key).to_string())
+            .collect();
+        let prefix = get_versions_prefix(node_id);
+        if let Some(writes) = self
+            .unsaved_writes
+            .lock()
+            .map_err(|err| {
+                PiError::InternalError(format!("Error locking unsaved writes of nodes: {}", err))
+            })?
+            .get(&get_chunk_id_and_node_ids(node_id).0)
+        {
+            keys.extend(
+                writes
+                    .iter()
+                    .filter(|(key, value)| key.starts_with(&prefix) && value.is_some())
+                    .map(|(key, _)| key.clone()),
+            );
+        }
+        Ok(keys)
+    }
+
+    // Replaces a node whose payload has not changed
+    fn replace_node(&mut self, db: &DB, node: NodeItem) -> PiResult<()> {
+        if let Some(index_entry) = self.index.get_mut(&node.id) {
+            index_entry.flags = node.flags.clone();
+        }
+        self.get_chunk_mut(db, &node.id)?
+            .insert(node.id, Arc::new(node));
+        Ok(())
+    }
+
+    pub(super) fn update_node(
+        &mut self,
+        db: &DB,
+        node_id: &NodeId,
+        payload: Payload,
+    ) -> PiResult<()> {
+        if let Some(node) = self.get_node_with_content(db, node_id)? {
+            // The earlier payload is kept as a version, unless it has not changed
+            if to_allocvec(&node.payload)? != to_allocvec(&payload)? {
+                let version = NodeVersion {
+                    node: node.as_ref().clone(),
+                    replaced_at: Utc::now(),
+                };
+                self.add_unsaved_write(
+                    node_id,
+                    get_version_key(&version),
+                    Some(to_versioned_bytes(&version)?),
+                )?;
+            }
+            self.insert_node(
+                db,
+                NodeItem {
+                    id: node.id,
+                    payload,
+                    labels: node.labels.clone(),
+                    flags: node.flags.clone(),
+                    written_at: Utc::now(),
+                    retry_count: node.retry_count,
+                    next_attempt_at: node.next_attempt_at,
+                },
+            )?;
+        }
         Ok(())
     }
 
-    pub(super) fn toggle_flag(&mut self, node_id: &NodeId, flag: NodeFlags) {
-        self.data.get_mut(node_id).map(|node| {
+    pub(super) fn toggle_flag(
+        &mut self,
+        db: &DB,
+        node_id: &NodeId,
+        flag: NodeFlags,
+    ) -> PiResult<()> {
+        if let Some(node) = self.get_node(db, node_id)? {
             let mut flags: NodeFlags = node.flags.clone();
-            flags.toggle(flag);
-            *node = Arc::new(NodeItem {
-                id: node.id.clone(),
-                payload: node.payload.clone(),
-                labels: node.labels.clone(),
-                flags,
-                written_at: Utc::now(),
-            });
-        });
+            flags.toggle(flag.clone());
+            let is_processed =
+                flag.contains(NodeFlags::IS_PROCESSED) && flags.contains(NodeFlags::IS_PROCESSED);
+            if is_processed {
+                // Errors before the node was processed do not count against its later attempts,
+                // like when a link is fetched again
+                flags.remove(NodeFlags::HAD_ERROR | NodeFlags::GAVE_UP);
+            }
+            self.replace_node(
+                db,
+                NodeItem {
+                    id: node.id,
+                    payload: node.payload.clone(),
+                    labels: node.labels.clone(),
+                    flags,
+                    written_at: Utc::now(),
+                    retry_count: if is_processed { 0 } else { node.retry_count },
+                    next_attempt_at: if is_processed {
+                        None
+                    } else {
+                        node.next_attempt_at
+                    },
+                },
+            )?;
+        }
+        Ok(())
+    }
+
+    // Called when an attempt at processing the node succeeded
+    pub(super) fn clear_error(&mut self, db: &DB, node_id: &NodeId) -> PiResult<()> {
+        if let Some(node) = self.get_node(db, node_id)? {
+            if node.retry_count == 0 && !node.flags.contains(NodeFlags::HAD_ERROR) {
+                return Ok(());
+            }
+            let mut flags: NodeFlags = node.flags.clone();
+            flags.remove(NodeFlags::HAD_ERROR | NodeFlags::GAVE_UP);
+            self.replace_node(
+                db,
+                NodeItem {
+                    id: node.id,
+                    payload: node.payload.clone(),
+                    labels: node.labels.clone(),
+                    flags,
+                    written_at: Utc::now(),
+                    retry_count: 0,
+                    next_attempt_at: None,
+                },
+            )?;
+        }
+        Ok(())
+    }
+
+    pub(super) fn record_error(
+        &mut self,
+        db: &DB,
+        node_id: &NodeId,
+        retry_policy: &RetryPolicy,
+    ) -> PiResult<()> {
+        if let Some(node) = self.get_node(db, node_id)? {
+            let mut flags: NodeFlags = node.flags.clone();
+            let retry_count = node.retry_count + 1;
+            let now = Utc::now();
+            flags.insert(NodeFlags::HAD_ERROR);
+            let next_attempt_at = if retry_policy.has_attempts_left(retry_count) {
+                Some(now + retry_policy.get_delay(retry_count))
+            } else {
+                flags.insert(NodeFlags::GAVE_UP);
+                None
+            };
+            self.replace_node(
+                db,
+                NodeItem {
+                    id: node.id,
+                    payload: node.payload.clone(),
+                    labels: node.labels.clone(),
+                    flags,
+                    written_at: now,
+                    retry_count,
+                    next_attempt_at,
+                },
+            )?;
+        }
+        Ok(())
     }
 }
 
@@ -172,6 +796,8 @@ mod tests {
                     labels: payload.1.to_vec(),
                     flags: NodeFlags::default(),
                     written_at: Utc::now(),
+                    retry_count: 0,
+                    next_attempt_at: None,
                 };
                 node_id += 1;
                 node
@@ -190,11 +816,9 @@ mod tests {
             let mut db_nodes: Nodes = Nodes::new();
             // Insert all nodes into the DB
             nodes.iter().for_each(|node| {
-                let key = Arc::new(node.id);
-                let value = Arc::new(node.clone());
-                db_nodes.data.insert(key.clone(), value);
+                db_nodes.insert_node(&arced_db, node.clone()).unwrap();
                 db_nodes
-                    .save_item_chunk_to_disk(arced_db.clone(), &key)
+                    .save_item_chunk_to_disk(arced_db.clone(), &node.id)
                     .unwrap();
             });
         }
@@ -202,10 +826,14 @@ mod tests {
         {
             // Load data from disk and check that it is the same as the original
             let (db_nodes, _last_node_id) = Nodes::open(&db_path).unwrap();
+            let db = DB::open_default(db_path.clone()).unwrap();
 
             for node in nodes.iter() {
                 // Check the ID and payload of each node against the one in the DB
-                let db_node = db_nodes.data.get(&node.id).unwrap();
+                let db_node = db_nodes
+                    .get_node_with_content(&db, &node.id)
+                    .unwrap()
+                    .unwrap();
                 assert_eq!(node.id, db_node.id);
                 // Match the payload data type and check the inner values
                 match node.payload {
@@ -245,10 +873,14 @@ mod tests {
         {
             // Load data from disk and check that it is the same as the original
             let (db_nodes, _last_node_id) = Nodes::open(&db_path).unwrap();
+            let db = DB::open_default(db_path.clone()).unwrap();
 
             for node in nodes.iter() {
                 // Check the ID and payload of each node against the one in the DB
-                let db_node = db_nodes.data.get(&node.id).unwrap();
+                let db_node = db_nodes
+                    .get_node_with_content(&db, &node.id)
+                    .unwrap()
+                    .unwrap();
                 assert_eq!(node.id, db_node.id);
                 // Match the payload data type and check the inner values
                 match node.payload {
@@ -280,4 +912,215 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    fn test_chunk_of_deleted_nodes_is_removed_from_disk() {
+        let temp_dir = tempfile::Builder::new()
+            .prefix("_path_for_rocksdb_storage2")
+            .tempdir()
+            .expect("Failed to create temporary path for the _path_for_rocksdb_storage2.");
+        let db_path = PathBuf::from(temp_dir.path());
+
+        {
+            let arced_db = Arc::new(DB::open_default(db_path.clone()).unwrap());
+            let mut db_nodes: Nodes = Nodes::new();
+            // One node in the first chunk and two nodes in the second chunk
+            for node_id in [7, 100, 101] {
+                db_nodes
+                    .insert_node(
+                        &arced_db,
+                        NodeItem {
+                            id: node_id,
+                            payload: Payload::Text(format!("Node {}", node_id)),
+                            labels: vec![NodeLabel::Paragraph],
+                            flags: NodeFlags::default(),
+                            written_at: Utc::now(),
+                            retry_count: 0,
+                            next_attempt_at: None,
+                        },
+                    )
+                    .unwrap();
+                db_nodes
+                    .save_item_chunk_to_disk(arced_db.clone(), &node_id)
+                    .unwrap();
+            }
+            for node_id in [100, 101] {
+                db_nodes.remove_node(&arced_db, &node_id).unwrap();
+                db_nodes
+                    .save_item_chunk_to_disk(arced_db.clone(), &node_id)
+                    .unwrap();
+            }
+        }
+
+        let (db_nodes, next_node_id) = Nodes::open(&db_path).unwrap();
+        assert_eq!(db_nodes.get_node_ids(), vec![7]);
+        // The IDs of the deleted nodes are not given to new nodes
+        assert_eq!(next_node_id, 102);
+    }
+
+    #[test]
+    fn test_label_and_payload_indexes() {
+        let temp_dir = tempfile::Builder::new()
+            .prefix("_path_for_rocksdb_storage2")
+            .tempdir()
+            .expect("Failed to create temporary path for the _path_for_rocksdb_storage2.");
+        let db_path = PathBuf::from(temp_dir.path());
+        let pixlie = Payload::Text("pixlie.com".to_string());
+
+        {
+            let arced_db = Arc::new(DB::open_default(db_path.clone()).unwrap());
+            let mut db_nodes: Nodes = Nodes::new();
+            for (node_id, payload, labels) in [
+                (0, pixlie.clone(), vec![NodeLabel::DomainName]),
+                (1, pixlie.clone(), vec![NodeLabel::SearchTerm]),
+                (
+                    2,
+                    Payload::Text("google.com".to_string()),
+                    vec![NodeLabel::DomainName],
+                ),
+            ] {
+                db_nodes
+                    .insert_node(
+                        &arced_db,
+                        NodeItem {
+                            id: node_id,
+                            payload,
+                            labels,
+                            flags: NodeFlags::default(),
+                            written_at: Utc::now(),
+                            retry_count: 0,
+                            next_attempt_at: None,
+                        },
+                    )
+                    .unwrap();
+                db_nodes
+                    .save_item_chunk_to_disk(arced_db.clone(), &node_id)
+                    .unwrap();
+            }
+            assert_eq!(
+                db_nodes.get_node_ids_with_label(&NodeLabel::DomainName),
+                vec![0, 2]
+            );
+            assert_eq!(
+                db_nodes
+                    .get_node_ids_with_payload(&arced_db, &NodeLabel::DomainName, &pixlie)
+                    .unwrap(),
+                vec![0]
+            );
+
+            // Changing the payload moves the node in the payload index
+            db_nodes
+                .update_node(&arced_db, &2, Payload::Text("pixlie.com".to_string()))
+                .unwrap();
+            db_nodes.remove_node(&arced_db, &0).unwrap();
+            db_nodes
+                .save_item_chunk_to_disk(arced_db.clone(), &0)
+                .unwrap();
+            assert_eq!(
+                db_nodes
+                    .get_node_ids_with_payload(&arced_db, &NodeLabel::DomainName, &pixlie)
+                    .unwrap(),
+                vec![2]
+            );
+        }
+
+        // The indexes are read back from disk
+        let (db_nodes, _last_node_id) = Nodes::open(&db_path).unwrap();
+        let db = DB::open_default(db_path.clone()).unwrap();
+        assert_eq!(
+            db_nodes.get_node_ids_with_label(&NodeLabel::DomainName),
+            vec![2]
+        );
+        assert_eq!(
+            db_nodes
+                .get_node_ids_with_payload(&db, &NodeLabel::SearchTerm, &pixlie)
+                .unwrap(),
+            vec![1]
+        );
+        let mut labels = db_nodes.get_all_labels();
+        labels.sort();
+        assert_eq!(labels, vec![NodeLabel::DomainName, NodeLabel::SearchTerm]);
+    }
+
+    #[test]
+    fn test_record_error_gives_up_after_max_attempts() {
+        let retry_policy = RetryPolicy {
+            max_attempts: 2,
+            ..Default::default()
+        };
+        let temp_dir = tempfile::Builder::new()
+            .prefix("_path_for_rocksdb_storage2")
+            .tempdir()
+            .expect("Failed to create temporary path for the _path_for_rocksdb_storage2.");
+        let db = DB::open_default(PathBuf::from(temp_dir.path())).unwrap();
+        let mut nodes = Nodes::new();
+        nodes
+            .insert_node(
+                &db,
+                NodeItem {
+                    id: 0,
+                    payload: Payload::Text("google.com".to_string()),
+                    labels: vec![NodeLabel::DomainName],
+                    flags: NodeFlags::default(),
+                    written_at: Utc::now(),
+                    retry_count: 0,
+                    next_attempt_at: None,
+                },
+            )
+            .unwrap();
+
+        nodes.record_error(&db, &0, &retry_policy).unwrap();
+        let node = nodes.get_node(&db, &0).unwrap().unwrap();
+        assert_eq!(node.retry_count, 1);
+        assert!(node.flags.contains(NodeFlags::HAD_ERROR));
+        assert!(!node.flags.contains(NodeFlags::GAVE_UP));
+        assert!(node.next_attempt_at.unwrap() > node.written_at);
+
+        nodes.record_error(&db, &0, &retry_policy).unwrap();
+        let node = nodes.get_node(&db, &0).unwrap().unwrap();
+        assert_eq!(node.retry_count, 2);
+        assert!(node.flags.contains(NodeFlags::GAVE_UP));
+        assert!(node.next_attempt_at.is_none());
+    }
+
+    #[test]
+    fn test_processed_node_has_its_errors_cleared() {
+        let retry_policy = RetryPolicy::default();
+        let temp_dir = tempfile::Builder::new()
+            .prefix("_path_for_rocksdb_storage3")
+            .tempdir()
+            .expect("Failed to create temporary path for the _path_for_rocksdb_storage3.");
+        let db = DB::open_default(PathBuf::from(temp_dir.path())).unwrap();
+        let mut nodes = Nodes::new();
+        nodes
+            .insert_node(
+                &db,
+                NodeItem {
+                    id: 0,
+                    payload: Payload::Text("google.com".to_string()),
+                    labels: vec![NodeLabel::DomainName],
+                    flags: NodeFlags::default(),
+                    written_at: Utc::now(),
+                    retry_count: 0,
+                    next_attempt_at: None,
+                },
+            )
+            .unwrap();
+
+        nodes.record_error(&db, &0, &retry_policy).unwrap();
+        nodes.record_error(&db, &0, &retry_policy).unwrap();
+        nodes.toggle_flag(&db, &0, NodeFlags::IS_PROCESSED).unwrap();
+        let node = nodes.get_node(&db, &0).unwrap().unwrap();
+        assert_eq!(node.retry_count, 0);
+        assert!(!node.flags.contains(NodeFlags::HAD_ERROR));
+        assert!(node.next_attempt_at.is_none());
+
+        // Unsetting IS_PROCESSED, like before a recrawl, keeps the errors of the next attempts
+        nodes.toggle_flag(&db, &0, NodeFlags::IS_PROCESSED).unwrap();
+        nodes.record_error(&db, &0, &retry_policy).unwrap();
+        nodes.clear_error(&db, &0).unwrap();
+        let node = nodes.get_node(&db, &0).unwrap().unwrap();
+        assert_eq!(node.retry_count, 0);
+        assert!(!node.flags.contains(NodeFlags::HAD_ERROR));
+    }
 }
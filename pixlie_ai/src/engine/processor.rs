@@ -0,0 +1,423 @@
+// Copyright 2025 Pixlie Web Solutions Pvt. Ltd.
+// Licensed under the GNU General Public License version 3.0;
+// You may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// https://github.com/pixlie/PixlieAI/blob/main/LICENSE
+
+use crate::engine::node::{NodeItem, NodeLabel};
+use crate::engine::{EdgeLabel, Engine};
+use crate::entity::classifier::Classifier;
+use crate::entity::named_entity::EntityExtraction;
+use crate::entity::objective::Objective;
+use crate::entity::question::Question;
+use crate::entity::search::web_search::WebSearch;
+use crate::entity::web::domain::Domain;
+use crate::entity::web::link::Link;
+use crate::entity::web::web_page::WebPage;
+use crate::error::PiResult;
+use crate::ExternalData;
+use log::error;
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+
+// A processor is called by the engine for every node that has the label it is registered with.
+// It is called once when the node is ready to be processed and again with the response (or error)
+// of any external data request the processor made for that node
+pub trait NodeProcessor: Send + Sync {
+    fn process(
+        &self,
+        node: &NodeItem,
+        engine: Arc<&Engine>,
+        data_from_previous_request: Option<ExternalData>,
+    ) -> PiResult<()>;
+}
+
+// Any function with the same signature as our entity `process` functions is a processor
+impl<F> NodeProcessor for F
+where
+    F: Fn(&NodeItem, Arc<&Engine>, Option<ExternalData>) -> PiResult<()> + Send + Sync,
+{
+    fn process(
+        &self,
+        node: &NodeItem,
+        engine: Arc<&Engine>,
+        data_from_previous_request: Option<ExternalData>,
+    ) -> PiResult<()> {
+        self(node, engine, data_from_previous_request)
+    }
+}
+
+pub type ArcedNodeProcessor = Arc<dyn NodeProcessor>;
+
+// What needs to be processed before a processor can be called for a node
+#[derive(Clone, PartialEq)]
+pub enum ProcessingDependency {
+    // All nodes with this label have to be processed, or have given up or be blocked
+    Label(NodeLabel),
+    // All nodes connected to the node being processed, with this edge label, have to be processed,
+    // or have given up or be blocked
+    ConnectedWith(EdgeLabel),
+}
+
+struct RegisteredProcessor {
+    order: u16,
+    dependencies: Vec<ProcessingDependency>,
+    processor: ArcedNodeProcessor,
+}
+
+// Maps node labels to the processors which handle nodes with that label.
+// Processors for a node run in ascending `order`, processors with the same order
+// run in the order they were registered
+pub struct ProcessorRegistry {
+    processors: HashMap<NodeLabel, Vec<RegisteredProcessor>>,
+}
+
+impl ProcessorRegistry {
+    pub fn new() -> Self {
+        ProcessorRegistry {
+            processors: HashMap::new(),
+        }
+    }
+
+    pub fn with_builtin_processors() -> Self {
+        let mut registry = ProcessorRegistry::new();
+        registry.register(NodeLabel::DomainName, 10, vec![], Arc::new(Domain::process));
+        registry.register(NodeLabel::Link, 10, vec![], Arc::new(Link::process));
+        registry.register(
+            NodeLabel::Objective,
+            10,
+            vec![],
+            Arc::new(Objective::process),
+        );
+        // Web search and web page processing use the crawl and classifier settings
+        // which are created when the Objective is processed
+        registry.register(
+            NodeLabel::WebSearch,
+            10,
+            vec![ProcessingDependency::Label(NodeLabel::Objective)],
+            Arc::new(WebSearch::process),
+        );
+        // A WebPage is first scraped, then classified and then entities are extracted from it
+        registry.register(
+            NodeLabel::WebPage,
+            10,
+            vec![ProcessingDependency::Label(NodeLabel::Objective)],
+            Arc::new(WebPage::process),
+        );
+        registry.register(
+            NodeLabel::WebPage,
+            20,
+            vec![],
+            Arc::new(Classifier::process),
+        );
+        registry.register(
+            NodeLabel::WebPage,
+            30,
+            vec![],
+            Arc::new(EntityExtraction::process),
+        );
+        registry.register(NodeLabel::Question, 10, vec![], Arc::new(Question::process));
+        registry
+    }
+
+    pub fn register(
+        &mut self,
+        label: NodeLabel,
+        order: u16,
+        dependencies: Vec<ProcessingDependency>,
+        processor: ArcedNodeProcessor,
+    ) {
+        let processors = self.processors.entry(label).or_default();
+        // Insert after all processors with the same or lower order to keep registration order
+        let position = processors
+            .iter()
+            .position(|x| x.order > order)
+            .unwrap_or(processors.len());
+        processors.insert(
+            position,
+            RegisteredProcessor {
+                order,
+                dependencies,
+                processor,
+            },
+        );
+    }
+
+    pub fn get_processors(&self, labels: &[NodeLabel]) -> Vec<ArcedNodeProcessor> {
+        let mut matching: Vec<&RegisteredProcessor> = vec![];
+        for label in labels {
+            if let Some(processors) = self.processors.get(label) {
+                for registered in processors {
+                    // The same processor may be registered for more than one label of a node
+                    if !matching
+                        .iter()
+                        .any(|x| Arc::ptr_eq(&x.processor, &registered.processor))
+                    {
+                        matching.push(registered);
+                    }
+                }
+            }
+        }
+        matching.sort_by_key(|x| x.order);
+        matching.iter().map(|x| x.processor.clone()).collect()
+    }
+
+    pub fn get_dependencies(&self, labels: &[NodeLabel]) -> Vec<ProcessingDependency> {
+        let mut dependencies: Vec<ProcessingDependency> = vec![];
+        for label in labels {
+            if let Some(processors) = self.processors.get(label) {
+                for registered in processors {
+                    for dependency in registered.dependencies.iter() {
+                        if !dependencies.contains(dependency) {
+                            dependencies.push(dependency.clone());
+                        }
+                    }
+                }
+            }
+        }
+        dependencies
+    }
+
+    // Labels with processors, sorted so that a label comes after all labels it depends on.
+    // Labels which are part of a dependency cycle can never be processed and are left out
+    pub fn get_labels_in_dependency_order(&self) -> Vec<NodeLabel> {
+        let mut dependencies_of: HashMap<NodeLabel, HashSet<NodeLabel>> = HashMap::new();
+        for label in self.processors.keys() {
+            let label_dependencies = dependencies_of.entry(label.clone()).or_default();
+            for dependency in self.get_dependencies(std::slice::from_ref(label)) {
+                if let ProcessingDependency::Label(dependency) = dependency {
+                    label_dependencies.insert(dependency);
+                }
+            }
+        }
+
+        let mut sorted: Vec<NodeLabel> = vec![];
+        let mut pending: Vec<NodeLabel> = dependencies_of.keys().cloned().collect();
+        pending.sort();
+        loop {
+            let (ready, blocked): (Vec<NodeLabel>, Vec<NodeLabel>) =
+                pending.into_iter().partition(|label| {
+                    dependencies_of[label]
+                        .iter()
+                        // Labels without processors of their own have no dependencies
+                        .all(|dependency| {
+                            sorted.contains(dependency) || !dependencies_of.contains_key(dependency)
+                        })
+                });
+            if ready.is_empty() {
+                if !blocked.is_empty() {
+                    error!(
+                        "Processor dependencies have a cycle, cannot process labels: {}",
+                        blocked
+                            .iter()
+                            .map(|x| x.to_string())
+                            .collect::<Vec<String>>()
+                            .join(", ")
+                    );
+                }
+                break;
+            }
+            sorted.extend(ready);
+            pending = blocked;
+        }
+        sorted
+    }
+}
+
+impl Default for ProcessorRegistry {
+    fn default() -> Self {
+        ProcessorRegistry::with_builtin_processors()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::engine::get_test_engine;
+    use crate::engine::node::Payload;
+    use crate::engine::{NodeFlags, RetryPolicy};
+    use std::sync::Mutex;
+
+    #[test]
+    fn test_registered_processors_run_in_order() {
+        let test_engine = get_test_engine();
+        let calls: Arc<Mutex<Vec<String>>> = Arc::new(Mutex::new(vec![]));
+        {
+            let calls = calls.clone();
+            test_engine.register_processor(
+                NodeLabel::Paragraph,
+                20,
+                vec![],
+                Arc::new(
+                    move |node: &NodeItem, _: Arc<&Engine>, _: Option<ExternalData>| {
+                        calls.lock().unwrap().push(format!("second {}", node.id));
+                        Ok(())
+                    },
+                ),
+            );
+        }
+        {
+            let calls = calls.clone();
+            test_engine.register_processor(
+                NodeLabel::Partial,
+                10,
+                vec![],
+                Arc::new(
+                    move |node: &NodeItem, _: Arc<&Engine>, _: Option<ExternalData>| {
+                        calls.lock().unwrap().push(format!("first {}", node.id));
+                        Ok(())
+                    },
+                ),
+            );
+        }
+        let node_id = test_engine
+            .get_or_add_node(
+                Payload::Text("A paragraph".to_string()),
+                vec![NodeLabel::Paragraph, NodeLabel::Partial],
+                true,
+                None,
+            )
+            .unwrap()
+            .get_node_id();
+        test_engine.process_nodes();
+
+        assert_eq!(
+            *calls.lock().unwrap(),
+            vec![format!("first {}", node_id), format!("second {}", node_id)]
+        );
+    }
+
+    #[test]
+    fn test_processors_wait_for_their_dependencies() {
+        let test_engine = get_test_engine();
+        let calls: Arc<Mutex<Vec<String>>> = Arc::new(Mutex::new(vec![]));
+        {
+            let calls = calls.clone();
+            test_engine.register_processor(
+                NodeLabel::Paragraph,
+                10,
+                vec![ProcessingDependency::Label(NodeLabel::Title)],
+                Arc::new(
+                    move |node: &NodeItem, engine: Arc<&Engine>, _: Option<ExternalData>| {
+                        calls.lock().unwrap().push("paragraph".to_string());
+                        engine.toggle_flag(&node.id, NodeFlags::IS_PROCESSED)
+                    },
+                ),
+            );
+        }
+        {
+            let calls = calls.clone();
+            test_engine.register_processor(
+                NodeLabel::Title,
+                10,
+                vec![],
+                Arc::new(
+                    move |node: &NodeItem, engine: Arc<&Engine>, _: Option<ExternalData>| {
+                        calls.lock().unwrap().push("title".to_string());
+                        engine.toggle_flag(&node.id, NodeFlags::IS_PROCESSED)
+                    },
+                ),
+            );
+        }
+        // The paragraph is added first but can only be processed after the title
+        for (text, label) in [
+            ("A paragraph", NodeLabel::Paragraph),
+            ("A title", NodeLabel::Title),
+        ] {
+            test_engine
+                .get_or_add_node(Payload::Text(text.to_string()), vec![label], true, None)
+                .unwrap();
+        }
+        test_engine.process_nodes();
+        assert_eq!(*calls.lock().unwrap(), vec!["title"]);
+
+        // Processing the title puts the waiting paragraph back in the work queue
+        test_engine.process_dirty_nodes();
+        assert_eq!(*calls.lock().unwrap(), vec!["title", "paragraph"]);
+    }
+
+    #[test]
+    fn test_dependencies_which_gave_up_do_not_block_processing() {
+        let test_engine = get_test_engine();
+        test_engine.set_retry_policy(RetryPolicy {
+            max_attempts: 1,
+            ..Default::default()
+        });
+        let calls: Arc<Mutex<Vec<String>>> = Arc::new(Mutex::new(vec![]));
+        {
+            let calls = calls.clone();
+            test_engine.register_processor(
+                NodeLabel::Paragraph,
+                10,
+                vec![ProcessingDependency::Label(NodeLabel::Title)],
+                Arc::new(
+                    move |node: &NodeItem, engine: Arc<&Engine>, _: Option<ExternalData>| {
+                        calls.lock().unwrap().push("paragraph".to_string());
+                        engine.toggle_flag(&node.id, NodeFlags::IS_PROCESSED)
+                    },
+                ),
+            );
+        }
+        // One title is processed, the other fails on its only attempt
+        test_engine.register_processor(
+            NodeLabel::Title,
+            10,
+            vec![],
+            Arc::new(
+                |node: &NodeItem, engine: Arc<&Engine>, _: Option<ExternalData>| match &node.payload
+                {
+                    Payload::Text(text) if text == "A failing title" => {
+                        engine.record_node_error(&node.id)
+                    }
+                    _ => engine.toggle_flag(&node.id, NodeFlags::IS_PROCESSED),
+                },
+            ),
+        );
+        for (text, label) in [
+            ("A paragraph", NodeLabel::Paragraph),
+            ("A title", NodeLabel::Title),
+            ("A failing title", NodeLabel::Title),
+        ] {
+            test_engine
+                .get_or_add_node(Payload::Text(text.to_string()), vec![label], true, None)
+                .unwrap();
+        }
+        test_engine.process_nodes();
+        assert!(calls.lock().unwrap().is_empty());
+
+        test_engine.process_dirty_nodes();
+        assert_eq!(*calls.lock().unwrap(), vec!["paragraph"]);
+    }
+
+    #[test]
+    fn test_labels_in_dependency_cycle_are_not_processed() {
+        let mut registry = ProcessorRegistry::new();
+        let processor: ArcedNodeProcessor =
+            Arc::new(|_: &NodeItem, _: Arc<&Engine>, _: Option<ExternalData>| Ok(()));
+        registry.register(
+            NodeLabel::Heading,
+            10,
+            vec![ProcessingDependency::Label(NodeLabel::Paragraph)],
+            processor.clone(),
+        );
+        registry.register(
+            NodeLabel::Paragraph,
+            10,
+            vec![ProcessingDependency::Label(NodeLabel::Heading)],
+            processor.clone(),
+        );
+        registry.register(
+            NodeLabel::ListItem,
+            10,
+            vec![ProcessingDependency::Label(NodeLabel::Title)],
+            processor.clone(),
+        );
+        registry.register(NodeLabel::Title, 10, vec![], processor);
+
+        assert_eq!(
+            registry.get_labels_in_dependency_order(),
+            vec![NodeLabel::Title, NodeLabel::ListItem]
+        );
+    }
+}
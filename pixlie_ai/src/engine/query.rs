@@ -0,0 +1,1151 @@
+// Copyright 2025 Pixlie Web Solutions Pvt. Ltd.
+// Licensed under the GNU General Public License version 3.0;
+// You may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// https://github.com/pixlie/PixlieAI/blob/main/LICENSE
+
+// A small, Cypher-like query language over the graph of a project, for example:
+//
+// MATCH (d:DomainName {text: "pixlie.com"})-[:OwnerOf]->(l:Link)-[:PathOf*1..2]->(p:WebPage)
+// WHERE l.path STARTS WITH "/blog" AND NOT p.id = 10
+// RETURN d.text, l.path, p
+// LIMIT 10
+//
+// - A pattern is a chain of nodes, each with an optional variable, node labels (all of which
+//   the node must have) and properties which must be equal
+// - Edges are followed from one node to the next, with any of the given edge labels
+//   (`[:OwnerOf|PathOf]`) or any edge (`[]`), and depth bounds (`*`, `*2`, `*1..3`, `*..3`)
+// - Conditions compare fields of nodes with `=`, `<>`, `!=`, `<`, `<=`, `>`, `>=`,
+//   `CONTAINS`, `STARTS WITH`, `ENDS WITH` and can be combined with `AND`, `OR`, `NOT`
+// - Nodes have the fields `id`, `labels`, `flags`, `written_at`, `payload` (the type of payload),
+//   `text` for text payloads and the fields of other payloads, like `path` of a link
+
+use crate::engine::api::{APINodeFlags, APINodeItem, APIPayload};
+use crate::engine::node::{ArcedNodeItem, NodeId, NodeLabel, Payload};
+use crate::engine::traversal::{Traversal, TraversalFilter, TraversalOrder};
+use crate::engine::{EdgeLabel, Engine};
+use crate::error::{PiError, PiResult};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::sync::Arc;
+use ts_rs::TS;
+use utoipa::ToSchema;
+
+// Edges are followed up to this depth when a query does not give a maximum, like in `[*]`
+const MAX_TRAVERSAL_DEPTH: usize = 10;
+
+/// A value in the results of a query.
+#[derive(Clone, Serialize, ToSchema, TS)]
+#[serde(tag = "type", content = "data")]
+#[ts(export)]
+pub enum QueryValue {
+    Null,
+    Bool(bool),
+    Number(f64),
+    Text(String),
+    List(Vec<String>),
+    /// A node, when a variable of the query is returned by itself
+    Node(Box<APINodeItem>),
+}
+
+/// Results of a query, with a row for each match of the pattern in the query.
+#[derive(Clone, Serialize, ToSchema, TS)]
+#[ts(export)]
+pub struct QueryResults {
+    /// The items of `RETURN` in the query, like `d.text`
+    pub columns: Vec<String>,
+    pub rows: Vec<Vec<QueryValue>>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Token {
+    Identifier(String),
+    Number(f64),
+    Text(String),
+    LeftParen,
+    RightParen,
+    LeftBracket,
+    RightBracket,
+    LeftBrace,
+    RightBrace,
+    Colon,
+    Comma,
+    Dot,
+    DotDot,
+    Star,
+    Pipe,
+    Minus,
+    Arrow,
+    Equal,
+    NotEqual,
+    Less,
+    LessOrEqual,
+    Greater,
+    GreaterOrEqual,
+}
+
+fn tokenize(query: &str) -> PiResult<Vec<Token>> {
+    let mut tokens: Vec<Token> = vec![];
+    let chars: Vec<char> = query.chars().collect();
+    let mut position: usize = 0;
+    while position < chars.len() {
+        let current = chars[position];
+        let next = chars.get(position + 1).copied();
+        if current.is_whitespace() {
+            position += 1;
+            continue;
+        }
+        if current.is_alphabetic() || current == '_' {
+            let start = position;
+            while position < chars.len()
+                && (chars[position].is_alphanumeric() || chars[position] == '_')
+            {
+                position += 1;
+            }
+            tokens.push(Token::Identifier(chars[start..position].iter().collect()));
+            continue;
+        }
+        if current.is_ascii_digit() {
+            let start = position;
+            while position < chars.len() && chars[position].is_ascii_digit() {
+                position += 1;
+            }
+            // A dot is part of the number only when a digit follows, so `1..3` is a range
+            if position + 1 < chars.len()
+                && chars[position] == '.'
+                && chars[position + 1].is_ascii_digit()
+            {
+                position += 1;
+                while position < chars.len() && chars[position].is_ascii_digit() {
+                    position += 1;
+                }
+            }
+            let number: String = chars[start..position].iter().collect();
+            tokens.push(Token::Number(number.parse::<f64>().map_err(|_| {
+                PiError::QueryError(format!("Invalid number {}", number))
+            })?));
+            continue;
+        }
+        if current == '"' || current == '\'' {
+            position += 1;
+            let mut text = String::new();
+            loop {
+                match chars.get(position) {
+                    Some('\\') => {
+                        if let Some(escaped) = chars.get(position + 1) {
+                            text.push(*escaped);
+                        }
+                        position += 2;
+                    }
+                    Some(x) if *x == current => {
+                        position += 1;
+                        tokens.push(Token::Text(text));
+                        break;
+                    }
+                    Some(x) => {
+                        text.push(*x);
+                        position += 1;
+                    }
+                    None => {
+                        return Err(PiError::QueryError(
+                            "Text is not closed with a quote".to_string(),
+                        ))
+                    }
+                }
+            }
+            continue;
+        }
+        let (token, length) = match (current, next) {
+            ('-', Some('>')) => (Token::Arrow, 2),
+            ('.', Some('.')) => (Token::DotDot, 2),
+            ('<', Some('>')) => (Token::NotEqual, 2),
+            ('!', Some('=')) => (Token::NotEqual, 2),
+            ('<', Some('=')) => (Token::LessOrEqual, 2),
+            ('>', Some('=')) => (Token::GreaterOrEqual, 2),
+            ('(', _) => (Token::LeftParen, 1),
+            (')', _) => (Token::RightParen, 1),
+            ('[', _) => (Token::LeftBracket, 1),
+            (']', _) => (Token::RightBracket, 1),
+            ('{', _) => (Token::LeftBrace, 1),
+            ('}', _) => (Token::RightBrace, 1),
+            (':', _) => (Token::Colon, 1),
+            (',', _) => (Token::Comma, 1),
+            ('.', _) => (Token::Dot, 1),
+            ('*', _) => (Token::Star, 1),
+            ('|', _) => (Token::Pipe, 1),
+            ('-', _) => (Token::Minus, 1),
+            ('=', _) => (Token::Equal, 1),
+            ('<', _) => (Token::Less, 1),
+            ('>', _) => (Token::Greater, 1),
+            _ => {
+                return Err(PiError::QueryError(format!(
+                    "Unexpected character {} at position {}",
+                    current, position
+                )))
+            }
+        };
+        tokens.push(token);
+        position += length;
+    }
+    Ok(tokens)
+}
+
+struct NodePattern {
+    variable: Option<String>,
+    labels: Vec<NodeLabel>,
+    properties: Vec<(String, QueryValue)>,
+}
+
+struct EdgePattern {
+    labels: Vec<EdgeLabel>, // Any of these, or any edge if empty
+    min_depth: usize,
+    max_depth: usize,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Operator {
+    Equal,
+    NotEqual,
+    Less,
+    LessOrEqual,
+    Greater,
+    GreaterOrEqual,
+    Contains,
+    StartsWith,
+    EndsWith,
+}
+
+enum Operand {
+    Node(String),
+    Field(String, String),
+    Value(QueryValue),
+}
+
+enum Condition {
+    And(Box<Condition>, Box<Condition>),
+    Or(Box<Condition>, Box<Condition>),
+    Not(Box<Condition>),
+    Compare(Operand, Operator, Operand),
+}
+
+struct ReturnItem {
+    column: String,
+    operand: Operand,
+}
+
+pub struct Query {
+    nodes: Vec<NodePattern>,
+    edges: Vec<EdgePattern>, // Edge at position N is between nodes N and N + 1
+    condition: Option<Condition>,
+    returns: Vec<ReturnItem>,
+    limit: Option<usize>,
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    position: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.position).cloned();
+        self.position += 1;
+        token
+    }
+
+    fn error<T>(&self, expected: &str) -> PiResult<T> {
+        Err(PiError::QueryError(match self.tokens.get(self.position) {
+            Some(token) => format!("Expected {}, found {:?}", expected, token),
+            None => format!("Expected {}, found the end of the query", expected),
+        }))
+    }
+
+    fn is_token(&self, token: &Token) -> bool {
+        self.peek() == Some(token)
+    }
+
+    fn is_keyword(&self, keyword: &str) -> bool {
+        matches!(self.peek(), Some(Token::Identifier(x)) if x.eq_ignore_ascii_case(keyword))
+    }
+
+    fn skip_token(&mut self, token: &Token) -> bool {
+        if self.is_token(token) {
+            self.position += 1;
+            return true;
+        }
+        false
+    }
+
+    fn skip_keyword(&mut self, keyword: &str) -> bool {
+        if self.is_keyword(keyword) {
+            self.position += 1;
+            return true;
+        }
+        false
+    }
+
+    fn expect_token(&mut self, token: Token) -> PiResult<()> {
+        if self.skip_token(&token) {
+            return Ok(());
+        }
+        self.error(&format!("{:?}", token))
+    }
+
+    fn expect_keyword(&mut self, keyword: &str) -> PiResult<()> {
+        if self.skip_keyword(keyword) {
+            return Ok(());
+        }
+        self.error(keyword)
+    }
+
+    fn expect_identifier(&mut self) -> PiResult<String> {
+        match self.peek() {
+            Some(Token::Identifier(identifier)) => {
+                let identifier = identifier.clone();
+                self.position += 1;
+                Ok(identifier)
+            }
+            _ => self.error("a name"),
+        }
+    }
+
+    fn expect_count(&mut self) -> PiResult<usize> {
+        match self.peek() {
+            Some(Token::Number(number)) if number.fract() == 0.0 && *number >= 0.0 => {
+                let count = *number as usize;
+                self.position += 1;
+                Ok(count)
+            }
+            _ => self.error("a whole number"),
+        }
+    }
+
+    fn parse_query(&mut self) -> PiResult<Query> {
+        self.expect_keyword("MATCH")?;
+        let mut nodes = vec![self.parse_node_pattern()?];
+        let mut edges = vec![];
+        while self.is_token(&Token::Minus) {
+            edges.push(self.parse_edge_pattern()?);
+            nodes.push(self.parse_node_pattern()?);
+        }
+        let condition = if self.skip_keyword("WHERE") {
+            Some(self.parse_or()?)
+        } else {
+            None
+        };
+        self.expect_keyword("RETURN")?;
+        let mut returns = vec![self.parse_return_item()?];
+        while self.skip_token(&Token::Comma) {
+            returns.push(self.parse_return_item()?);
+        }
+        let limit = if self.skip_keyword("LIMIT") {
+            Some(self.expect_count()?)
+        } else {
+            None
+        };
+        if self.peek().is_some() {
+            return self.error("the end of the query");
+        }
+        Ok(Query {
+            nodes,
+            edges,
+            condition,
+            returns,
+            limit,
+        })
+    }
+
+    fn parse_node_pattern(&mut self) -> PiResult<NodePattern> {
+        self.expect_token(Token::LeftParen)?;
+        let variable = match self.peek() {
+            Some(Token::Identifier(_)) => Some(self.expect_identifier()?),
+            _ => None,
+        };
+        let mut labels = vec![];
+        while self.skip_token(&Token::Colon) {
+            let label = self.expect_identifier()?;
+            labels.push(
+                NodeLabel::from_str(&label)
+                    .map_err(|_| PiError::QueryError(format!("Unknown node label {}", label)))?,
+            );
+        }
+        let mut properties = vec![];
+        if self.skip_token(&Token::LeftBrace) {
+            loop {
+                let field = self.expect_identifier()?;
+                self.expect_token(Token::Colon)?;
+                properties.push((field, self.parse_literal()?));
+                if !self.skip_token(&Token::Comma) {
+                    break;
+                }
+            }
+            self.expect_token(Token::RightBrace)?;
+        }
+        self.expect_token(Token::RightParen)?;
+        Ok(NodePattern {
+            variable,
+            labels,
+            properties,
+        })
+    }
+
+    fn parse_edge_pattern(&mut self) -> PiResult<EdgePattern> {
+        self.expect_token(Token::Minus)?;
+        let mut edge_pattern = EdgePattern {
+            labels: vec![],
+            min_depth: 1,
+            max_depth: 1,
+        };
+        if self.skip_token(&Token::LeftBracket) {
+            if self.skip_token(&Token::Colon) {
+                loop {
+                    let label = self.expect_identifier()?;
+                    edge_pattern
+                        .labels
+                        .push(EdgeLabel::from_str(&label).map_err(|_| {
+                            PiError::QueryError(format!("Unknown edge label {}", label))
+                        })?);
+                    if !self.skip_token(&Token::Pipe) {
+                        break;
+                    }
+                }
+            }
+            if self.skip_token(&Token::Star) {
+                // `*` is any depth, `*2` is exactly 2, `*1..3` and `*..3` are ranges
+                edge_pattern.max_depth = MAX_TRAVERSAL_DEPTH;
+                if let Some(Token::Number(_)) = self.peek() {
+                    edge_pattern.min_depth = self.expect_count()?;
+                    edge_pattern.max_depth = edge_pattern.min_depth;
+                }
+                if self.skip_token(&Token::DotDot) {
+                    edge_pattern.max_depth = match self.peek() {
+                        Some(Token::Number(_)) => self.expect_count()?,
+                        _ => MAX_TRAVERSAL_DEPTH,
+                    };
+                }
+                if edge_pattern.min_depth > edge_pattern.max_depth {
+                    return Err(PiError::QueryError(format!(
+                        "Minimum depth {} is more than maximum depth {}",
+                        edge_pattern.min_depth, edge_pattern.max_depth
+                    )));
+                }
+            }
+            // The closing `-` is part of the arrow in `]->`
+            self.expect_token(Token::RightBracket)?;
+        }
+        self.expect_token(Token::Arrow)?;
+        Ok(edge_pattern)
+    }
+
+    fn parse_or(&mut self) -> PiResult<Condition> {
+        let mut condition = self.parse_and()?;
+        while self.skip_keyword("OR") {
+            condition = Condition::Or(Box::new(condition), Box::new(self.parse_and()?));
+        }
+        Ok(condition)
+    }
+
+    fn parse_and(&mut self) -> PiResult<Condition> {
+        let mut condition = self.parse_not()?;
+        while self.skip_keyword("AND") {
+            condition = Condition::And(Box::new(condition), Box::new(self.parse_not()?));
+        }
+        Ok(condition)
+    }
+
+    fn parse_not(&mut self) -> PiResult<Condition> {
+        if self.skip_keyword("NOT") {
+            return Ok(Condition::Not(Box::new(self.parse_not()?)));
+        }
+        if self.skip_token(&Token::LeftParen) {
+            let condition = self.parse_or()?;
+            self.expect_token(Token::RightParen)?;
+            return Ok(condition);
+        }
+        let left = self.parse_operand()?;
+        let operator = self.parse_operator()?;
+        let right = self.parse_operand()?;
+        Ok(Condition::Compare(left, operator, right))
+    }
+
+    fn parse_operator(&mut self) -> PiResult<Operator> {
+        let operator = match self.peek() {
+            Some(Token::Equal) => Operator::Equal,
+            Some(Token::NotEqual) => Operator::NotEqual,
+            Some(Token::Less) => Operator::Less,
+            Some(Token::LessOrEqual) => Operator::LessOrEqual,
+            Some(Token::Greater) => Operator::Greater,
+            Some(Token::GreaterOrEqual) => Operator::GreaterOrEqual,
+            _ => {
+                if self.skip_keyword("CONTAINS") {
+                    return Ok(Operator::Contains);
+                }
+                if self.skip_keyword("STARTS") {
+                    self.expect_keyword("WITH")?;
+                    return Ok(Operator::StartsWith);
+                }
+                if self.skip_keyword("ENDS") {
+                    self.expect_keyword("WITH")?;
+                    return Ok(Operator::EndsWith);
+                }
+                return self.error("a comparison");
+            }
+        };
+        self.position += 1;
+        Ok(operator)
+    }
+
+    fn parse_operand(&mut self) -> PiResult<Operand> {
+        match self.peek() {
+            Some(Token::Identifier(identifier))
+                if !["true", "false", "null"]
+                    .iter()
+                    .any(|x| identifier.eq_ignore_ascii_case(x)) =>
+            {
+                let variable = self.expect_identifier()?;
+                if self.skip_token(&Token::Dot) {
+                    Ok(Operand::Field(variable, self.expect_identifier()?))
+                } else {
+                    Ok(Operand::Node(variable))
+                }
+            }
+            _ => Ok(Operand::Value(self.parse_literal()?)),
+        }
+    }
+
+    fn parse_literal(&mut self) -> PiResult<QueryValue> {
+        let negative = self.skip_token(&Token::Minus);
+        match self.next() {
+            Some(Token::Number(number)) => {
+                Ok(QueryValue::Number(if negative { -number } else { number }))
+            }
+            Some(Token::Text(text)) if !negative => Ok(QueryValue::Text(text)),
+            Some(Token::Identifier(x)) if !negative && x.eq_ignore_ascii_case("true") => {
+                Ok(QueryValue::Bool(true))
+            }
+            Some(Token::Identifier(x)) if !negative && x.eq_ignore_ascii_case("false") => {
+                Ok(QueryValue::Bool(false))
+            }
+            Some(Token::Identifier(x)) if !negative && x.eq_ignore_ascii_case("null") => {
+                Ok(QueryValue::Null)
+            }
+            _ => {
+                self.position -= 1;
+                self.error("a number, text, true, false or null")
+            }
+        }
+    }
+
+    fn parse_return_item(&mut self) -> PiResult<ReturnItem> {
+        let variable = self.expect_identifier()?;
+        if self.skip_token(&Token::Dot) {
+            let field = self.expect_identifier()?;
+            Ok(ReturnItem {
+                column: format!("{}.{}", variable, field),
+                operand: Operand::Field(variable, field),
+            })
+        } else {
+            Ok(ReturnItem {
+                column: variable.clone(),
+                operand: Operand::Node(variable),
+            })
+        }
+    }
+}
+
+impl QueryValue {
+    fn from_json(value: serde_json::Value) -> QueryValue {
+        match value {
+            serde_json::Value::Null => QueryValue::Null,
+            serde_json::Value::Bool(x) => QueryValue::Bool(x),
+            serde_json::Value::Number(x) => match x.as_f64() {
+                Some(x) => QueryValue::Number(x),
+                None => QueryValue::Null,
+            },
+            serde_json::Value::String(x) => QueryValue::Text(x),
+            serde_json::Value::Array(items) => QueryValue::List(
+                items
+                    .into_iter()
+                    .map(|item| match item {
+                        serde_json::Value::String(x) => x,
+                        item => item.to_string(),
+                    })
+                    .collect(),
+            ),
+            value => QueryValue::Text(value.to_string()),
+        }
+    }
+
+    fn is_equal(&self, other: &QueryValue) -> bool {
+        match (self, other) {
+            (QueryValue::Null, QueryValue::Null) => true,
+            (QueryValue::Bool(x), QueryValue::Bool(y)) => x == y,
+            (QueryValue::Number(x), QueryValue::Number(y)) => x == y,
+            (QueryValue::Text(x), QueryValue::Text(y)) => x == y,
+            (QueryValue::List(x), QueryValue::List(y)) => x == y,
+            (QueryValue::Node(x), QueryValue::Node(y)) => x.id == y.id,
+            _ => false,
+        }
+    }
+
+    fn compare(&self, operator: Operator, other: &QueryValue) -> bool {
+        match operator {
+            Operator::Equal => self.is_equal(other),
+            Operator::NotEqual => !self.is_equal(other),
+            Operator::Less
+            | Operator::LessOrEqual
+            | Operator::Greater
+            | Operator::GreaterOrEqual => {
+                let ordering = match (self, other) {
+                    (QueryValue::Number(x), QueryValue::Number(y)) => x.partial_cmp(y),
+                    (QueryValue::Text(x), QueryValue::Text(y)) => Some(x.cmp(y)),
+                    _ => None,
+                };
+                match ordering {
+                    Some(ordering) => match operator {
+                        Operator::Less => ordering.is_lt(),
+                        Operator::LessOrEqual => ordering.is_le(),
+                        Operator::Greater => ordering.is_gt(),
+                        _ => ordering.is_ge(),
+                    },
+                    None => false,
+                }
+            }
+            Operator::Contains => match (self, other) {
+                (QueryValue::Text(x), QueryValue::Text(y)) => x.contains(y.as_str()),
+                (QueryValue::List(x), QueryValue::Text(y)) => x.contains(y),
+                _ => false,
+            },
+            Operator::StartsWith => match (self, other) {
+                (QueryValue::Text(x), QueryValue::Text(y)) => x.starts_with(y.as_str()),
+                _ => false,
+            },
+            Operator::EndsWith => match (self, other) {
+                (QueryValue::Text(x), QueryValue::Text(y)) => x.ends_with(y.as_str()),
+                _ => false,
+            },
+        }
+    }
+}
+
+fn get_field(node: &ArcedNodeItem, field: &str) -> PiResult<QueryValue> {
+    Ok(match field {
+        "id" => QueryValue::Number(node.id as f64),
+        "labels" => QueryValue::List(node.labels.iter().map(|x| x.to_string()).collect()),
+        "flags" => QueryValue::from_json(serde_json::to_value(APINodeFlags::from_node_flags(
+            &node.flags,
+        ))?),
+        "written_at" => QueryValue::Number(node.written_at.timestamp_millis() as f64),
+        "payload" => QueryValue::Text(node.payload.to_string()),
+        "text" => match &node.payload {
+            Payload::Text(text) => QueryValue::Text(text.clone()),
+            _ => QueryValue::Null,
+        },
+        _ => {
+            // Other fields are read from the payload as it is given in the API
+            let api_payload: APIPayload = APINodeItem::from_node(node).payload;
+            match serde_json::to_value(api_payload)?.get("data") {
+                Some(serde_json::Value::Object(data)) => match data.get(field) {
+                    Some(value) => QueryValue::from_json(value.clone()),
+                    None => QueryValue::Null,
+                },
+                _ => QueryValue::Null,
+            }
+        }
+    })
+}
+
+impl Query {
+    pub fn parse(query: &str) -> PiResult<Query> {
+        let mut parser = Parser {
+            tokens: tokenize(query)?,
+            position: 0,
+        };
+        let query = parser.parse_query()?;
+        query.check_variables()?;
+        Ok(query)
+    }
+
+    // Position of the node pattern where a variable is first used
+    fn get_variable_position(&self, variable: &str) -> Option<usize> {
+        self.nodes
+            .iter()
+            .position(|x| x.variable.as_deref() == Some(variable))
+    }
+
+    fn check_variables(&self) -> PiResult<()> {
+        let mut operands: Vec<&Operand> = self.returns.iter().map(|x| &x.operand).collect();
+        let mut conditions: Vec<&Condition> = self.condition.iter().collect();
+        while let Some(condition) = conditions.pop() {
+            match condition {
+                Condition::And(left, right) | Condition::Or(left, right) => {
+                    conditions.push(left);
+                    conditions.push(right);
+                }
+                Condition::Not(inner) => conditions.push(inner),
+                Condition::Compare(left, _, right) => {
+                    operands.push(left);
+                    operands.push(right);
+                }
+            }
+        }
+        for operand in operands {
+            match operand {
+                Operand::Node(variable) | Operand::Field(variable, _) => {
+                    if self.get_variable_position(variable).is_none() {
+                        return Err(PiError::QueryError(format!(
+                            "Variable {} is not in the MATCH pattern",
+                            variable
+                        )));
+                    }
+                }
+                Operand::Value(_) => {}
+            }
+        }
+        Ok(())
+    }
+
+    pub fn execute(&self, engine: Arc<&Engine>) -> PiResult<QueryResults> {
+        let mut executor = Executor {
+            query: self,
+            engine,
+            nodes: HashMap::new(),
+            rows: vec![],
+        };
+        let first_node_ids = executor.get_first_node_ids();
+        let mut node_ids: Vec<NodeId> = vec![];
+        for node_id in first_node_ids {
+            if !executor.is_node_matching(0, &node_id, &node_ids)? {
+                continue;
+            }
+            node_ids.push(node_id);
+            let is_done = executor.match_from(&mut node_ids)?;
+            node_ids.pop();
+            if is_done {
+                break;
+            }
+        }
+        Ok(QueryResults {
+            columns: self.returns.iter().map(|x| x.column.clone()).collect(),
+            rows: executor.rows,
+        })
+    }
+}
+
+// Finds the matches of a query one by one, so we can stop at the limit
+struct Executor<'a> {
+    query: &'a Query,
+    engine: Arc<&'a Engine>,
+    nodes: HashMap<NodeId, Option<ArcedNodeItem>>,
+    rows: Vec<Vec<QueryValue>>,
+}
+
+impl Executor<'_> {
+    fn get_node(&mut self, node_id: &NodeId) -> Option<ArcedNodeItem> {
+        if !self.nodes.contains_key(node_id) {
+            let node = self.engine.get_node_without_content(node_id);
+            self.nodes.insert(*node_id, node);
+        }
+        self.nodes.get(node_id).cloned().flatten()
+    }
+
+    fn get_first_node_ids(&self) -> Vec<NodeId> {
+        let node_pattern = &self.query.nodes[0];
+        if let Some((_, QueryValue::Number(id))) = node_pattern
+            .properties
+            .iter()
+            .find(|(field, _)| field == "id")
+        {
+            return vec![*id as NodeId];
+        }
+        let mut node_ids: Vec<NodeId> = match node_pattern.labels.first() {
+            Some(label) => self
+                .engine
+                .get_node_ids_with_label(label)
+                .iter()
+                .map(|x| **x)
+                .collect(),
+            None => self.engine.get_all_node_ids(),
+        };
+        node_ids.sort();
+        node_ids
+    }
+
+    // Checks the node at the given position of the pattern, against the nodes matched before it
+    fn is_node_matching(
+        &mut self,
+        position: usize,
+        node_id: &NodeId,
+        matched_node_ids: &[NodeId],
+    ) -> PiResult<bool> {
+        let node_pattern = &self.query.nodes[position];
+        if let Some(variable) = &node_pattern.variable {
+            let first_position = self
+                .query
+                .get_variable_position(variable)
+                .unwrap_or(position);
+            if first_position < position && matched_node_ids[first_position] != *node_id {
+                return Ok(false);
+            }
+        }
+        let Some(node) = self.get_node(node_id) else {
+            return Ok(false);
+        };
+        if !node_pattern
+            .labels
+            .iter()
+            .all(|label| node.labels.contains(label))
+        {
+            return Ok(false);
+        }
+        for (field, value) in node_pattern.properties.iter() {
+            if !get_field(&node, field)?.is_equal(value) {
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+
+    // Nodes reached from a node by following edges with the given labels,
+    // breadth first and each node once
+    fn get_reached_node_ids(
+        &self,
+        node_id: &NodeId,
+        edge_pattern: &EdgePattern,
+    ) -> PiResult<Vec<NodeId>> {
+        Ok(self
+            .engine
+            .traverse(&Traversal {
+                start_node_ids: vec![*node_id],
+                filter: TraversalFilter {
+                    edge_labels: edge_pattern.labels.clone(),
+                    node_labels: vec![],
+                },
+                max_depth: edge_pattern.max_depth,
+                max_nodes: None,
+                order: TraversalOrder::BreadthFirst,
+            })?
+            .into_iter()
+            .filter(|traversed_node| traversed_node.depth >= edge_pattern.min_depth)
+            .map(|traversed_node| traversed_node.node_id)
+            .collect())
+    }
+
+    // Matches the rest of the pattern after the given nodes, returns true when the limit is reached
+    fn match_from(&mut self, node_ids: &mut Vec<NodeId>) -> PiResult<bool> {
+        let position = node_ids.len();
+        if position == self.query.nodes.len() {
+            return self.add_row(node_ids);
+        }
+        let reached_node_ids =
+            self.get_reached_node_ids(&node_ids[position - 1], &self.query.edges[position - 1])?;
+        for node_id in reached_node_ids {
+            if !self.is_node_matching(position, &node_id, node_ids)? {
+                continue;
+            }
+            node_ids.push(node_id);
+            let is_done = self.match_from(node_ids)?;
+            node_ids.pop();
+            if is_done {
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    }
+
+    fn evaluate(&mut self, operand: &Operand, node_ids: &[NodeId]) -> PiResult<QueryValue> {
+        let variable = match operand {
+            Operand::Value(value) => return Ok(value.clone()),
+            Operand::Node(variable) | Operand::Field(variable, _) => variable,
+        };
+        let node = self
+            .query
+            .get_variable_position(variable)
+            .and_then(|position| self.get_node(&node_ids[position]));
+        Ok(match (node, operand) {
+            (Some(node), Operand::Field(_, field)) => get_field(&node, field)?,
+            // A variable by itself is its node ID in conditions
+            (Some(node), _) => QueryValue::Number(node.id as f64),
+            (None, _) => QueryValue::Null,
+        })
+    }
+
+    fn is_condition_true(&mut self, condition: &Condition, node_ids: &[NodeId]) -> PiResult<bool> {
+        Ok(match condition {
+            Condition::And(left, right) => {
+                self.is_condition_true(left, node_ids)?
+                    && self.is_condition_true(right, node_ids)?
+            }
+            Condition::Or(left, right) => {
+                self.is_condition_true(left, node_ids)?
+                    || self.is_condition_true(right, node_ids)?
+            }
+            Condition::Not(inner) => !self.is_condition_true(inner, node_ids)?,
+            Condition::Compare(left, operator, right) => {
+                let left = self.evaluate(left, node_ids)?;
+                let right = self.evaluate(right, node_ids)?;
+                left.compare(*operator, &right)
+            }
+        })
+    }
+
+    fn add_row(&mut self, node_ids: &[NodeId]) -> PiResult<bool> {
+        let query = self.query;
+        if let Some(condition) = &query.condition {
+            if !self.is_condition_true(condition, node_ids)? {
+                return Ok(false);
+            }
+        }
+        let mut row: Vec<QueryValue> = vec![];
+        for return_item in query.returns.iter() {
+            row.push(match &return_item.operand {
+                Operand::Node(variable) => {
+                    let node = query
+                        .get_variable_position(variable)
+                        .and_then(|position| self.get_node(&node_ids[position]));
+                    match node {
+                        Some(node) => QueryValue::Node(Box::new(APINodeItem::from_node(&node))),
+                        None => QueryValue::Null,
+                    }
+                }
+                operand => self.evaluate(operand, node_ids)?,
+            });
+        }
+        self.rows.push(row);
+        Ok(query.limit.is_some_and(|limit| self.rows.len() >= limit))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::engine::{add_node, connect, get_test_engine};
+    use crate::entity::web::link::Link;
+
+    // A domain with two links, each with a web page which has a title
+    fn add_test_graph(engine: &Engine) -> (NodeId, Vec<NodeId>) {
+        let mut link_ids = vec![];
+        for (path, title) in [("/blog/graphs", "Graphs"), ("/about", "About Pixlie")] {
+            let link_id = Link::add(
+                Arc::new(engine),
+                &format!("https://pixlie.com{}", path),
+                vec![NodeLabel::Link],
+                vec![],
+                true,
+            )
+            .unwrap();
+            let web_page_id = add_node(
+                engine,
+                Payload::Text(format!("<html>{}</html>", title)),
+                vec![NodeLabel::WebPage],
+            );
+            engine
+                .add_connection(
+                    (link_id, web_page_id),
+                    (EdgeLabel::PathOf, EdgeLabel::ContentOf),
+                )
+                .unwrap();
+            let title_id = add_node(
+                engine,
+                Payload::Text(title.to_string()),
+                vec![NodeLabel::Title],
+            );
+            connect(engine, web_page_id, title_id);
+            link_ids.push(link_id);
+        }
+        let domain_id = *engine.get_node_ids_with_label(&NodeLabel::DomainName)[0];
+        (domain_id, link_ids)
+    }
+
+    fn run_query(engine: &Engine, query: &str) -> QueryResults {
+        Query::parse(query)
+            .unwrap()
+            .execute(Arc::new(engine))
+            .unwrap()
+    }
+
+    fn get_texts(results: &QueryResults) -> Vec<String> {
+        results
+            .rows
+            .iter()
+            .map(|row| match &row[0] {
+                QueryValue::Text(text) => text.clone(),
+                _ => panic!("Expected QueryValue::Text"),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_tokenize() {
+        assert_eq!(
+            tokenize("(a:Link)-[:OwnerOf*1..3]->(b {x: -2.5, y: 'it\\'s'}) <> !=").unwrap(),
+            vec![
+                Token::LeftParen,
+                Token::Identifier("a".to_string()),
+                Token::Colon,
+                Token::Identifier("Link".to_string()),
+                Token::RightParen,
+                Token::Minus,
+                Token::LeftBracket,
+                Token::Colon,
+                Token::Identifier("OwnerOf".to_string()),
+                Token::Star,
+                Token::Number(1.0),
+                Token::DotDot,
+                Token::Number(3.0),
+                Token::RightBracket,
+                Token::Arrow,
+                Token::LeftParen,
+                Token::Identifier("b".to_string()),
+                Token::LeftBrace,
+                Token::Identifier("x".to_string()),
+                Token::Colon,
+                Token::Minus,
+                Token::Number(2.5),
+                Token::Comma,
+                Token::Identifier("y".to_string()),
+                Token::Colon,
+                Token::Text("it's".to_string()),
+                Token::RightBrace,
+                Token::RightParen,
+                Token::NotEqual,
+                Token::NotEqual,
+            ]
+        );
+        assert!(tokenize("RETURN \"not closed").is_err());
+        assert!(tokenize("RETURN a; b").is_err());
+    }
+
+    #[test]
+    fn test_parse_query() {
+        let query = Query::parse(
+            "match (d:DomainName {text: \"pixlie.com\"})-[:OwnerOf|PathOf*..2]->(p)-->(t:Title:Partial) \
+             where not p.id = 3 and (t.text contains 'a' or t.id >= -1) \
+             return d, t.text limit 5",
+        )
+        .unwrap();
+        assert_eq!(query.nodes.len(), 3);
+        assert_eq!(query.nodes[0].variable, Some("d".to_string()));
+        assert_eq!(query.nodes[0].labels, vec![NodeLabel::DomainName]);
+        assert_eq!(query.nodes[0].properties.len(), 1);
+        assert_eq!(
+            query.nodes[2].labels,
+            vec![NodeLabel::Title, NodeLabel::Partial]
+        );
+        assert!(query.edges[0].labels == vec![EdgeLabel::OwnerOf, EdgeLabel::PathOf]);
+        assert_eq!((query.edges[0].min_depth, query.edges[0].max_depth), (1, 2));
+        assert!(query.edges[1].labels.is_empty());
+        assert_eq!((query.edges[1].min_depth, query.edges[1].max_depth), (1, 1));
+        assert!(matches!(query.condition, Some(Condition::And(_, _))));
+        assert_eq!(
+            query
+                .returns
+                .iter()
+                .map(|x| x.column.as_str())
+                .collect::<Vec<&str>>(),
+            vec!["d", "t.text"]
+        );
+        assert_eq!(query.limit, Some(5));
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        for query in [
+            "RETURN a",
+            "MATCH (a:NotALabel) RETURN a",
+            "MATCH (a)-[:NotAnEdge]->(b) RETURN a",
+            "MATCH (a)-[*3..1]->(b) RETURN a",
+            "MATCH (a) RETURN b",
+            "MATCH (a) WHERE b.id = 1 RETURN a",
+            "MATCH (a) WHERE a.id RETURN a",
+            "MATCH (a) RETURN a LIMIT 1.5",
+            "MATCH (a) RETURN a LIMIT 1 a",
+        ] {
+            assert!(Query::parse(query).is_err(), "{}", query);
+        }
+    }
+
+    #[test]
+    fn test_execute_traverses_edges() {
+        let engine = get_test_engine();
+        let (domain_id, link_ids) = add_test_graph(&engine);
+
+        let results = run_query(
+            &engine,
+            "MATCH (d:DomainName)-[:OwnerOf]->(l:Link)-[:PathOf]->(:WebPage)-[:ParentOf]->(t:Title) \
+             RETURN t.text, l.path, d",
+        );
+        assert_eq!(results.columns, vec!["t.text", "l.path", "d"]);
+        assert_eq!(get_texts(&results), vec!["Graphs", "About Pixlie"]);
+        match &results.rows[1][1] {
+            QueryValue::Text(path) => assert_eq!(path, "/about"),
+            _ => panic!("Expected QueryValue::Text"),
+        }
+        match &results.rows[0][2] {
+            QueryValue::Node(node) => assert_eq!(node.id, domain_id),
+            _ => panic!("Expected QueryValue::Node"),
+        }
+
+        // Variable depth reaches links, web pages and titles
+        let results = run_query(
+            &engine,
+            &format!(
+                "MATCH (d {{id: {}}})-[*1..3]->(n:Title) RETURN n.text",
+                domain_id
+            ),
+        );
+        assert_eq!(get_texts(&results), vec!["Graphs", "About Pixlie"]);
+        let results = run_query(
+            &engine,
+            &format!(
+                "MATCH (d {{id: {}}})-[:OwnerOf*2]->(n) RETURN n.id",
+                domain_id
+            ),
+        );
+        assert!(results.rows.is_empty());
+
+        // Edges are followed from a link back to its domain as well
+        let results = run_query(
+            &engine,
+            &format!(
+                "MATCH (l:Link)-[:BelongsTo]->(d)-[:OwnerOf]->(other) \
+                 WHERE l.id = {} AND other.id <> l.id RETURN other.path",
+                link_ids[0]
+            ),
+        );
+        assert_eq!(get_texts(&results), vec!["/about"]);
+    }
+
+    #[test]
+    fn test_execute_filters_and_limits() {
+        let engine = get_test_engine();
+        add_test_graph(&engine);
+
+        let results = run_query(
+            &engine,
+            "MATCH (l:Link) WHERE l.path STARTS WITH '/blog' RETURN l.path",
+        );
+        assert_eq!(get_texts(&results), vec!["/blog/graphs"]);
+        let results = run_query(
+            &engine,
+            "MATCH (t) WHERE t.labels CONTAINS 'Title' AND NOT (t.text ENDS WITH 's' OR t.text = 'x') \
+             RETURN t.text",
+        );
+        assert_eq!(get_texts(&results), vec!["About Pixlie"]);
+        let results = run_query(&engine, "MATCH (t:Title) RETURN t.text LIMIT 1");
+        assert_eq!(get_texts(&results), vec!["Graphs"]);
+        let results = run_query(
+            &engine,
+            "MATCH (t:Title {text: 'About Pixlie'}) RETURN t.text",
+        );
+        assert_eq!(get_texts(&results), vec!["About Pixlie"]);
+        let results = run_query(&engine, "MATCH (l:Link) RETURN l.missing_field");
+        assert!(matches!(results.rows[0][0], QueryValue::Null));
+    }
+}
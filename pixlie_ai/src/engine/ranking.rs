@@ -0,0 +1,421 @@
+// Copyright 2025 Pixlie Web Solutions Pvt. Ltd.
+// Licensed under the GNU General Public License version 3.0;
+// You may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// https://github.com/pixlie/PixlieAI/blob/main/LICENSE
+
+use crate::engine::node::{NodeId, NodeLabel, Payload};
+use crate::engine::text_index::TextSearchResult;
+use crate::engine::traversal::{Traversal, TraversalFilter, TraversalOrder};
+use crate::engine::{EdgeLabel, Engine};
+use crate::error::PiResult;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use ts_rs::TS;
+use utoipa::ToSchema;
+
+// Hybrid ranking re-ranks this many times the requested number of text matches,
+// so that results with a lower text score but better graph features can move up
+const CANDIDATES_PER_RESULT: usize = 5;
+const MIN_CANDIDATES: usize = 100;
+// Distance from the objective is measured along the edges which the crawl follows,
+// nodes which are further away than this are not considered close to the objective
+const MAX_OBJECTIVE_DISTANCE: usize = 12;
+const MAX_OBJECTIVE_TRAVERSAL_NODES: usize = 100_000;
+
+/// How the results of a full-text search are ranked.
+#[derive(Clone, Default, Deserialize, ToSchema, TS)]
+#[ts(export)]
+pub enum SearchRanking {
+    /// By the BM25 score of the text
+    #[default]
+    Text,
+    /// By the text score combined with graph features of the web page and link of each result
+    Hybrid,
+}
+
+/// Weights of the features in hybrid ranking, the score of a result is the weighted sum
+/// of its features. Each feature is from 0 to 1.
+#[derive(Clone, Deserialize, Serialize, ToSchema, TS)]
+#[ts(export)]
+pub struct RankingWeights {
+    /// BM25 score of the text, relative to the best match
+    pub text: f32,
+    /// Whether the web page was classified as relevant to the objective
+    pub relevance: f32,
+    /// How close the result is to the objective in the graph
+    pub objective_proximity: f32,
+    /// Whether the link was added by the user, found by web search or found while crawling
+    pub provenance: f32,
+}
+
+impl Default for RankingWeights {
+    fn default() -> Self {
+        RankingWeights {
+            text: 0.5,
+            relevance: 0.25,
+            objective_proximity: 0.15,
+            provenance: 0.1,
+        }
+    }
+}
+
+/// Where the link of a search result came from.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, ToSchema, TS)]
+#[ts(export)]
+pub enum LinkProvenance {
+    AddedByUser,
+    AddedByWebSearch,
+    /// Found in a web page while crawling
+    Crawled,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, ToSchema, TS)]
+#[ts(export)]
+pub enum RankingFeatureName {
+    Text,
+    Relevance,
+    ObjectiveProximity,
+    Provenance,
+}
+
+/// The value of one feature of a result and how much it added to the score.
+#[derive(Clone, Serialize, ToSchema, TS)]
+#[ts(export)]
+pub struct RankingFeature {
+    pub name: RankingFeatureName,
+    /// From 0 to 1
+    pub value: f32,
+    pub weight: f32,
+    /// Value times weight
+    pub contribution: f32,
+}
+
+/// Why a result of hybrid ranking has its score.
+#[derive(Clone, Serialize, ToSchema, TS)]
+#[ts(export)]
+pub struct RankingExplanation {
+    /// BM25 score of the text
+    pub text_score: f32,
+    /// Classification of the web page, if it has been classified
+    pub is_relevant: Option<bool>,
+    /// Number of edges from the closest objective, if it is close to one
+    pub objective_distance: Option<usize>,
+    pub provenance: Option<LinkProvenance>,
+    /// The features which add up to the score
+    pub features: Vec<RankingFeature>,
+}
+
+impl Engine {
+    // Whether the web page was classified as relevant to the objective, if it was classified
+    fn get_classification_of(&self, web_page_node_id: &NodeId) -> PiResult<Option<bool>> {
+        for classification_node_id in
+            self.get_node_ids_connected_with_label(web_page_node_id, &EdgeLabel::Classifies)?
+        {
+            if let Some(node) = self.get_node_without_content(&classification_node_id) {
+                if let Payload::Classification(classification) = &node.payload {
+                    return Ok(Some(classification.is_relevant));
+                }
+            }
+        }
+        Ok(None)
+    }
+
+    fn get_provenance_of(&self, link_node_id: &NodeId) -> Option<LinkProvenance> {
+        let link_node = self.get_node_without_content(link_node_id)?;
+        if link_node.labels.contains(&NodeLabel::AddedByUser) {
+            Some(LinkProvenance::AddedByUser)
+        } else if link_node.labels.contains(&NodeLabel::AddedByWebSearch) {
+            Some(LinkProvenance::AddedByWebSearch)
+        } else {
+            Some(LinkProvenance::Crawled)
+        }
+    }
+
+    // Number of edges from the objectives to each node near them. Follows the edges from
+    // objectives to links, links to their web pages and web pages to their content and links
+    fn get_objective_distances(&self) -> PiResult<HashMap<NodeId, usize>> {
+        let objective_node_ids: Vec<NodeId> = self
+            .get_node_ids_with_label(&NodeLabel::Objective)
+            .iter()
+            .map(|node_id| **node_id)
+            .collect();
+        if objective_node_ids.is_empty() {
+            return Ok(HashMap::new());
+        }
+        let traversed_nodes = self.traverse(&Traversal {
+            start_node_ids: objective_node_ids,
+            filter: TraversalFilter {
+                edge_labels: vec![
+                    EdgeLabel::RelatedTo,
+                    EdgeLabel::Suggests,
+                    EdgeLabel::PathOf,
+                    EdgeLabel::ParentOf,
+                    EdgeLabel::OwnerOf,
+                    EdgeLabel::BelongsTo,
+                ],
+                node_labels: vec![],
+            },
+            max_depth: MAX_OBJECTIVE_DISTANCE,
+            max_nodes: Some(MAX_OBJECTIVE_TRAVERSAL_NODES),
+            order: TraversalOrder::BreadthFirst,
+        })?;
+        Ok(traversed_nodes
+            .into_iter()
+            .map(|traversed_node| (traversed_node.node_id, traversed_node.depth))
+            .collect())
+    }
+
+    // Full-text search ranked by the text score combined with graph features, best first.
+    // Each result has an explanation of its score
+    pub fn search_text_hybrid(
+        &self,
+        query: &str,
+        limit: usize,
+        weights: &RankingWeights,
+    ) -> PiResult<Vec<TextSearchResult>> {
+        let candidates = self.search_text(
+            query,
+            limit
+                .saturating_mul(CANDIDATES_PER_RESULT)
+                .max(MIN_CANDIDATES),
+        )?;
+        if candidates.is_empty() {
+            return Ok(vec![]);
+        }
+        let best_text_score = candidates
+            .iter()
+            .map(|result| result.score)
+            .fold(0.0, f32::max);
+        let objective_distances = self.get_objective_distances()?;
+
+        let mut results: Vec<TextSearchResult> = vec![];
+        for mut result in candidates {
+            let is_relevant = match result.web_page_node_id {
+                Some(web_page_node_id) => self.get_classification_of(&web_page_node_id)?,
+                None => None,
+            };
+            let objective_distance = objective_distances.get(&result.node_id).copied();
+            let provenance = result
+                .link_node_id
+                .and_then(|link_node_id| self.get_provenance_of(&link_node_id));
+
+            let feature = |name: RankingFeatureName, value: f32, weight: f32| RankingFeature {
+                name,
+                value,
+                weight,
+                contribution: value * weight,
+            };
+            let features = vec![
+                feature(
+                    RankingFeatureName::Text,
+                    if best_text_score > 0.0 {
+                        result.score / best_text_score
+                    } else {
+                        0.0
+                    },
+                    weights.text,
+                ),
+                feature(
+                    RankingFeatureName::Relevance,
+                    // Pages which are not classified yet are between relevant and not relevant
+                    match is_relevant {
+                        Some(true) => 1.0,
+                        Some(false) => 0.0,
+                        None => 0.5,
+                    },
+                    weights.relevance,
+                ),
+                feature(
+                    RankingFeatureName::ObjectiveProximity,
+                    match objective_distance {
+                        Some(distance) => {
+                            1.0 - distance as f32 / (MAX_OBJECTIVE_DISTANCE + 1) as f32
+                        }
+                        None => 0.0,
+                    },
+                    weights.objective_proximity,
+                ),
+                feature(
+                    RankingFeatureName::Provenance,
+                    match provenance {
+                        Some(LinkProvenance::AddedByUser) => 1.0,
+                        Some(LinkProvenance::AddedByWebSearch) => 0.6,
+                        Some(LinkProvenance::Crawled) => 0.3,
+                        None => 0.0,
+                    },
+                    weights.provenance,
+                ),
+            ];
+            let text_score = result.score;
+            result.score = features.iter().map(|feature| feature.contribution).sum();
+            result.explanation = Some(RankingExplanation {
+                text_score,
+                is_relevant,
+                objective_distance,
+                provenance,
+                features,
+            });
+            results.push(result);
+        }
+        results.sort_by(|a, b| b.score.total_cmp(&a.score).then(a.node_id.cmp(&b.node_id)));
+        results.truncate(limit);
+        Ok(results)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::engine::get_test_engine;
+    use crate::entity::classifier::Classification;
+    use crate::entity::web::link::Link;
+    use std::sync::Arc;
+
+    // A link with its web page and one paragraph of content, returns the paragraph
+    fn add_page(engine: &Engine, url: &str, labels: Vec<NodeLabel>, text: &str) -> NodeId {
+        let link_node_id =
+            Link::add(Arc::new(engine), &url.to_string(), labels, vec![], true).unwrap();
+        let web_page_node_id = engine
+            .get_or_add_node(
+                Payload::Text(format!("<html>{}</html>", url)),
+                vec![NodeLabel::WebPage],
+                true,
+                None,
+            )
+            .unwrap()
+            .get_node_id();
+        engine
+            .add_connection(
+                (link_node_id, web_page_node_id),
+                (EdgeLabel::PathOf, EdgeLabel::ContentOf),
+            )
+            .unwrap();
+        let node_id = engine
+            .get_or_add_node(
+                Payload::Text(text.to_string()),
+                vec![NodeLabel::Paragraph, NodeLabel::Partial],
+                true,
+                None,
+            )
+            .unwrap()
+            .get_node_id();
+        engine
+            .add_connection(
+                (web_page_node_id, node_id),
+                (EdgeLabel::ParentOf, EdgeLabel::ChildOf),
+            )
+            .unwrap();
+        node_id
+    }
+
+    fn classify(engine: &Engine, node_id: &NodeId, is_relevant: bool) {
+        let web_page_node_id = engine
+            .get_node_ids_connected_with_label(node_id, &EdgeLabel::ChildOf)
+            .unwrap()[0];
+        let classification_node_id = engine
+            .get_or_add_node(
+                Payload::Classification(Classification {
+                    is_relevant,
+                    reason: "Test".to_string(),
+                    insight_if_classified_as_relevant: None,
+                }),
+                vec![NodeLabel::Classification, NodeLabel::AddedByAI],
+                true,
+                None,
+            )
+            .unwrap()
+            .get_node_id();
+        engine
+            .add_connection(
+                (web_page_node_id, classification_node_id),
+                (EdgeLabel::Classifies, EdgeLabel::ClassifiedFor),
+            )
+            .unwrap();
+    }
+
+    #[test]
+    fn test_hybrid_ranking_uses_graph_features() {
+        let engine = get_test_engine();
+        let objective_node_id = engine
+            .get_or_add_node(
+                Payload::Text("Find rust web crawlers".to_string()),
+                vec![NodeLabel::AddedByUser, NodeLabel::Objective],
+                true,
+                None,
+            )
+            .unwrap()
+            .get_node_id();
+        // More matches of the query, but crawled and classified as not relevant
+        let crawled = add_page(
+            &engine,
+            "https://example.com/crawlers",
+            vec![NodeLabel::Link],
+            "Crawlers crawl, a crawler crawls the web",
+        );
+        classify(&engine, &crawled, false);
+        let added_by_user = add_page(
+            &engine,
+            "https://pixlie.com/crawler",
+            vec![NodeLabel::AddedByUser, NodeLabel::Link],
+            "A crawler written in Rust",
+        );
+        classify(&engine, &added_by_user, true);
+        let link_node_id = engine
+            .get_node_ids_connected_with_label(
+                &engine
+                    .get_node_ids_connected_with_label(&added_by_user, &EdgeLabel::ChildOf)
+                    .unwrap()[0],
+                &EdgeLabel::ContentOf,
+            )
+            .unwrap()[0];
+        engine
+            .add_connection(
+                (objective_node_id, link_node_id),
+                (EdgeLabel::RelatedTo, EdgeLabel::RelatedTo),
+            )
+            .unwrap();
+
+        let text_results = engine.search_text("crawler", 10).unwrap();
+        assert_eq!(text_results[0].node_id, crawled);
+        assert!(text_results[0].explanation.is_none());
+
+        let results = engine
+            .search_text_hybrid("crawler", 10, &RankingWeights::default())
+            .unwrap();
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0].node_id, added_by_user);
+        let explanation = results[0].explanation.as_ref().unwrap();
+        assert_eq!(explanation.is_relevant, Some(true));
+        // Objective to link to web page to paragraph
+        assert_eq!(explanation.objective_distance, Some(3));
+        assert_eq!(explanation.provenance, Some(LinkProvenance::AddedByUser));
+        let sum: f32 = explanation
+            .features
+            .iter()
+            .map(|feature| feature.contribution)
+            .sum();
+        assert!((sum - results[0].score).abs() < 1e-6);
+
+        let explanation = results[1].explanation.as_ref().unwrap();
+        assert_eq!(explanation.is_relevant, Some(false));
+        assert_eq!(explanation.objective_distance, None);
+        assert_eq!(explanation.provenance, Some(LinkProvenance::Crawled));
+
+        // With only the text feature, the ranking is the same as text ranking
+        let results = engine
+            .search_text_hybrid(
+                "crawler",
+                10,
+                &RankingWeights {
+                    text: 1.0,
+                    relevance: 0.0,
+                    objective_proximity: 0.0,
+                    provenance: 0.0,
+                },
+            )
+            .unwrap();
+        assert_eq!(results[0].node_id, crawled);
+    }
+}
@@ -0,0 +1,763 @@
+// Copyright 2025 Pixlie Web Solutions Pvt. Ltd.
+// Licensed under the GNU General Public License version 3.0;
+// You may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// https://github.com/pixlie/PixlieAI/blob/main/LICENSE
+
+use crate::engine::migrations::{from_versioned_bytes, to_versioned_bytes};
+use crate::engine::node::{NodeId, NodeLabel, Payload};
+use crate::engine::ranking::RankingExplanation;
+use crate::error::{PiError, PiResult};
+use log::error;
+use rocksdb::{WriteBatch, DB};
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use ts_rs::TS;
+use utoipa::ToSchema;
+
+// Prefixes are at least as long as the fixed prefix of the prefix extractor, see `Nodes::open`
+// Number of tokens of each indexed node, a key per node
+pub(super) const TEXT_COUNTS_PREFIX: &str = "text/counts/";
+// Positions of a term in the text of a node, a key per term and node, read by term prefix
+pub(super) const TEXT_POSTINGS_PREFIX: &str = "text/posting/";
+
+// BM25 parameters, the usual defaults
+const BM25_K1: f32 = 1.2;
+const BM25_B: f32 = 0.75;
+
+// Snippets show this many bytes of text around the first match
+const SNIPPET_LENGTH: usize = 200;
+const SNIPPET_CONTEXT_BEFORE: usize = 60;
+
+// Nodes which contain this term, with the positions of the term in the text of each node
+type Postings = Vec<(NodeId, Vec<u32>)>;
+
+// A word of a text, lowercased and stemmed, with its byte range in the text
+struct Token {
+    term: String,
+    start: usize,
+    end: usize,
+}
+
+// Removes common English suffixes, so different forms of a word give the same term.
+// This is a light stemmer, the stems are not always words but they are consistent
+fn stem(word: &str) -> String {
+    if word.len() <= 3 || !word.is_ascii() {
+        return word.to_string();
+    }
+    let mut stem = if let Some(stem) = word.strip_suffix("sses") {
+        format!("{}ss", stem)
+    } else if let Some(stem) = word.strip_suffix("ies") {
+        format!("{}y", stem)
+    } else if word.ends_with("ss") || word.ends_with("us") || word.ends_with("is") {
+        word.to_string()
+    } else if let Some(stem) = word.strip_suffix('s') {
+        stem.to_string()
+    } else {
+        word.to_string()
+    };
+
+    let has_vowel = |x: &str| x.contains(['a', 'e', 'i', 'o', 'u', 'y']);
+    if let Some(without_suffix) = stem.strip_suffix("ing").or_else(|| stem.strip_suffix("ed")) {
+        if without_suffix.len() >= 3 && has_vowel(without_suffix) {
+            let bytes = without_suffix.as_bytes();
+            let last = bytes[bytes.len() - 1];
+            // Like running to run, but not falling to fal
+            stem = if last == bytes[bytes.len() - 2] && !b"aeiouylsz".contains(&last) {
+                without_suffix[..without_suffix.len() - 1].to_string()
+            } else {
+                without_suffix.to_string()
+            };
+        }
+    } else if let Some(without_suffix) = stem.strip_suffix("ly") {
+        if without_suffix.len() >= 4 {
+            stem = without_suffix.to_string();
+        }
+    }
+    if stem.len() >= 4 && stem.ends_with('e') {
+        stem.pop();
+    }
+    stem
+}
+
+// Words are runs of letters and digits, everything else separates them
+fn tokenize(text: &str) -> Vec<Token> {
+    let mut tokens: Vec<Token> = vec![];
+    let mut word_start: Option<usize> = None;
+    for (position, character) in text
+        .char_indices()
+        .chain(std::iter::once((text.len(), ' ')))
+    {
+        match (character.is_alphanumeric(), word_start) {
+            (true, None) => word_start = Some(position),
+            (false, Some(start)) => {
+                tokens.push(Token {
+                    term: stem(&text[start..position].to_lowercase()),
+                    start,
+                    end: position,
+                });
+                word_start = None;
+            }
+            _ => {}
+        }
+    }
+    tokens
+}
+
+fn get_terms(text: &str) -> Vec<String> {
+    tokenize(text).into_iter().map(|token| token.term).collect()
+}
+
+// Positions of each term in the text
+fn get_term_positions(text: &str) -> (BTreeMap<String, Vec<u32>>, u32) {
+    let mut term_positions: BTreeMap<String, Vec<u32>> = BTreeMap::new();
+    let tokens = tokenize(text);
+    for (position, token) in tokens.iter().enumerate() {
+        term_positions
+            .entry(token.term.clone())
+            .or_default()
+            .push(position as u32);
+    }
+    (term_positions, tokens.len() as u32)
+}
+
+// Content nodes created by the scraper are indexed, these have text payloads
+pub(super) fn get_indexed_text<'a>(labels: &[NodeLabel], payload: &'a Payload) -> Option<&'a str> {
+    match payload {
+        Payload::Text(text) if labels.contains(&NodeLabel::Partial) => Some(text),
+        _ => None,
+    }
+}
+
+#[derive(Clone)]
+enum TextClauseKind {
+    Term(String),
+    Phrase(Vec<String>), // Terms which must be next to each other, in this order
+}
+
+#[derive(Clone)]
+struct TextClause {
+    kind: TextClauseKind,
+    is_negated: bool,
+}
+
+impl TextClause {
+    fn get_terms(&self) -> Vec<&String> {
+        match &self.kind {
+            TextClauseKind::Term(term) => vec![term],
+            TextClauseKind::Phrase(terms) => terms.iter().collect(),
+        }
+    }
+}
+
+// A full-text query. Words and "quoted phrases" must all be in a text, words or phrases
+// after NOT or with a leading - must not be. OR separates groups of these, any of which may match
+pub struct TextQuery {
+    groups: Vec<Vec<TextClause>>,
+}
+
+impl TextQuery {
+    pub fn parse(query: &str) -> TextQuery {
+        let mut groups: Vec<Vec<TextClause>> = vec![vec![]];
+        let mut is_negated = false;
+        let mut rest = query.trim_start();
+        while !rest.is_empty() {
+            let is_negated_with_minus = match rest.strip_prefix('-') {
+                Some(after_minus) => {
+                    rest = after_minus;
+                    true
+                }
+                None => false,
+            };
+            let (part, is_phrase) = match rest.strip_prefix('"') {
+                // A phrase without a closing quote goes to the end of the query
+                Some(after_quote) => match after_quote.find('"') {
+                    Some(end) => {
+                        rest = &after_quote[end + 1..];
+                        (&after_quote[..end], true)
+                    }
+                    None => {
+                        rest = "";
+                        (after_quote, true)
+                    }
+                },
+                None => {
+                    let end = rest.find(char::is_whitespace).unwrap_or(rest.len());
+                    let part = &rest[..end];
+                    rest = &rest[end..];
+                    (part, false)
+                }
+            };
+            rest = rest.trim_start();
+
+            if !is_phrase && !is_negated_with_minus {
+                match part {
+                    "OR" => {
+                        groups.push(vec![]);
+                        is_negated = false;
+                        continue;
+                    }
+                    "AND" => continue,
+                    "NOT" => {
+                        is_negated = true;
+                        continue;
+                    }
+                    _ => {}
+                }
+            }
+            let mut terms = get_terms(part);
+            let kind = match terms.len() {
+                0 => continue,
+                1 if !is_phrase => TextClauseKind::Term(terms.remove(0)),
+                _ => TextClauseKind::Phrase(terms),
+            };
+            if let Some(group) = groups.last_mut() {
+                group.push(TextClause {
+                    kind,
+                    is_negated: is_negated || is_negated_with_minus,
+                });
+            }
+            is_negated = false;
+        }
+        // A group with only negated clauses would match almost everything
+        groups.retain(|group| group.iter().any(|clause| !clause.is_negated));
+        TextQuery { groups }
+    }
+
+    fn get_terms(&self) -> HashSet<&String> {
+        self.groups
+            .iter()
+            .flatten()
+            .flat_map(|clause| clause.get_terms())
+            .collect()
+    }
+
+    // Terms which count for the score of a matching text
+    fn get_positive_terms(&self) -> HashSet<&String> {
+        self.groups
+            .iter()
+            .flatten()
+            .filter(|clause| !clause.is_negated)
+            .flat_map(|clause| clause.get_terms())
+            .collect()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.groups.is_empty()
+    }
+
+    // Terms of a query to highlight in snippets
+    pub(super) fn get_highlighted_terms(&self) -> HashSet<String> {
+        self.get_positive_terms().into_iter().cloned().collect()
+    }
+}
+
+/// A part of the text of a search result, matching parts are highlighted.
+#[derive(Clone, Serialize, ToSchema, TS)]
+#[ts(export)]
+pub struct SnippetFragment {
+    pub text: String,
+    pub is_match: bool,
+}
+
+/// A content node which matches a full-text search, with the web page and link it is from.
+#[derive(Clone, Serialize, ToSchema, TS)]
+#[ts(export)]
+pub struct TextSearchResult {
+    pub node_id: NodeId,
+    /// BM25 score of the match, or the combined score with hybrid ranking, higher is better
+    pub score: f32,
+    pub web_page_node_id: Option<NodeId>,
+    pub link_node_id: Option<NodeId>,
+    /// The text around the first match
+    pub snippet: Vec<SnippetFragment>,
+    /// How the score was calculated, only with hybrid ranking
+    pub explanation: Option<RankingExplanation>,
+}
+
+// A part of the text around the first match of any of the terms, split into fragments
+pub(super) fn get_snippet(text: &str, terms: &HashSet<String>) -> Vec<SnippetFragment> {
+    let tokens = tokenize(text);
+    let matching_tokens: Vec<&Token> = tokens
+        .iter()
+        .filter(|token| terms.contains(&token.term))
+        .collect();
+    // Snippets start and end at words, or at the start or end of the text
+    let start = matching_tokens
+        .first()
+        .map(|token| token.start.saturating_sub(SNIPPET_CONTEXT_BEFORE))
+        .unwrap_or(0);
+    let start = match tokens.iter().find(|token| token.end > start) {
+        Some(token) if token.start < start => token.end,
+        _ => start,
+    };
+    let start = tokens
+        .iter()
+        .find(|token| token.start >= start)
+        .map(|token| token.start)
+        .unwrap_or(start);
+    let end = if start + SNIPPET_LENGTH >= text.len() {
+        text.len()
+    } else {
+        tokens
+            .iter()
+            .take_while(|token| token.end <= start + SNIPPET_LENGTH)
+            .last()
+            .map(|token| token.end)
+            .unwrap_or(text.len())
+    };
+
+    let mut fragments: Vec<SnippetFragment> = vec![];
+    let mut position = start;
+    for token in matching_tokens
+        .iter()
+        .filter(|token| token.start >= start && token.end <= end)
+    {
+        if token.start > position {
+            fragments.push(SnippetFragment {
+                text: text[position..token.start].to_string(),
+                is_match: false,
+            });
+        }
+        fragments.push(SnippetFragment {
+            text: text[token.start..token.end].to_string(),
+            is_match: true,
+        });
+        position = token.end;
+    }
+    if end > position {
+        fragments.push(SnippetFragment {
+            text: text[position..end].to_string(),
+            is_match: false,
+        });
+    }
+    fragments
+}
+
+// Inverted index of the text of content nodes. Only the number of tokens of each node
+// is kept in memory, postings are read from the DB by term when searching.
+// Postings and token counts are stored per node, so indexing a node writes only its own keys
+pub(super) struct TextIndex {
+    token_counts: HashMap<NodeId, u32>,
+    total_token_count: u64,
+}
+
+// A key to write, None deletes the key
+pub(super) type TextIndexWrite = (String, Option<Vec<u8>>);
+
+fn get_token_count_key(node_id: &NodeId) -> String {
+    format!("{}{}", TEXT_COUNTS_PREFIX, node_id)
+}
+
+fn get_postings_prefix(term: &str) -> String {
+    format!("{}{}/", TEXT_POSTINGS_PREFIX, term)
+}
+
+// Positions of a term in the text of a node
+fn get_posting_key(term: &str, node_id: &NodeId) -> String {
+    format!("{}{}", get_postings_prefix(term), node_id)
+}
+
+impl TextIndex {
+    pub(super) fn new() -> Self {
+        TextIndex {
+            token_counts: HashMap::new(),
+            total_token_count: 0,
+        }
+    }
+
+    pub(super) fn open(db: &DB) -> PiResult<Self> {
+        let mut text_index = TextIndex::new();
+        for item in db.prefix_iterator(TEXT_COUNTS_PREFIX) {
+            match item {
+                Ok((key, value)) => {
+                    if !key.starts_with(TEXT_COUNTS_PREFIX.as_bytes()) {
+                        break;
+                    }
+                    let node_id = Self::get_node_id_from_key(&key, TEXT_COUNTS_PREFIX)?;
+                    let token_count: u32 = from_versioned_bytes(&value)?;
+                    text_index.set_token_count(node_id, Some(token_count));
+                }
+                Err(err) => {
+                    error!("RocksDB error: {}", err);
+                    return Err(PiError::RocksdbError(err));
+                }
+            }
+        }
+        Ok(text_index)
+    }
+
+    // Index of the given texts, written to the batch. Used by migrations to index existing nodes,
+    // the index is written in the current format version
+    pub(super) fn write_new_index_to_batch(
+        batch: &mut WriteBatch,
+        texts: Vec<(NodeId, String)>,
+    ) -> PiResult<()> {
+        for (node_id, text) in texts {
+            for (key, value) in TextIndex::new().update_node(&node_id, None, Some(&text))? {
+                if let Some(value) = value {
+                    batch.put(key, value);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    pub(super) fn get_node_ids(&self) -> impl Iterator<Item = &NodeId> {
+        self.token_counts.keys()
+    }
+
+    fn set_token_count(&mut self, node_id: NodeId, token_count: Option<u32>) {
+        if let Some(previous) = self.token_counts.remove(&node_id) {
+            self.total_token_count -= previous as u64;
+        }
+        if let Some(token_count) = token_count {
+            self.token_counts.insert(node_id, token_count);
+            self.total_token_count += token_count as u64;
+        }
+    }
+
+    fn get_node_id_from_key(key: &[u8], prefix: &str) -> PiResult<NodeId> {
+        std::str::from_utf8(&key[prefix.len()..])
+            .ok()
+            .and_then(|node_id| node_id.parse::<NodeId>().ok())
+            .ok_or_else(|| {
+                PiError::InternalError(format!(
+                    "Invalid text index key {}",
+                    String::from_utf8_lossy(key)
+                ))
+            })
+    }
+
+    fn read_postings(db: &DB, term: &str) -> PiResult<Postings> {
+        let prefix = get_postings_prefix(term);
+        let mut postings: Postings = vec![];
+        for item in db.prefix_iterator(&prefix) {
+            match item {
+                Ok((key, value)) => {
+                    if !key.starts_with(prefix.as_bytes()) {
+                        break;
+                    }
+                    postings.push((
+                        Self::get_node_id_from_key(&key, &prefix)?,
+                        from_versioned_bytes(&value)?,
+                    ));
+                }
+                Err(err) => {
+                    error!("RocksDB error: {}", err);
+                    return Err(PiError::RocksdbError(err));
+                }
+            }
+        }
+        Ok(postings)
+    }
+
+    // Index of the text of a node, which replaces the text from `previous_text`. Only the keys
+    // of this node change, they are returned so the engine writes them with its batch
+    pub(super) fn update_node(
+        &mut self,
+        node_id: &NodeId,
+        previous_text: Option<&str>,
+        text: Option<&str>,
+    ) -> PiResult<Vec<TextIndexWrite>> {
+        let (term_positions, token_count) = match text {
+            Some(text) => {
+                let (term_positions, token_count) = get_term_positions(text);
+                (term_positions, Some(token_count))
+            }
+            None => (BTreeMap::new(), None),
+        };
+
+        let mut writes: Vec<TextIndexWrite> = vec![];
+        if let Some(previous_text) = previous_text {
+            let removed_terms: HashSet<String> = get_terms(previous_text)
+                .into_iter()
+                .filter(|term| !term_positions.contains_key(term))
+                .collect();
+            for term in removed_terms {
+                writes.push((get_posting_key(&term, node_id), None));
+            }
+        }
+        for (term, positions) in term_positions {
+            writes.push((
+                get_posting_key(&term, node_id),
+                Some(to_versioned_bytes(&positions)?),
+            ));
+        }
+        self.set_token_count(*node_id, token_count);
+        writes.push((
+            get_token_count_key(node_id),
+            match token_count {
+                Some(token_count) => Some(to_versioned_bytes(&token_count)?),
+                None => None,
+            },
+        ));
+        Ok(writes)
+    }
+
+    // Nodes matching the query with their BM25 score, best matches first
+    pub(super) fn search(
+        &self,
+        db: &DB,
+        query: &TextQuery,
+        limit: usize,
+    ) -> PiResult<Vec<(NodeId, f32)>> {
+        let mut positions_by_term: HashMap<&String, HashMap<NodeId, Vec<u32>>> = HashMap::new();
+        for term in query.get_terms() {
+            positions_by_term.insert(
+                term,
+                Self::read_postings(db, term)?
+                    .into_iter()
+                    .filter(|(node_id, _)| self.token_counts.contains_key(node_id))
+                    .collect(),
+            );
+        }
+        let get_positions = |term: &String, node_id: &NodeId| -> Option<&Vec<u32>> {
+            positions_by_term
+                .get(term)
+                .and_then(|positions| positions.get(node_id))
+        };
+        let matches_clause = |clause: &TextClause, node_id: &NodeId| -> bool {
+            match &clause.kind {
+                TextClauseKind::Term(term) => get_positions(term, node_id).is_some(),
+                TextClauseKind::Phrase(terms) => {
+                    let Some(first_positions) = get_positions(&terms[0], node_id) else {
+                        return false;
+                    };
+                    first_positions.iter().any(|first_position| {
+                        terms.iter().enumerate().skip(1).all(|(offset, term)| {
+                            get_positions(term, node_id).is_some_and(|positions| {
+                                positions.contains(&(first_position + offset as u32))
+                            })
+                        })
+                    })
+                }
+            }
+        };
+
+        let positive_terms = query.get_positive_terms();
+        let count_nodes = self.token_counts.len() as f32;
+        let average_token_count = self.total_token_count as f32 / count_nodes.max(1.0);
+        let mut candidate_node_ids: HashSet<NodeId> = HashSet::new();
+        for term in positive_terms.iter() {
+            if let Some(positions) = positions_by_term.get(term) {
+                candidate_node_ids.extend(positions.keys());
+            }
+        }
+        let mut results: Vec<(NodeId, f32)> = candidate_node_ids
+            .into_iter()
+            .filter(|node_id| {
+                query.groups.iter().any(|group| {
+                    group
+                        .iter()
+                        .all(|clause| matches_clause(clause, node_id) != clause.is_negated)
+                })
+            })
+            .map(|node_id| {
+                let token_count = *self.token_counts.get(&node_id).unwrap_or(&0) as f32;
+                let score: f32 = positive_terms
+                    .iter()
+                    .filter_map(|term| {
+                        let positions = positions_by_term.get(term)?;
+                        let term_frequency = positions.get(&node_id)?.len() as f32;
+                        let document_frequency = positions.len() as f32;
+                        let idf = (1.0
+                            + (count_nodes - document_frequency + 0.5)
+                                / (document_frequency + 0.5))
+                            .ln();
+                        Some(
+                            idf * term_frequency * (BM25_K1 + 1.0)
+                                / (term_frequency
+                                    + BM25_K1
+                                        * (1.0 - BM25_B
+                                            + BM25_B * token_count / average_token_count)),
+                        )
+                    })
+                    .sum();
+                (node_id, score)
+            })
+            .collect();
+        // Same scores are ordered by node ID, so results are stable
+        results.sort_by(|a, b| b.1.total_cmp(&a.1).then(a.0.cmp(&b.0)));
+        results.truncate(limit);
+        Ok(results)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::engine::{add_content_node, get_test_engine};
+    use crate::engine::{EdgeLabel, Engine};
+    use crate::entity::web::link::Link;
+    use std::sync::Arc;
+
+    fn search_node_ids(engine: &Engine, query: &str) -> Vec<NodeId> {
+        engine
+            .search_text(query, 10)
+            .unwrap()
+            .iter()
+            .map(|result| result.node_id)
+            .collect()
+    }
+
+    #[test]
+    fn test_tokenize_and_stem() {
+        assert_eq!(
+            get_terms("Crawling, crawled & crawls: the CRAWLER's pages!"),
+            vec!["crawl", "crawl", "crawl", "the", "crawler", "s", "pag"]
+        );
+        assert_eq!(stem("running"), "run");
+        assert_eq!(stem("falling"), "fall");
+        assert_eq!(stem("companies"), "company");
+        assert_eq!(stem("addresses"), "address");
+        assert_eq!(stem("searches"), stem("search"));
+        assert_eq!(stem("quickly"), "quick");
+        assert_eq!(stem("this"), "this");
+    }
+
+    #[test]
+    fn test_search_ranks_and_filters_content_nodes() {
+        let engine = get_test_engine();
+        let link_node_id = Link::add(
+            Arc::new(&engine),
+            &"https://pixlie.com/blog".to_string(),
+            vec![NodeLabel::Link],
+            vec![],
+            true,
+        )
+        .unwrap();
+        let web_page_node_id = engine
+            .get_or_add_node(
+                Payload::Text("<html></html>".to_string()),
+                vec![NodeLabel::WebPage],
+                true,
+                None,
+            )
+            .unwrap()
+            .get_node_id();
+        engine
+            .add_connection(
+                (link_node_id, web_page_node_id),
+                (EdgeLabel::PathOf, EdgeLabel::ContentOf),
+            )
+            .unwrap();
+        let crawler = add_content_node(
+            &engine,
+            web_page_node_id,
+            "The web crawler crawls web pages, crawling is what it does",
+        );
+        let graph = add_content_node(
+            &engine,
+            web_page_node_id,
+            "Web pages are stored in a graph of nodes and edges",
+        );
+        let search_node_id = add_content_node(
+            &engine,
+            web_page_node_id,
+            "Search the graph for nodes, not web pages",
+        );
+
+        // More matches of the term rank higher
+        assert_eq!(search_node_ids(&engine, "crawl"), vec![crawler]);
+        assert_eq!(search_node_ids(&engine, "web crawling")[0], crawler);
+        assert_eq!(search_node_ids(&engine, "web pages").len(), 3);
+        assert_eq!(search_node_ids(&engine, "\"web pages are\""), vec![graph]);
+        assert_eq!(
+            search_node_ids(&engine, "\"pages web\""),
+            Vec::<NodeId>::new()
+        );
+        let mut node_ids = search_node_ids(&engine, "crawler OR search");
+        node_ids.sort();
+        assert_eq!(node_ids, vec![crawler, search_node_id]);
+        assert_eq!(search_node_ids(&engine, "graph -search"), vec![graph]);
+        assert_eq!(
+            search_node_ids(&engine, "graph NOT \"for nodes\""),
+            vec![graph]
+        );
+        assert_eq!(search_node_ids(&engine, "-graph"), Vec::<NodeId>::new());
+
+        let results = engine.search_text("crawler", 10).unwrap();
+        assert_eq!(results[0].web_page_node_id, Some(web_page_node_id));
+        assert_eq!(results[0].link_node_id, Some(link_node_id));
+        let highlighted: Vec<&str> = results[0]
+            .snippet
+            .iter()
+            .filter(|fragment| fragment.is_match)
+            .map(|fragment| fragment.text.as_str())
+            .collect();
+        assert_eq!(highlighted, vec!["crawler"]);
+    }
+
+    #[test]
+    fn test_indexing_a_node_writes_only_its_own_keys() {
+        let mut text_index = TextIndex::new();
+        text_index
+            .update_node(&1, None, Some("Crawling the web"))
+            .unwrap();
+        let writes = text_index
+            .update_node(&2, Some("Crawling pages"), Some("Web pages"))
+            .unwrap();
+        let keys: Vec<(&str, bool)> = writes
+            .iter()
+            .map(|(key, value)| (key.as_str(), value.is_some()))
+            .collect();
+        assert_eq!(
+            keys,
+            vec![
+                ("text/posting/crawl/2", false),
+                ("text/posting/pag/2", true),
+                ("text/posting/web/2", true),
+                ("text/counts/2", true),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_index_follows_updated_and_deleted_nodes() {
+        let engine = get_test_engine();
+        let parent_node_id = engine
+            .get_or_add_node(
+                Payload::Text("<html></html>".to_string()),
+                vec![NodeLabel::WebPage],
+                true,
+                None,
+            )
+            .unwrap()
+            .get_node_id();
+        let node_id = add_content_node(&engine, parent_node_id, "Pixlie crawls the web");
+        let other_node_id = add_content_node(&engine, parent_node_id, "Pixlie reads the web");
+        assert_eq!(search_node_ids(&engine, "crawl"), vec![node_id]);
+
+        engine
+            .update_node(
+                &node_id,
+                Payload::Text("Pixlie searches the web".to_string()),
+            )
+            .unwrap();
+        assert_eq!(search_node_ids(&engine, "crawl"), Vec::<NodeId>::new());
+        assert_eq!(search_node_ids(&engine, "search"), vec![node_id]);
+
+        engine.delete_node(&node_id, false).unwrap();
+        assert_eq!(search_node_ids(&engine, "search"), Vec::<NodeId>::new());
+        assert_eq!(search_node_ids(&engine, "pixlie"), vec![other_node_id]);
+    }
+
+    #[test]
+    fn test_snippet_is_around_first_match() {
+        let text = format!(
+            "{} the crawler found it {}",
+            "word ".repeat(40),
+            "more ".repeat(60)
+        );
+        let terms: HashSet<String> = HashSet::from(["crawler".to_string()]);
+        let snippet = get_snippet(&text, &terms);
+        let snippet_text: String = snippet.iter().map(|x| x.text.as_str()).collect();
+        assert!(snippet_text.len() <= SNIPPET_LENGTH);
+        assert!(snippet_text.starts_with("word"));
+        assert!(snippet_text.ends_with("more"));
+        assert_eq!(snippet.iter().filter(|x| x.is_match).count(), 1);
+        assert!(snippet[1].is_match && snippet[1].text == "crawler");
+    }
+}
@@ -0,0 +1,463 @@
+// Copyright 2025 Pixlie Web Solutions Pvt. Ltd.
+// Licensed under the GNU General Public License version 3.0;
+// You may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// https://github.com/pixlie/PixlieAI/blob/main/LICENSE
+
+use crate::engine::node::{NodeId, NodeLabel};
+use crate::engine::{EdgeLabel, Engine};
+use crate::error::PiResult;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet, VecDeque};
+use ts_rs::TS;
+use utoipa::ToSchema;
+
+/// Which edges and nodes a traversal of the graph may follow.
+#[derive(Clone, Default, Deserialize, ToSchema, TS)]
+#[ts(export)]
+pub struct TraversalFilter {
+    /// Edges with any of these labels are followed. All edges are followed if this is empty.
+    #[serde(default)]
+    pub edge_labels: Vec<EdgeLabel>,
+    /// Nodes with any of these labels are visited. All nodes are visited if this is empty.
+    #[serde(default)]
+    pub node_labels: Vec<NodeLabel>,
+}
+
+#[derive(Clone, Default, Deserialize, ToSchema, TS)]
+#[ts(export)]
+pub enum TraversalOrder {
+    #[default]
+    BreadthFirst,
+    DepthFirst,
+}
+
+/// A traversal of the graph, from the start nodes up to a depth.
+#[derive(Clone, Deserialize, ToSchema, TS)]
+#[ts(export)]
+pub struct Traversal {
+    /// The nodes to start from, these are visited even if they do not match the filter
+    pub start_node_ids: Vec<NodeId>,
+    #[serde(default)]
+    pub filter: TraversalFilter,
+    /// Number of edges to follow from the start nodes
+    pub max_depth: usize,
+    /// The traversal stops when this many nodes are visited
+    pub max_nodes: Option<usize>,
+    #[serde(default)]
+    pub order: TraversalOrder,
+}
+
+/// A node visited in a traversal, with the node and the edge it was reached from.
+#[derive(Clone, Serialize, ToSchema, TS)]
+#[ts(export)]
+pub struct TraversedNode {
+    pub node_id: NodeId,
+    /// Number of edges from the start node
+    pub depth: usize,
+    /// The node this node was reached from, none for the start nodes
+    pub parent_node_id: Option<NodeId>,
+    pub edge_label: Option<EdgeLabel>,
+}
+
+impl TraversedNode {
+    fn start(node_id: NodeId) -> TraversedNode {
+        TraversedNode {
+            node_id,
+            depth: 0,
+            parent_node_id: None,
+            edge_label: None,
+        }
+    }
+}
+
+impl Engine {
+    // Nodes connected to a node with edges and nodes which match the filter
+    fn get_next_nodes(
+        &self,
+        node_id: &NodeId,
+        filter: &TraversalFilter,
+    ) -> PiResult<Vec<(NodeId, EdgeLabel)>> {
+        let Some(node_edges) = self.get_connected_nodes(node_id)? else {
+            return Ok(vec![]);
+        };
+        let mut next_nodes: Vec<(NodeId, EdgeLabel)> = vec![];
+        for (other_node_id, edge_label, _) in node_edges.edges {
+            if !filter.edge_labels.is_empty() && !filter.edge_labels.contains(&edge_label) {
+                continue;
+            }
+            if !filter.node_labels.is_empty() {
+                match self.get_node_without_content(&other_node_id) {
+                    Some(other_node) => {
+                        if !filter
+                            .node_labels
+                            .iter()
+                            .any(|label| other_node.labels.contains(label))
+                        {
+                            continue;
+                        }
+                    }
+                    None => continue,
+                }
+            }
+            next_nodes.push((other_node_id, edge_label));
+        }
+        Ok(next_nodes)
+    }
+
+    // Visits each node once, in the order of the traversal. Depth first traversal visits a node
+    // from the first path which reaches it, which may be longer than the shortest path
+    pub fn traverse(&self, traversal: &Traversal) -> PiResult<Vec<TraversedNode>> {
+        let mut visited: HashSet<NodeId> = HashSet::new();
+        let mut traversed_nodes: Vec<TraversedNode> = vec![];
+        let is_full = |traversed_nodes: &Vec<TraversedNode>| {
+            traversal
+                .max_nodes
+                .is_some_and(|max_nodes| traversed_nodes.len() >= max_nodes)
+        };
+        match traversal.order {
+            TraversalOrder::BreadthFirst => {
+                let mut queue: VecDeque<TraversedNode> = VecDeque::new();
+                for start_node_id in traversal.start_node_ids.iter() {
+                    if visited.insert(*start_node_id) {
+                        queue.push_back(TraversedNode::start(*start_node_id));
+                    }
+                }
+                while let Some(traversed_node) = queue.pop_front() {
+                    if is_full(&traversed_nodes) {
+                        break;
+                    }
+                    if traversed_node.depth < traversal.max_depth {
+                        for (next_node_id, edge_label) in
+                            self.get_next_nodes(&traversed_node.node_id, &traversal.filter)?
+                        {
+                            if visited.insert(next_node_id) {
+                                queue.push_back(TraversedNode {
+                                    node_id: next_node_id,
+                                    depth: traversed_node.depth + 1,
+                                    parent_node_id: Some(traversed_node.node_id),
+                                    edge_label: Some(edge_label),
+                                });
+                            }
+                        }
+                    }
+                    traversed_nodes.push(traversed_node);
+                }
+            }
+            TraversalOrder::DepthFirst => {
+                // Nodes are pushed in reverse, so they are visited in the order of their edges
+                let mut stack: Vec<TraversedNode> = traversal
+                    .start_node_ids
+                    .iter()
+                    .rev()
+                    .map(|start_node_id| TraversedNode::start(*start_node_id))
+                    .collect();
+                while let Some(traversed_node) = stack.pop() {
+                    if is_full(&traversed_nodes) {
+                        break;
+                    }
+                    if !visited.insert(traversed_node.node_id) {
+                        continue;
+                    }
+                    if traversed_node.depth < traversal.max_depth {
+                        for (next_node_id, edge_label) in self
+                            .get_next_nodes(&traversed_node.node_id, &traversal.filter)?
+                            .into_iter()
+                            .rev()
+                        {
+                            if !visited.contains(&next_node_id) {
+                                stack.push(TraversedNode {
+                                    node_id: next_node_id,
+                                    depth: traversed_node.depth + 1,
+                                    parent_node_id: Some(traversed_node.node_id),
+                                    edge_label: Some(edge_label),
+                                });
+                            }
+                        }
+                    }
+                    traversed_nodes.push(traversed_node);
+                }
+            }
+        }
+        Ok(traversed_nodes)
+    }
+
+    // The nodes on a shortest path from one node to another, starting with the `from` node.
+    // The nodes in between have to match the filter, the `to` node does not
+    pub fn find_shortest_path(
+        &self,
+        from_node_id: &NodeId,
+        to_node_id: &NodeId,
+        filter: &TraversalFilter,
+        max_depth: usize,
+    ) -> PiResult<Option<Vec<TraversedNode>>> {
+        let mut reached: HashMap<NodeId, TraversedNode> =
+            HashMap::from([(*from_node_id, TraversedNode::start(*from_node_id))]);
+        let mut frontier: Vec<NodeId> = vec![*from_node_id];
+        let mut depth: usize = 0;
+        // Node labels are checked here, since the `to` node does not have to match them
+        let edge_filter = TraversalFilter {
+            edge_labels: filter.edge_labels.clone(),
+            node_labels: vec![],
+        };
+        while !reached.contains_key(to_node_id) && !frontier.is_empty() && depth < max_depth {
+            depth += 1;
+            let mut next_frontier: Vec<NodeId> = vec![];
+            for node_id in frontier.iter() {
+                for (next_node_id, edge_label) in self.get_next_nodes(node_id, &edge_filter)? {
+                    if reached.contains_key(&next_node_id) {
+                        continue;
+                    }
+                    if next_node_id != *to_node_id && !filter.node_labels.is_empty() {
+                        let is_matching =
+                            self.get_node_without_content(&next_node_id)
+                                .is_some_and(|node| {
+                                    filter
+                                        .node_labels
+                                        .iter()
+                                        .any(|label| node.labels.contains(label))
+                                });
+                        if !is_matching {
+                            continue;
+                        }
+                    }
+                    reached.insert(
+                        next_node_id,
+                        TraversedNode {
+                            node_id: next_node_id,
+                            depth,
+                            parent_node_id: Some(*node_id),
+                            edge_label: Some(edge_label),
+                        },
+                    );
+                    next_frontier.push(next_node_id);
+                }
+            }
+            frontier = next_frontier;
+        }
+
+        // Walk back from the `to` node using the node each node was reached from
+        let mut path: Vec<TraversedNode> = vec![];
+        let mut current = reached.remove(to_node_id);
+        while let Some(traversed_node) = current {
+            current = traversed_node
+                .parent_node_id
+                .and_then(|parent_node_id| reached.remove(&parent_node_id));
+            path.push(traversed_node);
+        }
+        if path.is_empty() {
+            return Ok(None);
+        }
+        path.reverse();
+        Ok(Some(path))
+    }
+
+    // Groups of nodes which are connected to each other with edges which match the filter,
+    // each sorted by node ID, and the groups sorted by their first node ID
+    pub fn get_connected_components(&self, filter: &TraversalFilter) -> PiResult<Vec<Vec<NodeId>>> {
+        let node_ids: Vec<NodeId> = if filter.node_labels.is_empty() {
+            self.get_all_node_ids()
+        } else {
+            let mut node_ids: Vec<NodeId> = filter
+                .node_labels
+                .iter()
+                .flat_map(|label| self.get_node_ids_with_label(label))
+                .map(|node_id| *node_id)
+                .collect();
+            node_ids.sort();
+            node_ids.dedup();
+            node_ids
+        };
+        let mut visited: HashSet<NodeId> = HashSet::new();
+        let mut components: Vec<Vec<NodeId>> = vec![];
+        for node_id in node_ids {
+            if visited.contains(&node_id) {
+                continue;
+            }
+            let mut component: Vec<NodeId> = self
+                .traverse(&Traversal {
+                    start_node_ids: vec![node_id],
+                    filter: filter.clone(),
+                    max_depth: usize::MAX,
+                    max_nodes: None,
+                    order: TraversalOrder::BreadthFirst,
+                })?
+                .into_iter()
+                .map(|traversed_node| traversed_node.node_id)
+                .collect();
+            visited.extend(component.iter());
+            component.sort();
+            components.push(component);
+        }
+        Ok(components)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::engine::{add_node, connect, get_test_engine};
+    use crate::engine::node::Payload;
+
+    // A tree of a heading with two paragraphs, the first with a list item,
+    // and a separate title
+    fn add_test_graph(engine: &Engine) -> Vec<NodeId> {
+        let heading_id = add_node(
+            engine,
+            Payload::Text("Heading".to_string()),
+            vec![NodeLabel::Heading],
+        );
+        let first_id = add_node(
+            engine,
+            Payload::Text("First".to_string()),
+            vec![NodeLabel::Paragraph],
+        );
+        let second_id = add_node(
+            engine,
+            Payload::Text("Second".to_string()),
+            vec![NodeLabel::Paragraph],
+        );
+        let list_item_id = add_node(
+            engine,
+            Payload::Text("List item".to_string()),
+            vec![NodeLabel::ListItem],
+        );
+        let title_id = add_node(
+            engine,
+            Payload::Text("Title".to_string()),
+            vec![NodeLabel::Title],
+        );
+        connect(engine, heading_id, first_id);
+        connect(engine, heading_id, second_id);
+        connect(engine, first_id, list_item_id);
+        vec![heading_id, first_id, second_id, list_item_id, title_id]
+    }
+
+    fn get_node_ids(traversed_nodes: &[TraversedNode]) -> Vec<NodeId> {
+        traversed_nodes.iter().map(|x| x.node_id).collect()
+    }
+
+    #[test]
+    fn test_traverse_breadth_first_and_depth_first() {
+        let engine = get_test_engine();
+        let ids = add_test_graph(&engine);
+        let mut traversal = Traversal {
+            start_node_ids: vec![ids[0]],
+            filter: TraversalFilter {
+                edge_labels: vec![EdgeLabel::ParentOf],
+                node_labels: vec![],
+            },
+            max_depth: 5,
+            max_nodes: None,
+            order: TraversalOrder::BreadthFirst,
+        };
+        let traversed_nodes = engine.traverse(&traversal).unwrap();
+        assert_eq!(
+            get_node_ids(&traversed_nodes),
+            vec![ids[0], ids[1], ids[2], ids[3]]
+        );
+        assert_eq!(
+            traversed_nodes
+                .iter()
+                .map(|x| x.depth)
+                .collect::<Vec<usize>>(),
+            vec![0, 1, 1, 2]
+        );
+        assert_eq!(traversed_nodes[3].parent_node_id, Some(ids[1]));
+
+        traversal.order = TraversalOrder::DepthFirst;
+        assert_eq!(
+            get_node_ids(&engine.traverse(&traversal).unwrap()),
+            vec![ids[0], ids[1], ids[3], ids[2]]
+        );
+
+        traversal.max_depth = 1;
+        assert_eq!(
+            get_node_ids(&engine.traverse(&traversal).unwrap()),
+            vec![ids[0], ids[1], ids[2]]
+        );
+        traversal.max_depth = 5;
+        traversal.max_nodes = Some(2);
+        assert_eq!(
+            get_node_ids(&engine.traverse(&traversal).unwrap()),
+            vec![ids[0], ids[1]]
+        );
+    }
+
+    #[test]
+    fn test_traverse_with_node_labels() {
+        let engine = get_test_engine();
+        let ids = add_test_graph(&engine);
+        // Going up from the list item to the heading, but only through paragraphs
+        let traversal = Traversal {
+            start_node_ids: vec![ids[3]],
+            filter: TraversalFilter {
+                edge_labels: vec![],
+                node_labels: vec![NodeLabel::Paragraph],
+            },
+            max_depth: 5,
+            max_nodes: None,
+            order: TraversalOrder::BreadthFirst,
+        };
+        assert_eq!(
+            get_node_ids(&engine.traverse(&traversal).unwrap()),
+            vec![ids[3], ids[1]]
+        );
+    }
+
+    #[test]
+    fn test_find_shortest_path() {
+        let engine = get_test_engine();
+        let ids = add_test_graph(&engine);
+        let path = engine
+            .find_shortest_path(&ids[3], &ids[2], &TraversalFilter::default(), 5)
+            .unwrap()
+            .unwrap();
+        assert_eq!(get_node_ids(&path), vec![ids[3], ids[1], ids[0], ids[2]]);
+        assert!(matches!(path[1].edge_label, Some(EdgeLabel::ChildOf)));
+        assert!(matches!(path[3].edge_label, Some(EdgeLabel::ParentOf)));
+
+        // Too far, or not connected
+        assert!(engine
+            .find_shortest_path(&ids[3], &ids[2], &TraversalFilter::default(), 2)
+            .unwrap()
+            .is_none());
+        assert!(engine
+            .find_shortest_path(&ids[0], &ids[4], &TraversalFilter::default(), 5)
+            .unwrap()
+            .is_none());
+        // The path from a node to itself is the node
+        assert_eq!(
+            get_node_ids(
+                &engine
+                    .find_shortest_path(&ids[4], &ids[4], &TraversalFilter::default(), 5)
+                    .unwrap()
+                    .unwrap()
+            ),
+            vec![ids[4]]
+        );
+    }
+
+    #[test]
+    fn test_get_connected_components() {
+        let engine = get_test_engine();
+        let ids = add_test_graph(&engine);
+        assert_eq!(
+            engine
+                .get_connected_components(&TraversalFilter::default())
+                .unwrap(),
+            vec![vec![ids[0], ids[1], ids[2], ids[3]], vec![ids[4]]]
+        );
+        // Without the heading, the paragraphs are not connected to each other
+        assert_eq!(
+            engine
+                .get_connected_components(&TraversalFilter {
+                    edge_labels: vec![],
+                    node_labels: vec![NodeLabel::Paragraph, NodeLabel::ListItem],
+                })
+                .unwrap(),
+            vec![vec![ids[1], ids[3]], vec![ids[2]]]
+        );
+    }
+}
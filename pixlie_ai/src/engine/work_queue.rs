@@ -0,0 +1,271 @@
+// Copyright 2025 Pixlie Web Solutions Pvt. Ltd.
+// Licensed under the GNU General Public License version 3.0;
+// You may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// https://github.com/pixlie/PixlieAI/blob/main/LICENSE
+
+use crate::engine::node::NodeId;
+use log::error;
+use std::collections::{BTreeMap, BTreeSet};
+use std::sync::{Condvar, Mutex};
+use std::time::{Duration, Instant};
+
+#[derive(Default)]
+struct QueuedNodeIds {
+    // Nodes which have changed and should be considered for processing
+    dirty: BTreeSet<NodeId>,
+    // Nodes which could not be processed because of something outside the node,
+    // like a dependency which was not yet processed or a limit on open fetch requests
+    waiting: BTreeSet<NodeId>,
+    // Links which the crawl budget does not allow to be fetched, these are skipped by all
+    // processing till the budget changes
+    waiting_for_budget: BTreeSet<NodeId>,
+    // Nodes which cannot be processed before a time, like links of a domain which was fetched
+    // from very recently. These are dirty again once the time has passed
+    waiting_until: BTreeMap<NodeId, Instant>,
+}
+
+impl QueuedNodeIds {
+    fn requeue_due(&mut self) {
+        let now = Instant::now();
+        let due: Vec<NodeId> = self
+            .waiting_until
+            .iter()
+            .filter(|(_, waiting_until)| **waiting_until <= now)
+            .map(|(node_id, _)| *node_id)
+            .collect();
+        for node_id in due {
+            self.waiting_until.remove(&node_id);
+            self.dirty.insert(node_id);
+        }
+    }
+}
+
+// Work queue for the engine: nodes are marked dirty when they are created or changed
+// and the engine's ticker wakes up to process only those nodes
+#[derive(Default)]
+pub(super) struct WorkQueue {
+    node_ids: Mutex<QueuedNodeIds>,
+    has_dirty_nodes: Condvar,
+}
+
+impl WorkQueue {
+    pub(super) fn mark_dirty(&self, node_id: NodeId) {
+        match self.node_ids.lock() {
+            Ok(mut node_ids) => {
+                node_ids.dirty.insert(node_id);
+                self.has_dirty_nodes.notify_all();
+            }
+            Err(err) => {
+                error!("Error locking work queue: {}", err);
+            }
+        }
+    }
+
+    // A waiting node is not dirty, even if it changed since it was taken from the queue
+    pub(super) fn mark_waiting(&self, node_id: NodeId) {
+        match self.node_ids.lock() {
+            Ok(mut node_ids) => {
+                node_ids.dirty.remove(&node_id);
+                node_ids.waiting.insert(node_id);
+            }
+            Err(err) => {
+                error!("Error locking work queue: {}", err);
+            }
+        }
+    }
+
+    // Called when something changes which waiting nodes may have been waiting for
+    pub(super) fn requeue_waiting(&self) {
+        match self.node_ids.lock() {
+            Ok(mut node_ids) => {
+                if node_ids.waiting.is_empty() {
+                    return;
+                }
+                let waiting = std::mem::take(&mut node_ids.waiting);
+                node_ids.dirty.extend(waiting);
+                self.has_dirty_nodes.notify_all();
+            }
+            Err(err) => {
+                error!("Error locking work queue: {}", err);
+            }
+        }
+    }
+
+    pub(super) fn mark_waiting_for_budget(&self, node_id: NodeId) {
+        match self.node_ids.lock() {
+            Ok(mut node_ids) => {
+                node_ids.dirty.remove(&node_id);
+                node_ids.waiting_for_budget.insert(node_id);
+            }
+            Err(err) => {
+                error!("Error locking work queue: {}", err);
+            }
+        }
+    }
+
+    pub(super) fn mark_waiting_until(&self, node_id: NodeId, waiting_until: Instant) {
+        match self.node_ids.lock() {
+            Ok(mut node_ids) => {
+                node_ids.dirty.remove(&node_id);
+                node_ids.waiting_until.insert(node_id, waiting_until);
+            }
+            Err(err) => {
+                error!("Error locking work queue: {}", err);
+            }
+        }
+    }
+
+    // Nodes which are skipped even by a full scan, since they wait for the crawl budget
+    // or for a time which has not passed yet
+    pub(super) fn get_parked(&self) -> BTreeSet<NodeId> {
+        match self.node_ids.lock() {
+            Ok(mut node_ids) => {
+                node_ids.requeue_due();
+                node_ids
+                    .waiting_for_budget
+                    .iter()
+                    .chain(node_ids.waiting_until.keys())
+                    .copied()
+                    .collect()
+            }
+            Err(err) => {
+                error!("Error locking work queue: {}", err);
+                BTreeSet::new()
+            }
+        }
+    }
+
+    // Called when the crawl budget changes, or with the links which are closer to a seed link
+    // than before. None requeues all links waiting for the budget
+    pub(super) fn requeue_waiting_for_budget(&self, node_ids_to_requeue: Option<&[NodeId]>) {
+        match self.node_ids.lock() {
+            Ok(mut node_ids) => {
+                let requeued: Vec<NodeId> = match node_ids_to_requeue {
+                    Some(node_ids_to_requeue) => node_ids_to_requeue
+                        .iter()
+                        .filter(|node_id| node_ids.waiting_for_budget.remove(node_id))
+                        .copied()
+                        .collect(),
+                    None => std::mem::take(&mut node_ids.waiting_for_budget)
+                        .into_iter()
+                        .collect(),
+                };
+                if requeued.is_empty() {
+                    return;
+                }
+                node_ids.dirty.extend(requeued);
+                self.has_dirty_nodes.notify_all();
+            }
+            Err(err) => {
+                error!("Error locking work queue: {}", err);
+            }
+        }
+    }
+
+    // Takes all dirty nodes out of the queue, without waiting
+    pub(super) fn take_dirty(&self) -> Vec<NodeId> {
+        match self.node_ids.lock() {
+            Ok(mut node_ids) => {
+                node_ids.requeue_due();
+                std::mem::take(&mut node_ids.dirty).into_iter().collect()
+            }
+            Err(err) => {
+                error!("Error locking work queue: {}", err);
+                vec![]
+            }
+        }
+    }
+
+    // Blocks till there are dirty nodes or the timeout is reached, then takes all dirty nodes
+    pub(super) fn wait_for_dirty(&self, timeout: Duration) -> Vec<NodeId> {
+        let node_ids = match self.node_ids.lock() {
+            Ok(node_ids) => node_ids,
+            Err(err) => {
+                error!("Error locking work queue: {}", err);
+                return vec![];
+            }
+        };
+        match self
+            .has_dirty_nodes
+            .wait_timeout_while(node_ids, timeout, |node_ids| {
+                node_ids.requeue_due();
+                node_ids.dirty.is_empty()
+            }) {
+            Ok((mut node_ids, _)) => std::mem::take(&mut node_ids.dirty).into_iter().collect(),
+            Err(err) => {
+                error!("Error waiting on work queue: {}", err);
+                vec![]
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use std::thread;
+
+    #[test]
+    fn test_waiting_nodes_are_dirty_only_after_requeue() {
+        let work_queue = WorkQueue::default();
+        work_queue.mark_dirty(3);
+        work_queue.mark_waiting(1);
+        work_queue.mark_dirty(2);
+        assert_eq!(work_queue.take_dirty(), vec![2, 3]);
+        assert!(work_queue.take_dirty().is_empty());
+
+        work_queue.requeue_waiting();
+        assert_eq!(work_queue.take_dirty(), vec![1]);
+        work_queue.requeue_waiting();
+        assert!(work_queue.take_dirty().is_empty());
+    }
+
+    #[test]
+    fn test_nodes_waiting_for_budget_are_not_requeued_with_waiting_nodes() {
+        let work_queue = WorkQueue::default();
+        work_queue.mark_dirty(1);
+        work_queue.mark_waiting_for_budget(1);
+        work_queue.mark_waiting_for_budget(2);
+        assert!(work_queue.take_dirty().is_empty());
+        work_queue.requeue_waiting();
+        assert!(work_queue.take_dirty().is_empty());
+
+        work_queue.requeue_waiting_for_budget(Some(&[2, 3]));
+        assert_eq!(work_queue.take_dirty(), vec![2]);
+        assert_eq!(work_queue.get_parked(), BTreeSet::from([1]));
+        work_queue.requeue_waiting_for_budget(None);
+        assert_eq!(work_queue.take_dirty(), vec![1]);
+    }
+
+    #[test]
+    fn test_nodes_waiting_until_a_time_are_dirty_after_it() {
+        let work_queue = WorkQueue::default();
+        work_queue.mark_dirty(1);
+        work_queue.mark_waiting_until(1, Instant::now() + Duration::from_millis(50));
+        work_queue.requeue_waiting();
+        assert!(work_queue.take_dirty().is_empty());
+        assert_eq!(work_queue.get_parked(), BTreeSet::from([1]));
+
+        thread::sleep(Duration::from_millis(60));
+        assert_eq!(work_queue.take_dirty(), vec![1]);
+        assert!(work_queue.get_parked().is_empty());
+    }
+
+    #[test]
+    fn test_wait_for_dirty_wakes_up_on_mark_dirty() {
+        let work_queue = Arc::new(WorkQueue::default());
+        assert!(work_queue
+            .wait_for_dirty(Duration::from_millis(10))
+            .is_empty());
+
+        let handle = {
+            let work_queue = work_queue.clone();
+            thread::spawn(move || work_queue.wait_for_dirty(Duration::from_secs(10)))
+        };
+        work_queue.mark_dirty(7);
+        assert_eq!(handle.join().unwrap(), vec![7]);
+    }
+}
@@ -5,8 +5,8 @@
 //
 // https://github.com/pixlie/PixlieAI/blob/main/LICENSE
 
-use crate::engine::node::{NodeItem, NodeLabel, Payload};
-use crate::engine::{EdgeLabel, Engine, NodeFlags};
+use crate::engine::node::{NodeId, NodeItem, NodeLabel, Payload};
+use crate::engine::{EdgeLabel, EdgeProperties, EdgeSource, Engine, NodeFlags};
 use crate::error::PiError;
 use crate::error::PiResult;
 use crate::services::anthropic::Anthropic;
@@ -66,7 +66,7 @@ impl Classifier {
         let content = engine
             .get_node_ids_connected_with_label(&node.id, &EdgeLabel::ParentOf)?
             .into_iter()
-            .filter_map(|id| match engine.get_node_by_id(&id) {
+            .filter_map(|id| match engine.get_node_without_content(&id) {
                 None => None,
                 Some(node) => match &node.payload {
                     Payload::Text(text) => {
@@ -88,7 +88,7 @@ impl Classifier {
             .ok_or_else(|| PiError::InternalError("No ClassifierSettings found".to_string()))
             .and_then(|id| {
                 match &engine
-                    .get_node_by_id(id)
+                    .get_node_without_content(id)
                     .ok_or_else(|| PiError::InternalError(format!("ClassifierSettings node with id {} not found", id)))?
                     .payload
                 {
@@ -135,6 +135,28 @@ impl Classifier {
         Ok(Anthropic::parse_response::<Classification>(response)?)
     }
 
+    // The classification of only this node, a classification shared with other nodes is not updated
+    fn get_classification_node_id(
+        node: &NodeItem,
+        engine: Arc<&Engine>,
+    ) -> PiResult<Option<NodeId>> {
+        for classification_node_id in
+            engine.get_node_ids_connected_with_label(&node.id, &EdgeLabel::Classifies)?
+        {
+            if engine
+                .get_node_ids_connected_with_label(
+                    &classification_node_id,
+                    &EdgeLabel::ClassifiedFor,
+                )?
+                .iter()
+                .all(|classified_node_id| *classified_node_id == node.id)
+            {
+                return Ok(Some(classification_node_id));
+            }
+        }
+        Ok(None)
+    }
+
     pub fn process(
         node: &NodeItem,
         engine: Arc<&Engine>,
@@ -149,24 +171,36 @@ impl Classifier {
                     } else {
                         log::info!("🔴 WebPage node {} is not relevant.", node.id);
                     }
-                    let classification_node_id = engine
-                        .get_or_add_node(
-                            Payload::Classification(Classification {
-                                is_relevant: parsed_response.is_relevant.clone(),
-                                reason: parsed_response.reason.clone(),
-                                insight_if_classified_as_relevant: parsed_response
-                                    .insight_if_classified_as_relevant
-                                    .clone(),
-                            }),
-                            vec![NodeLabel::Classification, NodeLabel::AddedByAI],
-                            true,
-                            None,
-                        )?
-                        .get_node_id();
-                    engine.add_connection(
-                        (node.id.clone(), classification_node_id),
-                        (EdgeLabel::Classifies, EdgeLabel::ClassifiedFor),
-                    )?;
+                    let classification = Classification {
+                        is_relevant: parsed_response.is_relevant.clone(),
+                        reason: parsed_response.reason.clone(),
+                        insight_if_classified_as_relevant: parsed_response
+                            .insight_if_classified_as_relevant
+                            .clone(),
+                    };
+                    // A web page whose content changed is classified again, we update its
+                    // classification so the earlier one is kept as a version
+                    match Self::get_classification_node_id(node, engine.clone())? {
+                        Some(classification_node_id) => engine.update_node(
+                            &classification_node_id,
+                            Payload::Classification(classification),
+                        )?,
+                        None => {
+                            let classification_node_id = engine
+                                .get_or_add_node(
+                                    Payload::Classification(classification),
+                                    vec![NodeLabel::Classification, NodeLabel::AddedByAI],
+                                    true,
+                                    None,
+                                )?
+                                .get_node_id();
+                            engine.add_connection_with_properties(
+                                (node.id.clone(), classification_node_id),
+                                (EdgeLabel::Classifies, EdgeLabel::ClassifiedFor),
+                                EdgeProperties::new(EdgeSource::AI),
+                            )?;
+                        }
+                    }
                     engine.toggle_flag(&node.id, NodeFlags::IS_PROCESSED)?;
                 }
                 ExternalData::Error(_error) => {}
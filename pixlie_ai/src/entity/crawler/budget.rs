@@ -0,0 +1,401 @@
+// Copyright 2025 Pixlie Web Solutions Pvt. Ltd.
+// Licensed under the GNU General Public License version 3.0;
+// You may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// https://github.com/pixlie/PixlieAI/blob/main/LICENSE
+
+// The crawl budget of a project limits how much it crawls. The budget is kept in its own node,
+// connected to the ProjectSettings node, so project DBs with settings do not need a migration.
+// What has been crawled so far is kept by the engine, see `Engine::update_crawl_state`
+
+use crate::engine::node::{NodeId, NodeLabel, Payload};
+use crate::engine::{EdgeLabel, Engine};
+use crate::entity::web::discovery::LinkDiscovery;
+use crate::error::PiResult;
+use chrono::{DateTime, TimeDelta, Utc};
+use log::info;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashSet};
+use std::sync::Arc;
+use ts_rs::TS;
+use utoipa::ToSchema;
+
+/// Limits on how much a project crawls. Limits which are not set are not checked.
+#[derive(Clone, Default, Deserialize, Serialize, ToSchema, TS)]
+#[ts(export)]
+pub struct CrawlBudget {
+    /// Maximum number of web pages to fetch, including pages which are fetched again.
+    pub max_pages: Option<u32>,
+    /// Maximum number of links followed from a seed link to a web page. Seed links are added
+    /// by the user or found by web search and are at depth 0.
+    pub max_depth: Option<u32>,
+    /// Maximum number of web pages to fetch from any one domain.
+    pub max_pages_per_domain: Option<u32>,
+    /// Maximum minutes to crawl for, from when the first web page was fetched.
+    pub max_duration_in_minutes: Option<u32>,
+    /// Maximum spend on LLM requests, in US cents.
+    pub max_llm_spend_in_cents: Option<u32>,
+}
+
+/// Why a project stopped crawling. No more web pages are fetched till the budget is raised.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize, ToSchema, TS)]
+#[ts(export)]
+pub enum CrawlStopReason {
+    MaxPages,
+    MaxDuration,
+    /// LLM requests are stopped too.
+    MaxLLMSpend,
+}
+
+/// What a project has crawled so far.
+#[derive(Clone, Default, Deserialize, Serialize, ToSchema, TS)]
+#[ts(export)]
+pub struct CrawlState {
+    pub started_at: Option<DateTime<Utc>>,
+    pub count_pages_fetched: u32,
+    pub count_pages_fetched_by_domain: BTreeMap<String, u32>,
+    pub llm_spend_in_cents: f64,
+    pub stop_reason: Option<CrawlStopReason>,
+    pub stopped_at: Option<DateTime<Utc>>,
+}
+
+/// The crawl budget of a project with what it has crawled so far.
+#[derive(Clone, Serialize, ToSchema, TS)]
+#[ts(export)]
+pub struct CrawlStatus {
+    pub budget: CrawlBudget,
+    pub state: CrawlState,
+}
+
+impl CrawlState {
+    // Called when a web page is received, responses which say the page has not changed and
+    // failed requests are not counted
+    pub fn record_page_fetch(&mut self, domain_name: &str) {
+        if self.started_at.is_none() {
+            self.started_at = Some(Utc::now());
+        }
+        self.count_pages_fetched += 1;
+        *self
+            .count_pages_fetched_by_domain
+            .entry(domain_name.to_string())
+            .or_insert(0) += 1;
+    }
+}
+
+impl CrawlBudget {
+    pub fn find(engine: Arc<&Engine>) -> Option<(NodeId, CrawlBudget)> {
+        engine
+            .get_node_ids_with_label(&NodeLabel::CrawlBudget)
+            .iter()
+            .find_map(|node_id| match engine.get_node_without_content(node_id) {
+                Some(node) => match &node.payload {
+                    Payload::CrawlBudget(budget) => Some((node.id, budget.clone())),
+                    _ => None,
+                },
+                None => None,
+            })
+    }
+
+    // A project has one CrawlBudget node, which is updated when the budget changes
+    pub fn save(&self, engine: Arc<&Engine>) -> PiResult<NodeId> {
+        if let Some((node_id, _)) = Self::find(engine.clone()) {
+            engine.update_node(&node_id, Payload::CrawlBudget(self.clone()))?;
+            engine.requeue_links_waiting_for_budget();
+            return Ok(node_id);
+        }
+        let node_id = engine
+            .get_or_add_node(
+                Payload::CrawlBudget(self.clone()),
+                vec![NodeLabel::AddedByUser, NodeLabel::CrawlBudget],
+                true,
+                None,
+            )?
+            .get_node_id();
+        if let Some(project_settings_node_id) = engine
+            .get_node_ids_with_label(&NodeLabel::ProjectSettings)
+            .first()
+        {
+            engine.add_connection(
+                (**project_settings_node_id, node_id),
+                (EdgeLabel::RelatedTo, EdgeLabel::RelatedTo),
+            )?;
+        }
+        engine.requeue_links_waiting_for_budget();
+        Ok(node_id)
+    }
+
+    pub fn get_stop_reason(
+        &self,
+        state: &CrawlState,
+        now: DateTime<Utc>,
+    ) -> Option<CrawlStopReason> {
+        if self
+            .max_pages
+            .is_some_and(|max_pages| state.count_pages_fetched >= max_pages)
+        {
+            return Some(CrawlStopReason::MaxPages);
+        }
+        if let (Some(max_duration_in_minutes), Some(started_at)) =
+            (self.max_duration_in_minutes, state.started_at)
+        {
+            if started_at + TimeDelta::minutes(max_duration_in_minutes as i64) <= now {
+                return Some(CrawlStopReason::MaxDuration);
+            }
+        }
+        if self.is_llm_spend_reached(state) {
+            return Some(CrawlStopReason::MaxLLMSpend);
+        }
+        None
+    }
+
+    pub fn is_llm_spend_reached(&self, state: &CrawlState) -> bool {
+        self.max_llm_spend_in_cents
+            .is_some_and(|max_llm_spend_in_cents| {
+                state.llm_spend_in_cents >= max_llm_spend_in_cents as f64
+            })
+    }
+
+    // Checks the budget against what has been crawled, and records when the crawl stops or
+    // resumes, like when the budget is raised. Returns why the crawl is stopped, if it is
+    pub fn check(engine: Arc<&Engine>) -> PiResult<Option<CrawlStopReason>> {
+        let budget = Self::find(engine.clone())
+            .map(|(_, budget)| budget)
+            .unwrap_or_default();
+        let state = engine.get_crawl_state()?;
+        let stop_reason = budget.get_stop_reason(&state, Utc::now());
+        // The state is only written when the crawl stops or resumes
+        if stop_reason != state.stop_reason {
+            match &stop_reason {
+                Some(stop_reason) => info!("Crawl stopped, reached {:?}", stop_reason),
+                None => info!("Crawl resumed, the crawl budget is not reached"),
+            }
+            engine.update_crawl_state(|state| {
+                state.stopped_at = stop_reason.as_ref().map(|_| Utc::now());
+                state.stop_reason = stop_reason.clone();
+            })?;
+        }
+        Ok(stop_reason)
+    }
+
+    // Limits of the budget which apply to a single link, checked before it is fetched
+    pub fn can_fetch_link(
+        engine: Arc<&Engine>,
+        link_node_id: &NodeId,
+        domain_name: &str,
+    ) -> PiResult<bool> {
+        let Some((_, budget)) = Self::find(engine.clone()) else {
+            return Ok(true);
+        };
+        if let Some(max_pages_per_domain) = budget.max_pages_per_domain {
+            let count_pages_fetched = engine
+                .get_crawl_state()?
+                .count_pages_fetched_by_domain
+                .get(domain_name)
+                .copied()
+                .unwrap_or(0);
+            if count_pages_fetched >= max_pages_per_domain {
+                return Ok(false);
+            }
+        }
+        if let Some(max_depth) = budget.max_depth {
+            return match LinkDiscovery::get(engine.clone(), link_node_id)? {
+                Some(discovery) => Ok(discovery.depth <= max_depth),
+                // Links found before depths were recorded
+                None => Ok(get_depth_within(engine, link_node_id, max_depth)?.is_some()),
+            };
+        }
+        Ok(true)
+    }
+}
+
+// Depth of a link from the nearest seed link, following the web pages the link was found on.
+// None when the link is deeper than `max_depth`, or cannot be reached from any seed link
+fn get_depth_within(
+    engine: Arc<&Engine>,
+    link_node_id: &NodeId,
+    max_depth: u32,
+) -> PiResult<Option<u32>> {
+    let mut visited: HashSet<NodeId> = HashSet::from([*link_node_id]);
+    let mut current_level: Vec<NodeId> = vec![*link_node_id];
+    for depth in 0..=max_depth {
+        if current_level
+            .iter()
+            .any(|node_id| LinkDiscovery::is_seed_link(engine.clone(), node_id))
+        {
+            return Ok(Some(depth));
+        }
+        let mut next_level: Vec<NodeId> = vec![];
+        for node_id in current_level.iter() {
+            // A link is a child of the web pages it was found on, each web page is the content
+            // of the link it was fetched from
+            for web_page_node_id in
+                engine.get_node_ids_connected_with_label(node_id, &EdgeLabel::ChildOf)?
+            {
+                for parent_link_node_id in engine
+                    .get_node_ids_connected_with_label(&web_page_node_id, &EdgeLabel::ContentOf)?
+                {
+                    if visited.insert(parent_link_node_id) {
+                        next_level.push(parent_link_node_id);
+                    }
+                }
+            }
+        }
+        if next_level.is_empty() {
+            break;
+        }
+        current_level = next_level;
+    }
+    Ok(None)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::engine::get_test_engine;
+    use crate::entity::web::link::Link;
+
+    #[test]
+    fn test_stop_reason_of_budget() {
+        let budget = CrawlBudget {
+            max_pages: Some(2),
+            max_duration_in_minutes: Some(60),
+            max_llm_spend_in_cents: Some(50),
+            ..Default::default()
+        };
+        let now = Utc::now();
+        let mut state = CrawlState::default();
+        assert_eq!(budget.get_stop_reason(&state, now), None);
+
+        state.record_page_fetch("pixlie.com");
+        state.record_page_fetch("pixlie.com");
+        assert_eq!(state.count_pages_fetched_by_domain["pixlie.com"], 2);
+        assert_eq!(
+            budget.get_stop_reason(&state, now),
+            Some(CrawlStopReason::MaxPages)
+        );
+
+        state.count_pages_fetched = 1;
+        state.started_at = Some(now - TimeDelta::minutes(61));
+        assert_eq!(
+            budget.get_stop_reason(&state, now),
+            Some(CrawlStopReason::MaxDuration)
+        );
+
+        state.started_at = Some(now);
+        state.llm_spend_in_cents = 50.2;
+        assert_eq!(
+            budget.get_stop_reason(&state, now),
+            Some(CrawlStopReason::MaxLLMSpend)
+        );
+    }
+
+    #[test]
+    fn test_links_beyond_max_depth_or_domain_limit_are_not_fetched() {
+        let test_engine = get_test_engine();
+        let arced_test_engine = Arc::new(&test_engine);
+        // A seed link, whose web page has a link to a page which links to another page
+        let mut link_node_ids: Vec<NodeId> = vec![];
+        for (position, url) in [
+            "https://pixlie.com/",
+            "https://pixlie.com/about",
+            "https://pixlie.com/about/team",
+        ]
+        .iter()
+        .enumerate()
+        {
+            let labels = if position == 0 {
+                vec![NodeLabel::AddedByUser, NodeLabel::Link]
+            } else {
+                vec![NodeLabel::Link]
+            };
+            let link_node_id = Link::add(
+                arced_test_engine.clone(),
+                &url.to_string(),
+                labels,
+                vec![],
+                true,
+            )
+            .unwrap();
+            if let Some(parent_link_node_id) = link_node_ids.last() {
+                let web_page_node_id = test_engine
+                    .get_or_add_node(
+                        Payload::Text(format!("<html>{}</html>", position)),
+                        vec![NodeLabel::Content, NodeLabel::WebPage],
+                        true,
+                        None,
+                    )
+                    .unwrap()
+                    .get_node_id();
+                test_engine
+                    .add_connection(
+                        (*parent_link_node_id, web_page_node_id),
+                        (EdgeLabel::PathOf, EdgeLabel::ContentOf),
+                    )
+                    .unwrap();
+                test_engine
+                    .add_connection(
+                        (web_page_node_id, link_node_id),
+                        (EdgeLabel::ParentOf, EdgeLabel::ChildOf),
+                    )
+                    .unwrap();
+            }
+            link_node_ids.push(link_node_id);
+        }
+
+        let can_fetch = |link_node_id: &NodeId| {
+            CrawlBudget::can_fetch_link(arced_test_engine.clone(), link_node_id, "pixlie.com")
+                .unwrap()
+        };
+        assert!(can_fetch(&link_node_ids[2]));
+        CrawlBudget {
+            max_depth: Some(1),
+            max_pages_per_domain: Some(3),
+            ..Default::default()
+        }
+        .save(arced_test_engine.clone())
+        .unwrap();
+        assert_eq!(
+            get_depth_within(arced_test_engine.clone(), &link_node_ids[1], 1).unwrap(),
+            Some(1)
+        );
+        assert!(can_fetch(&link_node_ids[0]));
+        assert!(can_fetch(&link_node_ids[1]));
+        assert!(!can_fetch(&link_node_ids[2]));
+
+        test_engine
+            .update_crawl_state(|state| {
+                for _ in 0..3 {
+                    state.record_page_fetch("pixlie.com");
+                }
+            })
+            .unwrap();
+        assert!(!can_fetch(&link_node_ids[0]));
+        assert_eq!(CrawlBudget::check(arced_test_engine.clone()).unwrap(), None);
+
+        // Raising the budget resumes a stopped crawl
+        let budget_node_id = CrawlBudget {
+            max_pages: Some(3),
+            ..Default::default()
+        }
+        .save(arced_test_engine.clone())
+        .unwrap();
+        assert_eq!(
+            CrawlBudget::check(arced_test_engine.clone()).unwrap(),
+            Some(CrawlStopReason::MaxPages)
+        );
+        assert!(test_engine.get_crawl_state().unwrap().stopped_at.is_some());
+        CrawlBudget {
+            max_pages: Some(10),
+            ..Default::default()
+        }
+        .save(arced_test_engine.clone())
+        .unwrap();
+        assert_eq!(
+            CrawlBudget::find(arced_test_engine.clone()).unwrap().0,
+            budget_node_id
+        );
+        assert_eq!(CrawlBudget::check(arced_test_engine.clone()).unwrap(), None);
+        assert!(test_engine.get_crawl_state().unwrap().stop_reason.is_none());
+    }
+}
@@ -5,6 +5,8 @@
 //
 // https://github.com/pixlie/PixlieAI/blob/main/LICENSE
 
+pub mod budget;
+
 use crate::engine::node::{NodeItem, NodeLabel, Payload};
 use crate::engine::{EdgeLabel, Engine};
 use crate::error::PiResult;
@@ -36,7 +38,7 @@ impl LLMSchema for CrawlerSettings {
                         .get_node_ids_connected_with_label(&node.id, &EdgeLabel::RelatedTo)?;
                     if project_settings_node_ids.len() > 0 {
                         for project_settings_node_id in project_settings_node_ids {
-                            match engine.get_node_by_id(&project_settings_node_id) {
+                            match engine.get_node_without_content(&project_settings_node_id) {
                                 Some(project_settings_node) => match &project_settings_node.payload
                                 {
                                     Payload::ProjectSettings(project_settings) => {
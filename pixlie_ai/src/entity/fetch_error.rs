@@ -0,0 +1,198 @@
+// Copyright 2025 Pixlie Web Solutions Pvt. Ltd.
+// Licensed under the GNU General Public License version 3.0;
+// You may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// https://github.com/pixlie/PixlieAI/blob/main/LICENSE
+
+use crate::engine::node::{NodeId, NodeLabel, Payload};
+use crate::engine::{EdgeLabel, Engine};
+use crate::error::PiResult;
+use crate::FetchError;
+use chrono::{DateTime, Utc};
+use reqwest::StatusCode;
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+use std::sync::Arc;
+use ts_rs::TS;
+use utoipa::ToSchema;
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize, ToSchema, TS)]
+pub enum FetchErrorKind {
+    Timeout,
+    DNS,
+    TLS,
+    Connection,
+    ClientError, // 4xx response, other than rate limiting
+    ServerError, // 5xx response
+    RobotsDenied,
+    RateLimited, // 429 response
+    Other,
+    DomainUnavailable, // The domain of a link is blocked or its robots.txt could not be fetched
+}
+
+impl FetchErrorKind {
+    pub fn from_status(status: StatusCode) -> FetchErrorKind {
+        if status == StatusCode::TOO_MANY_REQUESTS {
+            FetchErrorKind::RateLimited
+        } else if status.is_client_error() {
+            FetchErrorKind::ClientError
+        } else if status.is_server_error() {
+            FetchErrorKind::ServerError
+        } else {
+            FetchErrorKind::Other
+        }
+    }
+
+    pub fn from_reqwest_error(error: &reqwest::Error) -> FetchErrorKind {
+        if error.is_timeout() {
+            return FetchErrorKind::Timeout;
+        }
+        if let Some(status) = error.status() {
+            return FetchErrorKind::from_status(status);
+        }
+        // reqwest does not tell us about DNS or TLS errors directly,
+        // so we look at the messages of the underlying errors
+        let mut source = error.source();
+        while let Some(inner) = source {
+            let message = inner.to_string().to_lowercase();
+            if message.contains("dns") || message.contains("lookup address") {
+                return FetchErrorKind::DNS;
+            }
+            if message.contains("certificate")
+                || message.contains("tls")
+                || message.contains("ssl")
+                || message.contains("handshake")
+            {
+                return FetchErrorKind::TLS;
+            }
+            source = inner.source();
+        }
+        if error.is_connect() {
+            FetchErrorKind::Connection
+        } else {
+            FetchErrorKind::Other
+        }
+    }
+}
+
+// Why an external data request of a node failed. This is stored as a node which is connected
+// to the node which made the request, so we can show why crawling has stalled
+#[derive(Clone, Deserialize, Serialize, ToSchema, TS)]
+pub struct FetchErrorDetails {
+    pub url: String,
+    pub status: Option<u16>,
+    pub kind: FetchErrorKind,
+    pub message: String,
+    pub failed_at: DateTime<Utc>,
+}
+
+impl FetchErrorDetails {
+    pub fn from_fetch_error(error: &FetchError) -> FetchErrorDetails {
+        FetchErrorDetails {
+            url: error.url.clone(),
+            status: error.status,
+            kind: error.kind.clone(),
+            message: error.error.clone(),
+            failed_at: Utc::now(),
+        }
+    }
+
+    fn is_same_error(&self, other: &FetchErrorDetails) -> bool {
+        self.url == other.url
+            && self.status == other.status
+            && self.kind == other.kind
+            && self.message == other.message
+    }
+
+    pub fn add(
+        engine: Arc<&Engine>,
+        failed_node_id: &NodeId,
+        details: FetchErrorDetails,
+    ) -> PiResult<NodeId> {
+        // When the same error happens again, for example on a retry, we only update its time
+        for error_node_id in
+            engine.get_node_ids_connected_with_label(failed_node_id, &EdgeLabel::FailedWith)?
+        {
+            if let Some(error_node) = engine.get_node_without_content(&error_node_id) {
+                if let Payload::FetchError(existing) = &error_node.payload {
+                    if existing.is_same_error(&details) {
+                        engine.update_node(&error_node_id, Payload::FetchError(details))?;
+                        return Ok(error_node_id);
+                    }
+                }
+            }
+        }
+
+        let error_node_id = engine
+            .get_or_add_node(
+                Payload::FetchError(details),
+                vec![NodeLabel::FetchError],
+                true,
+                None,
+            )?
+            .get_node_id();
+        engine.add_connection(
+            (*failed_node_id, error_node_id),
+            (EdgeLabel::FailedWith, EdgeLabel::FailureOf),
+        )?;
+        Ok(error_node_id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::engine::get_test_engine;
+    use crate::entity::web::link::Link;
+
+    #[test]
+    fn test_fetch_error_kind_from_status() {
+        assert_eq!(
+            FetchErrorKind::from_status(StatusCode::NOT_FOUND),
+            FetchErrorKind::ClientError
+        );
+        assert_eq!(
+            FetchErrorKind::from_status(StatusCode::TOO_MANY_REQUESTS),
+            FetchErrorKind::RateLimited
+        );
+        assert_eq!(
+            FetchErrorKind::from_status(StatusCode::BAD_GATEWAY),
+            FetchErrorKind::ServerError
+        );
+    }
+
+    #[test]
+    fn test_repeated_fetch_error_is_stored_once() {
+        let test_engine = get_test_engine();
+        let arced_test_engine = Arc::new(&test_engine);
+        let link_node_id = Link::add(
+            arced_test_engine.clone(),
+            &"https://pixlie.com/".to_string(),
+            vec![NodeLabel::Link],
+            vec![],
+            true,
+        )
+        .unwrap();
+        let details = FetchErrorDetails {
+            url: "https://pixlie.com/".to_string(),
+            status: Some(503),
+            kind: FetchErrorKind::ServerError,
+            message: "Service unavailable".to_string(),
+            failed_at: Utc::now(),
+        };
+
+        let first_error_node_id =
+            FetchErrorDetails::add(arced_test_engine.clone(), &link_node_id, details.clone())
+                .unwrap();
+        let second_error_node_id =
+            FetchErrorDetails::add(arced_test_engine.clone(), &link_node_id, details).unwrap();
+        assert_eq!(first_error_node_id, second_error_node_id);
+        assert_eq!(
+            test_engine
+                .get_node_ids_connected_with_label(&link_node_id, &EdgeLabel::FailedWith)
+                .unwrap(),
+            vec![first_error_node_id]
+        );
+    }
+}
@@ -18,10 +18,12 @@ pub mod classifier;
 pub mod content;
 pub mod crawler;
 pub mod email;
+pub mod fetch_error;
 pub mod named_entity;
 pub mod objective;
 pub mod pixlie;
 pub mod project_settings;
+pub mod question;
 pub mod search;
 pub mod text;
 pub mod web;
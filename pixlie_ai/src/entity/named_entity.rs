@@ -1,5 +1,5 @@
 use crate::engine::node::{NodeItem, NodeLabel, Payload};
-use crate::engine::{EdgeLabel, Engine};
+use crate::engine::{EdgeLabel, EdgeProperties, EdgeSource, Engine};
 use crate::error::{PiError, PiResult};
 use crate::services::gliner::extract_entities;
 use crate::utils::llm::{clean_ts_type, LLMSchema};
@@ -51,7 +51,7 @@ impl EntityExtraction {
         let content = engine
             .get_node_ids_connected_with_label(&node.id, &EdgeLabel::ParentOf)?
             .into_iter()
-            .filter_map(|id| match engine.get_node_by_id(&id) {
+            .filter_map(|id| match engine.get_node_without_content(&id) {
                 None => None,
                 Some(node) => match &node.payload {
                     Payload::Text(text) => {
@@ -76,7 +76,7 @@ impl EntityExtraction {
         let named_entities_to_extract: Option<Vec<EntityName>> = engine
             .get_node_ids_connected_with_label(&objective_id, &EdgeLabel::Suggests)?
             .iter()
-            .find_map(|node_id| match &engine.get_node_by_id(node_id) {
+            .find_map(|node_id| match &engine.get_node_without_content(node_id) {
                 None => None,
                 Some(node) => {
                     if node.labels.contains(&NodeLabel::NamedEntitiesToExtract) {
@@ -106,9 +106,10 @@ impl EntityExtraction {
                     )?
                     .get_node_id();
 
-                engine.add_connection(
+                engine.add_connection_with_properties(
                     (node.id, extracted_entities),
                     (EdgeLabel::Suggests, EdgeLabel::SuggestedFor),
+                    EdgeProperties::new(EdgeSource::Gliner),
                 )?;
             }
             None => {}
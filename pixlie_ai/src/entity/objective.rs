@@ -34,26 +34,12 @@ impl Objective {
         engine: Arc<&Engine>,
         topic: &String,
     ) -> PiResult<Option<(ArcedNodeItem, ArcedNodeId)>> {
-        let existing_node_ids: Vec<ArcedNodeId> =
-            engine.get_node_ids_with_label(&NodeLabel::Objective);
-        for node_id in existing_node_ids {
-            match engine.get_node_by_id(&node_id) {
-                Some(node) => {
-                    if node.labels.contains(&NodeLabel::Objective) {
-                        match &node.payload {
-                            Payload::Text(payload) => {
-                                if payload == topic {
-                                    return Ok(Some((node, node_id)));
-                                }
-                            }
-                            _ => {}
-                        }
-                    }
-                }
-                None => {}
-            }
-        }
-        Ok(None)
+        Ok(engine
+            .find_node_with_payload(&NodeLabel::Objective, &Payload::Text(topic.to_string()))?
+            .map(|node| {
+                let node_id = Arc::new(node.id);
+                (node, node_id)
+            }))
     }
 
     pub fn process(
@@ -82,7 +68,7 @@ impl Objective {
                         None
                     } else {
                         related_to_node_ids.iter().find_map(|node_id| {
-                            match engine.get_node_by_id(&node_id) {
+                            match engine.get_node_without_content(&node_id) {
                                 Some(node) => match &node.payload {
                                     Payload::ProjectSettings(payload) => {
                                         Some((node_id.clone(), payload.clone()))
@@ -113,7 +99,7 @@ impl Objective {
                         )?;
                     let link_ids_related_to_project_settings = link_ids_related_to_project_settings
                         .iter()
-                        .filter_map(|node_id| match engine.get_node_by_id(node_id) {
+                        .filter_map(|node_id| match engine.get_node_without_content(node_id) {
                             Some(node) => {
                                 if node.labels.contains(&NodeLabel::Link) {
                                     Some(node_id)
@@ -376,7 +362,7 @@ mod tests {
             .unwrap();
 
         let objective_node = arced_test_engine
-            .get_node_by_id(&objective_node_id.get_node_id())
+            .get_node_without_content(&objective_node_id.get_node_id())
             .unwrap();
 
         let llm_schema =
@@ -427,7 +413,7 @@ mod tests {
 //                 // Skip if the content node has already been evaluated
 //                 return None;
 //             }
-//             match engine.get_node_by_id(content_node_id) {
+//             match engine.get_node_without_content(content_node_id) {
 //                 Some(content_node) => {
 //                     return if !content_node.flags.contains(NodeFlags::IS_PROCESSED) {
 //                         // Skip if the content node has not been processed yet
@@ -40,7 +40,7 @@ impl ProjectSettings {
 
             let related_domain_nodes: Vec<(NodeId, NodeItem)> = related_link_node_ids
                 .iter()
-                .filter_map(|node_id| match engine.get_node_by_id(node_id) {
+                .filter_map(|node_id| match engine.get_node_without_content(node_id) {
                     Some(node) => match &node.payload {
                         Payload::Link(_) => {
                             Link::get_domain_node(node_id, engine.clone()).unwrap_or_else(|_| None)
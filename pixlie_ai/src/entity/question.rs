@@ -0,0 +1,393 @@
+// Copyright 2025 Pixlie Web Solutions Pvt. Ltd.
+// Licensed under the GNU General Public License version 3.0;
+// You may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// https://github.com/pixlie/PixlieAI/blob/main/LICENSE
+
+use crate::engine::node::{NodeId, NodeItem, NodeLabel, Payload};
+use crate::engine::ranking::RankingWeights;
+use crate::engine::{EdgeLabel, EdgeProperties, EdgeSource, Engine, NodeFlags};
+use crate::entity::web::link::Link;
+use crate::error::{PiError, PiResult};
+use crate::services::anthropic::Anthropic;
+use crate::utils::llm::{LLMPrompt, LLMProvider, LLMSchema};
+use crate::ExternalData;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use ts_rs::TS;
+use utoipa::ToSchema;
+
+/// A content node which an answer is based on, with the URL of the web page it is from.
+#[derive(Clone, Deserialize, Serialize, ToSchema, TS)]
+#[ts(export)]
+pub struct Citation {
+    pub node_id: NodeId,
+    pub url: Option<String>,
+}
+
+/// An answer to a question about a project, written by the LLM from the content in the graph.
+#[derive(Clone, Deserialize, Serialize, ToSchema, TS)]
+#[ts(export)]
+pub struct Answer {
+    pub text: String,
+    /// The sources which the answer is based on
+    pub citations: Vec<Citation>,
+}
+
+#[derive(Clone, Serialize, ToSchema, TS)]
+#[ts(export)]
+pub enum QuestionStatus {
+    /// Waiting for the LLM to answer
+    Pending,
+    Answered,
+    /// The LLM could not be asked or its response could not be used
+    Failed,
+}
+
+// What we ask the LLM to respond with
+#[derive(Deserialize, TS)]
+pub struct AnswerResponse {
+    pub answer: String,
+    pub numbers_of_sources_used_in_answer: Vec<usize>,
+}
+
+impl LLMSchema for AnswerResponse {}
+
+#[derive(Serialize)]
+struct Source {
+    number: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    url: Option<String>,
+    text: String,
+}
+
+// The question with the content it should be answered from
+#[derive(Serialize)]
+struct QuestionWithSources {
+    question: String,
+    sources: Vec<Source>,
+}
+
+impl LLMPrompt for QuestionWithSources {
+    fn get_prompt(
+        &self,
+        llm_response_schema: &String,
+        _node: &NodeItem,
+        _engine: Arc<&Engine>,
+    ) -> PiResult<String> {
+        Ok(format!(
+            r#"I am a software bot and I have collected content from the web for a project.
+Here is a question about the project, with numbered sources from the content:
+```json
+{}
+```
+
+Answer the question using only these sources. If they do not have the answer, say so.
+Using the following schema, please respond in JSON with `AnswerResponse`.
+```typescript
+{}
+```
+"#,
+            serde_json::to_string(self)?,
+            llm_response_schema
+        ))
+    }
+}
+
+pub struct Question;
+
+impl Question {
+    // Adds a question and connects it to the content nodes which are most relevant to it,
+    // the question is answered when it is processed
+    pub fn add(engine: Arc<&Engine>, question: &str, max_sources: usize) -> PiResult<NodeId> {
+        // Any of the words of the question may match, lowercased so they are not operators
+        let query = question
+            .split(|x: char| !x.is_alphanumeric())
+            .filter(|word| !word.is_empty())
+            .map(|word| word.to_lowercase())
+            .collect::<Vec<String>>()
+            .join(" OR ");
+        let results = engine.search_text_hybrid(&query, max_sources, &RankingWeights::default())?;
+
+        // Each ask is a new question, since the content may have changed since it was last asked.
+        // The question is processed only once the batch with its citations is written
+        let question_node_id = engine.batch_writes(|| {
+            let question_node_id = engine.add_node(
+                Payload::Text(question.to_string()),
+                vec![NodeLabel::AddedByUser, NodeLabel::Question],
+            )?;
+            for result in results {
+                engine.add_connection_with_properties(
+                    (question_node_id, result.node_id),
+                    (EdgeLabel::Cites, EdgeLabel::CitedBy),
+                    EdgeProperties {
+                        weight: Some(result.score),
+                        ..EdgeProperties::new(EdgeSource::Engine)
+                    },
+                )?;
+            }
+            Ok(question_node_id)
+        })?;
+        Ok(question_node_id)
+    }
+
+    // The content nodes of a question or an answer, in the order they were cited
+    pub fn get_citations(node_id: &NodeId, engine: Arc<&Engine>) -> PiResult<Vec<Citation>> {
+        let mut citations: Vec<Citation> = vec![];
+        for cited_node_id in engine.get_node_ids_connected_with_label(node_id, &EdgeLabel::Cites)? {
+            let url = match engine.find_web_page_and_link_of(&cited_node_id)? {
+                (_, Some(link_node_id)) => Link::get_url(&link_node_id, engine.clone())?,
+                _ => None,
+            };
+            citations.push(Citation {
+                node_id: cited_node_id,
+                url,
+            });
+        }
+        Ok(citations)
+    }
+
+    pub fn get_answer(
+        question_node_id: &NodeId,
+        engine: Arc<&Engine>,
+    ) -> PiResult<Option<(NodeId, Answer)>> {
+        for answer_node_id in
+            engine.get_node_ids_connected_with_label(question_node_id, &EdgeLabel::AnsweredBy)?
+        {
+            if let Some(answer_node) = engine.get_node_without_content(&answer_node_id) {
+                if let Payload::Answer(answer) = &answer_node.payload {
+                    return Ok(Some((answer_node_id, answer.clone())));
+                }
+            }
+        }
+        Ok(None)
+    }
+
+    pub fn get_status(question_node_id: &NodeId, engine: Arc<&Engine>) -> PiResult<QuestionStatus> {
+        if Self::get_answer(question_node_id, engine.clone())?.is_some() {
+            return Ok(QuestionStatus::Answered);
+        }
+        match engine.get_node_without_content(question_node_id) {
+            Some(node)
+                if node.flags.contains(NodeFlags::IS_PROCESSED)
+                    || node.flags.contains(NodeFlags::GAVE_UP) =>
+            {
+                Ok(QuestionStatus::Failed)
+            }
+            Some(_) => Ok(QuestionStatus::Pending),
+            None => Err(PiError::CrudNotFoundError(
+                "Question".to_string(),
+                question_node_id.to_string(),
+            )),
+        }
+    }
+
+    fn get_prompt(node: &NodeItem, engine: Arc<&Engine>) -> PiResult<String> {
+        let Payload::Text(question) = &node.payload else {
+            return Err(PiError::GraphError(
+                "Expected a Question node with Payload::Text".to_string(),
+            ));
+        };
+        let mut sources: Vec<Source> = vec![];
+        for (index, citation) in Self::get_citations(&node.id, engine.clone())?
+            .into_iter()
+            .enumerate()
+        {
+            if let Some(Payload::Text(text)) = engine
+                .get_node_without_content(&citation.node_id)
+                .map(|cited_node| cited_node.payload.clone())
+            {
+                sources.push(Source {
+                    number: index + 1,
+                    url: citation.url,
+                    text,
+                });
+            }
+        }
+        QuestionWithSources {
+            question: question.clone(),
+            sources,
+        }
+        .get_prompt(
+            &AnswerResponse::get_schema_for_llm(node, engine.clone())?,
+            node,
+            engine,
+        )
+    }
+
+    // Saves the answer of the LLM, connected to the question and to the sources it used
+    fn add_answer(
+        node: &NodeItem,
+        engine: Arc<&Engine>,
+        response: AnswerResponse,
+    ) -> PiResult<NodeId> {
+        let sources = Self::get_citations(&node.id, engine.clone())?;
+        let citations: Vec<Citation> = response
+            .numbers_of_sources_used_in_answer
+            .iter()
+            .filter_map(|number| sources.get(number.checked_sub(1)?).cloned())
+            .collect();
+        let answer_node_id = engine
+            .get_or_add_node(
+                Payload::Answer(Answer {
+                    text: response.answer,
+                    citations: citations.clone(),
+                }),
+                vec![NodeLabel::Answer, NodeLabel::AddedByAI],
+                true,
+                None,
+            )?
+            .get_node_id();
+        engine.add_connection_with_properties(
+            (answer_node_id, node.id),
+            (EdgeLabel::Answers, EdgeLabel::AnsweredBy),
+            EdgeProperties::new(EdgeSource::AI),
+        )?;
+        for citation in citations {
+            engine.add_connection_with_properties(
+                (answer_node_id, citation.node_id),
+                (EdgeLabel::Cites, EdgeLabel::CitedBy),
+                EdgeProperties::new(EdgeSource::AI),
+            )?;
+        }
+        Ok(answer_node_id)
+    }
+
+    pub fn process(
+        node: &NodeItem,
+        engine: Arc<&Engine>,
+        data_from_previous_request: Option<ExternalData>,
+    ) -> PiResult<()> {
+        match data_from_previous_request {
+            Some(ExternalData::Response(response)) => {
+                let response = Anthropic::parse_response::<AnswerResponse>(&response.contents);
+                // Questions are asked once, a question which could not be answered is failed
+                engine.toggle_flag(&node.id, NodeFlags::IS_PROCESSED)?;
+                Self::add_answer(node, engine, response?)?;
+            }
+            Some(ExternalData::Error(_)) => {
+                engine.toggle_flag(&node.id, NodeFlags::IS_PROCESSED)?;
+            }
+            None => {
+                let llm_prompt = Self::get_prompt(node, engine.clone())?;
+                let engine_request = Anthropic::get_request(&llm_prompt, node.id)?;
+                engine.fetch_api(engine_request)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::engine::{add_content_node, get_test_engine};
+    use crate::FetchResponse;
+    use reqwest::header::HeaderMap;
+
+    #[test]
+    fn test_question_is_answered_with_citations() {
+        let engine = get_test_engine();
+        let arced_engine = Arc::new(&engine);
+        let link_node_id = Link::add(
+            arced_engine.clone(),
+            &"https://pixlie.com/about".to_string(),
+            vec![NodeLabel::Link],
+            vec![],
+            true,
+        )
+        .unwrap();
+        let web_page_node_id = engine
+            .get_or_add_node(
+                Payload::Text("<html></html>".to_string()),
+                vec![NodeLabel::WebPage],
+                true,
+                None,
+            )
+            .unwrap()
+            .get_node_id();
+        engine
+            .add_connection(
+                (link_node_id, web_page_node_id),
+                (EdgeLabel::PathOf, EdgeLabel::ContentOf),
+            )
+            .unwrap();
+        let founded = add_content_node(&engine, web_page_node_id, "Pixlie was founded in 2024");
+        let graph = add_content_node(&engine, web_page_node_id, "Pixlie stores a graph");
+        add_content_node(&engine, web_page_node_id, "Nothing to see here");
+
+        let question_node_id =
+            Question::add(arced_engine.clone(), "When was Pixlie founded?", 5).unwrap();
+        let sources = Question::get_citations(&question_node_id, arced_engine.clone()).unwrap();
+        assert_eq!(sources.len(), 2);
+        assert_eq!(sources[0].node_id, founded);
+        assert_eq!(sources[0].url.as_deref(), Some("https://pixlie.com/about"));
+        assert!(matches!(
+            Question::get_status(&question_node_id, arced_engine.clone()).unwrap(),
+            QuestionStatus::Pending
+        ));
+
+        let question_node = engine.get_node_without_content(&question_node_id).unwrap();
+        let prompt = Question::get_prompt(&question_node, arced_engine.clone()).unwrap();
+        assert!(prompt.contains("When was Pixlie founded?"));
+        assert!(prompt.contains(
+            r#"{"number":1,"url":"https://pixlie.com/about","text":"Pixlie was founded in 2024"}"#
+        ));
+        assert!(prompt.contains("type AnswerResponse"));
+
+        let contents = serde_json::json!({
+            "id": "msg",
+            "content": [{
+                "type": "text",
+                "text": r#"{"answer": "In 2024 [1]", "numbers_of_sources_used_in_answer": [1, 7]}"#,
+            }],
+        })
+        .to_string();
+        Question::process(
+            &question_node,
+            arced_engine.clone(),
+            Some(ExternalData::Response(FetchResponse {
+                project_id: engine.get_project_id().to_string(),
+                node_id: question_node_id,
+                url: "https://api.anthropic.com/v1/messages".to_string(),
+                status: 200,
+                headers: HeaderMap::new(),
+                contents,
+            })),
+        )
+        .unwrap();
+
+        let (answer_node_id, answer) =
+            Question::get_answer(&question_node_id, arced_engine.clone())
+                .unwrap()
+                .unwrap();
+        assert_eq!(answer.text, "In 2024 [1]");
+        // Numbers which are not of a source are left out
+        assert_eq!(answer.citations.len(), 1);
+        assert_eq!(answer.citations[0].node_id, founded);
+        assert_eq!(
+            engine
+                .get_node_ids_connected_with_label(&answer_node_id, &EdgeLabel::Cites)
+                .unwrap(),
+            vec![founded]
+        );
+        assert!(!engine
+            .get_node_ids_connected_with_label(&graph, &EdgeLabel::CitedBy)
+            .unwrap()
+            .contains(&answer_node_id));
+        assert!(matches!(
+            Question::get_status(&question_node_id, arced_engine.clone()).unwrap(),
+            QuestionStatus::Answered
+        ));
+
+        // A question asked again is answered again
+        let asked_again_node_id =
+            Question::add(arced_engine.clone(), "When was Pixlie founded?", 5).unwrap();
+        assert_ne!(asked_again_node_id, question_node_id);
+        assert!(matches!(
+            Question::get_status(&asked_again_node_id, arced_engine).unwrap(),
+            QuestionStatus::Pending
+        ));
+    }
+}
@@ -1,4 +1,4 @@
-use crate::engine::node::{ArcedNodeId, ArcedNodeItem, NodeId, NodeItem, NodeLabel, Payload};
+use crate::engine::node::{ArcedNodeItem, NodeId, NodeItem, NodeLabel, Payload};
 use crate::engine::Engine;
 use crate::error::{PiError, PiResult};
 use std::sync::Arc;
@@ -10,22 +10,10 @@ impl SavedSearch {
         engine: Arc<&Engine>,
         search_term: &str,
     ) -> PiResult<Option<ArcedNodeItem>> {
-        let existing_node_ids: Vec<ArcedNodeId> =
-            engine.get_node_ids_with_label(&NodeLabel::SearchTerm);
-        for node_id in existing_node_ids {
-            match engine.get_node_by_id(&node_id) {
-                Some(node) => match &node.payload {
-                    Payload::Text(text) => {
-                        if text == search_term {
-                            return Ok(Some(node));
-                        }
-                    }
-                    _ => {}
-                },
-                None => {}
-            }
-        }
-        Ok(None)
+        engine.find_node_with_payload(
+            &NodeLabel::SearchTerm,
+            &Payload::Text(search_term.to_string()),
+        )
     }
 
     pub(crate) fn query(
@@ -44,41 +32,15 @@ impl SavedSearch {
             )));
         }
 
-        // We search all the content nodes in the engine for the search term
-        let results: Vec<Option<NodeItem>> = match &node.payload {
-            Payload::Text(search_term) => engine.map_nodes(|id, node| match node.payload {
-                Payload::Text(ref text) => {
-                    if node.labels.contains(&NodeLabel::Title)
-                        || node.labels.contains(&NodeLabel::Heading)
-                        || node.labels.contains(&NodeLabel::Paragraph)
-                    {
-                        if text.to_lowercase().contains(&search_term.to_lowercase()) {
-                            Some(NodeItem {
-                                id: **id,
-                                labels: node.labels.clone(),
-                                payload: node.payload.clone(),
-                                flags: node.flags.clone(),
-                                written_at: node.written_at.clone(),
-                            })
-                        } else {
-                            None
-                        }
-                    } else {
-                        None
-                    }
-                }
-                _ => None,
-            })?,
-            _ => {
-                vec![]
-            }
-        };
-        Ok(results
-            .iter()
-            .filter_map(|x| match x {
-                Some(node) => Some(node.clone()),
-                None => None,
-            })
-            .collect())
+        // We search the full-text index of the content nodes for the search term
+        match &node.payload {
+            Payload::Text(search_term) => Ok(engine
+                .search_text(search_term, usize::MAX)?
+                .iter()
+                .filter_map(|result| engine.get_node_without_content(&result.node_id))
+                .map(|node| node.as_ref().clone())
+                .collect()),
+            _ => Ok(vec![]),
+        }
     }
 }
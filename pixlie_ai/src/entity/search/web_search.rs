@@ -48,7 +48,7 @@ impl WebSearch {
                                     ));
                                 }
                                 let settings_node_id = *settings_node_ids[0];
-                                match engine.get_node_by_id(&settings_node_id) {
+                                match engine.get_node_without_content(&settings_node_id) {
                                     Some(settings_node) => {
                                         if settings_node
                                             .labels
@@ -128,7 +128,7 @@ impl WebSearch {
                 //                     "No ProjectSettings node found".to_string(),
                 //                 ));
                 //             }
-                //             match engine.get_node_by_id(&settings_node_ids[0]) {
+                //             match engine.get_node_without_content(&settings_node_ids[0]) {
                 //                 Some(settings_node) => {
                 //                     if settings_node.labels.contains(&NodeLabel::ProjectSettings) {
                 //                         match &settings_node.payload {
@@ -0,0 +1,253 @@
+// Copyright 2025 Pixlie Web Solutions Pvt. Ltd.
+// Licensed under the GNU General Public License version 3.0;
+// You may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// https://github.com/pixlie/PixlieAI/blob/main/LICENSE
+
+// Links found by the scraper are recorded with their depth from the seed links, which are added
+// by the user or found by web search, and the link whose web page they were found on.
+// This is kept by the engine next to the Link node, like the fetch state of a link, so project DBs
+// do not need a migration. See `Engine::get_link_discovery`
+
+use crate::engine::node::{NodeId, NodeLabel};
+use crate::engine::{EdgeLabel, Engine};
+use crate::entity::web::link::Link;
+use crate::error::PiResult;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashSet, VecDeque};
+use std::sync::Arc;
+use ts_rs::TS;
+use utoipa::ToSchema;
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct LinkDiscovery {
+    pub depth: u32,
+    pub discovered_from: Option<NodeId>, // None for seed links
+}
+
+/// A link on the path by which a link or web page was reached.
+#[derive(Clone, Serialize, ToSchema, TS)]
+#[ts(export)]
+pub struct DiscoveryStep {
+    pub link_node_id: NodeId,
+    pub url: Option<String>,
+    /// Number of links followed from the seed link, which is at depth 0.
+    pub depth: u32,
+}
+
+/// How a link or web page was reached from a seed link, added by the user or found by web search.
+#[derive(Clone, Serialize, ToSchema, TS)]
+#[ts(export)]
+pub struct DiscoveryPath {
+    pub node_id: NodeId,
+    /// None when the link was found before depths were recorded.
+    pub depth: Option<u32>,
+    /// Links followed from the seed link, the last one is the link itself.
+    pub steps: Vec<DiscoveryStep>,
+}
+
+impl LinkDiscovery {
+    pub fn is_seed_link(engine: Arc<&Engine>, node_id: &NodeId) -> bool {
+        engine
+            .get_node_without_content(node_id)
+            .is_some_and(|node| {
+                node.labels.contains(&NodeLabel::AddedByUser)
+                    || node.labels.contains(&NodeLabel::AddedByWebSearch)
+            })
+    }
+
+    pub fn get(engine: Arc<&Engine>, link_node_id: &NodeId) -> PiResult<Option<LinkDiscovery>> {
+        // A link found by the scraper may be added by the user later, it is then a seed link
+        if Self::is_seed_link(engine.clone(), link_node_id) {
+            return Ok(Some(LinkDiscovery {
+                depth: 0,
+                discovered_from: None,
+            }));
+        }
+        engine.get_link_discovery(link_node_id)
+    }
+
+    // Records that a link was found on the web page of another link. The shortest path is kept,
+    // and when a link is found closer to a seed link, the links found from it are updated too
+    pub fn record(
+        engine: Arc<&Engine>,
+        link_node_id: &NodeId,
+        discovered_from: &NodeId,
+    ) -> PiResult<()> {
+        let Some(parent) = Self::get(engine.clone(), discovered_from)? else {
+            // The depth of the parent is not known, so neither is the depth of this link
+            return Ok(());
+        };
+        let mut pending: VecDeque<(NodeId, LinkDiscovery)> = VecDeque::from([(
+            *link_node_id,
+            LinkDiscovery {
+                depth: parent.depth + 1,
+                discovered_from: Some(*discovered_from),
+            },
+        )]);
+        while let Some((node_id, discovery)) = pending.pop_front() {
+            if let Some(existing) = Self::get(engine.clone(), &node_id)? {
+                if existing.depth <= discovery.depth {
+                    continue;
+                }
+            }
+            engine.set_link_discovery(&node_id, &discovery)?;
+            for child_link_node_id in get_child_link_node_ids(engine.clone(), &node_id)? {
+                pending.push_back((
+                    child_link_node_id,
+                    LinkDiscovery {
+                        depth: discovery.depth + 1,
+                        discovered_from: Some(node_id),
+                    },
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    // The path from a seed link to a link, or to the link a web page was fetched from
+    pub fn get_path(engine: Arc<&Engine>, node_id: &NodeId) -> PiResult<DiscoveryPath> {
+        let link_node_id = match engine.get_node_without_content(node_id) {
+            Some(node) if node.labels.contains(&NodeLabel::WebPage) => engine
+                .get_node_ids_connected_with_label(node_id, &EdgeLabel::ContentOf)?
+                .first()
+                .copied(),
+            Some(node) if node.labels.contains(&NodeLabel::Link) => Some(*node_id),
+            _ => None,
+        };
+        let mut steps: Vec<DiscoveryStep> = vec![];
+        let mut visited: HashSet<NodeId> = HashSet::new();
+        let mut current_link_node_id = link_node_id;
+        while let Some(link_node_id) = current_link_node_id {
+            if !visited.insert(link_node_id) {
+                break;
+            }
+            let Some(discovery) = Self::get(engine.clone(), &link_node_id)? else {
+                break;
+            };
+            steps.push(DiscoveryStep {
+                link_node_id,
+                url: Link::get_url(&link_node_id, engine.clone())?,
+                depth: discovery.depth,
+            });
+            current_link_node_id = discovery.discovered_from;
+        }
+        steps.reverse();
+        Ok(DiscoveryPath {
+            node_id: *node_id,
+            depth: steps.last().map(|step| step.depth),
+            steps,
+        })
+    }
+}
+
+// Links found on the web page of a link
+fn get_child_link_node_ids(engine: Arc<&Engine>, link_node_id: &NodeId) -> PiResult<Vec<NodeId>> {
+    let Some(web_page_node_id) = Link::get_web_page_node_id(link_node_id, engine.clone())? else {
+        return Ok(vec![]);
+    };
+    Ok(engine
+        .get_node_ids_connected_with_label(&web_page_node_id, &EdgeLabel::ParentOf)?
+        .into_iter()
+        .filter(|node_id| {
+            engine
+                .get_node_without_content(node_id)
+                .is_some_and(|node| node.labels.contains(&NodeLabel::Link))
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::engine::get_test_engine;
+    use crate::engine::node::Payload;
+
+    fn add_web_page_with_links(engine: Arc<&Engine>, link_node_id: &NodeId, links: Vec<NodeId>) {
+        let web_page_node_id = engine
+            .get_or_add_node(
+                Payload::Text(format!("<html>{}</html>", link_node_id)),
+                vec![NodeLabel::Content, NodeLabel::WebPage],
+                true,
+                None,
+            )
+            .unwrap()
+            .get_node_id();
+        engine
+            .add_connection(
+                (*link_node_id, web_page_node_id),
+                (EdgeLabel::PathOf, EdgeLabel::ContentOf),
+            )
+            .unwrap();
+        for child_link_node_id in links {
+            engine
+                .add_connection(
+                    (web_page_node_id, child_link_node_id),
+                    (EdgeLabel::ParentOf, EdgeLabel::ChildOf),
+                )
+                .unwrap();
+            LinkDiscovery::record(engine.clone(), &child_link_node_id, link_node_id).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_shortest_discovery_path_is_kept() {
+        let test_engine = get_test_engine();
+        let arced_test_engine = Arc::new(&test_engine);
+        let add_link = |url: &str, labels: Vec<NodeLabel>| {
+            Link::add(
+                arced_test_engine.clone(),
+                &url.to_string(),
+                labels,
+                vec![],
+                true,
+            )
+            .unwrap()
+        };
+        let seed = add_link(
+            "https://pixlie.com/",
+            vec![NodeLabel::AddedByUser, NodeLabel::Link],
+        );
+        let blog = add_link("https://pixlie.com/blog", vec![NodeLabel::Link]);
+        let post = add_link("https://pixlie.com/blog/post", vec![NodeLabel::Link]);
+        let author = add_link("https://pixlie.com/team/author", vec![NodeLabel::Link]);
+
+        // The seed links to the blog, which links to a post, which links to its author
+        add_web_page_with_links(arced_test_engine.clone(), &seed, vec![blog]);
+        add_web_page_with_links(arced_test_engine.clone(), &blog, vec![post]);
+        add_web_page_with_links(arced_test_engine.clone(), &post, vec![author]);
+        let path = LinkDiscovery::get_path(arced_test_engine.clone(), &author).unwrap();
+        assert_eq!(path.depth, Some(3));
+        assert_eq!(
+            path.steps
+                .iter()
+                .map(|step| step.link_node_id)
+                .collect::<Vec<NodeId>>(),
+            vec![seed, blog, post, author]
+        );
+        assert_eq!(path.steps[0].url, Some("https://pixlie.com/".to_string()));
+
+        // A web search result links to the post, so the post and its author are closer to a seed
+        let search_result = add_link(
+            "https://pixlie.com/search",
+            vec![NodeLabel::AddedByWebSearch, NodeLabel::Link],
+        );
+        add_web_page_with_links(arced_test_engine.clone(), &search_result, vec![post]);
+        assert_eq!(
+            LinkDiscovery::get(arced_test_engine.clone(), &author).unwrap(),
+            Some(LinkDiscovery {
+                depth: 2,
+                discovered_from: Some(post)
+            })
+        );
+        let path = LinkDiscovery::get_path(arced_test_engine.clone(), &author).unwrap();
+        assert_eq!(
+            path.steps
+                .iter()
+                .map(|step| step.link_node_id)
+                .collect::<Vec<NodeId>>(),
+            vec![search_result, post, author]
+        );
+    }
+}
@@ -1,5 +1,6 @@
 use crate::engine::node::{ArcedNodeItem, NodeId, NodeItem, NodeLabel, Payload};
 use crate::engine::{EdgeLabel, Engine, NodeFlags};
+use crate::entity::fetch_error::FetchErrorKind;
 use crate::error::{PiError, PiResult};
 use crate::{ExternalData, FetchRequest};
 use log::error;
@@ -18,28 +19,10 @@ impl Domain {
         find_type: FindDomainOf,
     ) -> PiResult<Option<ArcedNodeItem>> {
         match find_type {
-            FindDomainOf::DomainName(domain_name) => {
-                // TODO: Implement and use better graph query API: https://github.com/pixlie/PixlieAI/issues/90, point 2
-                let domain_node_ids = engine.get_node_ids_with_label(&NodeLabel::DomainName);
-                for node_id in domain_node_ids {
-                    match engine.get_node_by_id(&node_id) {
-                        Some(node) => {
-                            if node.labels.contains(&NodeLabel::DomainName) {
-                                match node.payload {
-                                    Payload::Text(ref domain) => {
-                                        if domain == domain_name {
-                                            return Ok(Some(node));
-                                        }
-                                    }
-                                    _ => {}
-                                }
-                            }
-                        }
-                        None => {}
-                    }
-                }
-                Ok(None)
-            }
+            FindDomainOf::DomainName(domain_name) => engine.find_node_with_payload(
+                &NodeLabel::DomainName,
+                &Payload::Text(domain_name.to_string()),
+            ),
             FindDomainOf::Node(node_id) => {
                 // TODO: Implement and use better graph query API: https://github.com/pixlie/PixlieAI/issues/90, point 1
                 let belongs_to =
@@ -50,7 +33,7 @@ impl Domain {
                         node_id
                     ))
                 })?;
-                match engine.get_node_by_id(first_belongs_to) {
+                match engine.get_node_without_content(first_belongs_to) {
                     Some(node) => {
                         if node.labels.contains(&NodeLabel::DomainName) {
                             match node.payload {
@@ -115,21 +98,34 @@ impl Domain {
                     )?;
                     engine.toggle_flag(&node.id, NodeFlags::IS_PROCESSED)?;
                 }
-                ExternalData::Error(_error) => {
-                    // TODO: Make sure to not save timeout or other errors as blank robots.txt
-                    let content_node_id = engine
-                        .get_or_add_node(
-                            Payload::Text("".to_string()),
-                            vec![NodeLabel::RobotsTxt],
-                            true,
-                            None,
-                        )?
-                        .get_node_id();
-                    engine.add_connection(
-                        (node.id.clone(), content_node_id),
-                        (EdgeLabel::OwnerOf, EdgeLabel::BelongsTo),
-                    )?;
-                    engine.toggle_flag(&node.id, NodeFlags::IS_PROCESSED)?;
+                ExternalData::Error(error) => {
+                    if error.kind == FetchErrorKind::ClientError
+                        && error
+                            .status
+                            .is_some_and(|status| status == 404 || status == 410)
+                    {
+                        // The domain has no robots.txt, which is the same as an empty one
+                        let content_node_id = engine
+                            .get_or_add_node(
+                                Payload::Text("".to_string()),
+                                vec![NodeLabel::RobotsTxt],
+                                true,
+                                None,
+                            )?
+                            .get_node_id();
+                        engine.add_connection(
+                            (node.id, content_node_id),
+                            (EdgeLabel::OwnerOf, EdgeLabel::BelongsTo),
+                        )?;
+                        engine.toggle_flag(&node.id, NodeFlags::IS_PROCESSED)?;
+                    } else {
+                        // We do not know what is in robots.txt, so we try again later
+                        error!(
+                            "Error fetching robots.txt for domain node {}: {}",
+                            node.id, error.error
+                        );
+                        engine.record_node_error(&node.id)?;
+                    }
                 }
             },
             None => engine.fetch(FetchRequest::new(node.id, "/robots.txt"))?,
@@ -3,9 +3,11 @@ use crate::engine::node::{
 };
 use crate::engine::{EdgeLabel, Engine, NodeFlags};
 use crate::entity::web::domain::{Domain, FindDomainOf};
+use crate::entity::web::recrawl::{get_content_hash, ContentChange, LinkFetch};
 use crate::error::{PiError, PiResult};
 use crate::{ExternalData, FetchRequest};
 use log::{debug, error};
+use reqwest::StatusCode;
 use serde::{Deserialize, Serialize};
 use std::ops::Deref;
 use std::sync::Arc;
@@ -28,6 +30,24 @@ impl Link {
         domain_extra_labels: Vec<NodeLabel>,
         should_add_new_domain: bool,
     ) -> PiResult<NodeId> {
+        Ok(Self::get_or_add(
+            engine,
+            url,
+            labels,
+            domain_extra_labels,
+            should_add_new_domain,
+        )?
+        .get_node_id())
+    }
+
+    // Like `add`, but tells if the link was already in the graph
+    pub fn get_or_add(
+        engine: Arc<&Engine>,
+        url: &String,
+        labels: Vec<NodeLabel>,
+        domain_extra_labels: Vec<NodeLabel>,
+        should_add_new_domain: bool,
+    ) -> PiResult<ExistingOrNewNodeId> {
         // When we add a link to the graph, we check:
         // - if the domain already exists (no duplicates)
         // - if the path already exists
@@ -56,22 +76,20 @@ impl Link {
             )?
             .get_node_id();
 
-        let link_node_id = engine
-            .get_or_add_node(
-                Payload::Link(Link {
-                    path: parsed.path().to_string(),
-                    query: parsed.query().map(|x| x.to_string()),
-                    ..Default::default()
-                }),
-                labels,
-                true,
-                // Engine will find possible existing Link rooted to this domain
-                Some(domain_node_id),
-            )?
-            .get_node_id();
+        let link_node_id = engine.get_or_add_node(
+            Payload::Link(Link {
+                path: parsed.path().to_string(),
+                query: parsed.query().map(|x| x.to_string()),
+                ..Default::default()
+            }),
+            labels,
+            true,
+            // Engine will find possible existing Link rooted to this domain
+            Some(domain_node_id),
+        )?;
 
         engine.add_connection(
-            (domain_node_id, link_node_id.clone()),
+            (domain_node_id, link_node_id.get_node_id()),
             (EdgeLabel::OwnerOf, EdgeLabel::BelongsTo),
         )?;
         Ok(link_node_id)
@@ -92,7 +110,7 @@ impl Link {
         find_related_to: Option<NodeId>,
     ) -> PiResult<Option<ArcedNodeItem>> {
         let domain_node: ArcedNodeItem = match find_related_to {
-            Some(node_id) => match engine.get_node_by_id(&node_id) {
+            Some(node_id) => match engine.get_node_without_content(&node_id) {
                 Some(node) => node,
                 None => {
                     error!("Cannot find node with ID {} for URL {}", node_id, url);
@@ -177,7 +195,7 @@ impl Link {
                 };
 
                 for node_id in connected_node_ids {
-                    if let Some(node) = engine.get_node_by_id(&node_id) {
+                    if let Some(node) = engine.get_node_without_content(&node_id) {
                         match &node.payload {
                             Payload::Link(link) => {
                                 if link.path == path && link.query == query {
@@ -220,25 +238,55 @@ impl Link {
                 ExternalData::Response(response) => {
                     // We have received the contents of the URL from the previous request
                     debug!("Fetched HTML from {}", &url);
-                    let content_node_id = match engine.get_or_add_node(
-                        Payload::Text(response.contents),
-                        vec![NodeLabel::Content, NodeLabel::WebPage],
-                        true,
-                        None,
-                    ) {
-                        Ok(existing_or_new_node_id) => match existing_or_new_node_id {
-                            ExistingOrNewNodeId::Existing(id) => id,
-                            ExistingOrNewNodeId::New(id) => id,
-                        },
-                        Err(err) => {
-                            error!("Error adding node: {}", err);
-                            return Err(err);
+                    engine.clear_node_error(&node.id)?;
+                    let previous_fetch = engine.get_link_fetch(&node.id)?;
+                    let content_hash = match Self::get_web_page_node_id(&node.id, engine.clone())? {
+                        // The link was fetched again, see `RecrawlSettings`
+                        Some(web_page_node_id) => ContentChange::detect(
+                            engine.clone(),
+                            &web_page_node_id,
+                            &response.url,
+                            &response,
+                            previous_fetch.as_ref(),
+                        )?,
+                        None => {
+                            let content_hash = get_content_hash(&response.contents);
+                            let content_node_id = match engine.get_or_add_node(
+                                Payload::Text(response.contents.clone()),
+                                vec![NodeLabel::Content, NodeLabel::WebPage],
+                                true,
+                                None,
+                            ) {
+                                Ok(existing_or_new_node_id) => match existing_or_new_node_id {
+                                    ExistingOrNewNodeId::Existing(id) => id,
+                                    ExistingOrNewNodeId::New(id) => id,
+                                },
+                                Err(err) => {
+                                    error!("Error adding node: {}", err);
+                                    return Err(err);
+                                }
+                            };
+                            engine.add_connection(
+                                (node.id.clone(), content_node_id),
+                                (EdgeLabel::PathOf, EdgeLabel::ContentOf),
+                            )?;
+                            content_hash
                         }
                     };
-                    engine.add_connection(
-                        (node.id.clone(), content_node_id),
-                        (EdgeLabel::PathOf, EdgeLabel::ContentOf),
+                    engine.set_link_fetch(
+                        &node.id,
+                        &LinkFetch::new(&response, content_hash, previous_fetch.as_ref()),
                     )?;
+                    if response.status != StatusCode::NOT_MODIFIED.as_u16() {
+                        if let Some((_, domain_node)) =
+                            Self::get_domain_node(&node.id, engine.clone())?
+                        {
+                            let domain_name = Domain::get_domain_name(&domain_node)?;
+                            engine.update_crawl_state(|state| {
+                                state.record_page_fetch(&domain_name)
+                            })?;
+                        }
+                    }
                     engine.toggle_flag(&node.id, NodeFlags::IS_PROCESSED)?;
                 }
                 ExternalData::Error(error) => {
@@ -246,14 +294,56 @@ impl Link {
                         "Error processing link {}({}): {}. The link will be attempted again later.",
                         &url, node.id, error.error
                     );
-                    engine.toggle_flag(&node.id, NodeFlags::HAD_ERROR)?;
+                    engine.record_node_error(&node.id)?;
                 }
             },
-            None => engine.fetch(FetchRequest::new(node.id, &url))?,
+            None => {
+                let mut request = FetchRequest::new(node.id, &url);
+                // When fetching again, the server can tell us if the content has not changed
+                if Self::get_web_page_node_id(&node.id, engine.clone())?.is_some() {
+                    if let Some(previous_fetch) = engine.get_link_fetch(&node.id)? {
+                        previous_fetch.add_conditional_headers(&mut request.headers);
+                    }
+                }
+                engine.fetch(request)?
+            }
         }
         Ok(())
     }
 
+    // The full URL of a link node, with the domain it belongs to
+    pub fn get_url(node_id: &NodeId, engine: Arc<&Engine>) -> PiResult<Option<String>> {
+        let Some(link_node) = engine.get_node_without_content(node_id) else {
+            return Ok(None);
+        };
+        let Payload::Link(link) = &link_node.payload else {
+            return Ok(None);
+        };
+        match Self::get_domain_node(node_id, engine)? {
+            Some((_, domain_node)) => Ok(Some(format!(
+                "https://{}{}",
+                Domain::get_domain_name(&domain_node)?,
+                link.get_full_link()
+            ))),
+            None => Ok(None),
+        }
+    }
+
+    // The WebPage node with the fetched content of a link, if it was fetched
+    pub fn get_web_page_node_id(
+        node_id: &NodeId,
+        engine: Arc<&Engine>,
+    ) -> PiResult<Option<NodeId>> {
+        Ok(engine
+            .get_node_ids_connected_with_label(node_id, &EdgeLabel::PathOf)?
+            .into_iter()
+            .find(|connected_node_id| {
+                engine
+                    .get_node_without_content(connected_node_id)
+                    .is_some_and(|node| node.labels.contains(&NodeLabel::WebPage))
+            }))
+    }
+
     pub fn get_domain_node(
         node_id: &NodeId,
         engine: Arc<&Engine>,
@@ -262,7 +352,7 @@ impl Link {
             Ok(connected_node_ids) => {
                 // Find the first domain node
                 Ok(connected_node_ids.iter().find_map(|node_id| {
-                    match engine.get_node_by_id(node_id) {
+                    match engine.get_node_without_content(node_id) {
                         Some(node) => {
                             if node.labels.contains(&NodeLabel::DomainName) {
                                 match &node.payload {
@@ -1,5 +1,7 @@
+pub(crate) mod discovery;
 pub(crate) mod domain;
 pub(crate) mod link;
+pub(crate) mod recrawl;
 pub(crate) mod robots_txt;
 mod scraper;
 pub(super) mod tests;
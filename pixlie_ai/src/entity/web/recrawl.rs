@@ -0,0 +1,501 @@
+// Copyright 2025 Pixlie Web Solutions Pvt. Ltd.
+// Licensed under the GNU General Public License version 3.0;
+// You may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// https://github.com/pixlie/PixlieAI/blob/main/LICENSE
+
+// Links are fetched again once their recrawl interval has passed, so we can track how web pages
+// change. A web page is only processed again when its content has changed, in which case the
+// change is recorded as a ContentChange node connected to the web page
+
+use crate::engine::node::{NodeId, NodeLabel, Payload};
+use crate::engine::node_history::LineChangeType;
+use crate::engine::{EdgeLabel, Engine, NodeFlags};
+use crate::entity::web::domain::Domain;
+use crate::entity::web::link::Link;
+use crate::entity::web::web_page::WebPage;
+use crate::error::PiResult;
+use crate::FetchResponse;
+use chrono::{DateTime, TimeDelta, Utc};
+use log::{debug, info};
+use reqwest::header::{
+    HeaderMap, HeaderValue, ETAG, IF_MODIFIED_SINCE, IF_NONE_MATCH, LAST_MODIFIED,
+};
+use reqwest::StatusCode;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::sync::Arc;
+use ts_rs::TS;
+use utoipa::ToSchema;
+
+/// How often the web pages of a project are fetched again, to find out if they have changed.
+#[derive(Clone, Default, Deserialize, Serialize, ToSchema, TS)]
+#[ts(export)]
+pub struct RecrawlSettings {
+    /// Minutes after which a web page is fetched again.
+    /// If not set, web pages are fetched only once.
+    pub interval_in_minutes: Option<u32>,
+    /// Intervals for the web pages of specific domains, like `example.com`.
+    /// These are used instead of the interval of the project.
+    pub interval_in_minutes_by_domain: BTreeMap<String, u32>,
+}
+
+/// A change in the content of a web page, found when its link was fetched again.
+#[derive(Clone, Deserialize, Serialize, ToSchema, TS)]
+pub struct ContentChange {
+    pub url: String,
+    /// The version of the web page node before the change, see the versions of a node
+    pub previous_version: u32,
+    pub lines_added: u32,
+    pub lines_removed: u32,
+    pub detected_at: DateTime<Utc>,
+}
+
+// How a link was last fetched. The headers are sent with the next request, so the server
+// can tell us that the content has not changed without sending it again
+#[derive(Clone, Deserialize, Serialize)]
+pub struct LinkFetch {
+    pub fetched_at: DateTime<Utc>,
+    pub etag: Option<String>,
+    pub last_modified: Option<String>,
+    pub content_hash: u64,
+}
+
+// Links by the time they were last fetched, kept by the engine. Scheduling recrawls reads only
+// the links which were fetched long enough ago to be due
+#[derive(Default)]
+pub struct RecrawlQueue {
+    fetched_at_by_node_id: HashMap<NodeId, DateTime<Utc>>,
+    node_ids_by_fetched_at: BTreeSet<(DateTime<Utc>, NodeId)>,
+}
+
+impl RecrawlQueue {
+    // A link which is deleted has no fetch time
+    pub fn set_fetched_at(&mut self, node_id: NodeId, fetched_at: Option<DateTime<Utc>>) {
+        if let Some(previous) = self.fetched_at_by_node_id.remove(&node_id) {
+            self.node_ids_by_fetched_at.remove(&(previous, node_id));
+        }
+        if let Some(fetched_at) = fetched_at {
+            self.fetched_at_by_node_id.insert(node_id, fetched_at);
+            self.node_ids_by_fetched_at.insert((fetched_at, node_id));
+        }
+    }
+
+    pub fn contains(&self, node_id: &NodeId) -> bool {
+        self.fetched_at_by_node_id.contains_key(node_id)
+    }
+
+    // Links fetched at or before the given time, earliest first
+    pub fn get_fetched_before(&self, time: DateTime<Utc>) -> Vec<(NodeId, DateTime<Utc>)> {
+        self.node_ids_by_fetched_at
+            .iter()
+            .take_while(|(fetched_at, _)| *fetched_at <= time)
+            .map(|(fetched_at, node_id)| (*node_id, *fetched_at))
+            .collect()
+    }
+}
+
+// FNV-1a hash of the content. The hash is stored on disk,
+// so it must not change between Rust versions like `DefaultHasher` may
+pub fn get_content_hash(content: &str) -> u64 {
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for byte in content.as_bytes() {
+        hash ^= *byte as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    hash
+}
+
+impl RecrawlSettings {
+    pub fn get_interval(&self, domain_name: &str) -> Option<TimeDelta> {
+        self.interval_in_minutes_by_domain
+            .get(domain_name)
+            .or(self.interval_in_minutes.as_ref())
+            .map(|minutes| TimeDelta::minutes(*minutes as i64))
+    }
+
+    // No link is due before this interval has passed since it was fetched
+    fn get_shortest_interval(&self) -> Option<TimeDelta> {
+        self.interval_in_minutes
+            .iter()
+            .chain(self.interval_in_minutes_by_domain.values())
+            .min()
+            .map(|minutes| TimeDelta::minutes(*minutes as i64))
+    }
+
+    pub fn find(engine: Arc<&Engine>) -> Option<(NodeId, RecrawlSettings)> {
+        engine
+            .get_node_ids_with_label(&NodeLabel::RecrawlSettings)
+            .iter()
+            .find_map(|node_id| match engine.get_node_without_content(node_id) {
+                Some(node) => match &node.payload {
+                    Payload::RecrawlSettings(settings) => Some((node.id, settings.clone())),
+                    _ => None,
+                },
+                None => None,
+            })
+    }
+
+    // A project has one RecrawlSettings node, which is updated when the settings change
+    pub fn save(&self, engine: Arc<&Engine>) -> PiResult<NodeId> {
+        match Self::find(engine.clone()) {
+            Some((node_id, _)) => {
+                engine.update_node(&node_id, Payload::RecrawlSettings(self.clone()))?;
+                Ok(node_id)
+            }
+            None => Ok(engine
+                .get_or_add_node(
+                    Payload::RecrawlSettings(self.clone()),
+                    vec![NodeLabel::AddedByUser, NodeLabel::RecrawlSettings],
+                    true,
+                    None,
+                )?
+                .get_node_id()),
+        }
+    }
+
+    // Links whose web page was fetched longer than their interval ago are marked as not
+    // processed, so the engine fetches them again. Returns the number of links marked
+    pub fn schedule_recrawls(engine: Arc<&Engine>) -> PiResult<usize> {
+        let Some((_, settings)) = Self::find(engine.clone()) else {
+            return Ok(0);
+        };
+        let Some(shortest_interval) = settings.get_shortest_interval() else {
+            return Ok(0);
+        };
+        let now = Utc::now();
+        let mut count_scheduled: usize = 0;
+        for (link_node_id, fetched_at) in
+            engine.get_links_fetched_before(now - shortest_interval)?
+        {
+            let Some(link_node) = engine.get_node_without_content(&link_node_id) else {
+                continue;
+            };
+            if !link_node.flags.contains(NodeFlags::IS_PROCESSED)
+                || link_node
+                    .flags
+                    .intersects(NodeFlags::IS_REQUESTING | NodeFlags::IS_BLOCKED)
+            {
+                continue;
+            }
+            // Only links which have a web page have something to track
+            if Link::get_web_page_node_id(&link_node_id, engine.clone())?.is_none() {
+                continue;
+            }
+            let Some((_, domain_node)) = Link::get_domain_node(&link_node_id, engine.clone())?
+            else {
+                continue;
+            };
+            let Some(interval) = settings.get_interval(&Domain::get_domain_name(&domain_node)?)
+            else {
+                continue;
+            };
+            if fetched_at + interval <= now {
+                engine.toggle_flag(&link_node_id, NodeFlags::IS_PROCESSED)?;
+                count_scheduled += 1;
+            }
+        }
+        if count_scheduled > 0 {
+            info!("Scheduled {} links to be fetched again", count_scheduled);
+        }
+        Ok(count_scheduled)
+    }
+}
+
+impl LinkFetch {
+    // A response may not repeat the headers, like a 304 response, then we keep the earlier ones
+    pub fn new(
+        response: &FetchResponse,
+        content_hash: u64,
+        previous_fetch: Option<&LinkFetch>,
+    ) -> LinkFetch {
+        let get_header = |name| {
+            response
+                .headers
+                .get(name)
+                .and_then(|value| value.to_str().ok())
+                .map(|value| value.to_string())
+        };
+        LinkFetch {
+            fetched_at: Utc::now(),
+            etag: get_header(ETAG).or_else(|| previous_fetch.and_then(|x| x.etag.clone())),
+            last_modified: get_header(LAST_MODIFIED)
+                .or_else(|| previous_fetch.and_then(|x| x.last_modified.clone())),
+            content_hash,
+        }
+    }
+
+    pub fn add_conditional_headers(&self, headers: &mut HeaderMap) {
+        if let Some(value) = self
+            .etag
+            .as_ref()
+            .and_then(|etag| HeaderValue::from_str(etag).ok())
+        {
+            headers.insert(IF_NONE_MATCH, value);
+        }
+        if let Some(value) = self
+            .last_modified
+            .as_ref()
+            .and_then(|last_modified| HeaderValue::from_str(last_modified).ok())
+        {
+            headers.insert(IF_MODIFIED_SINCE, value);
+        }
+    }
+}
+
+impl ContentChange {
+    // Compares the fetched content of a link with its web page. When the content has changed,
+    // the web page is updated, which keeps its earlier content as a version, and it is
+    // processed again. Returns the hash of the current content
+    pub fn detect(
+        engine: Arc<&Engine>,
+        web_page_node_id: &NodeId,
+        url: &str,
+        response: &FetchResponse,
+        previous_fetch: Option<&LinkFetch>,
+    ) -> PiResult<u64> {
+        let previous_content_hash = match previous_fetch {
+            Some(previous_fetch) => previous_fetch.content_hash,
+            None => match engine.get_node_with_content(web_page_node_id) {
+                Some(web_page_node) => match &web_page_node.payload {
+                    Payload::Text(content) => get_content_hash(content),
+                    _ => 0,
+                },
+                None => 0,
+            },
+        };
+        if response.status == StatusCode::NOT_MODIFIED.as_u16() {
+            debug!("Web page at {} has not changed", url);
+            return Ok(previous_content_hash);
+        }
+        let content_hash = get_content_hash(&response.contents);
+        if content_hash == previous_content_hash {
+            debug!("Web page at {} has the same content", url);
+            return Ok(content_hash);
+        }
+
+        engine.update_node(web_page_node_id, Payload::Text(response.contents.clone()))?;
+        let previous_version = engine.get_node_versions(web_page_node_id)?.len() as u32;
+        let diff =
+            engine.diff_node_versions(web_page_node_id, previous_version, previous_version + 1)?;
+        let (mut lines_added, mut lines_removed): (u32, u32) = (0, 0);
+        for line in diff.changes.iter().flat_map(|change| change.lines.iter()) {
+            match line.change {
+                LineChangeType::Added => lines_added += 1,
+                LineChangeType::Removed => lines_removed += 1,
+            }
+        }
+        WebPage::clear_processed_content(engine.clone(), web_page_node_id)?;
+
+        let change_node_id = engine
+            .get_or_add_node(
+                Payload::ContentChange(ContentChange {
+                    url: url.to_string(),
+                    previous_version,
+                    lines_added,
+                    lines_removed,
+                    detected_at: Utc::now(),
+                }),
+                vec![NodeLabel::ContentChange],
+                true,
+                None,
+            )?
+            .get_node_id();
+        engine.add_connection(
+            (*web_page_node_id, change_node_id),
+            (EdgeLabel::ChangedWith, EdgeLabel::ChangeOf),
+        )?;
+        info!(
+            "Web page at {} has changed, {} lines added and {} removed",
+            url, lines_added, lines_removed
+        );
+        Ok(content_hash)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::engine::get_test_engine;
+    use crate::ExternalData;
+
+    fn get_response(link_node_id: NodeId, status: u16, contents: &str) -> ExternalData {
+        let mut headers = HeaderMap::new();
+        headers.insert(ETAG, HeaderValue::from_static("\"v1\""));
+        ExternalData::Response(FetchResponse {
+            project_id: "".to_string(),
+            node_id: link_node_id,
+            url: "https://pixlie.com/pricing".to_string(),
+            status,
+            headers,
+            contents: contents.to_string(),
+        })
+    }
+
+    #[test]
+    fn test_changed_content_is_recorded() {
+        let test_engine = get_test_engine();
+        let arced_test_engine = Arc::new(&test_engine);
+        let link_node_id = Link::add(
+            arced_test_engine.clone(),
+            &"https://pixlie.com/pricing".to_string(),
+            vec![NodeLabel::AddedByUser, NodeLabel::Link],
+            vec![],
+            true,
+        )
+        .unwrap();
+        let fetch = |status: u16, contents: &str| {
+            let link_node = test_engine.get_node_without_content(&link_node_id).unwrap();
+            Link::process(
+                &link_node,
+                arced_test_engine.clone(),
+                Some(get_response(link_node_id, status, contents)),
+            )
+            .unwrap();
+        };
+        let get_change_node_ids = |web_page_node_id: &NodeId| {
+            test_engine
+                .get_node_ids_connected_with_label(web_page_node_id, &EdgeLabel::ChangedWith)
+                .unwrap()
+        };
+
+        fetch(200, "<html>\n<p>Free</p>\n</html>");
+        let web_page_node_id = Link::get_web_page_node_id(&link_node_id, arced_test_engine.clone())
+            .unwrap()
+            .unwrap();
+        let link_fetch = test_engine.get_link_fetch(&link_node_id).unwrap().unwrap();
+        assert_eq!(link_fetch.etag.as_deref(), Some("\"v1\""));
+        let mut headers = HeaderMap::new();
+        link_fetch.add_conditional_headers(&mut headers);
+        assert_eq!(headers.get(IF_NONE_MATCH).unwrap(), "\"v1\"");
+
+        // Not modified, or the same content, is not a change
+        test_engine
+            .toggle_flag(&link_node_id, NodeFlags::IS_PROCESSED)
+            .unwrap();
+        fetch(304, "");
+        test_engine
+            .toggle_flag(&link_node_id, NodeFlags::IS_PROCESSED)
+            .unwrap();
+        fetch(200, "<html>\n<p>Free</p>\n</html>");
+        assert!(get_change_node_ids(&web_page_node_id).is_empty());
+        // Only responses with content count against the crawl budget
+        let crawl_state = test_engine.get_crawl_state().unwrap();
+        assert_eq!(crawl_state.count_pages_fetched, 2);
+        assert_eq!(crawl_state.count_pages_fetched_by_domain["pixlie.com"], 2);
+        assert!(test_engine
+            .get_node_without_content(&link_node_id)
+            .unwrap()
+            .flags
+            .contains(NodeFlags::IS_PROCESSED));
+
+        test_engine
+            .toggle_flag(&web_page_node_id, NodeFlags::IS_PROCESSED)
+            .unwrap();
+        test_engine
+            .toggle_flag(&link_node_id, NodeFlags::IS_PROCESSED)
+            .unwrap();
+        fetch(200, "<html>\n<p>Free for 14 days</p>\n</html>");
+        let change_node_ids = get_change_node_ids(&web_page_node_id);
+        assert_eq!(change_node_ids.len(), 1);
+        match &test_engine
+            .get_node_without_content(&change_node_ids[0])
+            .unwrap()
+            .payload
+        {
+            Payload::ContentChange(change) => {
+                assert_eq!(change.previous_version, 1);
+                assert_eq!((change.lines_added, change.lines_removed), (1, 1));
+            }
+            _ => panic!("Expected ContentChange payload"),
+        }
+        // The web page is processed again with its new content
+        let web_page_node = test_engine
+            .get_node_with_content(&web_page_node_id)
+            .unwrap();
+        assert!(!web_page_node.flags.contains(NodeFlags::IS_PROCESSED));
+        assert!(matches!(&web_page_node.payload, Payload::Text(text) if text.contains("14 days")));
+    }
+
+    #[test]
+    fn test_interval_of_domain_is_used_over_project_interval() {
+        let settings = RecrawlSettings {
+            interval_in_minutes: Some(60 * 24),
+            interval_in_minutes_by_domain: BTreeMap::from([(
+                "news.ycombinator.com".to_string(),
+                30,
+            )]),
+        };
+        assert_eq!(
+            settings.get_interval("news.ycombinator.com"),
+            Some(TimeDelta::minutes(30))
+        );
+        assert_eq!(
+            settings.get_interval("pixlie.com"),
+            Some(TimeDelta::minutes(60 * 24))
+        );
+        assert_eq!(RecrawlSettings::default().get_interval("pixlie.com"), None);
+    }
+
+    #[test]
+    fn test_only_links_fetched_before_the_interval_are_scheduled() {
+        let test_engine = get_test_engine();
+        let arced_test_engine = Arc::new(&test_engine);
+        let link_node_id = Link::add(
+            arced_test_engine.clone(),
+            &"https://pixlie.com/pricing".to_string(),
+            vec![NodeLabel::AddedByUser, NodeLabel::Link],
+            vec![],
+            true,
+        )
+        .unwrap();
+        let link_node = test_engine.get_node_without_content(&link_node_id).unwrap();
+        Link::process(
+            &link_node,
+            arced_test_engine.clone(),
+            Some(get_response(
+                link_node_id,
+                200,
+                "<html>\n<p>Free</p>\n</html>",
+            )),
+        )
+        .unwrap();
+        RecrawlSettings {
+            interval_in_minutes: Some(60),
+            interval_in_minutes_by_domain: BTreeMap::new(),
+        }
+        .save(arced_test_engine.clone())
+        .unwrap();
+
+        // The link was just fetched, so it is not read at all
+        let an_hour_ago = Utc::now() - TimeDelta::minutes(60);
+        assert!(test_engine
+            .get_links_fetched_before(an_hour_ago)
+            .unwrap()
+            .is_empty());
+        assert_eq!(
+            RecrawlSettings::schedule_recrawls(arced_test_engine.clone()).unwrap(),
+            0
+        );
+
+        let mut link_fetch = test_engine.get_link_fetch(&link_node_id).unwrap().unwrap();
+        link_fetch.fetched_at = an_hour_ago - TimeDelta::minutes(1);
+        test_engine
+            .set_link_fetch(&link_node_id, &link_fetch)
+            .unwrap();
+        assert_eq!(
+            test_engine.get_links_fetched_before(an_hour_ago).unwrap(),
+            vec![(link_node_id, link_fetch.fetched_at)]
+        );
+        assert_eq!(
+            RecrawlSettings::schedule_recrawls(arced_test_engine.clone()).unwrap(),
+            1
+        );
+        assert!(!test_engine
+            .get_node_without_content(&link_node_id)
+            .unwrap()
+            .flags
+            .contains(NodeFlags::IS_PROCESSED));
+    }
+}
@@ -6,8 +6,9 @@
 // https://github.com/pixlie/PixlieAI/blob/main/LICENSE
 
 use crate::engine::node::{NodeId, NodeItem, NodeLabel, Payload};
-use crate::engine::{EdgeLabel, Engine};
+use crate::engine::{EdgeLabel, EdgeProperties, EdgeSource, Engine};
 use crate::entity::project_settings::ProjectSettings;
+use crate::entity::web::discovery::LinkDiscovery;
 use crate::entity::web::domain::{Domain, FindDomainOf};
 use crate::entity::web::link::Link;
 use crate::entity::web::web_metadata::WebMetadata;
@@ -118,9 +119,10 @@ impl<'a> Traverser<'a> {
                             None,
                         )?
                         .get_node_id();
-                    self.arced_engine.add_connection(
+                    self.arced_engine.add_connection_with_properties(
                         (self.webpage_node_id.clone(), title_node_id),
                         (EdgeLabel::ParentOf, EdgeLabel::ChildOf),
+                        EdgeProperties::new(EdgeSource::Scraper),
                     )?;
                     self.update_metadata_node(
                         "title",
@@ -139,9 +141,10 @@ impl<'a> Traverser<'a> {
                             None,
                         )?
                         .get_node_id();
-                    self.arced_engine.add_connection(
+                    self.arced_engine.add_connection_with_properties(
                         (self.webpage_node_id.clone(), heading_node_id),
                         (EdgeLabel::ParentOf, EdgeLabel::ChildOf),
+                        EdgeProperties::new(EdgeSource::Scraper),
                     )?;
                 }
                 "p" => {
@@ -156,9 +159,10 @@ impl<'a> Traverser<'a> {
                             None,
                         )?
                         .get_node_id();
-                    self.arced_engine.add_connection(
+                    self.arced_engine.add_connection_with_properties(
                         (self.webpage_node_id.clone(), paragraph_node_id),
                         (EdgeLabel::ParentOf, EdgeLabel::ChildOf),
+                        EdgeProperties::new(EdgeSource::Scraper),
                     )?;
                 }
                 "ul" | "ol" => {
@@ -177,14 +181,16 @@ impl<'a> Traverser<'a> {
                         .arced_engine
                         .get_or_add_node(Payload::Tree, labels, true, None)?
                         .get_node_id();
-                    self.arced_engine.add_connection(
+                    self.arced_engine.add_connection_with_properties(
                         (self.webpage_node_id.clone(), bullet_points_node_id),
                         (EdgeLabel::ParentOf, EdgeLabel::ChildOf),
+                        EdgeProperties::new(EdgeSource::Scraper),
                     )?;
                     if let Some(parent_node_id) = parent_node_id {
-                        self.arced_engine.add_connection(
+                        self.arced_engine.add_connection_with_properties(
                             (parent_node_id, bullet_points_node_id.clone()),
                             (EdgeLabel::ParentOf, EdgeLabel::ChildOf),
+                            EdgeProperties::new(EdgeSource::Scraper),
                         )?;
                     }
                     self.traverse(
@@ -216,9 +222,10 @@ impl<'a> Traverser<'a> {
                                     None,
                                 )?
                                 .get_node_id();
-                            self.arced_engine.add_connection(
+                            self.arced_engine.add_connection_with_properties(
                                 (parent_node_id, list_item_node_id.clone()),
                                 (EdgeLabel::ParentOf, EdgeLabel::ChildOf),
+                                EdgeProperties::new(EdgeSource::Scraper),
                             )?;
                         }
                     }
@@ -290,9 +297,15 @@ impl<'a> Traverser<'a> {
                             }
                         }
                     };
-                    self.arced_engine.add_connection(
+                    self.arced_engine.add_connection_with_properties(
                         (self.webpage_node_id, link_node_id.clone()),
                         (EdgeLabel::ParentOf, EdgeLabel::ChildOf),
+                        EdgeProperties::new(EdgeSource::Scraper),
+                    )?;
+                    LinkDiscovery::record(
+                        self.arced_engine.clone(),
+                        &link_node_id,
+                        &self.link_node_id,
                     )?;
                 }
                 // "table" => {
@@ -379,7 +392,7 @@ impl<'a> Traverser<'a> {
         }
         let node = self
             .arced_engine
-            .get_node_by_id(&self.web_metadata_node_id)
+            .get_node_without_content(&self.web_metadata_node_id)
             .ok_or_else(|| PiError::GraphError("WebMetadata node not found".into()))?;
         let mut payload = match &node.payload {
             Payload::WebMetadata(existing) => existing.clone(),
@@ -479,9 +492,10 @@ pub fn scrape(node: &NodeItem, engine: Arc<&Engine>) -> PiResult<()> {
                 )?
                 .get_node_id();
 
-            engine.add_connection(
+            engine.add_connection_with_properties(
                 (node.id.clone(), new_id.clone()),
                 (EdgeLabel::ParentOf, EdgeLabel::ChildOf),
+                EdgeProperties::new(EdgeSource::Scraper),
             )?;
 
             new_id
@@ -521,15 +535,15 @@ pub fn scrape(node: &NodeItem, engine: Arc<&Engine>) -> PiResult<()> {
     {
         None
     } else {
-        project_settings_node_id
-            .iter()
-            .find_map(|node_id| match engine.get_node_by_id(&node_id) {
+        project_settings_node_id.iter().find_map(|node_id| {
+            match engine.get_node_without_content(&node_id) {
                 Some(node) => match &node.payload {
                     Payload::ProjectSettings(payload) => Some((node_id.clone(), payload.clone())),
                     _ => None,
                 },
                 None => None,
-            })
+            }
+        })
     };
 
     match &node.payload {
@@ -550,8 +564,11 @@ pub fn scrape(node: &NodeItem, engine: Arc<&Engine>) -> PiResult<()> {
                 arced_engine: engine,
                 project_settings,
             };
-            traverser.update_metadata_node("url", current_url.clone().as_str())?;
-            traverser.traverse(document.root_element(), None, None)?;
+            // All nodes and edges found in the page are written to the DB at once
+            traverser.arced_engine.batch_writes(|| {
+                traverser.update_metadata_node("url", current_url.clone().as_str())?;
+                traverser.traverse(document.root_element(), None, None)
+            })?;
         }
         _ => {
             return Err(PiError::InternalError(format!(
@@ -59,7 +59,7 @@ fn test_webpage_scraper_rlhf_book() {
         .get_node_ids_connected_with_label(&webpage_node_id, &EdgeLabel::ParentOf)
         .unwrap()
         .into_iter()
-        .filter_map(|id| test_engine.get_node_by_id(&id))
+        .filter_map(|id| test_engine.get_node_without_content(&id))
         .filter(|node| node.labels.contains(&NodeLabel::WebMetadata))
         .collect();
     assert_eq!(web_metadata_nodes.len(), 1);
@@ -95,7 +95,7 @@ fn test_webpage_scraper_rlhf_book() {
         .get_node_ids_connected_with_label(&webpage_node_id, &EdgeLabel::ParentOf)
         .unwrap()
         .into_iter()
-        .filter_map(|id| test_engine.get_node_by_id(&id))
+        .filter_map(|id| test_engine.get_node_without_content(&id))
         .filter(|node| node.labels.contains(&NodeLabel::Title))
         .collect();
     assert_eq!(title_nodes.len(), 1);
@@ -116,7 +116,7 @@ fn test_webpage_scraper_rlhf_book() {
         .get_node_ids_connected_with_label(&webpage_node_id, &EdgeLabel::ParentOf)
         .unwrap()
         .into_iter()
-        .filter_map(|id| test_engine.get_node_by_id(&id))
+        .filter_map(|id| test_engine.get_node_without_content(&id))
         .filter(|node| node.labels.contains(&NodeLabel::Heading))
         .collect();
     assert_eq!(heading_nodes.len(), 17);
@@ -134,7 +134,7 @@ fn test_webpage_scraper_rlhf_book() {
     assert_eq!(paragraph_nodes.len(), 37);
 
     let paragraph = test_engine
-        .get_node_by_id(paragraph_nodes.get(2).unwrap())
+        .get_node_without_content(paragraph_nodes.get(2).unwrap())
         .unwrap();
     assert_eq!(
         match paragraph.payload {
@@ -145,7 +145,7 @@ fn test_webpage_scraper_rlhf_book() {
     );
 
     let paragraph = test_engine
-        .get_node_by_id(paragraph_nodes.get(4).unwrap())
+        .get_node_without_content(paragraph_nodes.get(4).unwrap())
         .unwrap();
     assert_eq!(
         match paragraph.payload {
@@ -163,19 +163,21 @@ fn test_webpage_scraper_rlhf_book() {
 
     let all_domain_nodes: Vec<ArcedNodeItem> = domain_node_ids
         .iter()
-        .filter_map(|node_id| match test_engine.get_node_by_id(node_id) {
-            Some(node) => {
-                if node.labels.contains(&NodeLabel::DomainName) {
-                    match node.payload {
-                        Payload::Text(_) => Some(node.clone()),
-                        _ => None,
+        .filter_map(
+            |node_id| match test_engine.get_node_without_content(node_id) {
+                Some(node) => {
+                    if node.labels.contains(&NodeLabel::DomainName) {
+                        match node.payload {
+                            Payload::Text(_) => Some(node.clone()),
+                            _ => None,
+                        }
+                    } else {
+                        None
                     }
-                } else {
-                    None
                 }
-            }
-            None => None,
-        })
+                None => None,
+            },
+        )
         .collect();
 
     let some_links = [
@@ -208,7 +210,7 @@ fn test_webpage_scraper_rlhf_book() {
                                 let link_nodes = link_node_ids
                                     .iter()
                                     .filter_map(|node_id| {
-                                        match test_engine.get_node_by_id(node_id) {
+                                        match test_engine.get_node_without_content(node_id) {
                                             Some(node) => match node.payload {
                                                 Payload::Link(_) => Some(node.clone()),
                                                 _ => None,
@@ -253,7 +255,7 @@ fn test_webpage_scraper_rlhf_book() {
     assert_eq!(ordered_points_node_ids.len(), 6);
 
     let first_bullet_point_node = test_engine
-        .get_node_by_id(unordered_points_node_ids.first().unwrap())
+        .get_node_without_content(unordered_points_node_ids.first().unwrap())
         .unwrap();
     assert_eq!(
         first_bullet_point_node.labels,
@@ -266,7 +268,7 @@ fn test_webpage_scraper_rlhf_book() {
     assert_eq!(list_item_node_ids.len(), 2);
     let list_item_nodes = list_item_node_ids
         .iter()
-        .map(|node_id| test_engine.get_node_by_id(node_id).unwrap())
+        .map(|node_id| test_engine.get_node_without_content(node_id).unwrap())
         .collect::<Vec<ArcedNodeItem>>();
     assert_eq!(
         list_item_nodes
@@ -281,7 +283,7 @@ fn test_webpage_scraper_rlhf_book() {
     );
 
     let second_bullet_point_node = test_engine
-        .get_node_by_id(unordered_points_node_ids.get(1).unwrap())
+        .get_node_without_content(unordered_points_node_ids.get(1).unwrap())
         .unwrap();
     assert_eq!(
         second_bullet_point_node.labels,
@@ -294,7 +296,7 @@ fn test_webpage_scraper_rlhf_book() {
     assert_eq!(list_item_node_ids.len(), 4);
     let list_item_nodes = list_item_node_ids
         .iter()
-        .map(|node_id| test_engine.get_node_by_id(node_id).unwrap())
+        .map(|node_id| test_engine.get_node_without_content(node_id).unwrap())
         .collect::<Vec<ArcedNodeItem>>();
     assert_eq!(
         list_item_nodes
@@ -314,7 +316,7 @@ fn test_webpage_scraper_rlhf_book() {
     );
 
     let third_bullet_point_node = test_engine
-        .get_node_by_id(unordered_points_node_ids.get(2).unwrap())
+        .get_node_without_content(unordered_points_node_ids.get(2).unwrap())
         .unwrap();
     assert_eq!(
         third_bullet_point_node.labels,
@@ -327,7 +329,7 @@ fn test_webpage_scraper_rlhf_book() {
     assert_eq!(list_item_node_ids.len(), 4);
     let list_item_nodes = list_item_node_ids
         .iter()
-        .map(|node_id| test_engine.get_node_by_id(node_id).unwrap())
+        .map(|node_id| test_engine.get_node_without_content(node_id).unwrap())
         .collect::<Vec<ArcedNodeItem>>();
     assert_eq!(
         list_item_nodes
@@ -394,7 +396,7 @@ fn test_extraction_from_hn_homepage() {
         .get_node_ids_connected_with_label(&webpage_node_id, &EdgeLabel::ParentOf)
         .unwrap()
         .into_iter()
-        .filter_map(|id| test_engine.get_node_by_id(&id))
+        .filter_map(|id| test_engine.get_node_without_content(&id))
         .filter(|node| node.labels.contains(&NodeLabel::WebMetadata))
         .collect();
     assert_eq!(web_metadata_nodes.len(), 1);
@@ -415,7 +417,7 @@ fn test_extraction_from_hn_homepage() {
         .get_node_ids_connected_with_label(&webpage_node_id, &EdgeLabel::ParentOf)
         .unwrap()
         .into_iter()
-        .filter_map(|id| test_engine.get_node_by_id(&id))
+        .filter_map(|id| test_engine.get_node_without_content(&id))
         .filter(|node| node.labels.contains(&NodeLabel::Title))
         .collect();
     assert_eq!(title_nodes.len(), 1);
@@ -507,7 +509,7 @@ fn test_extract_data_only_from_specified_links() {
         .get_node_ids_connected_with_label(&webpage_node_id, &EdgeLabel::ParentOf)
         .unwrap()
         .into_iter()
-        .filter_map(|id| test_engine.get_node_by_id(&id))
+        .filter_map(|id| test_engine.get_node_without_content(&id))
         .filter(|node| node.labels.contains(&NodeLabel::WebMetadata))
         .collect();
     assert_eq!(web_metadata_nodes.len(), 1);
@@ -528,7 +530,7 @@ fn test_extract_data_only_from_specified_links() {
         .get_node_ids_connected_with_label(&webpage_node_id, &EdgeLabel::ParentOf)
         .unwrap()
         .into_iter()
-        .filter_map(|id| test_engine.get_node_by_id(&id))
+        .filter_map(|id| test_engine.get_node_without_content(&id))
         .filter(|node| node.labels.contains(&NodeLabel::Title))
         .collect();
     assert_eq!(title_nodes.len(), 1);
@@ -621,7 +623,7 @@ fn test_crawl_within_domains_of_specified_links() {
         .get_node_ids_connected_with_label(&webpage_node_id, &EdgeLabel::ParentOf)
         .unwrap()
         .into_iter()
-        .filter_map(|id| test_engine.get_node_by_id(&id))
+        .filter_map(|id| test_engine.get_node_without_content(&id))
         .filter(|node| node.labels.contains(&NodeLabel::WebMetadata))
         .collect();
     assert_eq!(web_metadata_nodes.len(), 1);
@@ -642,7 +644,7 @@ fn test_crawl_within_domains_of_specified_links() {
         .get_node_ids_connected_with_label(&webpage_node_id, &EdgeLabel::ParentOf)
         .unwrap()
         .into_iter()
-        .filter_map(|id| test_engine.get_node_by_id(&id))
+        .filter_map(|id| test_engine.get_node_without_content(&id))
         .filter(|node| node.labels.contains(&NodeLabel::Title))
         .collect();
     assert_eq!(title_nodes.len(), 1);
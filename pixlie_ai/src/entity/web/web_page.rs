@@ -5,8 +5,8 @@
 //
 // https://github.com/pixlie/PixlieAI/blob/main/LICENSE
 
-use crate::engine::node::{NodeId, NodeItem, Payload};
-use crate::engine::{EdgeLabel, Engine};
+use crate::engine::node::{NodeId, NodeItem, NodeLabel, Payload};
+use crate::engine::{EdgeLabel, Engine, NodeFlags};
 use crate::entity::web::link::Link;
 use crate::entity::web::scraper::scrape;
 use crate::entity::web::web_metadata::WebMetadata;
@@ -25,7 +25,7 @@ pub fn get_link_of_webpage(engine: Arc<&Engine>, node_id: &NodeId) -> PiResult<(
         PiError::InternalError("No related node ids found for WebPage node".to_string())
     })?;
 
-    match engine.get_node_by_id(first_related_node_id) {
+    match engine.get_node_without_content(first_related_node_id) {
         Some(node) => match node.payload {
             Payload::Link(ref link) => Ok((link.clone(), *first_related_node_id)),
             _ => Err(PiError::GraphError(
@@ -52,7 +52,7 @@ pub fn get_metadata_of_webpage(
         PiError::InternalError("No related node ids found for WebPage node".to_string())
     })?;
 
-    match engine.get_node_by_id(first_related_node_id) {
+    match engine.get_node_without_content(first_related_node_id) {
         Some(node) => match node.payload {
             Payload::WebMetadata(ref metadata) => Ok((metadata.clone(), *first_related_node_id)),
             _ => Err(PiError::GraphError(
@@ -81,7 +81,7 @@ impl WebPage {
 
         let mut content = String::new();
         for node_id in part_node_ids {
-            let node = match engine.get_node_by_id(&node_id) {
+            let node = match engine.get_node_without_content(&node_id) {
                 Some(node) => node,
                 None => continue,
             };
@@ -105,4 +105,57 @@ impl WebPage {
     ) -> PiResult<()> {
         scrape(node, engine.clone())
     }
+
+    // When the content of a web page changes, what we scraped and extracted from the earlier
+    // content is removed and the web page is processed again. The WebMetadata node is kept,
+    // the scraper updates it. Links found on the page are only disconnected, they may have
+    // been crawled already. Classification is updated by the classifier, keeping its versions
+    pub fn clear_processed_content(engine: Arc<&Engine>, node_id: &NodeId) -> PiResult<()> {
+        let child_node_ids =
+            engine.get_node_ids_connected_with_label(node_id, &EdgeLabel::ParentOf)?;
+        for child_node_id in child_node_ids.iter() {
+            let Some(child_node) = engine.get_node_without_content(child_node_id) else {
+                // Already deleted along with another child, like the items of a list
+                continue;
+            };
+            if child_node.labels.contains(&NodeLabel::WebMetadata) {
+                continue;
+            }
+            // Content which is also part of other web pages is kept for them
+            let is_only_on_this_page = !child_node.labels.contains(&NodeLabel::Link)
+                && engine
+                    .get_node_ids_connected_with_label(child_node_id, &EdgeLabel::ChildOf)?
+                    .iter()
+                    .all(|parent_node_id| {
+                        parent_node_id == node_id || child_node_ids.contains(parent_node_id)
+                    });
+            if is_only_on_this_page {
+                engine.delete_node(child_node_id, true)?;
+            } else {
+                engine.remove_connection(
+                    (*node_id, *child_node_id),
+                    (EdgeLabel::ParentOf, EdgeLabel::ChildOf),
+                )?;
+            }
+        }
+
+        for suggested_node_id in
+            engine.get_node_ids_connected_with_label(node_id, &EdgeLabel::Suggests)?
+        {
+            if engine
+                .get_node_without_content(&suggested_node_id)
+                .is_some_and(|node| node.labels.contains(&NodeLabel::ExtractedNamedEntities))
+            {
+                engine.delete_node(&suggested_node_id, true)?;
+            }
+        }
+
+        if engine
+            .get_node_without_content(node_id)
+            .is_some_and(|node| node.flags.contains(NodeFlags::IS_PROCESSED))
+        {
+            engine.toggle_flag(node_id, NodeFlags::IS_PROCESSED)?;
+        }
+        Ok(())
+    }
 }
@@ -49,6 +49,9 @@ pub enum PiError {
     #[error("Error in graph reading or writing: {0}")]
     GraphError(String),
 
+    #[error("Error in graph query: {0}")]
+    QueryError(String),
+
     #[error("Feature is not available: {0}")]
     FeatureNotAvailable(String),
 
@@ -58,9 +61,18 @@ pub enum PiError {
     #[error("{0} {1} not found")]
     CrudNotFoundError(String, String),
 
+    #[error("Project {0} is in use, it needs to be unloaded from the engine")]
+    ProjectInUseError(String),
+
+    #[error("Project {0} is being restored from a snapshot")]
+    ProjectRestoringError(String),
+
     #[error("Gliner: {0}")]
     GlinerError(String),
 
+    #[error("Embedding: {0}")]
+    EmbeddingError(String),
+
     #[error("IO: {0}")]
     IOError(#[from] std::io::Error),
 
@@ -85,6 +97,9 @@ pub enum PiError {
     #[error("Could not generate TypeScript schema: {0}")]
     CouldNotGenerateTypeScriptSchema(#[from] ts_rs::ExportError),
 
+    #[error("CSV: {0}")]
+    CsvError(#[from] csv::Error),
+
     #[error("Error in serde_json: {0}")]
     SerdeJsonError(#[from] serde_json::Error),
 
@@ -9,8 +9,10 @@ pub static PIXLIE_VERSION_NUMBER: &str = "0.2.0-beta";
 
 use crossbeam_channel::unbounded;
 use engine::api::{EngineRequestPayload, EngineResponsePayload};
+use entity::fetch_error::FetchErrorKind;
 use reqwest::header::HeaderMap;
 use reqwest::Method;
+use std::time::Duration;
 use strum::Display;
 
 pub mod api;
@@ -132,6 +134,8 @@ pub struct FetchResponse {
     pub project_id: String,
     pub node_id: u32,
     pub url: String,
+    pub status: u16, // 304 when the contents have not changed since a conditional request
+    pub headers: HeaderMap,
     pub contents: String,
 }
 
@@ -139,9 +143,22 @@ pub struct FetchResponse {
 pub struct FetchError {
     pub project_id: String,
     pub node_id: u32,
+    pub url: String,
+    pub status: Option<u16>, // HTTP status, when the server responded
+    pub kind: FetchErrorKind,
     pub error: String,
 }
 
+// A crawl request which was not sent since the domain or URL was fetched from very recently
+#[derive(Clone)]
+pub struct FetchThrottled {
+    pub project_id: String,
+    pub node_id: u32,
+    pub url: String,
+    pub reason: String,
+    pub wait_time: Duration, // Till the domain or URL can be fetched from again
+}
+
 #[derive(Clone, Display)]
 pub enum PiEvent {
     APIRequest {
@@ -158,8 +175,10 @@ pub enum PiEvent {
     FetchRequest(InternalFetchRequest),
     FetchResponse(FetchResponse),
     FetchError(FetchError),
+    FetchThrottled(FetchThrottled),
 
-    EngineExit(String), // The engine has nothing else to do, so it gives up
+    EngineExit(String),   // The engine has nothing else to do, so it gives up
+    UnloadEngine(String), // Stop the engine of a project and close its DB, like before a restore
 
     Shutdown,
 }
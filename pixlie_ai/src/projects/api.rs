@@ -1,7 +1,17 @@
+use super::snapshots::{Snapshot, SnapshotCreate};
 use super::{Project, ProjectCollection, ProjectCreate, ProjectOwner};
+use crate::api::ApiState;
+use crate::engine::api::{send_engine_request, EngineRequestPayload, EngineResponsePayload};
+use crate::error::PiError;
+use crate::PiEvent;
 use crate::{error::PiResult, utils::crud::Crud};
-use actix_web::{get, post, web};
+use actix_web::{delete, get, post, web};
 use log::error;
+use std::time::{Duration, Instant};
+
+// How long a restore waits for the engine to close the DB of the project
+const UNLOAD_TIMEOUT: Duration = Duration::from_secs(30);
+const UNLOAD_POLL_INTERVAL: Duration = Duration::from_millis(500);
 
 /// Get a list of all projects
 #[utoipa::path(
@@ -53,10 +63,151 @@ pub async fn create_project(project: web::Json<ProjectCreate>) -> PiResult<web::
     Ok(web::Json(project))
 }
 
+/// Get the snapshots of a project, newest first
+#[utoipa::path(
+    path = "/projects/{project_id}/snapshots",
+    responses(
+        (status = 200, description = "Snapshots retrieved successfully", body = Vec<Snapshot>),
+        (status = 500, description = "Internal server error"),
+    ),
+    params(
+        (
+            "project_id" = uuid::Uuid,
+            description = "The ID of the project",
+            example = "123e4567-e89b-12d3-a456-426614174000"
+        ),
+    ),
+    tag = "projects",
+)]
+#[get("/{project_id}/snapshots")]
+pub async fn read_snapshots(project_id: web::Path<String>) -> PiResult<web::Json<Vec<Snapshot>>> {
+    Ok(web::Json(Snapshot::read_list_of_project(&project_id)?))
+}
+
+/// Take a snapshot of a project
+///
+/// The snapshot is a consistent point-in-time copy of the project's graph, taken with a
+/// RocksDB checkpoint while the project keeps running.
+#[utoipa::path(
+    path = "/projects/{project_id}/snapshots",
+    request_body = SnapshotCreate,
+    responses(
+        (status = 200, description = "Snapshot created successfully", body = Snapshot),
+        (status = 500, description = "Internal server error"),
+    ),
+    params(
+        (
+            "project_id" = uuid::Uuid,
+            description = "The ID of the project",
+            example = "123e4567-e89b-12d3-a456-426614174000"
+        ),
+    ),
+    tag = "projects",
+)]
+#[post("/{project_id}/snapshots")]
+pub async fn create_snapshot(
+    project_id: web::Path<String>,
+    snapshot: web::Json<SnapshotCreate>,
+    api_state: web::Data<ApiState>,
+) -> PiResult<web::Json<Snapshot>> {
+    match send_engine_request(
+        project_id.into_inner(),
+        EngineRequestPayload::CreateSnapshot(snapshot.into_inner()),
+        &api_state,
+    )
+    .await?
+    {
+        EngineResponsePayload::Snapshot(snapshot) => Ok(web::Json(snapshot)),
+        EngineResponsePayload::Error(error) => Err(PiError::InternalError(error)),
+        _ => Err(PiError::InternalError(
+            "Unexpected response from engine".to_string(),
+        )),
+    }
+}
+
+/// Restore a project to a snapshot
+///
+/// The project is unloaded from the engine and its graph is replaced with the snapshot.
+/// The project is loaded again with its next request. The snapshot is kept.
+#[utoipa::path(
+    path = "/projects/{project_id}/snapshots/{snapshot_id}/restore",
+    responses(
+        (status = 200, description = "Snapshot restored successfully", body = Snapshot),
+        (status = 500, description = "Internal server error"),
+    ),
+    params(
+        (
+            "project_id" = uuid::Uuid,
+            description = "The ID of the project",
+            example = "123e4567-e89b-12d3-a456-426614174000"
+        ),
+        (
+            "snapshot_id" = uuid::Uuid,
+            description = "The ID of the snapshot",
+            example = "123e4567-e89b-12d3-a456-426614174000"
+        ),
+    ),
+    tag = "projects",
+)]
+#[post("/{project_id}/snapshots/{snapshot_id}/restore")]
+pub async fn restore_snapshot(
+    path: web::Path<(String, String)>,
+    api_state: web::Data<ApiState>,
+) -> PiResult<web::Json<Snapshot>> {
+    let (project_id, snapshot_id) = path.into_inner();
+    // Check the snapshot before the project is unloaded
+    Snapshot::read(&project_id, &snapshot_id)?;
+    // The project is not loaded again, by requests of the UI, till its DB is replaced
+    let _restoring = Snapshot::start_restoring(&project_id)?;
+    api_state
+        .main_tx
+        .send(PiEvent::UnloadEngine(project_id.clone()))?;
+    let started_at = Instant::now();
+    loop {
+        match Snapshot::restore(&project_id, &snapshot_id) {
+            Err(PiError::ProjectInUseError(_)) if started_at.elapsed() < UNLOAD_TIMEOUT => {
+                actix_web::rt::time::sleep(UNLOAD_POLL_INTERVAL).await;
+            }
+            result => return Ok(web::Json(result?)),
+        }
+    }
+}
+
+/// Delete a snapshot of a project
+#[utoipa::path(
+    path = "/projects/{project_id}/snapshots/{snapshot_id}",
+    responses(
+        (status = 200, description = "Snapshot deleted successfully", body = Snapshot),
+        (status = 500, description = "Internal server error"),
+    ),
+    params(
+        (
+            "project_id" = uuid::Uuid,
+            description = "The ID of the project",
+            example = "123e4567-e89b-12d3-a456-426614174000"
+        ),
+        (
+            "snapshot_id" = uuid::Uuid,
+            description = "The ID of the snapshot",
+            example = "123e4567-e89b-12d3-a456-426614174000"
+        ),
+    ),
+    tag = "projects",
+)]
+#[delete("/{project_id}/snapshots/{snapshot_id}")]
+pub async fn delete_snapshot(path: web::Path<(String, String)>) -> PiResult<web::Json<Snapshot>> {
+    let (project_id, snapshot_id) = path.into_inner();
+    Ok(web::Json(Snapshot::delete(&project_id, &snapshot_id)?))
+}
+
 pub fn configure_api_projects(app_config: &mut utoipa_actix_web::service_config::ServiceConfig) {
     app_config.service(
         utoipa_actix_web::scope::scope("/projects")
             .service(read_projects)
-            .service(create_project),
+            .service(create_project)
+            .service(read_snapshots)
+            .service(create_snapshot)
+            .service(restore_snapshot)
+            .service(delete_snapshot),
     );
 }
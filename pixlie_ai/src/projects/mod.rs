@@ -11,6 +11,7 @@ use ts_rs::TS;
 use utoipa::ToSchema;
 
 pub mod api;
+pub mod snapshots;
 
 #[derive(Clone, Deserialize, Serialize, ToSchema, TS)]
 #[ts(export)]
@@ -0,0 +1,328 @@
+// Copyright 2025 Pixlie Web Solutions Pvt. Ltd.
+// Licensed under the GNU General Public License version 3.0;
+// You may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// https://github.com/pixlie/PixlieAI/blob/main/LICENSE
+
+use super::Project;
+use crate::error::{PiError, PiResult};
+use crate::utils::crud::{Crud, CrudItem};
+use chrono::{DateTime, Utc};
+use log::error;
+use rocksdb::checkpoint::Checkpoint;
+use rocksdb::{Options, DB};
+use serde::{Deserialize, Serialize};
+use std::cmp::Reverse;
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, MutexGuard, OnceLock};
+use ts_rs::TS;
+use utoipa::ToSchema;
+
+// Snapshots are kept in `<storage>/snapshots/<project_uuid>/<snapshot_uuid>.rocksdb`
+const SNAPSHOTS_DIR: &str = "snapshots";
+
+// Projects whose DB is being replaced with a snapshot, the engine does not load these
+static RESTORING_PROJECTS: OnceLock<Mutex<HashSet<String>>> = OnceLock::new();
+
+fn lock_restoring_projects() -> PiResult<MutexGuard<'static, HashSet<String>>> {
+    RESTORING_PROJECTS
+        .get_or_init(|| Mutex::new(HashSet::new()))
+        .lock()
+        .map_err(|err| {
+            error!("Error locking restoring projects: {}", err);
+            PiError::InternalError(format!("Error locking restoring projects: {}", err))
+        })
+}
+
+// Marks a project as being restored until it is dropped, see `Snapshot::start_restoring`
+pub struct RestoringGuard {
+    project_uuid: String,
+}
+
+impl Drop for RestoringGuard {
+    fn drop(&mut self) {
+        if let Ok(mut restoring_projects) = lock_restoring_projects() {
+            restoring_projects.remove(&self.project_uuid);
+        }
+    }
+}
+
+#[derive(Clone, Deserialize, Serialize, ToSchema, TS)]
+#[ts(export)]
+pub struct Snapshot {
+    /// Snapshot ID (UUID)
+    pub uuid: String,
+    /// The project this is a snapshot of
+    pub project_uuid: String,
+    /// Optional label, like the reason for taking the snapshot
+    pub label: Option<String>,
+    pub created_at: DateTime<Utc>,
+    /// Size of the files of the snapshot on disk
+    #[ts(type = "number")]
+    pub size_in_bytes: u64,
+}
+
+#[derive(Clone, Deserialize, ToSchema, TS)]
+#[ts(export)]
+pub struct SnapshotCreate {
+    pub label: Option<String>,
+}
+
+impl CrudItem for Snapshot {
+    fn get_id(&self) -> String {
+        self.uuid.clone()
+    }
+}
+
+pub struct SnapshotCollection {}
+
+impl Crud for SnapshotCollection {
+    type Item = Snapshot;
+
+    fn get_collection_name() -> &'static str {
+        "snapshot"
+    }
+}
+
+impl Snapshot {
+    fn get_path_to_snapshots_dir(project_uuid: &str) -> PiResult<PathBuf> {
+        let (path_to_storage_dir, _) = Project::get_default_path_to_db(project_uuid)?;
+        Ok(path_to_storage_dir.join(SNAPSHOTS_DIR).join(project_uuid))
+    }
+
+    pub fn get_path_to_checkpoint(&self) -> PiResult<PathBuf> {
+        Ok(Self::get_path_to_snapshots_dir(&self.project_uuid)?
+            .join(format!("{}.rocksdb", self.uuid)))
+    }
+
+    // Takes a snapshot of an open project DB. The engine keeps the DB of a loaded project open,
+    // so snapshots of loaded projects are taken by the engine
+    pub fn create_from_db(
+        db: &DB,
+        project_uuid: &str,
+        label: Option<String>,
+    ) -> PiResult<Snapshot> {
+        let mut snapshot = Snapshot {
+            uuid: uuid::Uuid::new_v4().to_string(),
+            project_uuid: project_uuid.to_string(),
+            label,
+            created_at: Utc::now(),
+            size_in_bytes: 0,
+        };
+        let path_to_checkpoint = snapshot.get_path_to_checkpoint()?;
+        snapshot.size_in_bytes = create_checkpoint(db, &path_to_checkpoint)?;
+        match SnapshotCollection::create(snapshot.clone()) {
+            Ok(snapshot) => Ok(snapshot),
+            Err(err) => {
+                // A checkpoint which is not in the collection cannot be listed or deleted
+                if let Err(err) = fs::remove_dir_all(&path_to_checkpoint) {
+                    error!(
+                        "Error removing checkpoint at {}: {}",
+                        path_to_checkpoint.display(),
+                        err
+                    );
+                }
+                Err(err)
+            }
+        }
+    }
+
+    // Takes a snapshot of a project which is not loaded, like from the CLI
+    pub fn create(project_uuid: &str, label: Option<String>) -> PiResult<Snapshot> {
+        let (_, path_to_db) = Project::check_project_db(project_uuid)?;
+        let db = open_project_db(project_uuid, &path_to_db)?;
+        Self::create_from_db(&db, project_uuid, label)
+    }
+
+    // Snapshots of a project, newest first
+    pub fn read_list_of_project(project_uuid: &str) -> PiResult<Vec<Snapshot>> {
+        let mut snapshots: Vec<Snapshot> = SnapshotCollection::read_list()?
+            .into_iter()
+            .filter(|snapshot| snapshot.project_uuid == project_uuid)
+            .collect();
+        snapshots.sort_by_key(|snapshot| Reverse(snapshot.created_at));
+        Ok(snapshots)
+    }
+
+    pub fn read(project_uuid: &str, snapshot_uuid: &str) -> PiResult<Snapshot> {
+        match SnapshotCollection::read_item(snapshot_uuid) {
+            Ok(snapshot) if snapshot.project_uuid == project_uuid => Ok(snapshot),
+            Ok(_) | Err(PiError::CrudNotFoundError(_, _)) => Err(PiError::CrudNotFoundError(
+                "Snapshot".to_string(),
+                snapshot_uuid.to_string(),
+            )),
+            Err(err) => Err(err),
+        }
+    }
+
+    // The engine refuses to load a project while it is being restored, so the project is not
+    // loaded again between unloading it and replacing its DB. Only one restore runs at a time
+    pub fn start_restoring(project_uuid: &str) -> PiResult<RestoringGuard> {
+        if !lock_restoring_projects()?.insert(project_uuid.to_string()) {
+            return Err(PiError::ProjectRestoringError(project_uuid.to_string()));
+        }
+        Ok(RestoringGuard {
+            project_uuid: project_uuid.to_string(),
+        })
+    }
+
+    pub fn is_restoring(project_uuid: &str) -> PiResult<bool> {
+        Ok(lock_restoring_projects()?.contains(project_uuid))
+    }
+
+    // Replaces the DB of the project with the snapshot. The project cannot be loaded
+    // in the engine, since the engine keeps its DB open
+    pub fn restore(project_uuid: &str, snapshot_uuid: &str) -> PiResult<Snapshot> {
+        let snapshot = Self::read(project_uuid, snapshot_uuid)?;
+        let (_, path_to_db) = Project::check_project_db(project_uuid)?;
+        replace_db(
+            project_uuid,
+            &path_to_db,
+            &snapshot.get_path_to_checkpoint()?,
+        )?;
+        Ok(snapshot)
+    }
+
+    pub fn delete(project_uuid: &str, snapshot_uuid: &str) -> PiResult<Snapshot> {
+        let snapshot = Self::read(project_uuid, snapshot_uuid)?;
+        let path_to_checkpoint = snapshot.get_path_to_checkpoint()?;
+        if path_to_checkpoint.exists() {
+            fs::remove_dir_all(&path_to_checkpoint)?;
+        }
+        SnapshotCollection::delete(snapshot_uuid)?;
+        Ok(snapshot)
+    }
+}
+
+// RocksDB locks a DB while it is open, so this fails if the engine has the project loaded
+fn open_project_db(project_uuid: &str, path_to_db: &Path) -> PiResult<DB> {
+    let mut opts = Options::default();
+    opts.create_if_missing(false);
+    match DB::open(&opts, path_to_db.as_os_str()) {
+        Ok(db) => Ok(db),
+        Err(err) if err.to_string().to_lowercase().contains("lock") => {
+            Err(PiError::ProjectInUseError(project_uuid.to_string()))
+        }
+        Err(err) => {
+            error!("Could not open DB for project {}: {}", project_uuid, err);
+            Err(err.into())
+        }
+    }
+}
+
+// A checkpoint is a consistent copy of the DB, its files are hard links to the files of
+// the DB when they are on the same file system. Returns the size of the checkpoint
+fn create_checkpoint(db: &DB, path_to_checkpoint: &Path) -> PiResult<u64> {
+    if let Some(path_to_snapshots_dir) = path_to_checkpoint.parent() {
+        fs::create_dir_all(path_to_snapshots_dir)?;
+    }
+    Checkpoint::new(db)?.create_checkpoint(path_to_checkpoint)?;
+    get_size_of_dir(path_to_checkpoint)
+}
+
+// The snapshot is copied next to the DB first, so the DB is replaced only with a complete copy.
+// The snapshot itself is not changed, it can be restored again
+fn replace_db(project_uuid: &str, path_to_db: &Path, path_to_checkpoint: &Path) -> PiResult<()> {
+    if !path_to_checkpoint.is_dir() {
+        return Err(PiError::InternalError(format!(
+            "Files of snapshot at {} are missing",
+            path_to_checkpoint.display()
+        )));
+    }
+    // The engine could open the DB again after this check, the API server prevents that
+    // with `Snapshot::start_restoring` for as long as the restore runs
+    drop(open_project_db(project_uuid, path_to_db)?);
+
+    let path_to_restored_db = path_to_db.with_extension("rocksdb.restoring");
+    let path_to_replaced_db = path_to_db.with_extension("rocksdb.replaced");
+    for path in [&path_to_restored_db, &path_to_replaced_db] {
+        if path.exists() {
+            fs::remove_dir_all(path)?;
+        }
+    }
+    copy_dir(path_to_checkpoint, &path_to_restored_db)?;
+    fs::rename(path_to_db, &path_to_replaced_db)?;
+    fs::rename(&path_to_restored_db, path_to_db)?;
+    fs::remove_dir_all(&path_to_replaced_db)?;
+    Ok(())
+}
+
+fn copy_dir(from: &Path, to: &Path) -> PiResult<()> {
+    fs::create_dir_all(to)?;
+    for entry in fs::read_dir(from)? {
+        let entry = entry?;
+        if entry.file_type()?.is_dir() {
+            copy_dir(&entry.path(), &to.join(entry.file_name()))?;
+        } else {
+            fs::copy(entry.path(), to.join(entry.file_name()))?;
+        }
+    }
+    Ok(())
+}
+
+fn get_size_of_dir(path: &Path) -> PiResult<u64> {
+    let mut size: u64 = 0;
+    for entry in fs::read_dir(path)? {
+        let entry = entry?;
+        let metadata = entry.metadata()?;
+        if metadata.is_dir() {
+            size += get_size_of_dir(&entry.path())?;
+        } else {
+            size += metadata.len();
+        }
+    }
+    Ok(size)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_restored_db_has_data_of_checkpoint() {
+        let temp_dir = tempfile::Builder::new()
+            .prefix("_path_for_rocksdb_storage_")
+            .tempdir()
+            .expect("Failed to create temporary path for the _path_for_rocksdb_storage_");
+        let path_to_db = temp_dir.path().join("project.rocksdb");
+        let path_to_checkpoint = temp_dir.path().join("snapshots/checkpoint.rocksdb");
+
+        let db = DB::open_default(&path_to_db).unwrap();
+        db.put("question", "before crawl").unwrap();
+        let size_in_bytes = create_checkpoint(&db, &path_to_checkpoint).unwrap();
+        assert!(size_in_bytes > 0);
+        db.put("question", "after crawl").unwrap();
+
+        // The DB is open, as if the engine had loaded the project
+        assert!(matches!(
+            replace_db("project", &path_to_db, &path_to_checkpoint),
+            Err(PiError::ProjectInUseError(_))
+        ));
+        drop(db);
+
+        replace_db("project", &path_to_db, &path_to_checkpoint).unwrap();
+        let db = DB::open_default(&path_to_db).unwrap();
+        assert_eq!(
+            db.get("question").unwrap(),
+            Some("before crawl".as_bytes().to_vec())
+        );
+        // The snapshot can be restored again
+        assert!(path_to_checkpoint.is_dir());
+    }
+
+    #[test]
+    fn test_project_is_restored_once_at_a_time() {
+        let project_uuid = "project_being_restored";
+        let restoring = Snapshot::start_restoring(project_uuid).unwrap();
+        assert!(Snapshot::is_restoring(project_uuid).unwrap());
+        assert!(matches!(
+            Snapshot::start_restoring(project_uuid),
+            Err(PiError::ProjectRestoringError(_))
+        ));
+        drop(restoring);
+        assert!(!Snapshot::is_restoring(project_uuid).unwrap());
+    }
+}
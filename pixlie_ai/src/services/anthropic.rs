@@ -9,7 +9,7 @@ use crate::utils::llm::LLMProvider;
 use crate::workspace::{APIProvider, WorkspaceCollection};
 use crate::{
     error::{PiError, PiResult},
-    FetchRequest,
+    FetchRequest, FetchResponse,
 };
 use reqwest::header::{HeaderMap, CONTENT_TYPE};
 use reqwest::Method;
@@ -20,6 +20,13 @@ use serde::{Deserialize, Serialize};
 pub struct ClaudeResponse {
     pub id: String,
     pub content: Vec<ClaudeResponseContent>,
+    pub usage: Option<ClaudeUsage>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ClaudeUsage {
+    pub input_tokens: u32,
+    pub output_tokens: u32,
 }
 
 #[derive(Debug, Deserialize)]
@@ -63,8 +70,32 @@ pub struct SearchTermPromptInput {
 
 // const LL_MODEL_SONNET: &str = "claude-3-7-sonnet-latest";
 const LL_MODEL_HAIKU: &str = "claude-3-5-haiku-latest";
+// Price of the model in US cents per token, used to track the LLM spend of a project
+const LL_MODEL_HAIKU_CENTS_PER_INPUT_TOKEN: f64 = 0.00008;
+const LL_MODEL_HAIKU_CENTS_PER_OUTPUT_TOKEN: f64 = 0.0004;
+const MESSAGES_URL: &str = "https://api.anthropic.com/v1/messages";
 
 pub struct Anthropic;
+impl Anthropic {
+    pub fn is_llm_request(url: &str) -> bool {
+        url == MESSAGES_URL
+    }
+
+    // Cost of an LLM response in US cents, None for other responses
+    pub fn get_cost_in_cents(response: &FetchResponse) -> Option<f64> {
+        if !Self::is_llm_request(&response.url) {
+            return None;
+        }
+        let usage = serde_json::from_str::<ClaudeResponse>(&response.contents)
+            .ok()?
+            .usage?;
+        Some(
+            usage.input_tokens as f64 * LL_MODEL_HAIKU_CENTS_PER_INPUT_TOKEN
+                + usage.output_tokens as f64 * LL_MODEL_HAIKU_CENTS_PER_OUTPUT_TOKEN,
+        )
+    }
+}
+
 impl LLMProvider for Anthropic {
     fn get_request(prompt: &String, calling_node_id: u32) -> PiResult<FetchRequest> {
         // Skip if there is no Anthropic API key
@@ -72,8 +103,7 @@ impl LLMProvider for Anthropic {
             Some(key) => key.to_string(),
             None => return Err(PiError::ApiKeyNotConfigured("Anthropic".to_string())),
         };
-        let mut request =
-            FetchRequest::new(calling_node_id, "https://api.anthropic.com/v1/messages");
+        let mut request = FetchRequest::new(calling_node_id, MESSAGES_URL);
         request.method = Method::POST;
         request.headers = HeaderMap::from_iter(vec![
             (CONTENT_TYPE, "application/json".parse().unwrap()),
@@ -0,0 +1,188 @@
+// Copyright 2025 Pixlie Web Solutions Pvt. Ltd.
+// Licensed under the GNU General Public License version 3.0;
+// You may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// https://github.com/pixlie/PixlieAI/blob/main/LICENSE
+
+use crate::config::Settings;
+use crate::error::{PiError, PiResult};
+use composable::Composable;
+use ndarray::{Array2, Ix3};
+use orp::model::Model;
+use orp::params::RuntimeParameters;
+use orp::pipeline::{Pipeline, PostProcessor, PreProcessor};
+use ort::session::{SessionInputs, SessionOutputs};
+use std::path::PathBuf;
+use std::sync::Arc;
+use tokenizers::{Tokenizer, TruncationParams};
+
+// A sentence embedding model exported to ONNX, like all-MiniLM-L6-v2, in the storage directory
+const EMBEDDING_MODEL_DIR: &str = "embedding_onnx_models/all_minilm_l6_v2";
+// Longer texts are truncated, sentence embedding models are trained on short texts
+const MAX_TOKENS: usize = 256;
+
+// Turns texts into vectors, texts with similar meaning have vectors which are close
+pub trait Embedder: Send + Sync {
+    fn embed(&self, texts: Vec<String>) -> PiResult<Vec<Vec<f32>>>;
+}
+
+pub type ArcedEmbedder = Arc<dyn Embedder>;
+
+// Tokenizes a batch of texts into the inputs of the model. The attention mask is kept
+// as context, so padding tokens can be left out when pooling
+struct TextsToSessionInputs<'t> {
+    tokenizer: &'t Tokenizer,
+}
+
+impl<'a> Composable<Vec<String>, (SessionInputs<'a, 'a>, Array2<f32>)>
+    for TextsToSessionInputs<'_>
+{
+    fn apply(
+        &self,
+        texts: Vec<String>,
+    ) -> composable::Result<(SessionInputs<'a, 'a>, Array2<f32>)> {
+        let encodings = self.tokenizer.encode_batch(texts, true)?;
+        let count_tokens = encodings
+            .iter()
+            .map(|encoding| encoding.get_ids().len())
+            .max()
+            .unwrap_or(0);
+        let mut input_ids = Array2::<i64>::zeros((encodings.len(), count_tokens));
+        let mut attention_mask = Array2::<i64>::zeros((encodings.len(), count_tokens));
+        let mut token_type_ids = Array2::<i64>::zeros((encodings.len(), count_tokens));
+        for (row, encoding) in encodings.iter().enumerate() {
+            for (column, id) in encoding.get_ids().iter().enumerate() {
+                input_ids[[row, column]] = *id as i64;
+            }
+            for (column, mask) in encoding.get_attention_mask().iter().enumerate() {
+                attention_mask[[row, column]] = *mask as i64;
+            }
+            for (column, type_id) in encoding.get_type_ids().iter().enumerate() {
+                token_type_ids[[row, column]] = *type_id as i64;
+            }
+        }
+        let context = attention_mask.mapv(|mask| mask as f32);
+        let inputs = ort::inputs! {
+            "input_ids" => input_ids,
+            "attention_mask" => attention_mask,
+            "token_type_ids" => token_type_ids,
+        }?;
+        Ok((inputs.into(), context))
+    }
+}
+
+// Averages the token vectors of each text, without padding, and normalizes the average
+// so that the dot product of two embeddings is their cosine similarity
+struct MeanPooling;
+
+impl<'a> Composable<(SessionOutputs<'a, 'a>, Array2<f32>), Vec<Vec<f32>>> for MeanPooling {
+    fn apply(
+        &self,
+        input: (SessionOutputs<'a, 'a>, Array2<f32>),
+    ) -> composable::Result<Vec<Vec<f32>>> {
+        let (outputs, attention_mask) = input;
+        let token_vectors = outputs["last_hidden_state"]
+            .try_extract_tensor::<f32>()?
+            .into_dimensionality::<Ix3>()?;
+        let mut embeddings: Vec<Vec<f32>> = vec![];
+        for (row, text_token_vectors) in token_vectors.outer_iter().enumerate() {
+            let mut embedding: Vec<f32> = vec![0.0; text_token_vectors.shape()[1]];
+            let mut count_tokens: f32 = 0.0;
+            for (column, token_vector) in text_token_vectors.outer_iter().enumerate() {
+                let mask = attention_mask[[row, column]];
+                if mask > 0.0 {
+                    for (sum, value) in embedding.iter_mut().zip(token_vector.iter()) {
+                        *sum += value * mask;
+                    }
+                    count_tokens += mask;
+                }
+            }
+            let norm = embedding.iter().map(|x| x * x).sum::<f32>().sqrt();
+            if count_tokens > 0.0 && norm > 0.0 {
+                embedding.iter_mut().for_each(|x| *x /= norm);
+            }
+            embeddings.push(embedding);
+        }
+        Ok(embeddings)
+    }
+}
+
+struct EmbeddingPipeline {
+    tokenizer: Tokenizer,
+}
+
+impl<'a> Pipeline<'a> for EmbeddingPipeline {
+    type Input = Vec<String>;
+    type Output = Vec<Vec<f32>>;
+    type Context = Array2<f32>;
+    type Parameters = ();
+
+    fn pre_processor(
+        &self,
+        _params: &Self::Parameters,
+    ) -> impl PreProcessor<'a, Self::Input, Self::Context> {
+        TextsToSessionInputs {
+            tokenizer: &self.tokenizer,
+        }
+    }
+
+    fn post_processor(
+        &self,
+        _params: &Self::Parameters,
+    ) -> impl PostProcessor<'a, Self::Output, Self::Context> {
+        MeanPooling
+    }
+}
+
+// Embeddings with a local ONNX model, run with the same runtime as GLiNER
+pub struct TextEmbedder {
+    model: Model,
+    pipeline: EmbeddingPipeline,
+}
+
+impl TextEmbedder {
+    // Loads the model from the storage directory. Returns None if the model is not installed
+    pub fn load() -> PiResult<Option<TextEmbedder>> {
+        let settings: Settings = Settings::get_cli_settings()?;
+        let path_to_model_dir = match settings.path_to_storage_dir {
+            Some(path) => PathBuf::from(path).join(EMBEDDING_MODEL_DIR),
+            None => {
+                return Err(PiError::InternalError(
+                    "Cannot find path to storage directory".to_string(),
+                ));
+            }
+        };
+        let path_to_tokenizer = path_to_model_dir.join("tokenizer.json");
+        let path_to_model = path_to_model_dir.join("model.onnx");
+        if !path_to_tokenizer.exists() || !path_to_model.exists() {
+            return Ok(None);
+        }
+
+        let mut tokenizer = Tokenizer::from_file(path_to_tokenizer)
+            .map_err(|err| PiError::EmbeddingError(err.to_string()))?;
+        tokenizer
+            .with_truncation(Some(TruncationParams {
+                max_length: MAX_TOKENS,
+                ..TruncationParams::default()
+            }))
+            .map_err(|err| PiError::EmbeddingError(err.to_string()))?;
+        let model = Model::new(path_to_model, RuntimeParameters::default())
+            .map_err(|err| PiError::EmbeddingError(err.to_string()))?;
+        Ok(Some(TextEmbedder {
+            model,
+            pipeline: EmbeddingPipeline { tokenizer },
+        }))
+    }
+}
+
+impl Embedder for TextEmbedder {
+    fn embed(&self, texts: Vec<String>) -> PiResult<Vec<Vec<f32>>> {
+        if texts.is_empty() {
+            return Ok(vec![]);
+        }
+        self.model
+            .inference(texts, &self.pipeline, &())
+            .map_err(|err| PiError::EmbeddingError(err.to_string()))
+    }
+}
@@ -9,6 +9,7 @@ use crate::entity::named_entity::ExtractedEntity;
 use serde::Serialize;
 
 pub mod anthropic;
+pub mod embedding;
 pub mod gliner;
 pub mod llm_provider;
 pub mod ollama;
@@ -1,5 +1,9 @@
-use crate::{CrawlOrAPIRequest, FetchError, FetchResponse, InternalFetchRequest, PiEvent};
+use crate::entity::fetch_error::FetchErrorKind;
+use crate::{
+    CrawlOrAPIRequest, FetchError, FetchResponse, FetchThrottled, InternalFetchRequest, PiEvent,
+};
 use log::{debug, error};
+use reqwest::header::HeaderMap;
 use reqwest::{Client, Request, RequestBuilder, StatusCode, Url};
 use std::collections::HashMap;
 use std::time::{Duration, Instant};
@@ -16,29 +20,29 @@ struct DomainLog {
 
 type Logs = HashMap<String, DomainLog>;
 
+// A domain, or a URL, is fetched from again only when this much time has passed
+const FETCH_INTERVAL: Duration = Duration::from_secs(3);
+
 enum CanCrawl {
     Yes,
-    No(String),
+    No(String, Duration), // Why not, and how long till it can be fetched
+}
+
+fn get_wait_time(last_fetched_at: Instant) -> Duration {
+    FETCH_INTERVAL.saturating_sub(last_fetched_at.elapsed())
 }
 
 fn check_logs(domain: &str, url: &str, logs: &mut Logs) -> CanCrawl {
     match logs.get_mut(domain) {
         Some(domain_log) => {
             // Check the last fetch time for this domain. We do not want to fetch too often.
-            if domain_log.last_fetched_at.elapsed().as_secs() > 2 {
+            if domain_log.last_fetched_at.elapsed() >= FETCH_INTERVAL {
                 // We have fetched from this domain some time ago, let's check the URL logs
-                match domain_log.per_url.get(url) {
+                let can_crawl = match domain_log.per_url.get(url) {
                     Some(url_log) => {
                         // Check the last fetch time for this URL. We do not want to fetch too often.
-                        if url_log.last_fetched_at.elapsed().as_secs() > 2 {
+                        if url_log.last_fetched_at.elapsed() >= FETCH_INTERVAL {
                             // We have fetched from this URL some time ago, we can fetch now
-                            debug!("URL {} was recently fetched from, cannot fetch now", url);
-                            CanCrawl::No(format!(
-                                "URL {} was recently fetched from, cannot fetch now",
-                                url
-                            ))
-                        } else {
-                            // We have fetched from this URL recently, let's update the last fetched time
                             domain_log.per_url.insert(
                                 url.to_string(),
                                 FetchLog {
@@ -46,6 +50,13 @@ fn check_logs(domain: &str, url: &str, logs: &mut Logs) -> CanCrawl {
                                 },
                             );
                             CanCrawl::Yes
+                        } else {
+                            // We have fetched from this URL recently, we cannot fetch now
+                            debug!("URL {} was recently fetched from, cannot fetch now", url);
+                            CanCrawl::No(
+                                format!("URL {} was recently fetched from, cannot fetch now", url),
+                                get_wait_time(url_log.last_fetched_at),
+                            )
                         }
                     }
                     None => {
@@ -58,17 +69,24 @@ fn check_logs(domain: &str, url: &str, logs: &mut Logs) -> CanCrawl {
                         );
                         CanCrawl::Yes
                     }
+                };
+                if let CanCrawl::Yes = can_crawl {
+                    domain_log.last_fetched_at = Instant::now();
                 }
+                can_crawl
             } else {
                 // We have fetched from this domain very recently, we can not fetch now
                 debug!(
                     "Domain {} was recently fetched from, cannot fetch now",
                     domain
                 );
-                CanCrawl::No(format!(
-                    "Domain {} was recently fetched from, cannot fetch now",
-                    domain
-                ))
+                CanCrawl::No(
+                    format!(
+                        "Domain {} was recently fetched from, cannot fetch now",
+                        domain
+                    ),
+                    get_wait_time(domain_log.last_fetched_at),
+                )
             }
         }
         None => {
@@ -86,8 +104,12 @@ fn check_logs(domain: &str, url: &str, logs: &mut Logs) -> CanCrawl {
 }
 
 enum FetchResult {
-    Contents(StatusCode, String),
-    Error(String),
+    Contents(StatusCode, HeaderMap, String),
+    Error {
+        status: Option<StatusCode>,
+        kind: FetchErrorKind,
+        message: String,
+    },
 }
 
 async fn fetch(request: InternalFetchRequest) -> FetchResult {
@@ -102,16 +124,21 @@ async fn fetch(request: InternalFetchRequest) -> FetchResult {
     {
         Ok(client) => client,
         Err(err) => {
-            return FetchResult::Error(format!(
-                "Error building client to fetch URL {}: {}",
-                &url, err
-            ));
+            return FetchResult::Error {
+                status: None,
+                kind: FetchErrorKind::Other,
+                message: format!("Error building client to fetch URL {}: {}", &url, err),
+            };
         }
     };
     let url = match Url::parse(&url) {
         Ok(url) => url,
         Err(err) => {
-            return FetchResult::Error(format!("Error parsing URL {} to fetch URL: {}", &url, err));
+            return FetchResult::Error {
+                status: None,
+                kind: FetchErrorKind::Other,
+                message: format!("Error parsing URL {} to fetch URL: {}", &url, err),
+            };
         }
     };
 
@@ -124,19 +151,35 @@ async fn fetch(request: InternalFetchRequest) -> FetchResult {
     match request_builder.send().await {
         Ok(response) => {
             let status = response.status();
-            if status.is_success() {
+            let headers = response.headers().clone();
+            if status == StatusCode::NOT_MODIFIED {
+                // The response to a conditional request, the contents we have are still current
+                FetchResult::Contents(status, headers, "".to_string())
+            } else if status.is_success() {
                 match response.text().await {
-                    Ok(contents) => FetchResult::Contents(status, contents),
-                    Err(err) => FetchResult::Error(err.to_string()),
+                    Ok(contents) => FetchResult::Contents(status, headers, contents),
+                    Err(err) => FetchResult::Error {
+                        status: Some(status),
+                        kind: FetchErrorKind::from_reqwest_error(&err),
+                        message: err.to_string(),
+                    },
                 }
             } else {
-                FetchResult::Error(format!(
-                    "Fetch response status is not success, got response {}",
-                    response.text().await.unwrap_or_else(|_| "".to_string())
-                ))
+                FetchResult::Error {
+                    status: Some(status),
+                    kind: FetchErrorKind::from_status(status),
+                    message: format!(
+                        "Fetch response status is not success, got response {}",
+                        response.text().await.unwrap_or_else(|_| "".to_string())
+                    ),
+                }
             }
         }
-        Err(error) => FetchResult::Error(format!("Error getting response: {}", error)),
+        Err(error) => FetchResult::Error {
+            status: error.status(),
+            kind: FetchErrorKind::from_reqwest_error(&error),
+            message: format!("Error getting response: {}", error),
+        },
     }
 }
 
@@ -157,16 +200,27 @@ pub fn fetcher_runtime(
     rt.block_on(async {
         async fn make_request(request: InternalFetchRequest) -> PiEvent {
             match fetch(request.clone()).await {
-                FetchResult::Contents(_status, contents) => PiEvent::FetchResponse(FetchResponse {
+                FetchResult::Contents(status, headers, contents) => {
+                    PiEvent::FetchResponse(FetchResponse {
+                        project_id: request.project_id.clone(),
+                        node_id: request.node_id,
+                        url: request.crawl_or_api_request.get_url(),
+                        status: status.as_u16(),
+                        headers,
+                        contents,
+                    })
+                }
+                FetchResult::Error {
+                    status,
+                    kind,
+                    message,
+                } => PiEvent::FetchError(FetchError {
                     project_id: request.project_id.clone(),
                     node_id: request.node_id,
                     url: request.crawl_or_api_request.get_url(),
-                    contents,
-                }),
-                FetchResult::Error(err) => PiEvent::FetchError(FetchError {
-                    project_id: request.project_id.clone(),
-                    node_id: request.node_id,
-                    error: err,
+                    status: status.map(|status| status.as_u16()),
+                    kind,
+                    error: message,
                 }),
             }
         }
@@ -182,11 +236,16 @@ pub fn fetcher_runtime(
                                 &crawl_request.url,
                                 &mut domain_logs,
                             ) {
-                                CanCrawl::No(err) => PiEvent::FetchError(FetchError {
-                                    project_id: request.project_id.clone(),
-                                    node_id: request.node_id,
-                                    error: err,
-                                }),
+                                // The request was not sent, so this is not an error of the node
+                                CanCrawl::No(reason, wait_time) => {
+                                    PiEvent::FetchThrottled(FetchThrottled {
+                                        project_id: request.project_id.clone(),
+                                        node_id: request.node_id,
+                                        url: request.crawl_or_api_request.get_url(),
+                                        reason,
+                                        wait_time,
+                                    })
+                                }
                                 CanCrawl::Yes => make_request(request).await,
                             },
                             CrawlOrAPIRequest::API(_) => make_request(request).await,
@@ -206,3 +265,53 @@ pub fn fetcher_runtime(
         }
     });
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fetched_secs_ago(secs: u64) -> Instant {
+        Instant::now() - Duration::from_secs(secs)
+    }
+
+    #[test]
+    fn test_check_logs_throttles_recent_fetches_only() {
+        let mut logs: Logs = HashMap::new();
+        let url = "https://pixlie.com/about";
+        assert!(matches!(
+            check_logs("pixlie.com", url, &mut logs),
+            CanCrawl::Yes
+        ));
+        // The domain was just fetched from
+        assert!(matches!(
+            check_logs("pixlie.com", "https://pixlie.com/", &mut logs),
+            CanCrawl::No(..)
+        ));
+
+        // A URL fetched some time ago can be fetched again, like when retrying or recrawling
+        let domain_log = logs.get_mut("pixlie.com").unwrap();
+        domain_log.last_fetched_at = fetched_secs_ago(3);
+        domain_log.per_url.insert(
+            url.to_string(),
+            FetchLog {
+                last_fetched_at: fetched_secs_ago(3),
+            },
+        );
+        assert!(matches!(
+            check_logs("pixlie.com", url, &mut logs),
+            CanCrawl::Yes
+        ));
+        // Fetching it updated the last fetch time of the domain
+        assert!(matches!(
+            check_logs("pixlie.com", "https://pixlie.com/blog", &mut logs),
+            CanCrawl::No(..)
+        ));
+
+        // A URL fetched recently cannot be fetched, even if the domain was not
+        logs.get_mut("pixlie.com").unwrap().last_fetched_at = fetched_secs_ago(3);
+        assert!(matches!(
+            check_logs("pixlie.com", url, &mut logs),
+            CanCrawl::No(..)
+        ));
+    }
+}